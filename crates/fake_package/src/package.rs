@@ -6,6 +6,8 @@ use crate::dependency::FakeDependency;
 pub struct FakePackage {
     name: String,
     dependencies: Vec<FakeDependency>,
+    /// If true, the package has a `lib` target.
+    is_library: bool,
 }
 
 impl FakePackage {
@@ -13,6 +15,15 @@ impl FakePackage {
         Self {
             name: name.into(),
             dependencies: vec![],
+            is_library: false,
+        }
+    }
+
+    /// Add a `lib` target to the package.
+    pub fn library(self) -> Self {
+        Self {
+            is_library: true,
+            ..self
         }
     }
 
@@ -29,6 +40,16 @@ impl From<FakePackage> for Package {
         let dependencies: Vec<Dependency> =
             pkg.dependencies.into_iter().map(Dependency::from).collect();
         let name = pkg.name;
+        let targets = if pkg.is_library {
+            serde_json::json!([{
+                "name": name,
+                "kind": ["lib"],
+                "crate_types": ["lib"],
+                "src_path": format!("{name}/src/lib.rs"),
+            }])
+        } else {
+            serde_json::json!([])
+        };
         serde_json::from_value(serde_json::json!({
             "name": name,
             "version": "0.1.0",
@@ -36,7 +57,7 @@ impl From<FakePackage> for Package {
             "dependencies": dependencies,
             "features": {},
             "manifest_path": format!("{name}/Cargo.toml"),
-            "targets": [],
+            "targets": targets,
         }))
         .unwrap()
     }
@@ -8,6 +8,7 @@ use std::{
     fmt,
     path::{Path, PathBuf},
     process::Command,
+    sync::OnceLock,
 };
 
 use anyhow::{anyhow, Context};
@@ -18,10 +19,44 @@ use tracing::{debug, instrument, trace, warn, Span};
 pub struct Repo {
     /// Directory where you want to run git operations
     directory: PathBuf,
-    /// Branch name before running any git operation
-    original_branch: String,
+    /// Branch checked out before running any git operation.
+    /// `None` if HEAD is detached.
+    current_branch: Option<String>,
+    /// Branch name before running any git operation.
+    /// If HEAD is detached, it's the default branch of the remote,
+    /// looked up the first time it's needed.
+    original_branch: OnceLock<String>,
+    /// Branch or commit checked out before running any git operation.
+    original_head: String,
     /// Remote name before running any git operation
     original_remote: String,
+    /// Remote where commits and tags are pushed.
+    /// If `None`, `original_remote` is used.
+    push_remote: Option<String>,
+    /// Author and committer of the commits and tags.
+    identity: GitIdentity,
+    /// If true, the commits at a path are looked up following only the first parent
+    /// of merge commits.
+    first_parent: bool,
+}
+
+/// Name and email of the author and committer of the commits and tags created by [`Repo`].
+/// If a field is `None`, git reads it from its configuration.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GitIdentity {
+    pub name: Option<String>,
+    pub email: Option<String>,
+}
+
+impl GitIdentity {
+    /// Arguments of git that set the identity, e.g. `-c user.name=release-plz`.
+    fn config_args(&self) -> Vec<String> {
+        [("user.name", &self.name), ("user.email", &self.email)]
+            .into_iter()
+            .filter_map(|(key, value)| value.as_ref().map(|value| format!("{key}={value}")))
+            .flat_map(|config| ["-c".to_string(), config])
+            .collect()
+    }
 }
 
 impl Repo {
@@ -32,21 +67,62 @@ impl Repo {
 
         let (current_remote, current_branch) = Self::get_current_remote_and_branch(&directory)
             .context("cannot determine current branch")?;
+        let original_head = match &current_branch {
+            Some(branch) => branch.clone(),
+            None => git_in_dir(directory.as_ref(), &["rev-parse", "HEAD"])?,
+        };
+        let original_branch = OnceLock::new();
+        if let Some(branch) = &current_branch {
+            original_branch.get_or_init(|| branch.clone());
+        }
 
         Ok(Self {
             directory: directory.as_ref().to_path_buf(),
-            original_branch: current_branch,
+            current_branch,
+            original_branch,
+            original_head,
             original_remote: current_remote,
+            push_remote: None,
+            identity: GitIdentity::default(),
+            first_parent: false,
         })
     }
 
+    /// Create the commits and tags with the given `identity`.
+    pub fn with_identity(mut self, identity: GitIdentity) -> Self {
+        self.identity = identity;
+        self
+    }
+
+    /// Look up the commits at a path following only the first parent of merge commits,
+    /// so that merge commits are visited instead of the commits of the merged branches.
+    pub fn with_first_parent(mut self, first_parent: bool) -> Self {
+        self.first_parent = first_parent;
+        self
+    }
+
+    /// Push commits and tags to `remote` instead of the original remote,
+    /// e.g. to push to a fork.
+    pub fn with_push_remote(mut self, remote: impl Into<String>) -> Self {
+        self.push_remote = Some(remote.into());
+        self
+    }
+
+    /// Use `branch` as original branch, e.g. because HEAD is detached
+    /// and the branch it corresponds to can't be inferred.
+    pub fn with_original_branch(mut self, branch: impl Into<String>) -> Self {
+        self.original_branch = OnceLock::from(branch.into());
+        self
+    }
+
     pub fn directory(&self) -> &Path {
         &self.directory
     }
 
+    /// Current remote and branch. The branch is `None` if HEAD is detached.
     fn get_current_remote_and_branch(
         directory: impl AsRef<Path>,
-    ) -> anyhow::Result<(String, String)> {
+    ) -> anyhow::Result<(String, Option<String>)> {
         match git_in_dir(
             directory.as_ref(),
             &[
@@ -58,7 +134,7 @@ impl Repo {
         ) {
             Ok(output) => output
                 .split_once('/')
-                .map(|(remote, branch)| (remote.to_string(), branch.to_string()))
+                .map(|(remote, branch)| (remote.to_string(), Some(branch.to_string())))
                 .context("cannot determine current remote and branch"),
 
             Err(e) => {
@@ -66,9 +142,11 @@ impl Repo {
                 if err.contains("fatal: no upstream configured for branch") {
                     let branch = Self::get_current_branch(directory)?;
                     warn!("no upstream configured for branch {branch}");
-                    Ok(("origin".to_string(), branch))
+                    Ok(("origin".to_string(), Some(branch)))
                 } else if err.contains("fatal: ambiguous argument 'HEAD': unknown revision or path not in the working tree.") {
                     Err(anyhow!("git repository does not contain any commit."))
+                } else if err.contains("fatal: HEAD does not point to a branch") {
+                    Ok(("origin".to_string(), None))
                 } else {
                     Err(e)
                 }
@@ -76,6 +154,45 @@ impl Repo {
         }
     }
 
+    /// Default branch of the `origin` remote.
+    /// Used when HEAD is detached, e.g. in CI checkouts.
+    /// If the default branch is unknown, return `HEAD` as branch.
+    fn get_default_branch(directory: &Path) -> String {
+        let local_default_branch = || {
+            // e.g. `origin/main`
+            git_in_dir(
+                directory,
+                &["symbolic-ref", "--short", "refs/remotes/origin/HEAD"],
+            )
+            .ok()?
+            .split_once('/')
+            .map(|(remote, branch)| (remote.to_string(), branch.to_string()))
+        };
+        let remote_default_branch = || {
+            // e.g. `ref: refs/heads/main\tHEAD`
+            let output =
+                git_in_dir(directory, &["ls-remote", "--symref", "origin", "HEAD"]).ok()?;
+            let branch = output
+                .lines()
+                .find_map(|line| line.strip_prefix("ref: refs/heads/"))?
+                .split_once('\t')?
+                .0;
+            Some(("origin".to_string(), branch.to_string()))
+        };
+        match local_default_branch().or_else(remote_default_branch) {
+            Some((remote, branch)) => {
+                debug!(
+                    "HEAD is detached: using the default branch `{branch}` of remote `{remote}`"
+                );
+                branch
+            }
+            None => {
+                warn!("HEAD is detached and the default branch of remote `origin` is unknown");
+                "HEAD".to_string()
+            }
+        }
+    }
+
     fn get_current_branch(directory: impl AsRef<Path>) -> anyhow::Result<String> {
         git_in_dir(directory.as_ref(), &["rev-parse", "--abbrev-ref", "HEAD"])
         .map_err(|e|
@@ -102,7 +219,7 @@ impl Repo {
 
     pub fn add_all_and_commit(&self, message: &str) -> anyhow::Result<()> {
         self.git(&["add", "."])?;
-        self.git(&["commit", "-m", message])?;
+        self.commit(message)?;
         Ok(())
     }
 
@@ -112,6 +229,20 @@ impl Repo {
         Ok(changed_files)
     }
 
+    /// Files changed by `commit`, relative to the repository directory.
+    pub fn commit_files(&self, commit: &str) -> anyhow::Result<Vec<String>> {
+        let output = self.git(&[
+            "diff-tree",
+            "--no-commit-id",
+            "--name-only",
+            "-r",
+            "--root",
+            "--relative",
+            commit,
+        ])?;
+        Ok(output.lines().map(|l| l.to_string()).collect())
+    }
+
     pub fn add<T: AsRef<str>>(&self, paths: &[T]) -> anyhow::Result<()> {
         let mut args = vec!["add"];
         let paths: Vec<&str> = paths.iter().map(|p| p.as_ref()).collect();
@@ -121,17 +252,17 @@ impl Repo {
     }
 
     pub fn commit(&self, message: &str) -> anyhow::Result<()> {
-        self.git(&["commit", "-m", message])?;
+        self.git_as_identity(&["commit", "-m", message])?;
         Ok(())
     }
 
     pub fn commit_signed(&self, message: &str) -> anyhow::Result<()> {
-        self.git(&["commit", "-s", "-m", message])?;
+        self.git_as_identity(&["commit", "-s", "-m", message])?;
         Ok(())
     }
 
     pub fn push(&self, obj: &str) -> anyhow::Result<()> {
-        self.git(&["push", &self.original_remote, obj])?;
+        self.git(&["push", self.push_remote(), obj])?;
         Ok(())
     }
 
@@ -141,20 +272,33 @@ impl Repo {
     }
 
     pub fn force_push(&self, obj: &str) -> anyhow::Result<()> {
-        self.git(&["push", &self.original_remote, obj, "--force"])?;
+        self.git(&["push", self.push_remote(), obj, "--force"])?;
         Ok(())
     }
 
+    /// Remote where commits and tags are pushed.
+    pub fn push_remote(&self) -> &str {
+        self.push_remote.as_deref().unwrap_or(&self.original_remote)
+    }
+
     #[instrument(skip(self))]
     pub fn checkout_head(&self) -> anyhow::Result<()> {
-        self.checkout(&self.original_branch)?;
+        self.checkout(&self.original_head)?;
         Ok(())
     }
 
     /// Branch name before running any git operation.
     /// I.e. when the [`Repo`] was created.
+    /// If HEAD is detached, it's the default branch of the remote.
     pub fn original_branch(&self) -> &str {
-        &self.original_branch
+        self.original_branch
+            .get_or_init(|| Self::get_default_branch(&self.directory))
+    }
+
+    /// Branch checked out when the [`Repo`] was created.
+    /// `None` if HEAD is detached.
+    pub fn current_branch(&self) -> Option<&str> {
+        self.current_branch.as_deref()
     }
 
     #[instrument(skip(self))]
@@ -190,6 +334,17 @@ impl Repo {
         git_in_dir(&self.directory, args)
     }
 
+    /// Run a git command that creates a commit or a tag, using the configured identity.
+    fn git_as_identity(&self, args: &[&str]) -> anyhow::Result<String> {
+        let config_args = self.identity.config_args();
+        let args: Vec<&str> = config_args
+            .iter()
+            .map(String::as_str)
+            .chain(args.iter().copied())
+            .collect();
+        self.git(&args)
+    }
+
     pub fn stash_pop(&self) -> anyhow::Result<()> {
         self.git(&["stash", "pop"])?;
         Ok(())
@@ -239,7 +394,12 @@ impl Repo {
             .as_ref()
             .to_str()
             .ok_or_else(|| anyhow!("invalid path"))?;
-        let commit_list = self.git(&["log", "--format=%H", "-n", &nth_str, "--", path])?;
+        let mut args = vec!["log", "--format=%H", "-n", &nth_str];
+        if self.first_parent {
+            args.push("--first-parent");
+        }
+        args.extend(["--", path]);
+        let commit_list = self.git(&args)?;
         let mut commits = commit_list.lines();
         let last_commit = commits.nth(nth - 1).context("not enough commits")?;
 
@@ -252,13 +412,24 @@ impl Repo {
         self.git(&["log", "-1", "--pretty=format:%B"])
     }
 
+    /// Message of the given git revision, e.g. `HEAD^2`.
+    pub fn commit_message(&self, rev: &str) -> anyhow::Result<String> {
+        self.git(&["log", "-1", "--pretty=format:%B", rev])
+            .with_context(|| format!("cannot find the message of git revision `{rev}`"))
+    }
+
     pub fn current_commit_hash(&self) -> anyhow::Result<String> {
         self.git(&["log", "-1", "--pretty=format:%H"])
     }
 
-    /// Create a git tag
+    /// Create a lightweight git tag
     pub fn tag(&self, name: &str) -> anyhow::Result<String> {
-        self.git(&["tag", name])
+        self.git_as_identity(&tag_args(name, None))
+    }
+
+    /// Create an annotated git tag with the given message
+    pub fn annotated_tag(&self, name: &str, message: &str) -> anyhow::Result<String> {
+        self.git_as_identity(&tag_args(name, Some(message)))
     }
 
     /// Get the commit hash of the given tag
@@ -266,6 +437,19 @@ impl Repo {
         self.git(&["rev-list", "-n", "1", tag]).ok()
     }
 
+    /// Get the commit hash of the given git revision, e.g. a tag, a branch or a commit hash.
+    pub fn resolve_commit(&self, rev: &str) -> anyhow::Result<String> {
+        self.git(&["rev-list", "-n", "1", rev])
+            .with_context(|| format!("cannot find git revision `{rev}`"))
+    }
+
+    /// Committer date of the given git revision, in strict ISO 8601 format,
+    /// e.g. `2024-01-31T10:00:00+01:00`.
+    pub fn commit_date(&self, rev: &str) -> anyhow::Result<String> {
+        self.git(&["log", "-1", "--format=%cI", rev])
+            .with_context(|| format!("cannot find the date of git revision `{rev}`"))
+    }
+
     /// Check if a commit comes before another one.
     ///
     /// ## Example
@@ -293,6 +477,20 @@ impl Repo {
         self.git(&["config", "--get", &param])
     }
 
+    /// Tags reachable from the current commit, from the most recent to the oldest.
+    pub fn tags_merged_into_head(&self) -> anyhow::Result<Vec<String>> {
+        let output = self
+            .git(&["tag", "--merged", "HEAD", "--sort=-creatordate"])
+            .context("cannot list git tags")?;
+        Ok(output.lines().map(|l| l.to_string()).collect())
+    }
+
+    /// All the tags of the repository.
+    pub fn tags(&self) -> anyhow::Result<Vec<String>> {
+        let output = self.git(&["tag", "-l"]).context("cannot list git tags")?;
+        Ok(output.lines().map(|l| l.to_string()).collect())
+    }
+
     pub fn tag_exists(&self, tag: &str) -> anyhow::Result<bool> {
         let output = self
             .git(&["tag", "-l", tag])
@@ -301,6 +499,16 @@ impl Repo {
     }
 }
 
+/// Arguments of the `git tag` command.
+/// If `message` is specified, the tag is annotated.
+/// Lines starting with `#` are kept, because they are markdown headings of the changelog.
+fn tag_args<'a>(name: &'a str, message: Option<&'a str>) -> Vec<&'a str> {
+    match message {
+        Some(message) => vec!["tag", "-a", name, "--cleanup=whitespace", "-m", message],
+        None => vec!["tag", name],
+    }
+}
+
 fn changed_files(output: &str) -> Vec<String> {
     output
         .lines()
@@ -352,6 +560,73 @@ mod tests {
 
     use super::*;
 
+    #[test]
+    fn detached_head_uses_default_branch_of_remote() {
+        test_logs::init();
+        let repository_dir = tempdir().unwrap();
+        let repo = Repo::init(&repository_dir);
+        repo.git(&["update-ref", "refs/remotes/origin/main", "HEAD"])
+            .unwrap();
+        repo.git(&[
+            "symbolic-ref",
+            "refs/remotes/origin/HEAD",
+            "refs/remotes/origin/main",
+        ])
+        .unwrap();
+        repo.git(&["checkout", "--detach"]).unwrap();
+        let repo = Repo::new(&repository_dir).unwrap();
+        assert_eq!(repo.current_branch(), None);
+        assert_eq!(repo.original_branch(), "main");
+    }
+
+    #[test]
+    fn detached_head_is_restored() {
+        test_logs::init();
+        let repository_dir = tempdir().unwrap();
+        let repo = Repo::init(&repository_dir);
+        let file1 = repository_dir.as_ref().join("file1.txt");
+        fs::write(&file1, b"Hello, file1!-1").unwrap();
+        repo.add_all_and_commit("file1-1").unwrap();
+        fs::write(&file1, b"Hello, file1!-2").unwrap();
+        repo.add_all_and_commit("file1-2").unwrap();
+        repo.git(&["checkout", "--detach"]).unwrap();
+        let repo = Repo::new(&repository_dir)
+            .unwrap()
+            .with_original_branch("main");
+        assert_eq!(repo.original_branch(), "main");
+
+        repo.checkout_previous_commit_at_path(&file1).unwrap();
+        assert_eq!(repo.current_commit_message().unwrap(), "file1-1");
+        repo.checkout_head().unwrap();
+        assert_eq!(repo.current_commit_message().unwrap(), "file1-2");
+    }
+
+    #[test]
+    fn tags_are_pushed_to_push_remote() {
+        test_logs::init();
+        let repository_dir = tempdir().unwrap();
+        let fork_dir = tempdir().unwrap();
+        git_in_dir(fork_dir.path(), &["init", "--bare"]).unwrap();
+        let repo = Repo::init(&repository_dir);
+        repo.git(&["remote", "add", "fork", fork_dir.path().to_str().unwrap()])
+            .unwrap();
+        let repo = repo.with_push_remote("fork");
+        assert_eq!(repo.push_remote(), "fork");
+
+        repo.tag("v1.0.0").unwrap();
+        repo.push("v1.0.0").unwrap();
+
+        let fork_tags = git_in_dir(fork_dir.path(), &["tag"]).unwrap();
+        assert_eq!(fork_tags, "v1.0.0");
+    }
+
+    #[test]
+    fn push_remote_defaults_to_original_remote() {
+        let repository_dir = tempdir().unwrap();
+        let repo = Repo::init(&repository_dir);
+        assert_eq!(repo.push_remote(), "origin");
+    }
+
     #[test]
     fn inexistent_previous_commit_detected() {
         let repository_dir = tempdir().unwrap();
@@ -379,6 +654,23 @@ mod tests {
         assert_eq!(repo.current_commit_message().unwrap(), "file2-1");
     }
 
+    #[test]
+    fn merge_commit_is_retrieved_following_first_parent() {
+        test_logs::init();
+        let repository_dir = tempdir().unwrap();
+        let repo = Repo::init(&repository_dir).with_first_parent(true);
+        let file1 = repository_dir.as_ref().join("file1.txt");
+        let default_branch = repo.original_branch().to_string();
+        repo.git(&["checkout", "-b", "feature"]).unwrap();
+        fs::write(&file1, b"Hello, file1!").unwrap();
+        repo.add_all_and_commit("feature commit").unwrap();
+        repo.checkout(&default_branch).unwrap();
+        repo.git(&["merge", "--no-ff", "-m", "Merge feature", "feature"])
+            .unwrap();
+        repo.checkout_last_commit_at_path(&file1).unwrap();
+        assert_eq!(repo.current_commit_message().unwrap(), "Merge feature");
+    }
+
     #[test]
     fn current_commit_is_retrieved() {
         test_logs::init();
@@ -446,6 +738,21 @@ D  crates/git_cmd/CHANGELOG.md
         assert!(repo.tag_exists(version).unwrap())
     }
 
+    #[test]
+    fn commit_date_is_retrieved() {
+        test_logs::init();
+        let repository_dir = tempdir().unwrap();
+        let repo = Repo::init(&repository_dir);
+        fs::write(repository_dir.as_ref().join("file1.txt"), b"Hello, file1!").unwrap();
+        repo.add_all_and_commit("file1").unwrap();
+        repo.tag("v1.0.0").unwrap();
+        let date = repo.commit_date("v1.0.0").unwrap();
+        assert_eq!(date, repo.commit_date("HEAD").unwrap());
+        // e.g. `2024-01-31T10:00:00+01:00`
+        assert_eq!(date.len(), 25, "{date}");
+        assert_eq!(&date[10..11], "T");
+    }
+
     #[test]
     fn non_existing_tag_is_recognized() {
         test_logs::init();
@@ -459,4 +766,176 @@ D  crates/git_cmd/CHANGELOG.md
         repo.tag("v1.0.0").unwrap();
         assert!(!repo.tag_exists("v2.0.0").unwrap())
     }
+
+    #[test]
+    fn annotated_tag_args_contain_message() {
+        assert_eq!(
+            tag_args("v1.0.0", Some("my-pkg 1.0.0")),
+            vec![
+                "tag",
+                "-a",
+                "v1.0.0",
+                "--cleanup=whitespace",
+                "-m",
+                "my-pkg 1.0.0"
+            ]
+        );
+    }
+
+    #[test]
+    fn lightweight_tag_args_skip_message() {
+        assert_eq!(tag_args("v1.0.0", None), vec!["tag", "v1.0.0"]);
+    }
+
+    #[test]
+    fn annotated_tag_is_created() {
+        test_logs::init();
+        let repository_dir = tempdir().unwrap();
+        let repo = Repo::init(&repository_dir);
+        let file1 = repository_dir.as_ref().join("file1.txt");
+        fs::write(file1, b"Hello, file1!").unwrap();
+        repo.add_all_and_commit("file1").unwrap();
+        repo.annotated_tag("v1.0.0", "my-pkg 1.0.0").unwrap();
+        repo.tag("v2.0.0").unwrap();
+        assert_eq!(repo.git(&["cat-file", "-t", "v1.0.0"]).unwrap(), "tag");
+        assert_eq!(repo.git(&["cat-file", "-t", "v2.0.0"]).unwrap(), "commit");
+        assert_eq!(
+            repo.git(&["tag", "-l", "--format=%(contents)", "v1.0.0"])
+                .unwrap(),
+            "my-pkg 1.0.0"
+        );
+    }
+
+    #[test]
+    fn annotated_tag_message_keeps_markdown_headings() {
+        test_logs::init();
+        let repository_dir = tempdir().unwrap();
+        let repo = Repo::init(&repository_dir);
+        let file1 = repository_dir.as_ref().join("file1.txt");
+        fs::write(file1, b"Hello, file1!").unwrap();
+        repo.add_all_and_commit("file1").unwrap();
+        let message = "my-pkg 1.0.0\n\n### Fixed\n- myfix";
+        repo.annotated_tag("v1.0.0", message).unwrap();
+        assert_eq!(
+            repo.git(&["tag", "-l", "--format=%(contents)", "v1.0.0"])
+                .unwrap(),
+            message
+        );
+    }
+
+    #[test]
+    fn files_of_commit_are_listed() {
+        test_logs::init();
+        let repository_dir = tempdir().unwrap();
+        let repo = Repo::init(&repository_dir);
+        let src = repository_dir.as_ref().join("src");
+        fs::create_dir(&src).unwrap();
+        fs::write(src.join("lib.rs"), b"").unwrap();
+        fs::write(repository_dir.as_ref().join("file1.txt"), b"Hello, file1!").unwrap();
+        repo.add_all_and_commit("add files").unwrap();
+        let commit = repo.current_commit_hash().unwrap();
+        assert_eq!(
+            repo.commit_files(&commit).unwrap(),
+            ["file1.txt", "src/lib.rs"]
+        );
+    }
+
+    #[test]
+    fn inexistent_revision_is_not_resolved() {
+        test_logs::init();
+        let repository_dir = tempdir().unwrap();
+        let repo = Repo::init(&repository_dir);
+        assert!(repo.resolve_commit("inexistent").is_err());
+        assert_eq!(
+            repo.resolve_commit("HEAD").unwrap(),
+            repo.current_commit_hash().unwrap()
+        );
+    }
+
+    #[test]
+    fn only_tags_merged_into_head_are_listed() {
+        test_logs::init();
+        let repository_dir = tempdir().unwrap();
+        let repo = Repo::init(&repository_dir);
+        let file1 = repository_dir.as_ref().join("file1.txt");
+        fs::write(&file1, b"Hello, file1!").unwrap();
+        repo.add_all_and_commit("file1").unwrap();
+        repo.tag("v1.0.0").unwrap();
+        let first_commit = repo.current_commit_hash().unwrap();
+        fs::write(&file1, b"Hello again, file1!").unwrap();
+        repo.add_all_and_commit("file1 again").unwrap();
+        repo.tag("v2.0.0").unwrap();
+        repo.checkout(&first_commit).unwrap();
+        assert_eq!(repo.tags_merged_into_head().unwrap(), vec!["v1.0.0"]);
+    }
+
+    #[test]
+    fn identity_is_passed_as_git_config() {
+        let identity = GitIdentity {
+            name: Some("release-plz".to_string()),
+            email: Some("release-plz@example.com".to_string()),
+        };
+        assert_eq!(
+            identity.config_args(),
+            [
+                "-c",
+                "user.name=release-plz",
+                "-c",
+                "user.email=release-plz@example.com"
+            ]
+        );
+        let identity = GitIdentity {
+            name: None,
+            email: Some("release-plz@example.com".to_string()),
+        };
+        assert_eq!(
+            identity.config_args(),
+            ["-c", "user.email=release-plz@example.com"]
+        );
+        assert!(GitIdentity::default().config_args().is_empty());
+    }
+
+    #[test]
+    fn commit_and_tag_use_configured_identity() {
+        test_logs::init();
+        let repository_dir = tempdir().unwrap();
+        let repo = Repo::init(&repository_dir).with_identity(GitIdentity {
+            name: Some("release-plz".to_string()),
+            email: Some("release-plz@example.com".to_string()),
+        });
+        fs::write(repository_dir.as_ref().join("file1.txt"), b"Hello").unwrap();
+        repo.add_all_and_commit("file1").unwrap();
+        assert_eq!(
+            repo.git(&["log", "-1", "--format=%an <%ae>, %cn <%ce>"])
+                .unwrap(),
+            "release-plz <release-plz@example.com>, release-plz <release-plz@example.com>"
+        );
+        repo.annotated_tag("v1.0.0", "release").unwrap();
+        assert_eq!(
+            repo.git(&[
+                "tag",
+                "-l",
+                "v1.0.0",
+                "--format=%(taggername) %(taggeremail)"
+            ])
+            .unwrap(),
+            "release-plz <release-plz@example.com>"
+        );
+    }
+
+    #[test]
+    fn commit_uses_git_config_without_identity() {
+        test_logs::init();
+        let repository_dir = tempdir().unwrap();
+        let repo = Repo::init(&repository_dir).with_identity(GitIdentity {
+            name: Some("release-plz".to_string()),
+            email: None,
+        });
+        fs::write(repository_dir.as_ref().join("file1.txt"), b"Hello").unwrap();
+        repo.add_all_and_commit("file1").unwrap();
+        assert_eq!(
+            repo.git(&["log", "-1", "--format=%an <%ae>"]).unwrap(),
+            "release-plz <author@example.com>"
+        );
+    }
 }
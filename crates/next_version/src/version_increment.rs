@@ -3,7 +3,7 @@ use semver::Version;
 
 use crate::{NextVersion, VersionUpdater};
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum VersionIncrement {
     Major,
     Minor,
@@ -31,7 +31,9 @@ impl VersionIncrement {
         Self::from_commits_with_updater(&updater, current_version, commits)
     }
 
-    pub(crate) fn from_commits_with_updater<I>(
+    /// Same as [`VersionIncrement::from_commits`], but using the rules of the given
+    /// [`VersionUpdater`].
+    pub fn from_commits_with_updater<I>(
         updater: &VersionUpdater,
         current_version: &Version,
         commits: I,
@@ -93,13 +95,22 @@ impl VersionIncrement {
         commits: &[ConventionalCommit],
         updater: &VersionUpdater,
     ) -> Self {
+        let custom_increment = |commit: &ConventionalCommit| {
+            updater
+                .commit_type_increments
+                .get(commit.commit_type.as_ref())
+        };
+
         let is_there_a_feature = || {
-            commits
-                .iter()
-                .any(|commit| commit.commit_type == CommitType::Feature)
+            commits.iter().any(|commit| match custom_increment(commit) {
+                Some(increment) => *increment == Self::Minor,
+                None => commit.commit_type == CommitType::Feature,
+            })
         };
 
-        let is_there_a_breaking_change = commits.iter().any(|commit| commit.is_breaking_change);
+        let is_there_a_breaking_change = commits.iter().any(|commit| {
+            commit.is_breaking_change || custom_increment(commit) == Some(&Self::Major)
+        });
 
         let is_major_bump = || {
             is_there_a_breaking_change
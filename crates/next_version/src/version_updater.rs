@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use semver::Version;
 
 use crate::VersionIncrement;
@@ -24,6 +26,7 @@ use crate::VersionIncrement;
 pub struct VersionUpdater {
     pub(crate) features_always_increment_minor: bool,
     pub(crate) breaking_always_increment_major: bool,
+    pub(crate) commit_type_increments: HashMap<String, VersionIncrement>,
 }
 
 impl Default for VersionUpdater {
@@ -54,6 +57,7 @@ impl VersionUpdater {
         Self {
             features_always_increment_minor: false,
             breaking_always_increment_major: false,
+            commit_type_increments: HashMap::new(),
         }
     }
 
@@ -128,6 +132,44 @@ impl VersionUpdater {
         self
     }
 
+    /// Configures the increment triggered by the commits of the given types,
+    /// overriding the default rules, e.g. to treat `perf` commits like features.
+    ///
+    /// - [`VersionIncrement::Major`]: the commit is treated as a breaking change.
+    /// - [`VersionIncrement::Minor`]: the commit is treated as a feature.
+    /// - [`VersionIncrement::Patch`] or [`VersionIncrement::Prerelease`]: the commit is treated
+    ///   as a fix, unless it contains a breaking change.
+    ///
+    /// Commits of types that aren't listed follow the default rules.
+    ///
+    /// ```rust
+    /// use std::collections::HashMap;
+    /// use semver::Version;
+    /// use next_version::{VersionIncrement, VersionUpdater};
+    ///
+    /// let commits = ["perf: faster coffee"];
+    /// let version = Version::new(1, 2, 3);
+    /// let increments = HashMap::from([("perf".to_string(), VersionIncrement::Minor)]);
+    /// assert_eq!(
+    ///     VersionUpdater::new()
+    ///         .with_commit_type_increments(increments)
+    ///         .increment(&version, &commits),
+    ///     Version::new(1, 3, 0)
+    /// );
+    /// assert_eq!(
+    ///     VersionUpdater::new()
+    ///         .increment(&version, &commits),
+    ///     Version::new(1, 2, 4)
+    /// );
+    /// ```
+    pub fn with_commit_type_increments(
+        mut self,
+        commit_type_increments: HashMap<String, VersionIncrement>,
+    ) -> Self {
+        self.commit_type_increments = commit_type_increments;
+        self
+    }
+
     /// Analyze commits and determine the next version.
     pub fn increment<I>(self, version: &Version, commits: I) -> Version
     where
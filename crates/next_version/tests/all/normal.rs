@@ -1,4 +1,6 @@
-use next_version::{NextVersion, VersionUpdater};
+use std::collections::HashMap;
+
+use next_version::{NextVersion, VersionIncrement, VersionUpdater};
 use semver::Version;
 
 #[test]
@@ -68,3 +70,40 @@ fn commit_with_breaking_change_increments_major_version_when_major_is_zero() {
         Version::new(1, 0, 0)
     );
 }
+
+#[test]
+fn custom_commit_type_increments_override_default_rules() {
+    let increments = HashMap::from([
+        ("perf".to_string(), VersionIncrement::Minor),
+        ("feat".to_string(), VersionIncrement::Patch),
+    ]);
+    let updater = || VersionUpdater::new().with_commit_type_increments(increments.clone());
+    let version = Version::new(1, 2, 3);
+    assert_eq!(
+        updater().increment(&version, ["perf: faster coffee"]),
+        Version::new(1, 3, 0)
+    );
+    assert_eq!(
+        updater().increment(&version, ["feat: make coffee"]),
+        Version::new(1, 2, 4)
+    );
+    assert_eq!(
+        updater().increment(&version, ["feat!: remove coffee"]),
+        Version::new(2, 0, 0)
+    );
+}
+
+#[test]
+fn unlisted_commit_types_follow_default_rules() {
+    let increments = HashMap::from([("perf".to_string(), VersionIncrement::Minor)]);
+    let updater = || VersionUpdater::new().with_commit_type_increments(increments.clone());
+    let version = Version::new(1, 2, 3);
+    assert_eq!(
+        updater().increment(&version, ["feat: make coffee"]),
+        Version::new(1, 3, 0)
+    );
+    assert_eq!(
+        updater().increment(&version, ["fix: serious bug"]),
+        Version::new(1, 2, 4)
+    );
+}
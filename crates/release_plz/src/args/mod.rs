@@ -59,53 +59,31 @@ fn local_manifest(project_manifest: Option<&Path>) -> PathBuf {
     }
 }
 
-fn parse_config(config_path: Option<&Path>) -> anyhow::Result<Config> {
-    let (config, path) = if let Some(config_path) = config_path {
-        match std::fs::read_to_string(config_path) {
-            Ok(config) => (config, config_path),
-            Err(e) => match e.kind() {
-                std::io::ErrorKind::NotFound => {
-                    anyhow::bail!("specified config does not exist at path {config_path:?}")
-                }
-                _ => anyhow::bail!("can't read {config_path:?}: {e:?}"),
-            },
-        }
-    } else {
-        match first_file_contents([
-            Path::new("release-plz.toml"),
-            Path::new(".release-plz.toml"),
-        ])? {
-            Some((config, path)) => (config, path),
-            None => {
-                info!("release-plz config file not found, using default configuration");
-                return Ok(Config::default());
-            }
+/// Read the configuration file.
+/// If `ignore_package_overrides` is true, the `[[package]]` sections are ignored.
+fn parse_config(
+    config_path: Option<&Path>,
+    ignore_package_overrides: bool,
+) -> anyhow::Result<Config> {
+    let (config, path) = match config_path {
+        Some(config_path) => (Config::read(config_path)?, Some(config_path.to_path_buf())),
+        None => {
+            let current_dir =
+                std::env::current_dir().context("cannot retrieve current directory")?;
+            Config::discover(&current_dir)?
         }
     };
+    let Some(path) = path else {
+        info!("release-plz config file not found, using default configuration");
+        return Ok(config);
+    };
 
     info!("using release-plz config file {}", path.display());
-    toml::from_str(&config).with_context(|| format!("invalid config file {config_path:?}"))
-}
-
-/// Returns the contents of the first file that exists.
-///
-/// If none of the files exist, returns `Ok(None)`.
-///
-/// # Errors
-///
-/// Errors if opening and reading one of files paths fails for reasons other that it doesn't exist.
-fn first_file_contents<'a>(
-    paths: impl IntoIterator<Item = &'a Path>,
-) -> anyhow::Result<Option<(String, &'a Path)>> {
-    let paths = paths.into_iter();
-
-    for path in paths {
-        match std::fs::read_to_string(path) {
-            Ok(config) => return Ok(Some((config, path))),
-            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
-            Err(err) => return Err(err.into()),
-        }
-    }
-
-    Ok(None)
+    let config = if ignore_package_overrides {
+        info!("ignoring the `[[package]]` sections of the config file");
+        config.without_package_overrides()
+    } else {
+        config
+    };
+    Ok(config)
 }
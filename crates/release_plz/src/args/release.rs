@@ -31,11 +31,13 @@ pub struct Release {
     #[arg(long)]
     pub dry_run: bool,
     /// Don't verify the contents by building them.
-    /// When you pass this flag, `release-plz` adds the `--no-verify` flag to `cargo publish`.
+    /// When you pass this flag, `release-plz` adds the `--no-verify` flag to `cargo publish`,
+    /// unless the `[[package]]` section of the configuration sets `publish_no_verify`.
     #[arg(long)]
     pub no_verify: bool,
     /// Allow dirty working directories to be packaged.
-    /// When you pass this flag, `release-plz` adds the `--allow-dirty` flag to `cargo publish`.
+    /// When you pass this flag, `release-plz` adds the `--allow-dirty` flag to `cargo publish`,
+    /// unless the `[[package]]` section of the configuration sets `publish_allow_dirty`.
     #[arg(long)]
     pub allow_dirty: bool,
     /// GitHub/Gitea/Gitlab repository url where your project is hosted.
@@ -58,6 +60,11 @@ pub struct Release {
         value_parser = PathBufValueParser::new()
     )]
     config: Option<PathBuf>,
+    /// Ignore the `[[package]]` sections of the config file: every package uses
+    /// the `[workspace]` defaults.
+    /// Useful to check whether a package-specific setting causes an unexpected behavior.
+    #[arg(long)]
+    ignore_package_overrides: bool,
 }
 
 #[derive(ValueEnum, Clone, Copy, Debug, Eq, PartialEq)]
@@ -72,7 +79,7 @@ pub enum ReleaseGitBackendKind {
 
 impl Release {
     pub fn config(&self) -> anyhow::Result<Config> {
-        super::parse_config(self.config.as_deref())
+        super::parse_config(self.config.as_deref(), self.ignore_package_overrides)
     }
 
     pub fn release_request(
@@ -80,21 +87,24 @@ impl Release {
         config: Config,
         metadata: cargo_metadata::Metadata,
     ) -> anyhow::Result<ReleaseRequest> {
-        let git_release = if let Some(git_token) = &self.git_token {
-            let git_token = SecretString::from(git_token.clone());
-            let repo_url = self.get_repo_url(&config)?;
+        let git_token = self
+            .git_token
+            .clone()
+            .or_else(|| config.workspace.git_token_from_env());
+        let git_release = if let Some(git_token) = git_token {
+            let git_token = SecretString::from(git_token);
+            let repo_url = self.get_repo_url(&config, &std::env::vars().collect())?;
+            let backend = match self.backend {
+                ReleaseGitBackendKind::Gitea => GitBackend::Gitea(Gitea::new(repo_url, git_token)?),
+                ReleaseGitBackendKind::Github => {
+                    GitBackend::Github(GitHub::new(repo_url.owner, repo_url.name, git_token))
+                }
+                ReleaseGitBackendKind::Gitlab => {
+                    GitBackend::Gitlab(GitLab::new(repo_url.owner, repo_url.name, git_token))
+                }
+            };
             let release = release_plz_core::GitRelease {
-                backend: match self.backend {
-                    ReleaseGitBackendKind::Gitea => {
-                        GitBackend::Gitea(Gitea::new(repo_url, git_token)?)
-                    }
-                    ReleaseGitBackendKind::Github => {
-                        GitBackend::Github(GitHub::new(repo_url.owner, repo_url.name, git_token))
-                    }
-                    ReleaseGitBackendKind::Gitlab => {
-                        GitBackend::Gitlab(GitLab::new(repo_url.owner, repo_url.name, git_token))
-                    }
-                },
+                backend: config.workspace.configure_git_backend(backend)?,
             };
             Some(release)
         } else {
@@ -116,6 +126,29 @@ impl Release {
         }
 
         req = req.with_publish_timeout(config.workspace.publish_timeout()?);
+        req = req.with_publish_require_token(config.workspace.publish_require_token == Some(true));
+        if let Some(token_env) = &config.workspace.publish_token_env {
+            req = req.with_publish_token_env(token_env.clone().into_iter().collect());
+        }
+        req = req.with_git_identity(config.workspace.git_identity());
+        req = req.with_git_push_remote(config.workspace.git_push_remote.clone());
+        req = req.with_idempotent(config.workspace.idempotent == Some(true));
+        req = req.with_verbose_plan(config.workspace.verbose_plan == Some(true));
+        if let Some(branches) = &config.workspace.git_release_branches {
+            req = req.with_git_release_branches(branches)?;
+        }
+        if let Some(trailer) = &config.workspace.git_release_type_trailer {
+            req = req.with_release_type_trailer(trailer);
+        }
+        if let Some(scope) = config.workspace.publish_timeout_scope {
+            req = req.with_publish_timeout_scope(scope.into());
+        }
+        if let Some(changelog_format) = config.workspace.changelog_format {
+            req = req.with_changelog_format(changelog_format.into());
+        }
+        if let Some(on_error) = config.workspace.on_error {
+            req = req.with_on_error(on_error.into());
+        }
 
         req = config.fill_release_config(self.allow_dirty, self.no_verify, req);
 
@@ -182,6 +215,23 @@ mod tests {
         assert!(actual_request.no_verify("aaa"));
     }
 
+    #[test]
+    fn release_request_without_package_overrides_uses_workspace_defaults() {
+        let config = r#"
+            [workspace]
+            publish_allow_dirty = false
+
+            [[package]]
+            name = "aaa"
+            publish_allow_dirty = true
+        "#;
+        let config: Config = toml::from_str(config).unwrap();
+        let request = default_args()
+            .release_request(config.without_package_overrides(), fake_metadata())
+            .unwrap();
+        assert!(!request.allow_dirty("aaa"));
+    }
+
     fn default_args() -> Release {
         Release {
             allow_dirty: false,
@@ -194,6 +244,7 @@ mod tests {
             git_token: None,
             backend: ReleaseGitBackendKind::Github,
             config: None,
+            ignore_package_overrides: false,
         }
     }
 
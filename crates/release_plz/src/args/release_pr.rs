@@ -6,6 +6,8 @@ use clap::ValueEnum;
 use release_plz_core::{GitBackend, GitHub, Gitea, RepoUrl};
 use secrecy::SecretString;
 
+use crate::config::Config;
+
 use super::update::Update;
 
 #[derive(clap::Parser, Debug)]
@@ -13,8 +15,10 @@ pub struct ReleasePr {
     #[command(flatten)]
     pub update: Update,
     /// Git token used to create the pull request.
+    /// If not provided, the token is read from the environment variable
+    /// specified in the `git_token_env` config field.
     #[arg(long, value_parser = NonEmptyStringValueParser::new(), visible_alias = "github-token", env, hide_env_values=true)]
-    git_token: String,
+    git_token: Option<String>,
     /// Kind of git host where your project is hosted.
     #[arg(long, value_enum, default_value_t = GitBackendKind::Github)]
     backend: GitBackendKind,
@@ -29,8 +33,16 @@ pub enum GitBackendKind {
 }
 
 impl ReleasePr {
-    pub fn git_backend(&self, repo: RepoUrl) -> anyhow::Result<GitBackend> {
-        let token = SecretString::from_str(&self.git_token).context("Invalid git backend token")?;
+    pub fn git_token(&self, config: &Config) -> anyhow::Result<SecretString> {
+        let token = self
+            .git_token
+            .clone()
+            .or_else(|| config.workspace.git_token_from_env())
+            .context("git token not found. Pass it with `--git-token` or set the `git_token_env` config field")?;
+        SecretString::from_str(&token).context("Invalid git backend token")
+    }
+
+    pub fn git_backend(&self, repo: RepoUrl, token: SecretString) -> anyhow::Result<GitBackend> {
         Ok(match self.backend {
             GitBackendKind::Github => {
                 anyhow::ensure!(
@@ -1,7 +1,12 @@
-use std::path::{Path, PathBuf};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
 
+use cargo_metadata::Package;
 use git_cmd::Repo;
 use release_plz_core::RepoUrl;
+use tracing::debug;
 
 use crate::config::Config;
 
@@ -19,16 +24,39 @@ pub trait RepoCommand {
         cargo_utils::get_manifest_metadata(&self.project_manifest())
     }
 
-    fn get_repo_url(&self, config: &Config) -> anyhow::Result<RepoUrl> {
-        match &self.user_repo_url(config) {
-            Some(url) => RepoUrl::new(url),
-            None => {
-                let project_manifest = self.project_manifest();
-                let project_dir = release_plz_core::manifest_dir(&project_manifest)?;
-                let repo = Repo::new(project_dir)?;
-                RepoUrl::from_repo(&repo)
-            }
+    /// Repo url resolution order:
+    /// 1. url specified by the user.
+    /// 2. url of the repository where the CI is running.
+    /// 3. url of the default git remote.
+    /// 4. `repository` field of the `Cargo.toml` of the workspace packages.
+    ///
+    /// `env` contains the environment variables, where the CI repository is read from.
+    fn get_repo_url(
+        &self,
+        config: &Config,
+        env: &HashMap<String, String>,
+    ) -> anyhow::Result<RepoUrl> {
+        if let Some(url) = self.user_repo_url(config) {
+            return RepoUrl::new(url);
         }
+        resolve_repo_url(|| self.git_remote_repo_url(), env).or_else(|remote_error| {
+            let metadata = self.cargo_metadata()?;
+            match manifest_repo_url(&metadata.workspace_packages()) {
+                Some(repo_url) => {
+                    debug!("cannot use the git remote as repo url, using the `repository` field of Cargo.toml: {remote_error:#}");
+                    Ok(repo_url)
+                }
+                None => Err(remote_error),
+            }
+        })
+    }
+
+    /// Url of the default git remote.
+    fn git_remote_repo_url(&self) -> anyhow::Result<RepoUrl> {
+        let project_manifest = self.project_manifest();
+        let project_dir = release_plz_core::manifest_dir(&project_manifest)?;
+        let repo = Repo::new(project_dir)?;
+        RepoUrl::from_repo(&repo)
     }
 
     /// Repo url specified by user
@@ -37,3 +65,197 @@ pub trait RepoCommand {
             .or_else(|| config.workspace.repo_url.as_ref().map(|u| u.as_str()))
     }
 }
+
+/// Url of the repository where the CI is running or, if the CI repository is unknown,
+/// url of the default git remote.
+/// The CI repository has precedence because the git remote of the CI checkout
+/// can be misconfigured.
+fn resolve_repo_url(
+    git_remote_repo_url: impl FnOnce() -> anyhow::Result<RepoUrl>,
+    env: &HashMap<String, String>,
+) -> anyhow::Result<RepoUrl> {
+    match ci_repo_url(env) {
+        Some(url) => {
+            debug!("using the repository of the CI as repo url: {url}");
+            RepoUrl::new(&url)
+        }
+        None => git_remote_repo_url(),
+    }
+}
+
+/// First valid `repository` field of the manifests of the `packages`.
+fn manifest_repo_url(packages: &[&Package]) -> Option<RepoUrl> {
+    packages
+        .iter()
+        .filter_map(|package| package.repository.as_deref())
+        .find_map(|repository| RepoUrl::new(repository).ok())
+}
+
+/// Url of the repository where the CI is running, read from the CI environment variables.
+/// Supports GitHub Actions (and compatible CIs, like Gitea Actions) and GitLab CI.
+fn ci_repo_url(env: &HashMap<String, String>) -> Option<String> {
+    if let Some(repository) = env.get("GITHUB_REPOSITORY") {
+        let server_url = env
+            .get("GITHUB_SERVER_URL")
+            .map(|url| url.trim_end_matches('/'))
+            .unwrap_or("https://github.com");
+        return Some(format!("{server_url}/{repository}"));
+    }
+    env.get("CI_PROJECT_URL").cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env(vars: &[(&str, &str)]) -> HashMap<String, String> {
+        vars.iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn github_repo_url_is_read_from_env() {
+        let env = env(&[
+            ("GITHUB_SERVER_URL", "https://github.com"),
+            ("GITHUB_REPOSITORY", "MarcoIeni/release-plz"),
+        ]);
+        assert_eq!(
+            ci_repo_url(&env).as_deref(),
+            Some("https://github.com/MarcoIeni/release-plz")
+        );
+    }
+
+    #[test]
+    fn github_server_url_defaults_to_github() {
+        let env = env(&[("GITHUB_REPOSITORY", "MarcoIeni/release-plz")]);
+        assert_eq!(
+            ci_repo_url(&env).as_deref(),
+            Some("https://github.com/MarcoIeni/release-plz")
+        );
+    }
+
+    #[test]
+    fn gitlab_repo_url_is_read_from_env() {
+        let env = env(&[("CI_PROJECT_URL", "https://gitlab.com/MarcoIeni/release-plz")]);
+        assert_eq!(
+            ci_repo_url(&env).as_deref(),
+            Some("https://gitlab.com/MarcoIeni/release-plz")
+        );
+    }
+
+    fn no_git_remote() -> anyhow::Result<RepoUrl> {
+        Err(anyhow::anyhow!("no git remote"))
+    }
+
+    #[test]
+    fn ci_repo_url_is_used_when_git_remote_is_unavailable() {
+        let env = env(&[("CI_PROJECT_URL", "https://gitlab.com/MarcoIeni/release-plz")]);
+        let repo_url = resolve_repo_url(no_git_remote, &env).unwrap();
+        assert_eq!(
+            repo_url.web_url(),
+            "https://gitlab.com/MarcoIeni/release-plz"
+        );
+    }
+
+    #[test]
+    fn ci_repo_url_has_precedence_over_git_remote() {
+        let env = env(&[("GITHUB_REPOSITORY", "MarcoIeni/release-plz")]);
+        let git_remote = || RepoUrl::new("https://github.com/misconfigured/remote");
+        let repo_url = resolve_repo_url(git_remote, &env).unwrap();
+        assert_eq!(
+            repo_url.web_url(),
+            "https://github.com/MarcoIeni/release-plz"
+        );
+    }
+
+    #[test]
+    fn git_remote_is_used_outside_ci() {
+        let git_remote = || RepoUrl::new("https://github.com/owner/repo");
+        let repo_url = resolve_repo_url(git_remote, &HashMap::new()).unwrap();
+        assert_eq!(repo_url.web_url(), "https://github.com/owner/repo");
+    }
+
+    #[test]
+    fn git_remote_error_is_returned_without_ci_repo_url() {
+        let error = resolve_repo_url(no_git_remote, &HashMap::new()).unwrap_err();
+        assert_eq!(error.to_string(), "no git remote");
+    }
+
+    fn package_with_repository(name: &str, repository: Option<&str>) -> Package {
+        let mut package: Package = fake_package::FakePackage::new(name).into();
+        package.repository = repository.map(String::from);
+        package
+    }
+
+    #[test]
+    fn manifest_repository_is_used_as_repo_url() {
+        let packages = [
+            package_with_repository("a", None),
+            package_with_repository("b", Some("file:///tmp/b")),
+            package_with_repository("c", Some("https://github.com/owner/repo")),
+        ];
+        let repo_url = manifest_repo_url(&packages.iter().collect::<Vec<_>>()).unwrap();
+        assert_eq!(repo_url.web_url(), "https://github.com/owner/repo");
+    }
+
+    #[test]
+    fn no_repo_url_without_manifest_repository() {
+        let packages = [package_with_repository("a", None)];
+        assert!(manifest_repo_url(&packages.iter().collect::<Vec<_>>()).is_none());
+    }
+
+    struct Command {
+        manifest: PathBuf,
+    }
+
+    impl RepoCommand for Command {
+        fn optional_project_manifest(&self) -> Option<&Path> {
+            Some(&self.manifest)
+        }
+
+        fn repo_url(&self) -> Option<&str> {
+            None
+        }
+    }
+
+    /// Command acting on a package with the `repository` field, in a directory that
+    /// isn't a git repository, so that the git remote can't be read.
+    fn command_with_manifest_repository(dir: &Path) -> Command {
+        let manifest = dir.join("Cargo.toml");
+        std::fs::write(
+            &manifest,
+            "[package]\nname = \"aaa\"\nversion = \"0.1.0\"\nedition = \"2021\"\nrepository = \"https://github.com/owner/repo\"\n",
+        )
+        .unwrap();
+        std::fs::create_dir(dir.join("src")).unwrap();
+        std::fs::write(dir.join("src/lib.rs"), "").unwrap();
+        Command { manifest }
+    }
+
+    #[test]
+    fn manifest_repository_is_used_when_git_remote_is_unavailable() {
+        let dir = tempfile::tempdir().unwrap();
+        let command = command_with_manifest_repository(dir.path());
+        let repo_url = command
+            .get_repo_url(&Config::default(), &HashMap::new())
+            .unwrap();
+        assert_eq!(repo_url.web_url(), "https://github.com/owner/repo");
+    }
+
+    #[test]
+    fn config_repo_url_has_precedence_over_manifest_repository() {
+        let dir = tempfile::tempdir().unwrap();
+        let command = command_with_manifest_repository(dir.path());
+        let config: Config =
+            toml::from_str("[workspace]\nrepo_url = \"https://github.com/owner/config-repo\"")
+                .unwrap();
+        let repo_url = command.get_repo_url(&config, &HashMap::new()).unwrap();
+        assert_eq!(repo_url.web_url(), "https://github.com/owner/config-repo");
+    }
+
+    #[test]
+    fn no_repo_url_outside_ci() {
+        assert_eq!(ci_repo_url(&env(&[("HOME", "/home/me")])), None);
+    }
+}
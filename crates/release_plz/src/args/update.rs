@@ -1,10 +1,14 @@
-use std::path::{Path, PathBuf};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
 
 use anyhow::Context;
 use chrono::NaiveDate;
 use clap::builder::{NonEmptyStringValueParser, PathBufValueParser};
 use git_cliff_core::config::Config as GitCliffConfig;
-use release_plz_core::{ChangelogRequest, UpdateRequest};
+use regex::Regex;
+use release_plz_core::{ChangelogRequest, RepoUrl, UpdateRequest};
 
 use crate::config::Config;
 
@@ -61,7 +65,8 @@ pub struct Update {
     #[arg(short, long)]
     update_deps: bool,
     /// Path to the git-cliff configuration file.
-    /// If not provided, `dirs::config_dir()/git-cliff/cliff.toml` is used if present.
+    /// If not provided, the `cliff.toml` or `git-cliff.toml` file of the workspace root is used
+    /// if present. Otherwise, `dirs::config_dir()/git-cliff/cliff.toml` is used if present.
     #[arg(
         long,
         env = "GIT_CLIFF_CONFIG",
@@ -88,6 +93,18 @@ pub struct Update {
         value_parser = PathBufValueParser::new()
     )]
     config: Option<PathBuf>,
+    /// Print the configuration resolved by release-plz, without updating the project.
+    /// Every package of the workspace is listed with its effective settings.
+    #[arg(long)]
+    pub print_config: bool,
+    /// Print a unified diff of the changes to the changelogs, without updating the project.
+    #[arg(long)]
+    changelog_diff: bool,
+    /// Ignore the `[[package]]` sections of the config file: every package uses
+    /// the `[workspace]` defaults.
+    /// Useful to check whether a package-specific setting causes an unexpected behavior.
+    #[arg(long)]
+    ignore_package_overrides: bool,
 }
 
 impl RepoCommand for Update {
@@ -102,7 +119,7 @@ impl RepoCommand for Update {
 
 impl Update {
     pub fn config(&self) -> anyhow::Result<Config> {
-        super::parse_config(self.config.as_deref())
+        super::parse_config(self.config.as_deref(), self.ignore_package_overrides)
     }
 
     fn dependencies_update(&self, config: &Config) -> bool {
@@ -113,23 +130,63 @@ impl Update {
         self.allow_dirty || config.workspace.allow_dirty == Some(true)
     }
 
+    pub fn changelog_diff(&self, config: &Config) -> bool {
+        self.changelog_diff || config.workspace.changelog_diff == Some(true)
+    }
+
     pub fn update_request(
         &self,
-        config: Config,
+        config: &Config,
         cargo_metadata: cargo_metadata::Metadata,
     ) -> anyhow::Result<UpdateRequest> {
         let project_manifest = self.project_manifest();
+        let workspace_root = cargo_metadata.workspace_root.clone();
         let mut update = UpdateRequest::new(cargo_metadata)
             .with_context(|| {
                 format!("Cannot find file {project_manifest:?}. Make sure you are inside a rust project or that --project-manifest points to a valid Cargo.toml file.")
             })?
-            .with_dependencies_update(self.dependencies_update(&config))
-            .with_allow_dirty(self.allow_dirty(&config));
-        match self.get_repo_url(&config) {
+            .with_dependencies_update(self.dependencies_update(config))
+            .with_lockfile_update(config.workspace.update_lockfile != Some(false))
+            .with_allow_dirty(self.allow_dirty(config));
+        match self.get_repo_url(config, &std::env::vars().collect()) {
             Ok(repo_url) => {
                 update = update.with_repo_url(repo_url);
             }
-            Err(e) => tracing::warn!("Cannot determine repo url. The changelog won't contain the release link. Error: {:?}", e),
+            Err(e) => tracing::warn!("Cannot determine repo url: the changelog won't contain the release links. Error: {e:#}"),
+        }
+        if let Some(jobs) = config.workspace.jobs {
+            anyhow::ensure!(jobs > 0, "jobs must be greater than 0");
+            update = update.with_jobs(jobs);
+        }
+        if let Some(release_commit_marker) = &config.workspace.release_commit_marker {
+            update = update.with_release_commit_marker(release_commit_marker);
+        }
+        update = update.with_verbose_plan(config.workspace.verbose_plan == Some(true));
+        if let Some(skip_yanked_baseline) = config.workspace.skip_yanked_baseline {
+            update = update.with_skip_yanked_baseline(skip_yanked_baseline);
+        }
+        update = update
+            .with_changelog_credit_authors(config.workspace.changelog_credit_authors == Some(true));
+        if let Some(semver_check_missing) = config.workspace.semver_check_missing {
+            update = update.with_semver_check_missing(semver_check_missing.into());
+        }
+        if let Some(workspace_changelog_path) = &config.workspace.workspace_changelog_path {
+            update = update.with_workspace_changelog_path(workspace_changelog_path.clone());
+        }
+        if let Some(line_ending) = config.workspace.changelog_line_ending {
+            update = update.with_changelog_line_ending(line_ending.into());
+        }
+        if let Some(version_bump_rules) = &config.workspace.version_bump_rules {
+            let version_bump_rules = version_bump_rules
+                .iter()
+                .map(|(commit_type, level)| (commit_type.clone(), (*level).into()))
+                .collect();
+            update = update.with_version_bump_rules(version_bump_rules);
+        }
+        if let Some(changelog_repo_url) = &config.workspace.changelog_repo_url {
+            let changelog_repo_url = RepoUrl::new(changelog_repo_url.as_str())
+                .with_context(|| format!("invalid changelog_repo_url {changelog_repo_url}"))?;
+            update = update.with_changelog_repo_url(changelog_repo_url);
         }
 
         if let Some(registry_project_manifest) = &self.registry_project_manifest {
@@ -151,7 +208,41 @@ impl Update {
                 .transpose()?;
             let changelog_req = ChangelogRequest {
                 release_date,
-                changelog_config: self.changelog_config(&config)?,
+                changelog_config: self.changelog_config(
+                    config,
+                    workspace_root.as_std_path(),
+                    &default_changelog_config_path()?,
+                )?,
+                commit_summary_prefix: config
+                    .workspace
+                    .changelog_strip_prefix
+                    .as_deref()
+                    .map(|prefix| {
+                        Regex::new(prefix)
+                            .with_context(|| format!("invalid changelog_strip_prefix `{prefix}`"))
+                    })
+                    .transpose()?,
+                sort_by_scope: config.workspace.changelog_sort_by_scope == Some(true),
+                commit_sort: config.workspace.changelog_commit_sort.map(Into::into),
+                skip_ci_commits: config.workspace.changelog_skip_ci_commits == Some(true),
+                unreleased_section: config.workspace.changelog_unreleased_section == Some(true),
+                dedup_existing: config.workspace.changelog_dedup_existing == Some(true),
+                include_merge_commits: config.workspace.changelog_include_merge_commits
+                    == Some(true),
+                header_links: config.workspace.changelog_header_links != Some(false),
+                commit_links: config.workspace.changelog_commit_links == Some(true),
+                max_entries: config.workspace.changelog_max_entries,
+                context: config
+                    .workspace
+                    .changelog_context
+                    .clone()
+                    .map(|context| context.0)
+                    .unwrap_or_default(),
+                format: config
+                    .workspace
+                    .changelog_format
+                    .map(Into::into)
+                    .unwrap_or_default(),
             };
             update = update.with_changelog_req(changelog_req);
         }
@@ -165,38 +256,172 @@ impl Update {
         Ok(update)
     }
 
-    fn changelog_config(&self, config: &Config) -> anyhow::Result<Option<GitCliffConfig>> {
-        let default_config_path = dirs::config_dir()
-            .context("cannot get config dir")?
-            .join("git-cliff")
-            .join(git_cliff_core::DEFAULT_CONFIG);
+    /// `default_config_path` is the git-cliff configuration file used if no other one
+    /// is provided or discovered, see [`default_changelog_config_path`].
+    fn changelog_config(
+        &self,
+        config: &Config,
+        workspace_root: &Path,
+        default_config_path: &Path,
+    ) -> anyhow::Result<Option<GitCliffConfig>> {
+        let discovered_config_path =
+            if config.workspace.changelog_config_autodiscover != Some(false) {
+                discover_changelog_config(workspace_root)
+            } else {
+                None
+            };
 
-        let path = match self.user_changelog_config(config) {
-            Some(provided_path) => {
-                if provided_path.exists() {
-                    provided_path
-                } else {
-                    anyhow::bail!("cannot read {:?}", provided_path)
+        let paths = match (self.user_changelog_config(config), &discovered_config_path) {
+            (Some(provided_paths), _) => {
+                for path in &provided_paths {
+                    anyhow::ensure!(path.exists(), "cannot read {:?}", path);
                 }
+                provided_paths
             }
-            None => &default_config_path,
-        };
-
-        // Parse the configuration file.
-        let config = if path.exists() {
-            Some(GitCliffConfig::parse(path).context("failed to parse git-cliff config file")?)
-        } else {
-            None
+            (None, Some(discovered_config_path)) => {
+                tracing::info!("using git-cliff config file {discovered_config_path:?}");
+                vec![discovered_config_path.as_path()]
+            }
+            (None, None) if default_config_path.exists() => vec![default_config_path],
+            (None, None) => return Ok(None),
         };
 
-        Ok(config)
+        parse_changelog_config(&paths).map(Some)
     }
 
     /// Changelog configuration specified by user
-    fn user_changelog_config<'a>(&'a self, config: &'a Config) -> Option<&'a Path> {
-        self.changelog_config
-            .as_deref()
-            .or(config.workspace.changelog_config.as_deref())
+    fn user_changelog_config<'a>(&'a self, config: &'a Config) -> Option<Vec<&'a Path>> {
+        match &self.changelog_config {
+            Some(path) => Some(vec![path.as_path()]),
+            None => config
+                .workspace
+                .changelog_config
+                .as_ref()
+                .map(|c| c.paths().iter().map(|p| p.as_path()).collect()),
+        }
+    }
+}
+
+/// Path of the git-cliff configuration file of the user, e.g. `~/.config/git-cliff/cliff.toml`.
+fn default_changelog_config_path() -> anyhow::Result<PathBuf> {
+    Ok(dirs::config_dir()
+        .context("cannot get config dir")?
+        .join("git-cliff")
+        .join(git_cliff_core::DEFAULT_CONFIG))
+}
+
+/// Find the git-cliff configuration file in the workspace root.
+fn discover_changelog_config(workspace_root: &Path) -> Option<PathBuf> {
+    [git_cliff_core::DEFAULT_CONFIG, "git-cliff.toml"]
+        .into_iter()
+        .map(|file_name| workspace_root.join(file_name))
+        .find(|path| path.is_file())
+}
+
+/// Prefix of the environment variables that override the git-cliff configuration,
+/// e.g. `GIT_CLIFF__GIT__TAG_PATTERN` overrides `tag_pattern` of the `[git]` table.
+const GIT_CLIFF_ENV_PREFIX: &str = "GIT_CLIFF__";
+
+/// Parse the git-cliff configuration files.
+/// If there are multiple files, they are merged in order, so later files
+/// override the fields of earlier ones.
+/// Like in git-cliff, the `GIT_CLIFF__*` environment variables override the configuration.
+fn parse_changelog_config(paths: &[&Path]) -> anyhow::Result<GitCliffConfig> {
+    if let [path] = paths {
+        return GitCliffConfig::parse(path).context("failed to parse git-cliff config file");
+    }
+    let env: Vec<(String, String)> = std::env::vars()
+        .filter(|(name, _)| name.starts_with(GIT_CLIFF_ENV_PREFIX))
+        .collect();
+    merge_changelog_config(paths, &env)
+}
+
+/// Merge the git-cliff configuration files in order, then apply the `env` overrides,
+/// i.e. the `GIT_CLIFF__*` variables with their value.
+fn merge_changelog_config(
+    paths: &[&Path],
+    env: &[(String, String)],
+) -> anyhow::Result<GitCliffConfig> {
+    let mut merged = toml::Table::new();
+    for path in paths {
+        let contents =
+            fs::read_to_string(path).with_context(|| format!("cannot read {:?}", path))?;
+        let table: toml::Table = toml::from_str(&contents)
+            .with_context(|| format!("failed to parse git-cliff config file {:?}", path))?;
+        merge_toml_tables(&mut merged, git_cliff_table(path, table));
+    }
+    for (name, value) in env {
+        merge_toml_tables(&mut merged, env_override(name, value));
+    }
+    toml::Value::Table(merged)
+        .try_into()
+        .context("failed to parse merged git-cliff config files")
+}
+
+/// Table setting the field named by the environment variable `name` to `value`,
+/// e.g. `GIT_CLIFF__GIT__TAG_PATTERN` sets `tag_pattern` of the `[git]` table.
+/// Booleans and integers are parsed, the other values are strings.
+fn env_override(name: &str, value: &str) -> toml::Table {
+    let mut keys: Vec<String> = name
+        .trim_start_matches(GIT_CLIFF_ENV_PREFIX)
+        .split("__")
+        .map(str::to_lowercase)
+        .collect();
+    let value = if let Ok(value) = value.parse::<bool>() {
+        toml::Value::Boolean(value)
+    } else if let Ok(value) = value.parse::<i64>() {
+        toml::Value::Integer(value)
+    } else {
+        toml::Value::String(value.to_string())
+    };
+    let mut table = toml::Table::new();
+    let last = keys.pop().unwrap_or_default();
+    table.insert(last, value);
+    for key in keys.into_iter().rev() {
+        let mut parent = toml::Table::new();
+        parent.insert(key, toml::Value::Table(table));
+        table = parent;
+    }
+    table
+}
+
+/// git-cliff configuration contained in the file at `path`.
+/// Like git-cliff, read it from the `[package.metadata.git-cliff]` and
+/// `[workspace.metadata.git-cliff]` tables of `Cargo.toml` files and from the
+/// `[tool.git-cliff]` table of `pyproject.toml` files.
+fn git_cliff_table(path: &Path, table: toml::Table) -> toml::Table {
+    let tables: &[&[&str]] = match path.file_name().and_then(|f| f.to_str()) {
+        Some("Cargo.toml") => &[
+            &["workspace", "metadata", "git-cliff"],
+            &["package", "metadata", "git-cliff"],
+        ],
+        Some("pyproject.toml") => &[&["tool", "git-cliff"]],
+        _ => return table,
+    };
+    let mut git_cliff = toml::Table::new();
+    for keys in tables {
+        let nested = keys
+            .iter()
+            .try_fold(&table, |t, key| t.get(*key)?.as_table());
+        if let Some(nested) = nested {
+            merge_toml_tables(&mut git_cliff, nested.clone());
+        }
+    }
+    git_cliff
+}
+
+/// Recursively merge `overlay` into `base`.
+/// Values of `overlay` replace the ones of `base`, except tables, which are merged.
+fn merge_toml_tables(base: &mut toml::Table, overlay: toml::Table) {
+    for (key, value) in overlay {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(base_table)), toml::Value::Table(overlay_table)) => {
+                merge_toml_tables(base_table, overlay_table);
+            }
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
     }
 }
 
@@ -220,10 +445,390 @@ mod tests {
             allow_dirty: false,
             repo_url: None,
             config: None,
+            print_config: false,
+            changelog_diff: false,
+            ignore_package_overrides: false,
         };
         let config: Config = toml::from_str("").unwrap();
-        let req = update_args.update_request(config, fake_metadata()).unwrap();
+        let req = update_args
+            .update_request(&config, fake_metadata())
+            .unwrap();
         let pkg_config = req.get_package_config("aaa");
         assert_eq!(pkg_config, release_plz_core::PackageUpdateConfig::default());
     }
+
+    #[test]
+    fn update_request_without_package_overrides_uses_workspace_defaults() {
+        let config: Config = toml::from_str(
+            r#"
+            [workspace]
+            semver_check = false
+
+            [[package]]
+            name = "aaa"
+            semver_check = true
+            changelog_path = "docs/CHANGELOG.md"
+            "#,
+        )
+        .unwrap();
+        let req = update_args()
+            .update_request(&config, fake_metadata())
+            .unwrap();
+        assert!(req.get_package_config("aaa").generic.semver_check);
+
+        let req = update_args()
+            .update_request(&config.without_package_overrides(), fake_metadata())
+            .unwrap();
+        let pkg_config = req.get_package_config("aaa");
+        assert!(!pkg_config.generic.semver_check);
+        assert_eq!(pkg_config.changelog_path, None);
+    }
+
+    #[test]
+    fn changelog_credit_authors_is_passed_to_update_request() {
+        let req = update_args()
+            .update_request(&Config::default(), fake_metadata())
+            .unwrap();
+        assert!(!req.changelog_credit_authors());
+
+        let config: Config = toml::from_str(
+            r#"
+            [workspace]
+            changelog_credit_authors = true
+            "#,
+        )
+        .unwrap();
+        let req = update_args()
+            .update_request(&config, fake_metadata())
+            .unwrap();
+        assert!(req.changelog_credit_authors());
+    }
+
+    fn update_args_with_repo_url(repo_url: &str) -> Update {
+        Update {
+            project_manifest: None,
+            registry_project_manifest: None,
+            package: None,
+            no_changelog: false,
+            release_date: None,
+            registry: None,
+            update_deps: false,
+            changelog_config: None,
+            allow_dirty: false,
+            repo_url: Some(repo_url.to_string()),
+            config: None,
+            print_config: false,
+            changelog_diff: false,
+            ignore_package_overrides: false,
+        }
+    }
+
+    #[test]
+    fn changelog_links_use_changelog_repo_url() {
+        let update_args = update_args_with_repo_url("https://git.internal.example.com/owner/repo");
+        let config: Config = toml::from_str(
+            r#"
+            [workspace]
+            changelog_repo_url = "https://github.com/owner/repo"
+            "#,
+        )
+        .unwrap();
+        let req = update_args
+            .update_request(&config, fake_metadata())
+            .unwrap();
+        assert_eq!(
+            req.changelog_repo_url().unwrap().web_url(),
+            "https://github.com/owner/repo"
+        );
+        assert_eq!(
+            req.repo_url().unwrap().web_url(),
+            "https://git.internal.example.com/owner/repo"
+        );
+    }
+
+    #[test]
+    fn changelog_links_fall_back_to_repo_url() {
+        let update_args = update_args_with_repo_url("https://git.internal.example.com/owner/repo");
+        let config: Config = toml::from_str("").unwrap();
+        let req = update_args
+            .update_request(&config, fake_metadata())
+            .unwrap();
+        assert_eq!(
+            req.changelog_repo_url().unwrap().web_url(),
+            "https://git.internal.example.com/owner/repo"
+        );
+    }
+
+    #[test]
+    fn config_repo_url_has_precedence_over_manifest_repository() {
+        // The manifest of the fake metadata has `repository = "https://github.com/MarcoIeni/release-plz"`.
+        let config: Config = toml::from_str(
+            r#"
+            [workspace]
+            repo_url = "https://github.com/fork/release-plz"
+            "#,
+        )
+        .unwrap();
+        let req = update_args()
+            .update_request(&config, fake_metadata())
+            .unwrap();
+        assert_eq!(
+            req.repo_url().unwrap().web_url(),
+            "https://github.com/fork/release-plz"
+        );
+    }
+
+    #[test]
+    fn changelog_diff_is_enabled_by_flag_or_config() {
+        let config: Config = toml::from_str("").unwrap();
+        assert!(!update_args().changelog_diff(&config));
+        let diff_args = Update {
+            changelog_diff: true,
+            ..update_args()
+        };
+        assert!(diff_args.changelog_diff(&config));
+        let config: Config = toml::from_str(
+            r#"
+            [workspace]
+            changelog_diff = true
+            "#,
+        )
+        .unwrap();
+        assert!(update_args().changelog_diff(&config));
+    }
+
+    #[test]
+    fn local_repo_url_disables_changelog_links() {
+        let update_args = update_args_with_repo_url("file:///tmp/repos/owner/repo.git");
+        let config: Config = toml::from_str("").unwrap();
+        let req = update_args
+            .update_request(&config, fake_metadata())
+            .unwrap();
+        assert!(req.repo_url().is_none());
+        assert!(req.changelog_repo_url().is_none());
+    }
+
+    fn update_args() -> Update {
+        Update {
+            project_manifest: None,
+            registry_project_manifest: None,
+            package: None,
+            no_changelog: false,
+            release_date: None,
+            registry: None,
+            update_deps: false,
+            changelog_config: None,
+            allow_dirty: false,
+            repo_url: None,
+            config: None,
+            print_config: false,
+            changelog_diff: false,
+            ignore_package_overrides: false,
+        }
+    }
+
+    /// Default git-cliff configuration path in `dir`, so that the tests ignore the one of the user.
+    fn default_config_path(dir: &Path) -> PathBuf {
+        dir.join("git-cliff").join("cliff.toml")
+    }
+
+    fn changelog_header(config: Option<GitCliffConfig>) -> Option<String> {
+        config.and_then(|c| c.changelog.header)
+    }
+
+    #[test]
+    fn root_cliff_config_is_discovered() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cliff.toml");
+        fs::write(&path, "[changelog]\nheader = \"root header\"\n").unwrap();
+        assert_eq!(discover_changelog_config(dir.path()), Some(path));
+
+        let config: Config = toml::from_str("").unwrap();
+        let changelog_config = update_args()
+            .changelog_config(&config, dir.path(), &default_config_path(dir.path()))
+            .unwrap();
+        assert_eq!(
+            changelog_header(changelog_config).as_deref(),
+            Some("root header")
+        );
+    }
+
+    #[test]
+    fn root_git_cliff_config_is_discovered() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("git-cliff.toml");
+        fs::write(&path, "[changelog]\n").unwrap();
+        assert_eq!(discover_changelog_config(dir.path()), Some(path));
+    }
+
+    #[test]
+    fn no_changelog_config_is_discovered_without_cliff_config() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(discover_changelog_config(dir.path()), None);
+    }
+
+    #[test]
+    fn explicit_changelog_config_wins_over_discovered_one() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("cliff.toml"),
+            "[changelog]\nheader = \"root header\"\n",
+        )
+        .unwrap();
+        let explicit = dir.path().join("explicit.toml");
+        fs::write(&explicit, "[changelog]\nheader = \"explicit header\"\n").unwrap();
+        let config: Config = toml::from_str(&format!(
+            "[workspace]\nchangelog_config = {:?}\n",
+            explicit.to_str().unwrap()
+        ))
+        .unwrap();
+        let changelog_config = update_args()
+            .changelog_config(&config, dir.path(), &default_config_path(dir.path()))
+            .unwrap();
+        assert_eq!(
+            changelog_header(changelog_config).as_deref(),
+            Some("explicit header")
+        );
+    }
+
+    #[test]
+    fn layered_changelog_config_of_workspace_is_merged() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = dir.path().join("base-cliff.toml");
+        fs::write(
+            &base,
+            "[changelog]\nheader = \"base header\"\nfooter = \"base footer\"\n",
+        )
+        .unwrap();
+        let overrides = dir.path().join("cliff.toml");
+        fs::write(&overrides, "[changelog]\nheader = \"my header\"\n").unwrap();
+        let config: Config = toml::from_str(&format!(
+            "[workspace]\nchangelog_config = [{:?}, {:?}]\n",
+            base.to_str().unwrap(),
+            overrides.to_str().unwrap()
+        ))
+        .unwrap();
+        let changelog_config = update_args()
+            .changelog_config(&config, dir.path(), &default_config_path(dir.path()))
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            changelog_config.changelog.header.as_deref(),
+            Some("my header")
+        );
+        assert_eq!(
+            changelog_config.changelog.footer.as_deref(),
+            Some("base footer")
+        );
+    }
+
+    #[test]
+    fn disabled_autodiscover_ignores_root_cliff_config() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("cliff.toml"),
+            "[changelog]\nheader = \"root header\"\n",
+        )
+        .unwrap();
+        let config: Config =
+            toml::from_str("[workspace]\nchangelog_config_autodiscover = false\n").unwrap();
+        let changelog_config = update_args()
+            .changelog_config(&config, dir.path(), &default_config_path(dir.path()))
+            .unwrap();
+        assert!(changelog_config.is_none());
+    }
+
+    #[test]
+    fn no_changelog_config_is_used_without_any_cliff_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let config: Config = toml::from_str("").unwrap();
+        let changelog_config = update_args()
+            .changelog_config(&config, dir.path(), &default_config_path(dir.path()))
+            .unwrap();
+        assert!(changelog_config.is_none());
+    }
+
+    #[test]
+    fn default_changelog_config_is_used_without_other_cliff_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let default_config = default_config_path(dir.path());
+        fs::create_dir(default_config.parent().unwrap()).unwrap();
+        fs::write(
+            &default_config,
+            "[changelog]\nheader = \"default header\"\n",
+        )
+        .unwrap();
+        let config: Config = toml::from_str("").unwrap();
+        let changelog_config = update_args()
+            .changelog_config(&config, dir.path(), &default_config)
+            .unwrap();
+        assert_eq!(
+            changelog_header(changelog_config).as_deref(),
+            Some("default header")
+        );
+    }
+
+    #[test]
+    fn single_changelog_config_is_parsed() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cliff.toml");
+        fs::write(&path, "[changelog]\nheader = \"my header\"\n").unwrap();
+        let config = parse_changelog_config(&[&path]).unwrap();
+        assert_eq!(config.changelog.header.as_deref(), Some("my header"));
+    }
+
+    #[test]
+    fn later_changelog_config_overrides_earlier_one() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = dir.path().join("base.toml");
+        fs::write(
+            &base,
+            "[changelog]\nheader = \"base header\"\nfooter = \"base footer\"\n",
+        )
+        .unwrap();
+        let overrides = dir.path().join("cliff.toml");
+        fs::write(&overrides, "[changelog]\nheader = \"my header\"\n").unwrap();
+        let config = parse_changelog_config(&[&base, &overrides]).unwrap();
+        assert_eq!(config.changelog.header.as_deref(), Some("my header"));
+        assert_eq!(config.changelog.footer.as_deref(), Some("base footer"));
+    }
+
+    #[test]
+    fn changelog_config_is_read_from_cargo_metadata_of_layers() {
+        let dir = tempfile::tempdir().unwrap();
+        let manifest = dir.path().join("Cargo.toml");
+        fs::write(
+            &manifest,
+            "[workspace]\nmembers = []\n\n[workspace.metadata.git-cliff.changelog]\nheader = \"workspace header\"\nfooter = \"workspace footer\"\n",
+        )
+        .unwrap();
+        let overrides = dir.path().join("cliff.toml");
+        fs::write(&overrides, "[changelog]\nheader = \"my header\"\n").unwrap();
+        let config = parse_changelog_config(&[&manifest, &overrides]).unwrap();
+        assert_eq!(config.changelog.header.as_deref(), Some("my header"));
+        assert_eq!(config.changelog.footer.as_deref(), Some("workspace footer"));
+    }
+
+    #[test]
+    fn env_variables_override_layered_changelog_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = dir.path().join("base.toml");
+        fs::write(&base, "[git]\ntag_pattern = \"base-.*\"\n").unwrap();
+        let overrides = dir.path().join("cliff.toml");
+        fs::write(&overrides, "[changelog]\nheader = \"my header\"\n").unwrap();
+        let env = [
+            (
+                "GIT_CLIFF__GIT__TAG_PATTERN".to_string(),
+                "env-.*".to_string(),
+            ),
+            (
+                "GIT_CLIFF__GIT__CONVENTIONAL_COMMITS".to_string(),
+                "true".to_string(),
+            ),
+        ];
+        let config = merge_changelog_config(&[&base, &overrides], &env).unwrap();
+        assert_eq!(config.changelog.header.as_deref(), Some("my header"));
+        assert_eq!(config.git.tag_pattern.unwrap().as_str(), "env-.*");
+        assert_eq!(config.git.conventional_commits, Some(true));
+    }
 }
@@ -1,13 +1,22 @@
 use anyhow::Context;
-use release_plz_core::{ReleaseRequest, UpdateRequest};
+use git_cmd::GitIdentity;
+use release_plz_core::{GitBackend, ReleaseRequest, UpdateRequest, VersionIncrement};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, path::PathBuf, time::Duration};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+use tracing::{debug, warn};
 use url::Url;
 
+/// Names of the configuration file, looked up in this order.
+const CONFIG_FILE_NAMES: [&str; 2] = ["release-plz.toml", ".release-plz.toml"];
+
 /// You can find the documentation of the configuration file
 /// [here](https://release-plz.ieni.dev/docs/config).
-#[derive(Serialize, Deserialize, Default, PartialEq, Eq, Debug, JsonSchema)]
+#[derive(Serialize, Deserialize, Default, PartialEq, Debug, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct Config {
     /// # Workspace
@@ -22,6 +31,47 @@ pub struct Config {
 }
 
 impl Config {
+    /// Find the configuration file in `dir`, i.e. `release-plz.toml` or `.release-plz.toml`.
+    /// Returns the configuration with the path of its file.
+    /// If neither file exists, returns the default configuration without path.
+    /// Fails if both files exist, because it's ambiguous which one to use.
+    pub fn discover(dir: &Path) -> anyhow::Result<(Self, Option<PathBuf>)> {
+        let paths: Vec<PathBuf> = CONFIG_FILE_NAMES
+            .iter()
+            .map(|name| dir.join(name))
+            .filter(|path| path.exists())
+            .collect();
+        match paths.as_slice() {
+            [] => Ok((Self::default(), None)),
+            [path] => Ok((Self::read(path)?, Some(path.clone()))),
+            _ => anyhow::bail!(
+                "both `release-plz.toml` and `.release-plz.toml` exist in {dir:?}. Remove one of them"
+            ),
+        }
+    }
+
+    /// Read the configuration file at `path`.
+    pub fn read(path: &Path) -> anyhow::Result<Self> {
+        let config = match std::fs::read_to_string(path) {
+            Ok(config) => config,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                anyhow::bail!("specified config does not exist at path {path:?}")
+            }
+            Err(e) => anyhow::bail!("can't read {path:?}: {e:?}"),
+        };
+        toml::from_str(&config).with_context(|| format!("invalid config file {path:?}"))
+    }
+
+    /// Configuration without the `[[package]]` sections, so that every package
+    /// uses the `[workspace]` defaults.
+    /// Useful to find out whether a package-specific setting causes an unexpected behavior.
+    pub fn without_package_overrides(&self) -> Config {
+        Config {
+            workspace: self.workspace.clone(),
+            package: vec![],
+        }
+    }
+
     /// Package-specific configurations.
     /// Returns `<package name, package config>`.
     fn packages(&self) -> HashMap<&str, &PackageSpecificConfig> {
@@ -31,20 +81,170 @@ impl Config {
             .collect()
     }
 
+    /// Names of the packages with a `[[package]]` section, in declaration order.
+    pub fn configured_package_names(&self) -> Vec<&str> {
+        self.package.iter().map(|p| p.name.as_str()).collect()
+    }
+
+    /// Package-specific configurations merged with the workspace defaults.
+    /// If `workspace.only` is set, the packages not listed in it have `release = false`.
+    fn merged_packages(&self) -> BTreeMap<&str, PackageSpecificConfig> {
+        let mut packages: BTreeMap<&str, PackageSpecificConfig> = self
+            .packages()
+            .into_iter()
+            .map(|(name, config)| (name, config.clone()))
+            .collect();
+        if let Some(only) = &self.workspace.only {
+            for package in only {
+                packages.entry(package.as_str()).or_default();
+            }
+        }
+        packages
+            .into_iter()
+            .map(|(name, config)| {
+                let mut config = config.merge(self.workspace.packages_defaults.clone());
+                if !self.workspace.is_package_selected(name) {
+                    config.common.release = Some(false);
+                }
+                (name, config)
+            })
+            .collect()
+    }
+
+    /// Configuration that applies to the given package, after merging its
+    /// `[[package]]` section with the `[workspace]` defaults.
+    /// Returns `None` if the package doesn't have package-specific configuration,
+    /// i.e. the configuration of [`Self::default_package_config`] applies to it.
+    pub fn effective_package_config(&self, name: &str) -> Option<PackageConfig> {
+        self.merged_packages()
+            .remove(name)
+            .map(|config| config.common)
+    }
+
+    /// Log the effective configuration of the packages with a `[[package]]` section,
+    /// to make it easier to debug which settings apply.
+    pub fn log_effective_package_configs(&self, metadata: &cargo_metadata::Metadata) {
+        for name in self.configured_package_names() {
+            debug!(
+                "effective configuration of package {name}: {:?}",
+                self.effective_package_config(name)
+            );
+        }
+        let members: Vec<&str> = metadata
+            .workspace_packages()
+            .iter()
+            .map(|p| p.name.as_str())
+            .collect();
+        match self.flattened_changelog_includes(&members) {
+            Ok(includes) => {
+                for (name, included) in includes.iter().filter(|(_, i)| !i.is_empty()) {
+                    debug!("changelog of package {name} includes the changelogs of {included:?}");
+                }
+            }
+            Err(e) => warn!("{e:#}"),
+        }
+    }
+
+    /// Packages whose changelogs are included in the changelog of each package
+    /// with a `[[package]]` section, following `changelog_include` transitively.
+    /// The packages excluded with `changelog_exclude` are not followed.
+    /// Globs, e.g. `api-*`, are expanded to the matching `members` before flattening,
+    /// except the package that owns the glob.
+    /// Returns an error if a package includes its own changelog, directly or not.
+    pub fn flattened_changelog_includes<'a>(
+        &'a self,
+        members: &[&'a str],
+    ) -> anyhow::Result<BTreeMap<&'a str, BTreeSet<&'a str>>> {
+        let packages = self.packages();
+        let direct_includes = |name: &str| -> Vec<&'a str> {
+            let Some(config) = packages.get(name) else {
+                return vec![];
+            };
+            let members: Vec<&'a str> = members.iter().copied().filter(|m| *m != name).collect();
+            let expand = |entries: &'a Option<Vec<String>>| -> Vec<&'a str> {
+                entries
+                    .iter()
+                    .flatten()
+                    .flat_map(|entry| release_plz_core::expand_package_glob(entry, &members))
+                    .collect()
+            };
+            let excluded = expand(&config.changelog_exclude);
+            let mut included = expand(&config.changelog_include);
+            included.retain(|p| !excluded.contains(p));
+            included
+        };
+        let mut flattened = BTreeMap::new();
+        for name in self.configured_package_names() {
+            let mut included = BTreeSet::new();
+            let mut path = vec![name];
+            flatten_includes(&direct_includes, &mut path, &mut included)?;
+            flattened.insert(name, included);
+        }
+        Ok(flattened)
+    }
+
+    /// Fully-resolved configuration, as release-plz sees it.
+    /// Every workspace member has a `[[package]]` section with its effective configuration,
+    /// where the unspecified settings are set to their default value.
+    pub fn resolved(&self, members: &[&str]) -> Config {
+        let mut merged_packages = self.merged_packages();
+        let default_config = PackageSpecificConfig {
+            common: self.default_package_config(),
+            ..Default::default()
+        };
+        let package = members
+            .iter()
+            .map(|&name| {
+                let mut config = merged_packages
+                    .remove(name)
+                    .unwrap_or_else(|| default_config.clone());
+                config.common = config.common.resolved();
+                PackageSpecificConfigWithName {
+                    name: name.to_string(),
+                    config,
+                }
+            })
+            .collect();
+        Config {
+            workspace: Workspace {
+                packages_defaults: self.workspace.packages_defaults.clone().resolved(),
+                ..self.workspace.clone()
+            },
+            package,
+        }
+    }
+
+    /// [`Self::resolved`] configuration of the workspace members, serialized as TOML.
+    pub fn resolved_toml(&self, metadata: &cargo_metadata::Metadata) -> anyhow::Result<String> {
+        let members: Vec<&str> = metadata
+            .workspace_packages()
+            .iter()
+            .map(|p| p.name.as_str())
+            .collect();
+        toml::to_string(&self.resolved(&members)).context("can't serialize configuration")
+    }
+
+    /// Configuration of the packages without a `[[package]]` section.
+    fn default_package_config(&self) -> PackageConfig {
+        let mut default_config = self.workspace.packages_defaults.clone();
+        if self.workspace.only.is_some() {
+            default_config.release = Some(false);
+        }
+        default_config
+    }
+
     pub fn fill_update_config(
         &self,
         is_changelog_update_disabled: bool,
         update_request: UpdateRequest,
     ) -> UpdateRequest {
-        let mut default_update_config = self.workspace.packages_defaults.clone();
+        let mut default_update_config = self.default_package_config();
         if is_changelog_update_disabled {
             default_update_config.changelog_update = false.into();
         }
         let mut update_request =
             update_request.with_default_package_config(default_update_config.into());
-        for (package, config) in self.packages() {
-            let mut update_config = config.clone();
-            update_config = update_config.merge(self.workspace.packages_defaults.clone());
+        for (package, mut update_config) in self.merged_packages() {
             if is_changelog_update_disabled {
                 update_config.common.changelog_update = false.into();
             }
@@ -53,13 +253,16 @@ impl Config {
         update_request
     }
 
+    /// The `allow_dirty` and `no_verify` CLI flags apply to all the packages,
+    /// except the ones setting `publish_allow_dirty` or `publish_no_verify`
+    /// in their `[[package]]` section.
     pub fn fill_release_config(
         &self,
         allow_dirty: bool,
         no_verify: bool,
         release_request: ReleaseRequest,
     ) -> ReleaseRequest {
-        let mut default_config = self.workspace.packages_defaults.clone();
+        let mut default_config = self.default_package_config();
         if no_verify {
             default_config.publish_no_verify = Some(true);
         }
@@ -69,14 +272,13 @@ impl Config {
         let mut release_request =
             release_request.with_default_package_config(default_config.into());
 
-        for (package, config) in self.packages() {
-            let mut release_config = config.clone();
-            release_config = release_config.merge(self.workspace.packages_defaults.clone());
-
-            if no_verify {
+        let packages = self.packages();
+        for (package, mut release_config) in self.merged_packages() {
+            let package_config = packages.get(package).map(|p| &p.common);
+            if no_verify && package_config.and_then(|c| c.publish_no_verify).is_none() {
                 release_config.common.publish_no_verify = Some(true);
             }
-            if allow_dirty {
+            if allow_dirty && package_config.and_then(|c| c.publish_allow_dirty).is_none() {
                 release_config.common.publish_allow_dirty = Some(true);
             }
             release_request = release_request.with_package_config(package, release_config.into());
@@ -86,7 +288,7 @@ impl Config {
 }
 
 /// Config at the `[workspace]` level.
-#[derive(Serialize, Deserialize, Default, PartialEq, Eq, Debug, JsonSchema)]
+#[derive(Serialize, Deserialize, Default, PartialEq, Debug, Clone, JsonSchema)]
 pub struct Workspace {
     /// Configuration applied at the `[[package]]` level, too.
     #[serde(flatten)]
@@ -95,29 +297,281 @@ pub struct Workspace {
     /// - If `true`, allow dirty working directories to be updated. The uncommitted changes will be part of the update.
     /// - If `false` or [`Option::None`], the command will fail if the working directory is dirty.
     pub allow_dirty: Option<bool>,
+    /// # Changelog Diff
+    /// If `true`, `release-plz update` prints a unified diff of the changes to the changelogs,
+    /// without updating the project.
+    pub changelog_diff: Option<bool>,
+    /// # Base Branch
+    /// Base branch of the release PR.
+    /// If unspecified, release-plz uses the current branch, or the default branch of the
+    /// remote if HEAD is detached, like in some CI checkouts.
+    pub base_branch: Option<String>,
     /// # Changelog Config
     /// Path to the git cliff configuration file. Defaults to the `keep a changelog` configuration.
-    pub changelog_config: Option<PathBuf>,
+    /// It can also be a list of paths: the files are merged in order, so later files override earlier ones.
+    pub changelog_config: Option<ChangelogConfigPath>,
+    /// # Changelog Config Autodiscover
+    /// If `changelog_config` is unspecified, use the `cliff.toml` or `git-cliff.toml`
+    /// file in the workspace root, if present.
+    /// Enabled by default.
+    pub changelog_config_autodiscover: Option<bool>,
+    /// # Changelog Repo URL
+    /// Repository url used to generate the links of the changelog.
+    /// Useful if readers should click on a public mirror of the repository.
+    /// It defaults to `repo_url`.
+    pub changelog_repo_url: Option<Url>,
+    /// # Changelog Strip Prefix
+    /// Regex matching a prefix to remove from the commit summaries before adding them to the changelog.
+    /// E.g. `PROJ-\d+:\s*` to remove Jira keys.
+    pub changelog_strip_prefix: Option<String>,
+    /// # Changelog Header Links
+    /// If `false`, the version heading of each release isn't a link to the
+    /// comparison with the previous release.
+    /// Enabled by default.
+    pub changelog_header_links: Option<bool>,
+    /// # Changelog Commit Links
+    /// If `true`, append the short hash of the commit, linking to the commit,
+    /// to each changelog entry.
+    pub changelog_commit_links: Option<bool>,
+    /// # Changelog Max Entries
+    /// Maximum number of entries of each section of a release in the changelog.
+    /// The other entries are summarized in a "…and X more" line.
+    pub changelog_max_entries: Option<usize>,
+    /// # Changelog Context
+    /// Variables available in the changelog template as `context`,
+    /// e.g. `{{ context.product_name }}`.
+    #[schemars(with = "Option<HashMap<String, serde_json::Value>>")]
+    pub changelog_context: Option<ChangelogContext>,
+    /// # Changelog Sort By Scope
+    /// If `true`, sort the entries of each changelog section by the scope of the commit.
+    /// Entries without scope are placed last.
+    pub changelog_sort_by_scope: Option<bool>,
+    /// # Changelog Commit Sort
+    /// Order of the entries of each changelog section.
+    /// If unspecified, the `sort_commits` field of the git-cliff configuration is used,
+    /// which defaults to `newest`.
+    pub changelog_commit_sort: Option<ChangelogCommitSort>,
+    /// # Changelog Source
+    /// Where the entries of the changelog come from.
+    /// With `pull_requests`, the `release-pr` command uses the titles of the Pull Requests
+    /// merged since the last tag. If they can't be retrieved, the commit messages are used.
+    /// If unspecified, release-plz uses the commit messages.
+    pub changelog_source: Option<ChangelogSource>,
+    /// # Changelog Credit Authors
+    /// If `true`, append the author of each commit to its changelog entry,
+    /// e.g. `fix bug (@user)`.
+    /// The `release-pr` command credits the GitHub or Gitea username of the author of the
+    /// merged Pull Request. Otherwise, the name of the git author is used.
+    pub changelog_credit_authors: Option<bool>,
+    /// # Changelog Format
+    /// Output format of the changelog.
+    /// If unspecified, the changelog is written in Markdown.
+    pub changelog_format: Option<ChangelogFormat>,
+    /// # Changelog Skip CI Commits
+    /// If `true`, the `ci`, `build` and `chore(ci)` commits aren't added to the changelog.
+    pub changelog_skip_ci_commits: Option<bool>,
+    /// # Changelog Unreleased Section
+    /// If `true`, move the entries of the `Unreleased` section of the changelog
+    /// to the new release, leaving an empty `Unreleased` section.
+    pub changelog_unreleased_section: Option<bool>,
+    /// # Changelog Dedup Existing
+    /// If `true`, don't add the commits that are already listed in the `Unreleased` section
+    /// of the changelog, e.g. because the section was edited by hand.
+    /// An entry lists a commit if it contains its short hash or its summary.
+    pub changelog_dedup_existing: Option<bool>,
+    /// # Changelog Include Merge Commits
+    /// If `true`, add the merge commits to the changelog, with the title of the merged
+    /// Pull Request, instead of the commits of the merged branches.
+    /// If `false` or [`Option::None`], the merge commits are excluded from the changelog.
+    pub changelog_include_merge_commits: Option<bool>,
+    /// # Changelog Line Ending
+    /// Line ending of the changelogs written by release-plz.
+    /// If [`Option::None`], `lf` is used.
+    pub changelog_line_ending: Option<LineEnding>,
+    /// # Commit Lockfile
+    /// If `false`, the changes of the `Cargo.lock` file aren't committed in the release PR,
+    /// even if `update_lockfile` updates it.
+    /// Enabled by default.
+    pub commit_lockfile: Option<bool>,
     /// # Dependencies Update
     /// - If `true`, update all the dependencies in the Cargo.lock file by running `cargo update`.
     /// - If `false` or [`Option::None`], only update the workspace packages by running `cargo update --workspace`.
     pub dependencies_update: Option<bool>,
+    /// # Forge API Retries
+    /// Maximum number of times a request to the GitHub/Gitea/GitLab API is retried
+    /// when it fails because of a server or network error. Defaults to `3`.
+    pub forge_api_retries: Option<u32>,
+    /// # Forge API Timeout
+    /// Timeout of each request to the GitHub/Gitea/GitLab API.
+    /// If unspecified, requests don't time out.
+    pub forge_api_timeout: Option<String>,
+    /// # Git Push Remote
+    /// Name of the git remote where release-plz pushes the release branch and the git tags,
+    /// e.g. `fork`.
+    /// If unspecified, release-plz pushes to the remote of the current branch, or `origin`.
+    pub git_push_remote: Option<String>,
+    /// # Git Release API Headers
+    /// Additional HTTP headers sent with every request to the GitHub/Gitea/GitLab API.
+    /// Useful if the API is behind an authentication proxy.
+    pub git_release_api_headers: Option<HashMap<String, String>>,
+    /// # Git Release Branches
+    /// Globs of the branches where release-plz creates the git releases, e.g. `release/*`.
+    /// If the current branch doesn't match any glob, the git releases aren't created.
+    /// If unspecified, the git releases are created from any branch.
+    pub git_release_branches: Option<Vec<String>>,
+    /// # Git Release Type Trailer
+    /// Key of the commit trailer that overrides the `git_release_type`, e.g. `Release-Type`.
+    /// The trailer is read from the commit that triggers the release.
+    pub git_release_type_trailer: Option<String>,
+    /// # Git Token Env
+    /// Name of the environment variable containing the git token.
+    /// Used when the git token isn't passed via the `--git-token` flag.
+    pub git_token_env: Option<String>,
+    /// # Git User Name
+    /// Name of the author and committer of the release commit and of the git tags.
+    /// If unset, the name of the git configuration is used.
+    pub git_user_name: Option<String>,
+    /// # Git User Email
+    /// Email of the author and committer of the release commit and of the git tags.
+    /// If unset, the email of the git configuration is used.
+    pub git_user_email: Option<String>,
+    /// # GitHub Output
+    /// - If `true` or [`Option::None`], write the step outputs to the file of the
+    ///   `GITHUB_OUTPUT` environment variable, if set (i.e. when running in GitHub Actions).
+    /// - If `false`, don't write the step outputs.
+    pub github_output: Option<bool>,
+    /// # Idempotent
+    /// If `true`, `release-plz release` skips the steps that were already done,
+    /// e.g. because the git tag or the git release already exist,
+    /// instead of failing. Useful to re-run a release that failed halfway.
+    pub idempotent: Option<bool>,
+    /// # Jobs
+    /// Maximum number of packages processed in parallel when updating the packages,
+    /// e.g. when running cargo-semver-checks or generating the changelogs.
+    /// If unspecified, release-plz uses one thread per CPU.
+    pub jobs: Option<usize>,
+    /// # On Error
+    /// What to do if the release of a package fails, when releasing multiple packages.
+    /// Default: `stop`.
+    pub on_error: Option<OnError>,
+    /// # Only
+    /// Names of the packages to process.
+    /// If set, all the other packages are ignored, as if they had `release = false`.
+    pub only: Option<Vec<String>>,
+    /// # PR Closes Issues
+    /// Numbers of the issues closed when the release PR is merged.
+    /// The body of the release PR links them with a closing keyword, e.g. `Closes <issue url>`.
+    pub pr_closes_issues: Option<Vec<u64>>,
     /// # PR Draft
     /// If `true`, the created release PR will be marked as a draft.
     #[serde(default)]
     pub pr_draft: bool,
     /// # PR Labels
     /// Labels to add to the release PR.
+    /// Entries starting with `!` remove a previously added label.
+    /// Entries starting with `\!` add a label starting with `!`.
     #[serde(default)]
     pub pr_labels: Vec<String>,
+    /// # PR Marker Label
+    /// Label added to every release PR, in addition to `pr_labels`.
+    /// release-plz uses it to find the open release PR to update.
+    /// Default: `release-plz`.
+    pub pr_marker_label: Option<String>,
+    /// # PR Pre Hook
+    /// Shell command run in the project root after the packages are updated,
+    /// before committing the changes of the release PR.
+    /// The files modified by the command are included in the release PR.
+    /// If the command fails, the release PR isn't opened.
+    pub pr_pre_hook: Option<String>,
+    /// # Publish Require Token
+    /// If `true`, don't release the packages if the registry token isn't set
+    /// in the environment, instead of failing.
+    pub publish_require_token: Option<bool>,
+    /// # Publish Token Env
+    /// Environment variables containing the token of each registry.
+    /// The key is the registry name, the value is the name of the environment variable.
+    /// If a registry isn't listed, cargo looks for its token as usual.
+    pub publish_token_env: Option<HashMap<String, String>>,
     /// # Publish Timeout
     /// Timeout for the publishing process
     pub publish_timeout: Option<String>,
+    /// # Publish Timeout Scope
+    /// Whether `publish_timeout` applies to each package or to the whole publish phase.
+    /// Default: `per_package`.
+    pub publish_timeout_scope: Option<PublishTimeoutScope>,
+    /// # Release Commit Marker
+    /// Trailer added to the release commit, e.g. `release-plz: true`.
+    /// Commits containing this trailer are ignored when computing the next version
+    /// and the changelog.
+    /// Default: `release-plz: true`.
+    pub release_commit_marker: Option<String>,
+    /// # Release PR
+    /// If `false`, the `release-pr` command updates the packages, but doesn't open
+    /// the release PR. Useful to release directly from the default branch.
+    /// Enabled by default.
+    pub release_pr: Option<bool>,
     /// # Repo URL
     /// GitHub/Gitea repository url where your project is hosted.
     /// It is used to generate the changelog release link.
     /// It defaults to the url of the default remote.
     pub repo_url: Option<Url>,
+    /// # Semver Check Report In PR
+    /// - If `true` or [`Option::None`], add the breaking changes found by cargo-semver-checks
+    ///   to the release PR body, in a collapsible section for each package.
+    /// - If `false`, only mark the packages with breaking changes in the PR summary.
+    pub semver_check_report_in_pr: Option<bool>,
+    /// # Semver Check Missing
+    /// What to do if `semver_check` is enabled, but cargo-semver-checks isn't installed.
+    /// If unspecified, release-plz logs a warning and skips the semver check.
+    pub semver_check_missing: Option<SemverCheckMissing>,
+    /// # Skip Yanked Baseline
+    /// - If `true` or [`Option::None`], when the latest version of a package was yanked,
+    ///   compare the local package with the latest non-yanked version of the registry
+    ///   to determine the next version and run cargo-semver-checks.
+    /// - If `false`, compare the local package with the yanked version.
+    pub skip_yanked_baseline: Option<bool>,
+    /// # Update Lockfile
+    /// - If `true` or [`Option::None`], update the Cargo.lock file. See `dependencies_update`.
+    /// - If `false`, don't touch the Cargo.lock file.
+    pub update_lockfile: Option<bool>,
+    /// # Verbose Plan
+    /// - If `true`, log the decision trail of each package: the previous version, the commits
+    ///   considered, the version increment and whether the package is published, tagged and
+    ///   released, with the reason of each decision.
+    /// - If `false` or [`Option::None`], log only the outcome.
+    pub verbose_plan: Option<bool>,
+    /// # Version Bump Rules
+    /// Version increment caused by the commits of each type, e.g. `perf = "minor"`.
+    /// Commit types that aren't listed follow the conventional commits rules.
+    pub version_bump_rules: Option<HashMap<String, BumpLevel>>,
+    /// # Workspace Changelog Path
+    /// Path of the changelog aggregating the changes of all the updated packages.
+    /// It needs to be relative to the root of the workspace.
+    /// If unspecified, the aggregated changelog isn't written.
+    pub workspace_changelog_path: Option<PathBuf>,
+}
+
+/// Path of the git-cliff configuration file, or list of paths of
+/// git-cliff configuration files to merge.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, JsonSchema)]
+#[serde(untagged)]
+pub enum ChangelogConfigPath {
+    Single(PathBuf),
+    Layered(Vec<PathBuf>),
+}
+
+/// Variables of the changelog template, by name.
+#[derive(Serialize, Deserialize, Default, PartialEq, Debug, Clone)]
+#[serde(transparent)]
+pub struct ChangelogContext(pub HashMap<String, toml::Value>);
+
+impl ChangelogConfigPath {
+    pub fn paths(&self) -> &[PathBuf] {
+        match self {
+            ChangelogConfigPath::Single(path) => std::slice::from_ref(path),
+            ChangelogConfigPath::Layered(paths) => paths,
+        }
+    }
 }
 
 impl Workspace {
@@ -127,10 +581,62 @@ impl Workspace {
         duration_str::parse(publish_timeout)
             .with_context(|| format!("invalid publish_timeout {}", publish_timeout))
     }
+
+    /// Get the timeout of the requests to the git forge API.
+    pub fn forge_api_timeout(&self) -> anyhow::Result<Option<Duration>> {
+        self.forge_api_timeout
+            .as_deref()
+            .map(|timeout| {
+                duration_str::parse(timeout)
+                    .with_context(|| format!("invalid forge_api_timeout {}", timeout))
+            })
+            .transpose()
+    }
+
+    /// Apply the settings of the requests to the git forge API to the git backend.
+    pub fn configure_git_backend(&self, backend: GitBackend) -> anyhow::Result<GitBackend> {
+        let mut backend =
+            backend.with_custom_headers(self.git_release_api_headers.clone().unwrap_or_default());
+        if let Some(retries) = self.forge_api_retries {
+            backend = backend.with_api_retries(retries);
+        }
+        if let Some(timeout) = self.forge_api_timeout()? {
+            backend = backend.with_api_timeout(timeout);
+        }
+        Ok(backend)
+    }
+
+    /// Whether the package is selected by the `only` field.
+    /// If `only` is unset, all packages are selected.
+    fn is_package_selected(&self, package: &str) -> bool {
+        self.only
+            .as_ref()
+            .map_or(true, |only| only.iter().any(|p| p == package))
+    }
+
+    /// Labels to add to the release PR, with negated entries applied.
+    pub fn pr_labels(&self) -> Vec<String> {
+        merge_labels(&[], &self.pr_labels)
+    }
+
+    /// Identity used to create the release commit and the git tags.
+    pub fn git_identity(&self) -> GitIdentity {
+        GitIdentity {
+            name: self.git_user_name.clone(),
+            email: self.git_user_email.clone(),
+        }
+    }
+
+    /// Read the git token from the environment variable specified in `git_token_env`.
+    pub fn git_token_from_env(&self) -> Option<String> {
+        self.git_token_env
+            .as_ref()
+            .and_then(|var| std::env::var(var).ok())
+    }
 }
 
 /// Config at the `[[package]]` level.
-#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, JsonSchema)]
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Default, Clone, JsonSchema)]
 pub struct PackageSpecificConfig {
     /// Configuration that can be specified at the `[workspace]` level, too.
     #[serde(flatten)]
@@ -145,6 +651,63 @@ pub struct PackageSpecificConfig {
     /// List of package names.
     /// Include the changelogs of these packages in the changelog of the current package.
     changelog_include: Option<Vec<String>>,
+    /// # Changelog Exclude
+    /// List of package names.
+    /// Don't include the changelogs of these packages, even if they are listed in `changelog_include`.
+    changelog_exclude: Option<Vec<String>>,
+    /// # Changelog Include Mode
+    /// How the changelogs of `changelog_include` are included.
+    changelog_include_mode: Option<ChangelogIncludeMode>,
+    /// # Changelog Include Order
+    /// Order of the changelogs of `changelog_include`.
+    changelog_include_order: Option<ChangelogIncludeOrder>,
+}
+
+/// Add the packages included by the last package of `path` to `included`, recursively.
+/// `path` is the chain of inclusions that led to the package, used to detect cycles.
+fn flatten_includes<'a>(
+    direct_includes: &impl Fn(&str) -> Vec<&'a str>,
+    path: &mut Vec<&'a str>,
+    included: &mut BTreeSet<&'a str>,
+) -> anyhow::Result<()> {
+    let package = *path.last().expect("path is not empty");
+    for include in direct_includes(package) {
+        if path.contains(&include) {
+            let cycle = path
+                .iter()
+                .skip_while(|p| **p != include)
+                .chain([&include])
+                .map(|p| format!("`{p}`"))
+                .collect::<Vec<_>>()
+                .join(" -> ");
+            anyhow::bail!("cycle in `changelog_include`: {cycle}");
+        }
+        if included.insert(include) {
+            path.push(include);
+            flatten_includes(direct_includes, path, included)?;
+            path.pop();
+        }
+    }
+    Ok(())
+}
+
+/// Add the `labels` to the `inherited` ones.
+/// Labels starting with `!` are removed instead, after all the additions.
+/// A leading `\!` is an escaped `!`, i.e. the label is added.
+fn merge_labels(inherited: &[String], labels: &[String]) -> Vec<String> {
+    let mut result: Vec<String> = inherited.to_vec();
+    let mut removals = vec![];
+    for label in labels {
+        if let Some(escaped) = label.strip_prefix("\\!") {
+            result.push(format!("!{escaped}"));
+        } else if let Some(removed) = label.strip_prefix('!') {
+            removals.push(removed);
+        } else {
+            result.push(label.clone());
+        }
+    }
+    result.retain(|label| !removals.contains(&label.as_str()));
+    result
 }
 
 impl PackageSpecificConfig {
@@ -154,6 +717,9 @@ impl PackageSpecificConfig {
             common: self.common.merge(default),
             changelog_path: self.changelog_path,
             changelog_include: self.changelog_include,
+            changelog_exclude: self.changelog_exclude,
+            changelog_include_mode: self.changelog_include_mode,
+            changelog_include_order: self.changelog_include_order,
         }
     }
 }
@@ -181,16 +747,39 @@ impl From<PackageConfig> for release_plz_core::ReleaseConfig {
         let is_publish_enabled = value.publish != Some(false);
         let is_git_release_enabled = value.git_release_enable != Some(false);
         let is_git_release_draft = value.git_release_draft == Some(true);
+        let is_git_release_backfill = value.git_release_backfill == Some(true);
+        let is_git_release_publish_draft_on_success =
+            value.git_release_publish_draft_on_success == Some(true);
         let is_git_tag_enabled = value.git_tag_enable != Some(false);
+        let is_git_tag_annotated = value.git_tag_annotated != Some(false);
         let release = value.release != Some(false);
+        let git_tag_name = value.git_tag_name();
         let mut cfg = Self::default()
             .with_publish(release_plz_core::PublishConfig::enabled(is_publish_enabled))
             .with_git_release(
                 release_plz_core::GitReleaseConfig::enabled(is_git_release_enabled)
-                    .set_draft(is_git_release_draft),
+                    .set_draft(is_git_release_draft)
+                    .set_backfill(is_git_release_backfill)
+                    .set_publish_draft_on_success(is_git_release_publish_draft_on_success)
+                    .set_release_type(value.git_release_type.unwrap_or_default().into())
+                    .set_release_type_strict(value.git_release_type_strict == Some(true))
+                    .set_never_prerelease(value.git_release_never_prerelease == Some(true))
+                    .set_expect_existing_tag(value.git_release_expect_existing_tag == Some(true))
+                    .set_after_tag_push(value.git_release_after_tag_push == Some(true))
+                    .set_latest(value.git_release_latest),
             )
-            .with_git_tag(release_plz_core::GitTagConfig::enabled(is_git_tag_enabled))
-            .with_release(release);
+            .with_git_tag(
+                release_plz_core::GitTagConfig::enabled(is_git_tag_enabled)
+                    .set_annotated(is_git_tag_annotated)
+                    .set_message(value.git_tag_message)
+                    .set_name(git_tag_name),
+            )
+            .with_release(release)
+            .with_env(value.env.unwrap_or_default().into_iter().collect())
+            .with_publish_registries(value.publish_registries.unwrap_or_default())
+            .with_allow_dirty_auto(value.publish_allow_dirty_auto == Some(true))
+            .with_version_files(value.version_files.unwrap_or_default())
+            .with_check_msrv(value.check_msrv == Some(true));
 
         if let Some(no_verify) = value.publish_no_verify {
             cfg = cfg.with_no_verify(no_verify);
@@ -209,6 +798,22 @@ pub struct PackageConfig {
     /// Whether to create/update changelog or not.
     /// If unspecified, the changelog is updated.
     pub changelog_update: Option<bool>,
+    /// # Changelog Tag Pattern
+    /// Regex matching the git tags that can be used as previous release of the package.
+    /// Useful in repositories with mixed tag styles.
+    pub changelog_tag_pattern: Option<String>,
+    /// # Changelog Dependency Note
+    /// Changelog entry of the releases caused only by the update of local dependencies.
+    /// `{{ dependencies }}` is replaced with the names of the updated dependencies.
+    pub changelog_dependency_note: Option<String>,
+    /// # Changelog Group By Path
+    /// Changelog groups of the commits, by glob of the files they change,
+    /// e.g. `"src/parser/**" = "Parser"`. The globs are relative to the package directory.
+    /// A commit changing files of multiple groups appears in each of them.
+    pub changelog_group_by_path: Option<HashMap<String, String>>,
+    /// # Changelog Require Nonempty
+    /// If `Some(true)`, fail if the changelog section of a release has no entries.
+    pub changelog_require_nonempty: Option<bool>,
     /// # Git Release Enable
     /// Publish the GitHub/Gitea release for the created git tag.
     /// Enabled by default.
@@ -216,37 +821,147 @@ pub struct PackageConfig {
     /// # Git Release Type
     /// Whether to mark the created release as not ready for production.
     pub git_release_type: Option<ReleaseType>,
+    /// # Git Release Type Strict
+    /// If `true`, releasing a semver pre-release (e.g. `1.0.0-rc1`) with
+    /// `git_release_type = "prod"` is an error instead of a warning.
+    pub git_release_type_strict: Option<bool>,
+    /// # Git Release Never Prerelease
+    /// If `Some(true)`, the git release is always marked as ready for production,
+    /// even if the version is a semver pre-release. Overrides `git_release_type`.
+    pub git_release_never_prerelease: Option<bool>,
+    /// # Git Release Expect Existing Tag
+    /// If `Some(true)`, release-plz doesn't create the git tag and creates the
+    /// git release for the tag pushed by someone else, failing if the tag doesn't exist.
+    pub git_release_expect_existing_tag: Option<bool>,
+    /// # Git Release After Tag Push
+    /// If `Some(true)`, release-plz waits for the git forge to know the pushed tag
+    /// before creating the git release.
+    /// If `Some(false)` or [`Option::None`], the git release is created right after the tag push.
+    pub git_release_after_tag_push: Option<bool>,
+    /// # Git Release Latest
+    /// Whether to mark the release as the latest release of the repository.
+    /// If unset, the git forge decides. A draft or a pre-release can't be marked as latest.
+    pub git_release_latest: Option<bool>,
     /// # Git Release Draft
     /// If true, will not auto-publish the release.
     pub git_release_draft: Option<bool>,
+    /// # Git Release Backfill
+    /// If true, create the git release of already existing tags that don't have one.
+    pub git_release_backfill: Option<bool>,
+    /// # Git Release Publish Draft On Success
+    /// If true and `git_release_draft` is true, don't create the release as draft
+    /// if the package is published to the cargo registry.
+    pub git_release_publish_draft_on_success: Option<bool>,
+    /// # Git Tag Annotated
+    /// If `Some(false)`, create a lightweight git tag instead of an annotated one.
+    /// Annotated by default.
+    pub git_tag_annotated: Option<bool>,
     /// # Git Tag Enable
     /// Publish the git tag for the new package version.
     /// Enabled by default.
     pub git_tag_enable: Option<bool>,
+    /// # Git Tag Message
+    /// Template of the message of annotated git tags.
+    /// `{{ package }}`, `{{ version }}`, `{{ tag }}`, `{{ date }}` and `{{ changelog }}` are
+    /// replaced with the package name, the new version, the git tag, the release date
+    /// and the changes of the release.
+    pub git_tag_message: Option<String>,
+    /// # Git Tag Name
+    /// Template of the name of the git tag.
+    /// `{{ package }}`, `{{ version }}` and `{{ date }}` are replaced with the package name,
+    /// the new version and the release date, e.g. `v{{ version }}-{{ date }}`.
+    /// If unspecified, the tag is `v{{ version }}`, or `{{ package }}-v{{ version }}`
+    /// in workspaces with multiple public packages.
+    pub git_tag_name: Option<String>,
+    /// # Git Tag Date Format
+    /// Format of `{{ date }}` in `git_tag_name` and `git_tag_message`, e.g. `%Y.%m`.
+    /// Default: `%Y-%m-%d`.
+    pub git_tag_date_format: Option<String>,
+    /// # Check MSRV
+    /// If `Some(true)`, before publishing, check that the `rust-version` of the package
+    /// isn't newer than the Rust toolchain used to publish it.
+    pub check_msrv: Option<bool>,
     /// # Publish
     /// If `Some(false)`, don't run `cargo publish`.
     pub publish: Option<bool>,
     /// # Publish Allow Dirty
     /// If `Some(true)`, add the `--allow-dirty` flag to the `cargo publish` command.
     pub publish_allow_dirty: Option<bool>,
+    /// # Publish Allow Dirty Auto
+    /// If `Some(true)`, add the `--allow-dirty` flag to the `cargo publish` command
+    /// only if the uncommitted changes are in files written by release-plz,
+    /// i.e. the manifests, the lockfile, the changelog and the `version_files`.
+    pub publish_allow_dirty_auto: Option<bool>,
     /// # Publish No Verify
     /// If `Some(true)`, add the `--no-verify` flag to the `cargo publish` command.
     pub publish_no_verify: Option<bool>,
+    /// # Publish Registries
+    /// Registries where the package is published, in order.
+    /// If unspecified, the `publish` field of the package manifest is used.
+    /// The versions already published are looked up in the first registry.
+    pub publish_registries: Option<Vec<String>>,
     /// # Semver Check
     /// Controls when to run cargo-semver-checks.
     /// If unspecified, run cargo-semver-checks if the package is a library.
     pub semver_check: Option<bool>,
+    /// # Semver Check Baseline Ref
+    /// Git revision (e.g. tag or branch) to run cargo-semver-checks against.
+    /// If unspecified, cargo-semver-checks compares the package with the version published in the registry.
+    pub semver_check_baseline_ref: Option<String>,
     /// # Release
     /// Used to toggle off the update/release process for a workspace or package.
     pub release: Option<bool>,
+    /// # Release Commits
+    /// Regex matching the commit messages that can trigger a release.
+    /// If no commit since the last release matches, the package isn't released.
+    /// If unspecified, every commit can trigger a release.
+    pub release_commits: Option<String>,
+    /// # Since
+    /// Git revision (e.g. tag, branch or commit) used as starting point of the release:
+    /// only the commits after it are used to compute the next version and the changelog.
+    /// If unspecified, release-plz starts from the last released version.
+    pub since: Option<String>,
+    /// # Version Files
+    /// Files containing the version of the package, besides `Cargo.toml`,
+    /// relative to the package directory.
+    /// A path can be followed by `:` and the text containing the version,
+    /// with `{version}` in place of the version, e.g. `src/version.rs:pub const VERSION: &str = "{version}";`.
+    /// Otherwise, every occurrence of the current version in the file is replaced.
+    pub version_files: Option<Vec<String>>,
+    /// # Zero Based Versioning
+    /// If `true`, `0.0.z` versions are only incremented in the last segment,
+    /// even if a bigger increment is requested, until the version is manually promoted.
+    pub zero_based_versioning: Option<bool>,
+    /// # Env
+    /// Environment variables set when running `cargo publish`.
+    /// Values can reference existing environment variables with `${VAR}`.
+    pub env: Option<HashMap<String, String>>,
 }
 
 impl From<PackageConfig> for release_plz_core::UpdateConfig {
     fn from(config: PackageConfig) -> Self {
+        let git_tag_name = config.git_tag_name();
         Self {
             semver_check: config.semver_check != Some(false),
+            semver_check_baseline_ref: config.semver_check_baseline_ref,
+            changelog_tag_pattern: config.changelog_tag_pattern,
+            release_commits: config.release_commits,
+            since: config.since,
             changelog_update: config.changelog_update != Some(false),
+            changelog_dependency_note: config.changelog_dependency_note,
+            changelog_group_by_path: config
+                .changelog_group_by_path
+                .unwrap_or_default()
+                .into_iter()
+                .collect(),
+            changelog_require_nonempty: config.changelog_require_nonempty == Some(true),
+            publish_registry: config
+                .publish_registries
+                .and_then(|registries| registries.into_iter().next()),
+            version_files: config.version_files.unwrap_or_default(),
+            zero_based_versioning: config.zero_based_versioning == Some(true),
             release: config.release != Some(false),
+            git_tag_name,
         }
     }
 }
@@ -257,26 +972,123 @@ impl From<PackageSpecificConfig> for release_plz_core::PackageUpdateConfig {
             generic: config.common.into(),
             changelog_path: config.changelog_path,
             changelog_include: config.changelog_include.unwrap_or_default(),
+            changelog_exclude: config.changelog_exclude.unwrap_or_default(),
+            changelog_include_mode: config.changelog_include_mode.unwrap_or_default().into(),
+            changelog_include_order: config.changelog_include_order.unwrap_or_default().into(),
         }
     }
 }
 
 impl PackageConfig {
+    fn git_tag_name(&self) -> release_plz_core::GitTagName {
+        release_plz_core::GitTagName::default()
+            .set_template(self.git_tag_name.clone())
+            .set_date_format(self.git_tag_date_format.clone())
+    }
+
+    /// Set the unspecified settings to their default value.
+    pub fn resolved(self) -> Self {
+        let defaults = Self {
+            changelog_update: Some(true),
+            changelog_require_nonempty: Some(false),
+            git_release_enable: Some(true),
+            git_release_type: Some(ReleaseType::default()),
+            git_release_type_strict: Some(false),
+            git_release_never_prerelease: Some(false),
+            git_release_expect_existing_tag: Some(false),
+            git_release_after_tag_push: Some(false),
+            git_release_draft: Some(false),
+            git_release_backfill: Some(false),
+            git_release_publish_draft_on_success: Some(false),
+            git_tag_annotated: Some(true),
+            git_tag_enable: Some(true),
+            check_msrv: Some(false),
+            publish: Some(true),
+            publish_allow_dirty: Some(false),
+            publish_allow_dirty_auto: Some(false),
+            publish_no_verify: Some(false),
+            semver_check: Some(true),
+            release: Some(true),
+            zero_based_versioning: Some(false),
+            ..Default::default()
+        };
+        self.merge(defaults)
+    }
+
     /// Merge the package-specific configuration with the global configuration.
     pub fn merge(self, default: Self) -> Self {
         Self {
             semver_check: self.semver_check.or(default.semver_check),
+            semver_check_baseline_ref: self
+                .semver_check_baseline_ref
+                .or(default.semver_check_baseline_ref),
             changelog_update: self.changelog_update.or(default.changelog_update),
+            changelog_tag_pattern: self.changelog_tag_pattern.or(default.changelog_tag_pattern),
+            changelog_dependency_note: self
+                .changelog_dependency_note
+                .or(default.changelog_dependency_note),
+            changelog_group_by_path: self
+                .changelog_group_by_path
+                .or(default.changelog_group_by_path),
+            changelog_require_nonempty: self
+                .changelog_require_nonempty
+                .or(default.changelog_require_nonempty),
             git_release_enable: self.git_release_enable.or(default.git_release_enable),
             git_release_type: self.git_release_type.or(default.git_release_type),
+            git_release_type_strict: self
+                .git_release_type_strict
+                .or(default.git_release_type_strict),
+            git_release_never_prerelease: self
+                .git_release_never_prerelease
+                .or(default.git_release_never_prerelease),
+            git_release_expect_existing_tag: self
+                .git_release_expect_existing_tag
+                .or(default.git_release_expect_existing_tag),
+            git_release_after_tag_push: self
+                .git_release_after_tag_push
+                .or(default.git_release_after_tag_push),
+            git_release_latest: self.git_release_latest.or(default.git_release_latest),
             git_release_draft: self.git_release_draft.or(default.git_release_draft),
+            git_release_backfill: self.git_release_backfill.or(default.git_release_backfill),
+            git_release_publish_draft_on_success: self
+                .git_release_publish_draft_on_success
+                .or(default.git_release_publish_draft_on_success),
 
+            check_msrv: self.check_msrv.or(default.check_msrv),
             publish: self.publish.or(default.publish),
             publish_allow_dirty: self.publish_allow_dirty.or(default.publish_allow_dirty),
+            publish_allow_dirty_auto: self
+                .publish_allow_dirty_auto
+                .or(default.publish_allow_dirty_auto),
             publish_no_verify: self.publish_no_verify.or(default.publish_no_verify),
+            publish_registries: self.publish_registries.or(default.publish_registries),
+            git_tag_annotated: self.git_tag_annotated.or(default.git_tag_annotated),
             git_tag_enable: self.git_tag_enable.or(default.git_tag_enable),
+            git_tag_message: self.git_tag_message.or(default.git_tag_message),
+            git_tag_name: self.git_tag_name.or(default.git_tag_name),
+            git_tag_date_format: self.git_tag_date_format.or(default.git_tag_date_format),
             release: self.release.or(default.release),
+            release_commits: self.release_commits.or(default.release_commits),
+            since: self.since.or(default.since),
+            version_files: self.version_files.or(default.version_files),
+            zero_based_versioning: self.zero_based_versioning.or(default.zero_based_versioning),
+            env: merge_env(self.env, default.env),
+        }
+    }
+}
+
+/// Merge the package environment variables with the default ones.
+/// Package variables override default variables with the same name.
+fn merge_env(
+    env: Option<HashMap<String, String>>,
+    default: Option<HashMap<String, String>>,
+) -> Option<HashMap<String, String>> {
+    match (env, default) {
+        (Some(env), Some(mut default)) => {
+            default.extend(env);
+            Some(default)
         }
+        (env, default) => env.or(default),
     }
 }
 
@@ -292,6 +1104,60 @@ pub enum SemverCheck {
     No,
 }
 
+#[derive(Serialize, Deserialize, Default, PartialEq, Eq, Debug, Clone, Copy, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PublishTimeoutScope {
+    /// # Per Package
+    /// The timeout resets for each package.
+    #[default]
+    PerPackage,
+    /// # Total
+    /// The timeout caps the whole publish phase.
+    Total,
+}
+
+#[derive(Serialize, Deserialize, Default, PartialEq, Eq, Debug, Clone, Copy, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum OnError {
+    /// # Stop
+    /// Don't release the remaining packages.
+    /// The packages released before the failure stay released.
+    #[default]
+    Stop,
+    /// # Continue
+    /// Release the remaining packages, then report the status of each package.
+    Continue,
+}
+
+impl From<OnError> for release_plz_core::OnError {
+    fn from(on_error: OnError) -> Self {
+        match on_error {
+            OnError::Stop => Self::Stop,
+            OnError::Continue => Self::Continue,
+        }
+    }
+}
+
+impl From<PublishTimeoutScope> for release_plz_core::PublishTimeoutScope {
+    fn from(scope: PublishTimeoutScope) -> Self {
+        match scope {
+            PublishTimeoutScope::PerPackage => Self::PerPackage,
+            PublishTimeoutScope::Total => Self::Total,
+        }
+    }
+}
+
+impl From<ReleaseType> for release_plz_core::ReleaseType {
+    fn from(release_type: ReleaseType) -> Self {
+        match release_type {
+            ReleaseType::Prod => Self::Prod,
+            ReleaseType::Pre => Self::Pre,
+            ReleaseType::Auto => Self::Auto,
+            ReleaseType::Draft => Self::Draft,
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Default, PartialEq, Eq, Debug, Clone, Copy, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ReleaseType {
@@ -308,6 +1174,191 @@ pub enum ReleaseType {
     /// in case there is a semver pre-release in the tag e.g. v1.0.0-rc1.
     /// Otherwise, will mark the release as ready for production.
     Auto,
+    /// # Draft
+    /// Will create the release as draft, like `git_release_draft = true`.
+    /// The release isn't marked as pre-release.
+    Draft,
+}
+
+#[derive(Serialize, Deserialize, Default, PartialEq, Eq, Debug, Clone, Copy, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangelogIncludeMode {
+    /// # Inline
+    /// Add the commits of the included packages to the changelog.
+    #[default]
+    Inline,
+    /// # Link
+    /// Add an entry linking to the release of each included package.
+    Link,
+}
+
+impl From<ChangelogIncludeMode> for release_plz_core::ChangelogIncludeMode {
+    fn from(mode: ChangelogIncludeMode) -> Self {
+        match mode {
+            ChangelogIncludeMode::Inline => Self::Inline,
+            ChangelogIncludeMode::Link => Self::Link,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Default, PartialEq, Eq, Debug, Clone, Copy, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangelogIncludeOrder {
+    /// # Declaration
+    /// Include the changelogs in the order of `changelog_include`.
+    #[default]
+    Declaration,
+    /// # Dependencies
+    /// Include the changelogs of the dependencies before the ones of the packages
+    /// depending on them.
+    Dependencies,
+}
+
+impl From<ChangelogIncludeOrder> for release_plz_core::ChangelogIncludeOrder {
+    fn from(order: ChangelogIncludeOrder) -> Self {
+        match order {
+            ChangelogIncludeOrder::Declaration => Self::Declaration,
+            ChangelogIncludeOrder::Dependencies => Self::Dependencies,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Copy, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangelogCommitSort {
+    /// # Newest
+    /// Newest commits first.
+    Newest,
+    /// # Oldest
+    /// Oldest commits first.
+    Oldest,
+}
+
+impl From<ChangelogCommitSort> for release_plz_core::CommitSort {
+    fn from(sort: ChangelogCommitSort) -> Self {
+        match sort {
+            ChangelogCommitSort::Newest => Self::Newest,
+            ChangelogCommitSort::Oldest => Self::Oldest,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Copy, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SemverCheckMissing {
+    /// # Error
+    /// Fail the run.
+    Error,
+    /// # Warn
+    /// Log a warning and skip the semver check.
+    Warn,
+    /// # Skip
+    /// Skip the semver check.
+    Skip,
+}
+
+impl From<SemverCheckMissing> for release_plz_core::SemverCheckMissing {
+    fn from(missing: SemverCheckMissing) -> Self {
+        match missing {
+            SemverCheckMissing::Error => Self::Error,
+            SemverCheckMissing::Warn => Self::Warn,
+            SemverCheckMissing::Skip => Self::Skip,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Copy, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangelogSource {
+    /// # Commits
+    /// Use the commit messages.
+    Commits,
+    /// # Pull Requests
+    /// Use the titles of the Pull Requests merged since the last tag.
+    PullRequests,
+}
+
+impl From<ChangelogSource> for release_plz_core::ChangelogSource {
+    fn from(source: ChangelogSource) -> Self {
+        match source {
+            ChangelogSource::Commits => Self::Commits,
+            ChangelogSource::PullRequests => Self::PullRequests,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Copy, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangelogFormat {
+    /// # Markdown
+    /// `CHANGELOG.md`, rendered with the git-cliff template.
+    Markdown,
+    /// # Json
+    /// `CHANGELOG.json`, a list of the releases with their sections and entries.
+    Json,
+    /// # Rst
+    /// `CHANGELOG.rst`, in reStructuredText.
+    Rst,
+}
+
+impl From<ChangelogFormat> for release_plz_core::ChangelogFormat {
+    fn from(format: ChangelogFormat) -> Self {
+        match format {
+            ChangelogFormat::Markdown => Self::Markdown,
+            ChangelogFormat::Json => Self::Json,
+            ChangelogFormat::Rst => Self::Rst,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Default, PartialEq, Eq, Debug, Clone, Copy, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum LineEnding {
+    /// # Lf
+    /// Use `\n`.
+    #[default]
+    Lf,
+    /// # Crlf
+    /// Use `\r\n`.
+    Crlf,
+    /// # Auto
+    /// Use the line ending of most lines of the existing changelog.
+    /// If the changelog doesn't exist, use `\n`.
+    Auto,
+}
+
+impl From<LineEnding> for release_plz_core::LineEnding {
+    fn from(line_ending: LineEnding) -> Self {
+        match line_ending {
+            LineEnding::Lf => Self::Lf,
+            LineEnding::Crlf => Self::Crlf,
+            LineEnding::Auto => Self::Auto,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Copy, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BumpLevel {
+    /// # Major
+    /// Treat the commit as a breaking change.
+    Major,
+    /// # Minor
+    /// Treat the commit as a feature.
+    Minor,
+    /// # Patch
+    /// Treat the commit as a fix.
+    Patch,
+}
+
+impl From<BumpLevel> for VersionIncrement {
+    fn from(level: BumpLevel) -> Self {
+        match level {
+            BumpLevel::Major => Self::Major,
+            BumpLevel::Minor => Self::Minor,
+            BumpLevel::Patch => Self::Patch,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -335,20 +1386,78 @@ mod tests {
         Config {
             workspace: Workspace {
                 dependencies_update: Some(false),
-                changelog_config: Some("../git-cliff.toml".into()),
+                changelog_config: Some(ChangelogConfigPath::Single("../git-cliff.toml".into())),
+                changelog_config_autodiscover: None,
+                changelog_repo_url: None,
+                changelog_strip_prefix: None,
+                changelog_header_links: None,
+                changelog_commit_links: None,
+                changelog_max_entries: None,
+                changelog_context: None,
+                changelog_sort_by_scope: None,
+                changelog_commit_sort: None,
+                changelog_source: None,
+                changelog_credit_authors: None,
+                changelog_format: None,
+                changelog_skip_ci_commits: None,
+                changelog_line_ending: None,
+                commit_lockfile: None,
+                changelog_unreleased_section: None,
+                changelog_dedup_existing: None,
+                changelog_include_merge_commits: None,
                 allow_dirty: Some(false),
+                changelog_diff: None,
+                base_branch: None,
+                forge_api_retries: None,
+                forge_api_timeout: None,
+                git_push_remote: None,
+                git_release_api_headers: None,
+                git_release_branches: None,
+                git_release_type_trailer: None,
+                git_token_env: None,
+                git_user_name: None,
+                git_user_email: None,
+                github_output: None,
+                idempotent: None,
+                jobs: None,
+                on_error: None,
+                only: None,
+                release_commit_marker: None,
+                release_pr: None,
                 repo_url: Some("https://github.com/MarcoIeni/release-plz".parse().unwrap()),
+                semver_check_report_in_pr: None,
+                semver_check_missing: None,
+                skip_yanked_baseline: None,
+                update_lockfile: None,
+                verbose_plan: None,
+                version_bump_rules: None,
+                workspace_changelog_path: None,
                 packages_defaults: PackageConfig {
                     semver_check: None,
+                    semver_check_baseline_ref: None,
+                    changelog_tag_pattern: None,
                     changelog_update: None,
+                    changelog_dependency_note: None,
                     git_release_enable: Some(true),
                     git_release_type: Some(ReleaseType::Prod),
+                    git_release_type_strict: None,
                     git_release_draft: Some(false),
+                    git_release_backfill: None,
+                    git_release_publish_draft_on_success: None,
+                    release_commits: None,
+                    since: None,
+                    env: None,
                     ..Default::default()
                 },
+                pr_closes_issues: None,
                 pr_draft: false,
                 pr_labels: vec![],
+                pr_marker_label: None,
+                pr_pre_hook: None,
+                publish_require_token: None,
+                publish_token_env: None,
                 publish_timeout: Some("10m".to_string()),
+                publish_timeout_scope: None,
             },
             package: [].into(),
         }
@@ -360,14 +1469,26 @@ mod tests {
             config: PackageSpecificConfig {
                 common: PackageConfig {
                     semver_check: None,
+                    semver_check_baseline_ref: None,
+                    changelog_tag_pattern: None,
                     changelog_update: None,
+                    changelog_dependency_note: None,
                     git_release_enable: None,
                     git_release_type: None,
+                    git_release_type_strict: None,
                     git_release_draft: None,
+                    git_release_backfill: None,
+                    git_release_publish_draft_on_success: None,
+                    release_commits: None,
+                    since: None,
+                    env: None,
                     ..Default::default()
                 },
                 changelog_path: None,
                 changelog_include: None,
+                changelog_exclude: None,
+                changelog_include_mode: None,
+                changelog_include_order: None,
             },
         }
     }
@@ -445,41 +1566,617 @@ mod tests {
         config_workspace_release_is_deserialized("false", false);
     }
 
+    #[test]
+    fn git_release_api_headers_are_set_on_the_git_backend() {
+        let config = r#"
+            [workspace.git_release_api_headers]
+            X-Proxy-Auth = "secret"
+        "#;
+        let config: Config = toml::from_str(config).unwrap();
+        let github = release_plz_core::GitHub::new(
+            "owner".to_string(),
+            "repo".to_string(),
+            secrecy::SecretString::from("token".to_string()),
+        );
+        let backend = config
+            .workspace
+            .configure_git_backend(GitBackend::Github(github))
+            .unwrap();
+        let GitBackend::Github(github) = backend else {
+            panic!("unexpected git backend {backend:?}");
+        };
+        assert_eq!(
+            github.remote.custom_headers,
+            HashMap::from([("X-Proxy-Auth".to_string(), "secret".to_string())])
+        );
+    }
+
+    #[test]
+    fn only_restricts_processed_packages() {
+        use release_plz_core::RequestReleaseValidator as _;
+
+        let config = r#"
+            [workspace]
+            only = ["crate1", "crate2"]
+
+            [[package]]
+            name = "crate2"
+            semver_check = false
+
+            [[package]]
+            name = "crate3"
+            release = true
+        "#;
+        let config: Config = toml::from_str(config).unwrap();
+        let metadata = fake_package::metadata::fake_metadata();
+
+        let update_request = UpdateRequest::new(metadata.clone()).unwrap();
+        let update_request = config.fill_update_config(false, update_request);
+        assert!(update_request.is_release_enabled("crate1"));
+        assert!(update_request.is_release_enabled("crate2"));
+        assert!(!update_request.get_package_config("crate2").semver_check());
+        assert!(!update_request.is_release_enabled("crate3"));
+        assert!(!update_request.is_release_enabled("crate4"));
+
+        let release_request =
+            config.fill_release_config(false, false, ReleaseRequest::new(metadata));
+        assert!(release_request.is_release_enabled("crate1"));
+        assert!(release_request.is_release_enabled("crate2"));
+        assert!(!release_request.is_release_enabled("crate3"));
+        assert!(!release_request.is_release_enabled("crate4"));
+    }
+
+    #[test]
+    fn workspace_publish_no_verify_can_be_disabled_per_package() {
+        let config = r#"
+            [workspace]
+            publish_no_verify = true
+
+            [[package]]
+            name = "crate1"
+            publish_no_verify = false
+        "#;
+        let config: Config = toml::from_str(config).unwrap();
+        for cli_no_verify in [false, true] {
+            let metadata = fake_package::metadata::fake_metadata();
+            let release_request =
+                config.fill_release_config(false, cli_no_verify, ReleaseRequest::new(metadata));
+            assert!(!release_request.no_verify("crate1"));
+            assert!(release_request.no_verify("crate2"));
+        }
+    }
+
+    #[test]
+    fn package_config_overrides_cli_publish_flags() {
+        let config = r#"
+            [workspace]
+            publish_no_verify = false
+
+            [[package]]
+            name = "crate1"
+            publish_no_verify = false
+            publish_allow_dirty = false
+
+            [[package]]
+            name = "crate2"
+            semver_check = false
+        "#;
+        let config: Config = toml::from_str(config).unwrap();
+        let metadata = fake_package::metadata::fake_metadata();
+        let release_request = config.fill_release_config(true, true, ReleaseRequest::new(metadata));
+        assert!(!release_request.no_verify("crate1"));
+        assert!(!release_request.allow_dirty("crate1"));
+        assert!(release_request.no_verify("crate2"));
+        assert!(release_request.allow_dirty("crate2"));
+        assert!(release_request.no_verify("crate3"));
+        assert!(release_request.allow_dirty("crate3"));
+    }
+
+    #[test]
+    fn effective_package_config_merges_workspace_defaults() {
+        let config = r#"
+            [workspace]
+            semver_check = false
+            changelog_update = false
+            publish = false
+
+            [[package]]
+            name = "crate1"
+            changelog_update = true
+            git_release_draft = true
+        "#;
+        let config: Config = toml::from_str(config).unwrap();
+        let expected = PackageConfig {
+            semver_check: Some(false),
+            changelog_update: Some(true),
+            git_release_draft: Some(true),
+            publish: Some(false),
+            ..Default::default()
+        };
+        assert_eq!(config.effective_package_config("crate1"), Some(expected));
+    }
+
+    #[test]
+    fn effective_package_config_of_package_excluded_by_only_is_not_released() {
+        let config = r#"
+            [workspace]
+            only = ["crate2"]
+
+            [[package]]
+            name = "crate1"
+            semver_check = false
+        "#;
+        let config: Config = toml::from_str(config).unwrap();
+        let expected = PackageConfig {
+            semver_check: Some(false),
+            release: Some(false),
+            ..Default::default()
+        };
+        assert_eq!(config.effective_package_config("crate1"), Some(expected));
+        assert_eq!(
+            config.effective_package_config("crate2"),
+            Some(PackageConfig::default())
+        );
+    }
+
+    #[test]
+    fn effective_package_config_of_unconfigured_package_is_none() {
+        let config: Config = toml::from_str("").unwrap();
+        assert_eq!(config.effective_package_config("crate1"), None);
+    }
+
+    #[test]
+    fn negated_label_removes_inherited_label() {
+        let inherited = ["foo".to_string(), "bar".to_string()];
+        let labels = ["!foo".to_string(), "baz".to_string()];
+        assert_eq!(merge_labels(&inherited, &labels), vec!["bar", "baz"]);
+    }
+
+    #[test]
+    fn escaped_negation_adds_label_starting_with_exclamation_mark() {
+        let config = r#"
+            [workspace]
+            pr_labels = ["\\!important", "release", "!release"]
+        "#;
+        let config: Config = toml::from_str(config).unwrap();
+        assert_eq!(config.workspace.pr_labels(), vec!["!important"]);
+    }
+
+    #[test]
+    fn configured_package_names_are_in_declaration_order() {
+        let config = r#"
+            [[package]]
+            name = "pkg_c"
+
+            [[package]]
+            name = "pkg_a"
+            publish = false
+
+            [[package]]
+            name = "pkg_b"
+        "#;
+        let config: Config = toml::from_str(config).unwrap();
+        assert_eq!(
+            config.configured_package_names(),
+            ["pkg_c", "pkg_a", "pkg_b"]
+        );
+    }
+
+    const DISCOVERED_CONFIG: &str = r#"
+        [workspace]
+        pr_draft = true
+    "#;
+
+    #[test]
+    fn config_is_discovered_in_each_file_name() {
+        for name in ["release-plz.toml", ".release-plz.toml"] {
+            let dir = tempfile::tempdir().unwrap();
+            let path = dir.path().join(name);
+            std::fs::write(&path, DISCOVERED_CONFIG).unwrap();
+            let (config, discovered_path) = Config::discover(dir.path()).unwrap();
+            assert!(config.workspace.pr_draft);
+            assert_eq!(discovered_path, Some(path));
+        }
+    }
+
+    #[test]
+    fn config_discovery_fails_if_both_files_exist() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["release-plz.toml", ".release-plz.toml"] {
+            std::fs::write(dir.path().join(name), DISCOVERED_CONFIG).unwrap();
+        }
+        let error = Config::discover(dir.path()).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            format!(
+                "both `release-plz.toml` and `.release-plz.toml` exist in {:?}. Remove one of them",
+                dir.path()
+            )
+        );
+    }
+
+    #[test]
+    fn default_config_is_used_if_no_file_is_discovered() {
+        let dir = tempfile::tempdir().unwrap();
+        let (config, path) = Config::discover(dir.path()).unwrap();
+        assert_eq!(config, Config::default());
+        assert_eq!(path, None);
+    }
+
+    #[test]
+    fn diamond_changelog_include_is_flattened() {
+        let config = r#"
+            [[package]]
+            name = "a"
+            changelog_include = ["b", "c"]
+
+            [[package]]
+            name = "b"
+            changelog_include = ["d"]
+
+            [[package]]
+            name = "c"
+            changelog_include = ["d"]
+        "#;
+        let config: Config = toml::from_str(config).unwrap();
+        let includes = config
+            .flattened_changelog_includes(&["a", "b", "c", "d"])
+            .unwrap();
+        assert_eq!(includes["a"], BTreeSet::from(["b", "c", "d"]));
+        assert_eq!(includes["b"], BTreeSet::from(["d"]));
+        assert_eq!(includes["c"], BTreeSet::from(["d"]));
+    }
+
+    #[test]
+    fn excluded_changelog_is_not_followed() {
+        let config = r#"
+            [[package]]
+            name = "a"
+            changelog_include = ["b", "c"]
+            changelog_exclude = ["c"]
+
+            [[package]]
+            name = "b"
+            changelog_include = ["d"]
+        "#;
+        let config: Config = toml::from_str(config).unwrap();
+        let includes = config
+            .flattened_changelog_includes(&["a", "b", "c", "d"])
+            .unwrap();
+        assert_eq!(includes["a"], BTreeSet::from(["b", "d"]));
+    }
+
+    #[test]
+    fn changelog_include_glob_is_expanded_before_flattening() {
+        let config = r#"
+            [[package]]
+            name = "cli"
+            changelog_include = ["api-*"]
+
+            [[package]]
+            name = "api-server"
+            changelog_include = ["utils"]
+        "#;
+        let config: Config = toml::from_str(config).unwrap();
+        let members = ["cli", "api-server", "api-client", "utils"];
+        let includes = config.flattened_changelog_includes(&members).unwrap();
+        assert_eq!(
+            includes["cli"],
+            BTreeSet::from(["api-client", "api-server", "utils"])
+        );
+    }
+
+    #[test]
+    fn changelog_include_glob_doesnt_include_own_changelog() {
+        let config = r#"
+            [[package]]
+            name = "api-server"
+            changelog_include = ["api-*"]
+        "#;
+        let config: Config = toml::from_str(config).unwrap();
+        let members = ["cli", "api-server", "api-client"];
+        let includes = config.flattened_changelog_includes(&members).unwrap();
+        assert_eq!(includes["api-server"], BTreeSet::from(["api-client"]));
+    }
+
+    #[test]
+    fn excluded_changelog_is_removed_from_glob() {
+        let config = r#"
+            [[package]]
+            name = "cli"
+            changelog_include = ["api-*"]
+            changelog_exclude = ["api-server"]
+        "#;
+        let config: Config = toml::from_str(config).unwrap();
+        let members = ["cli", "api-server", "api-client", "api-types"];
+        let includes = config.flattened_changelog_includes(&members).unwrap();
+        assert_eq!(includes["cli"], BTreeSet::from(["api-client", "api-types"]));
+    }
+
+    #[test]
+    fn changelog_include_cycle_is_an_error() {
+        let config = r#"
+            [[package]]
+            name = "a"
+            changelog_include = ["b"]
+
+            [[package]]
+            name = "b"
+            changelog_include = ["c"]
+
+            [[package]]
+            name = "c"
+            changelog_include = ["b"]
+        "#;
+        let config: Config = toml::from_str(config).unwrap();
+        let error = config
+            .flattened_changelog_includes(&["a", "b", "c"])
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "cycle in `changelog_include`: `b` -> `c` -> `b`"
+        );
+    }
+
+    #[test]
+    fn nested_changelog_context_is_rendered_in_changelog() {
+        let config = r#"
+            [workspace.changelog_context]
+            product_name = "Acme"
+            support = { url = "https://acme.com/support" }
+        "#;
+        let config: Config = toml::from_str(config).unwrap();
+        let commits = vec![git_cliff_core::commit::Commit::new(
+            "1111111".to_string(),
+            "fix: myfix".to_string(),
+        )];
+        let changelog = release_plz_core::ChangelogBuilder::new(commits, "1.1.1")
+            .with_context(config.workspace.changelog_context.unwrap().0)
+            .with_config(git_cliff_core::config::Config {
+                changelog: git_cliff_core::config::ChangelogConfig {
+                    body: Some(
+                        "{{ context.product_name }} {{ version }}: {{ context.support.url }}"
+                            .to_string(),
+                    ),
+                    ..Default::default()
+                },
+                git: git_cliff_core::config::GitConfig::default(),
+            })
+            .build();
+        assert_eq!(
+            changelog.generate().unwrap(),
+            "Acme 1.1.1: https://acme.com/support"
+        );
+    }
+
+    #[test]
+    fn package_env_overrides_workspace_env() {
+        let config = r#"
+            [workspace]
+            env = { CARGO_BUILD_TARGET = "x86_64-unknown-linux-gnu", RUSTFLAGS = "-Dwarnings" }
+
+            [[package]]
+            name = "crate1"
+            env = { CARGO_BUILD_TARGET = "wasm32-unknown-unknown" }
+        "#;
+        let config: Config = toml::from_str(config).unwrap();
+        let env = config.effective_package_config("crate1").unwrap().env;
+        let expected = HashMap::from([
+            (
+                "CARGO_BUILD_TARGET".to_string(),
+                "wasm32-unknown-unknown".to_string(),
+            ),
+            ("RUSTFLAGS".to_string(), "-Dwarnings".to_string()),
+        ]);
+        assert_eq!(env, Some(expected));
+    }
+
+    #[test]
+    fn disabling_publish_keeps_git_tag_and_release() {
+        let config = PackageConfig {
+            publish: Some(false),
+            ..Default::default()
+        };
+        let release_config: release_plz_core::ReleaseConfig = config.into();
+        assert!(!release_config.publish().is_enabled());
+        assert!(release_config.git_tag().is_enabled());
+        assert!(release_config.git_release().is_enabled());
+    }
+
+    #[test]
+    fn disabling_publish_with_explicit_git_release_keeps_git_tag_and_release() {
+        let config = PackageConfig {
+            publish: Some(false),
+            git_release_enable: Some(true),
+            git_tag_enable: Some(true),
+            ..Default::default()
+        };
+        let release_config: release_plz_core::ReleaseConfig = config.into();
+        assert!(!release_config.publish().is_enabled());
+        assert!(release_config.git_tag().is_enabled());
+        assert!(release_config.git_release().is_enabled());
+    }
+
+    #[test]
+    fn disabling_git_release_keeps_git_tag() {
+        let config = PackageConfig {
+            git_release_enable: Some(false),
+            git_tag_enable: Some(true),
+            ..Default::default()
+        };
+        let release_config: release_plz_core::ReleaseConfig = config.into();
+        assert!(release_config.publish().is_enabled());
+        assert!(release_config.git_tag().is_enabled());
+        assert!(!release_config.git_release().is_enabled());
+    }
+
+    #[test]
+    fn git_tag_name_is_used_by_update_and_release() {
+        let config = r#"
+            [workspace]
+            git_tag_name = "v{{ version }}-{{ date }}"
+            git_tag_date_format = "%Y.%m"
+        "#;
+        let config: Config = toml::from_str(config).unwrap();
+        let expected = release_plz_core::GitTagName::default()
+            .set_template(Some("v{{ version }}-{{ date }}".to_string()))
+            .set_date_format(Some("%Y.%m".to_string()));
+        let release_config: release_plz_core::ReleaseConfig =
+            config.default_package_config().into();
+        assert_eq!(release_config.git_tag().name(), &expected);
+        let update_config: release_plz_core::UpdateConfig = config.default_package_config().into();
+        assert_eq!(update_config.git_tag_name, expected);
+    }
+
+    #[test]
+    fn package_can_be_tagged_without_git_release() {
+        let config = r#"
+            [workspace]
+            git_release_enable = true
+
+            [[package]]
+            name = "tagged"
+            git_release_enable = false
+        "#;
+        let config: Config = toml::from_str(config).unwrap();
+        let packages = config.merged_packages();
+        let release_config: release_plz_core::PackageReleaseConfig =
+            packages["tagged"].clone().into();
+        assert!(release_config.generic.git_tag().is_enabled());
+        assert!(!release_config.generic.git_release().is_enabled());
+
+        let default_config: release_plz_core::ReleaseConfig =
+            config.default_package_config().into();
+        assert!(default_config.git_tag().is_enabled());
+        assert!(default_config.git_release().is_enabled());
+    }
+
+    #[test]
+    fn forge_api_settings_are_parsed() {
+        let config = r#"
+            [workspace]
+            forge_api_retries = 5
+            forge_api_timeout = "30s"
+        "#;
+        let config: Config = toml::from_str(config).unwrap();
+        assert_eq!(config.workspace.forge_api_retries, Some(5));
+        assert_eq!(
+            config.workspace.forge_api_timeout().unwrap(),
+            Some(Duration::from_secs(30))
+        );
+    }
+
+    #[test]
+    fn forge_api_timeout_defaults_to_none() {
+        let config: Config = toml::from_str("").unwrap();
+        assert_eq!(config.workspace.forge_api_timeout().unwrap(), None);
+    }
+
     #[test]
     fn config_is_serialized() {
         let config = Config {
             workspace: Workspace {
                 dependencies_update: None,
-                changelog_config: Some("../git-cliff.toml".into()),
+                changelog_config: Some(ChangelogConfigPath::Single("../git-cliff.toml".into())),
+                changelog_config_autodiscover: None,
+                changelog_repo_url: None,
+                changelog_strip_prefix: None,
+                changelog_header_links: None,
+                changelog_commit_links: None,
+                changelog_max_entries: None,
+                changelog_context: None,
+                changelog_sort_by_scope: None,
+                changelog_commit_sort: None,
+                changelog_source: None,
+                changelog_credit_authors: None,
+                changelog_format: None,
+                changelog_skip_ci_commits: None,
+                changelog_line_ending: None,
+                commit_lockfile: None,
+                changelog_unreleased_section: None,
+                changelog_dedup_existing: None,
+                changelog_include_merge_commits: None,
                 allow_dirty: None,
+                changelog_diff: None,
+                base_branch: None,
+                forge_api_retries: None,
+                forge_api_timeout: None,
+                git_push_remote: None,
+                git_release_api_headers: None,
+                git_release_branches: None,
+                git_release_type_trailer: None,
+                git_token_env: None,
+                git_user_name: None,
+                git_user_email: None,
+                github_output: None,
+                idempotent: None,
+                jobs: None,
+                on_error: None,
+                only: None,
+                release_commit_marker: None,
+                release_pr: None,
                 repo_url: Some("https://github.com/MarcoIeni/release-plz".parse().unwrap()),
+                semver_check_report_in_pr: None,
+                semver_check_missing: None,
+                skip_yanked_baseline: None,
+                update_lockfile: None,
+                verbose_plan: None,
+                version_bump_rules: None,
+                workspace_changelog_path: None,
+                pr_closes_issues: None,
                 pr_draft: false,
                 pr_labels: vec!["label1".to_string()],
+                pr_marker_label: None,
+                pr_pre_hook: None,
                 packages_defaults: PackageConfig {
                     semver_check: None,
+                    semver_check_baseline_ref: None,
+                    changelog_tag_pattern: None,
                     changelog_update: true.into(),
+                    changelog_dependency_note: None,
                     git_release_enable: true.into(),
                     git_release_type: Some(ReleaseType::Prod),
+                    git_release_type_strict: None,
                     git_release_draft: Some(false),
+                    git_release_backfill: None,
+                    git_release_publish_draft_on_success: None,
+                    release_commits: None,
+                    since: None,
+                    env: None,
                     release: Some(true),
                     ..Default::default()
                 },
+                publish_require_token: None,
+                publish_token_env: None,
                 publish_timeout: Some("10m".to_string()),
+                publish_timeout_scope: None,
             },
             package: [PackageSpecificConfigWithName {
                 name: "crate1".to_string(),
                 config: PackageSpecificConfig {
                     common: PackageConfig {
                         semver_check: Some(false),
+                        semver_check_baseline_ref: None,
+                        changelog_tag_pattern: None,
                         changelog_update: true.into(),
+                        changelog_dependency_note: None,
                         git_release_enable: true.into(),
                         git_release_type: Some(ReleaseType::Prod),
+                        git_release_type_strict: None,
                         git_release_draft: Some(false),
+                        git_release_backfill: None,
+                        git_release_publish_draft_on_success: None,
+                        env: None,
                         release: Some(false),
+                        release_commits: None,
+                        since: None,
                         ..Default::default()
                     },
                     changelog_path: Some("./CHANGELOG.md".into()),
                     changelog_include: Some(vec!["pkg1".to_string()]),
+                    changelog_exclude: None,
+                    changelog_include_mode: None,
+                    changelog_include_order: None,
                 },
             }]
             .into(),
@@ -511,4 +2208,96 @@ mod tests {
         "#]]
         .assert_eq(&toml::to_string(&config).unwrap());
     }
+
+    #[test]
+    fn resolved_config_expands_packages() {
+        let config = r#"
+            [workspace]
+            semver_check = false
+            publish_timeout = "10m"
+
+            [[package]]
+            name = "crate1"
+            changelog_update = false
+            git_release_draft = true
+        "#;
+        let config: Config = toml::from_str(config).unwrap();
+        let resolved = config.resolved(&["crate1", "crate2"]);
+        expect_test::expect![[r#"
+            [workspace]
+            changelog_update = true
+            changelog_require_nonempty = false
+            git_release_enable = true
+            git_release_type = "prod"
+            git_release_type_strict = false
+            git_release_never_prerelease = false
+            git_release_expect_existing_tag = false
+            git_release_after_tag_push = false
+            git_release_draft = false
+            git_release_backfill = false
+            git_release_publish_draft_on_success = false
+            git_tag_annotated = true
+            git_tag_enable = true
+            check_msrv = false
+            publish = true
+            publish_allow_dirty = false
+            publish_allow_dirty_auto = false
+            publish_no_verify = false
+            semver_check = false
+            release = true
+            zero_based_versioning = false
+            pr_draft = false
+            pr_labels = []
+            publish_timeout = "10m"
+
+            [[package]]
+            name = "crate1"
+            changelog_update = false
+            changelog_require_nonempty = false
+            git_release_enable = true
+            git_release_type = "prod"
+            git_release_type_strict = false
+            git_release_never_prerelease = false
+            git_release_expect_existing_tag = false
+            git_release_after_tag_push = false
+            git_release_draft = true
+            git_release_backfill = false
+            git_release_publish_draft_on_success = false
+            git_tag_annotated = true
+            git_tag_enable = true
+            check_msrv = false
+            publish = true
+            publish_allow_dirty = false
+            publish_allow_dirty_auto = false
+            publish_no_verify = false
+            semver_check = false
+            release = true
+            zero_based_versioning = false
+
+            [[package]]
+            name = "crate2"
+            changelog_update = true
+            changelog_require_nonempty = false
+            git_release_enable = true
+            git_release_type = "prod"
+            git_release_type_strict = false
+            git_release_never_prerelease = false
+            git_release_expect_existing_tag = false
+            git_release_after_tag_push = false
+            git_release_draft = false
+            git_release_backfill = false
+            git_release_publish_draft_on_success = false
+            git_tag_annotated = true
+            git_tag_enable = true
+            check_msrv = false
+            publish = true
+            publish_allow_dirty = false
+            publish_allow_dirty_auto = false
+            publish_no_verify = false
+            semver_check = false
+            release = true
+            zero_based_versioning = false
+        "#]]
+        .assert_eq(&toml::to_string(&resolved).unwrap());
+    }
 }
@@ -0,0 +1,154 @@
+//! Write the [step outputs](https://docs.github.com/en/actions/using-workflows/workflow-commands-for-github-actions#setting-an-output-parameter)
+//! of GitHub Actions, so that the following steps of the workflow can use them.
+
+use std::{fs::OpenOptions, io::Write, path::Path};
+
+use anyhow::Context;
+use release_plz_core::{GitPr, ReleasePlan};
+use serde_json::json;
+
+/// Environment variable containing the path of the file where GitHub Actions reads the step outputs.
+const GITHUB_OUTPUT: &str = "GITHUB_OUTPUT";
+
+const DELIMITER: &str = "RELEASE_PLZ_EOF";
+
+/// Append the `outputs` to the `GITHUB_OUTPUT` file.
+/// - `enabled` is the `github_output` field of the configuration.
+///   If `None`, the outputs are written only if release-plz runs in GitHub Actions.
+pub fn write(enabled: Option<bool>, outputs: &[(&str, String)]) -> anyhow::Result<()> {
+    if enabled == Some(false) {
+        return Ok(());
+    }
+    match std::env::var_os(GITHUB_OUTPUT) {
+        Some(path) => append(Path::new(&path), outputs),
+        None => {
+            if enabled == Some(true) {
+                tracing::warn!(
+                    "{GITHUB_OUTPUT} environment variable not set: can't write the step outputs"
+                );
+            }
+            Ok(())
+        }
+    }
+}
+
+fn append(path: &Path, outputs: &[(&str, String)]) -> anyhow::Result<()> {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("cannot open {GITHUB_OUTPUT} file {path:?}"))?;
+    file.write_all(format_outputs(outputs).as_bytes())
+        .with_context(|| format!("cannot write to {GITHUB_OUTPUT} file {path:?}"))
+}
+
+/// Outputs of the `release` command:
+/// - `released`: `true` if at least one package was released.
+/// - `version`: version of the released package, if only one package was released.
+/// - `releases`: JSON array of the released packages.
+pub fn release_outputs(plan: &ReleasePlan) -> Vec<(&'static str, String)> {
+    let mut outputs = vec![("released", (!plan.releases.is_empty()).to_string())];
+    if let [release] = plan.releases.as_slice() {
+        outputs.push(("version", release.version.to_string()));
+    }
+    let releases = serde_json::to_string_pretty(&plan.releases).expect("releases are serializable");
+    outputs.push(("releases", releases));
+    outputs
+}
+
+/// Outputs of the `release-pr` command:
+/// - `prs_created`: `true` if a release PR was opened or updated.
+/// - `pr`: JSON object describing the release PR, if any.
+pub fn release_pr_outputs(pr: Option<&GitPr>) -> Vec<(&'static str, String)> {
+    let mut outputs = vec![("prs_created", pr.is_some().to_string())];
+    if let Some(pr) = pr {
+        let pr = json!({
+            "number": pr.number,
+            "html_url": pr.html_url,
+            "head_branch": pr.branch(),
+        });
+        outputs.push(("pr", pr.to_string()));
+    }
+    outputs
+}
+
+fn format_outputs(outputs: &[(&str, String)]) -> String {
+    outputs
+        .iter()
+        .map(|(key, value)| format_output(key, value))
+        .collect()
+}
+
+/// Multiline values use the heredoc syntax, with a delimiter that doesn't appear in the value.
+fn format_output(key: &str, value: &str) -> String {
+    if !value.contains('\n') {
+        return format!("{key}={value}\n");
+    }
+    let mut delimiter = DELIMITER.to_string();
+    while value.contains(&delimiter) {
+        delimiter.push('_');
+    }
+    format!("{key}<<{delimiter}\n{value}\n{delimiter}\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use cargo_metadata::semver::Version;
+    use release_plz_core::PackageRelease;
+
+    use super::*;
+
+    #[test]
+    fn release_outputs_are_formatted() {
+        let plan = ReleasePlan {
+            releases: vec![PackageRelease {
+                package_name: "my-crate".to_string(),
+                version: Version::new(1, 2, 3),
+                tag: "v1.2.3".to_string(),
+            }],
+        };
+        expect_test::expect![[r#"
+            released=true
+            version=1.2.3
+            releases<<RELEASE_PLZ_EOF
+            [
+              {
+                "package_name": "my-crate",
+                "version": "1.2.3",
+                "tag": "v1.2.3"
+              }
+            ]
+            RELEASE_PLZ_EOF
+        "#]]
+        .assert_eq(&format_outputs(&release_outputs(&plan)));
+    }
+
+    #[test]
+    fn empty_release_outputs_are_formatted() {
+        expect_test::expect![[r#"
+            released=false
+            releases=[]
+        "#]]
+        .assert_eq(&format_outputs(&release_outputs(&ReleasePlan::default())));
+    }
+
+    #[test]
+    fn delimiter_is_not_contained_in_value() {
+        assert_eq!(
+            format_output("notes", "a\nRELEASE_PLZ_EOF\nb"),
+            "notes<<RELEASE_PLZ_EOF_\na\nRELEASE_PLZ_EOF\nb\nRELEASE_PLZ_EOF_\n"
+        );
+    }
+
+    #[test]
+    fn outputs_are_appended_to_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("github_output");
+        std::fs::write(&path, "existing=value\n").unwrap();
+        append(&path, &[("released", "true".to_string())]).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "existing=value\nreleased=true\n"
+        );
+    }
+}
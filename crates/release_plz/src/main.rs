@@ -1,15 +1,19 @@
 mod args;
 mod config;
 mod generate_schema;
+mod github_output;
 mod log;
 mod update_checker;
 
 use anyhow::Context;
 use clap::Parser;
-use release_plz_core::{ReleasePrRequest, ReleaseRequest};
-use tracing::error;
+use release_plz_core::{ReleasePrRequest, ReleaseRequest, DEFAULT_PR_MARKER_LABEL};
+use tracing::{error, warn};
 
-use crate::args::{repo_command::RepoCommand as _, CliArgs, Command};
+use crate::{
+    args::{repo_command::RepoCommand as _, CliArgs, Command},
+    config::ChangelogSource,
+};
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
@@ -28,32 +32,83 @@ async fn run(args: CliArgs) -> anyhow::Result<()> {
         Command::Update(cmd_args) => {
             let cargo_metadata = cmd_args.cargo_metadata()?;
             let config = cmd_args.config()?;
-            let update_request = cmd_args.update_request(config, cargo_metadata)?;
+            config.log_effective_package_configs(&cargo_metadata);
+            if cmd_args.print_config {
+                print!("{}", config.resolved_toml(&cargo_metadata)?);
+                return Ok(());
+            }
+            if config.workspace.changelog_source == Some(ChangelogSource::PullRequests) {
+                warn!("`changelog_source = \"pull_requests\"` is only supported by the `release-pr` command: the changelog uses the commit messages");
+            }
+            let update_request = cmd_args.update_request(&config, cargo_metadata)?;
+            if cmd_args.changelog_diff(&config) {
+                print!("{}", release_plz_core::changelog_diff(&update_request)?);
+                return Ok(());
+            }
             let updates = release_plz_core::update(&update_request)?;
             println!("{}", updates.0.summary());
         }
         Command::ReleasePr(cmd_args) => {
             let cargo_metadata = cmd_args.update.cargo_metadata()?;
             let config = cmd_args.update.config()?;
-            let pr_labels = config.workspace.pr_labels.clone();
+            config.log_effective_package_configs(&cargo_metadata);
+            let pr_labels = config.workspace.pr_labels();
             let pr_draft = config.workspace.pr_draft;
-            let update_request = cmd_args.update.update_request(config, cargo_metadata)?;
+            let github_output = config.workspace.github_output;
+            let update_request = cmd_args.update.update_request(&config, cargo_metadata)?;
             let repo_url = update_request
                 .repo_url()
                 .context("can't determine repo url")?;
+            let git_token = cmd_args.git_token(&config)?;
             let git = cmd_args
-                .git_backend(repo_url.clone())
+                .git_backend(repo_url.clone(), git_token)
                 .context("invalid git backend settings")?;
+            let git = config.workspace.configure_git_backend(git)?;
             let request = ReleasePrRequest::new(git, update_request)
                 .mark_as_draft(pr_draft)
-                .with_labels(pr_labels);
-            release_plz_core::release_pr(&request).await?;
+                .with_labels(pr_labels)
+                .with_marker_label(
+                    config
+                        .workspace
+                        .pr_marker_label
+                        .as_deref()
+                        .unwrap_or(DEFAULT_PR_MARKER_LABEL),
+                )
+                .with_pre_hook(config.workspace.pr_pre_hook.clone())
+                .with_commit_lockfile(config.workspace.commit_lockfile != Some(false))
+                .with_semver_check_report(config.workspace.semver_check_report_in_pr != Some(false))
+                .with_base_branch(config.workspace.base_branch.clone())
+                .with_git_identity(config.workspace.git_identity())
+                .with_git_push_remote(config.workspace.git_push_remote.clone())
+                .with_closed_issues(
+                    config
+                        .workspace
+                        .pr_closes_issues
+                        .clone()
+                        .unwrap_or_default(),
+                )
+                .with_changelog_source(
+                    config
+                        .workspace
+                        .changelog_source
+                        .map(Into::into)
+                        .unwrap_or_default(),
+                )
+                .with_create_pr(config.workspace.release_pr != Some(false));
+            let release_pr = release_plz_core::release_pr(&request).await?;
+            github_output::write(
+                github_output,
+                &github_output::release_pr_outputs(release_pr.as_ref()),
+            )?;
         }
         Command::Release(cmd_args) => {
             let cargo_metadata = cmd_args.cargo_metadata()?;
             let config = cmd_args.config()?;
+            config.log_effective_package_configs(&cargo_metadata);
+            let github_output = config.workspace.github_output;
             let request: ReleaseRequest = cmd_args.release_request(config, cargo_metadata)?;
-            release_plz_core::release(&request).await?;
+            let plan = release_plz_core::release(&request).await?;
+            github_output::write(github_output, &github_output::release_outputs(&plan))?;
         }
         Command::GenerateCompletions(cmd_args) => cmd_args.print(),
         Command::CheckUpdates => update_checker::check_update().await?,
@@ -55,8 +55,7 @@ impl TestContext {
         } else {
             "ERROR"
         };
-        super::cmd::release_plz_cmd()
-            .current_dir(&self.repo_dir())
+        self.release_plz_cmd()
             .env("RUST_LOG", log_level)
             .arg("release-pr")
             .arg("--verbose")
@@ -76,8 +75,7 @@ impl TestContext {
             "ERROR"
         };
 
-        super::cmd::release_plz_cmd()
-            .current_dir(&self.repo_dir())
+        self.release_plz_cmd()
             .env("RUST_LOG", log_level)
             .arg("release")
             .arg("--verbose")
@@ -92,6 +90,18 @@ impl TestContext {
             .assert()
     }
 
+    /// release-plz command running in the repository.
+    /// The tests check the Gitea repository of the git remote, so the repository of
+    /// the CI running the tests must not be used as repo url.
+    fn release_plz_cmd(&self) -> assert_cmd::Command {
+        let mut cmd = super::cmd::release_plz_cmd();
+        cmd.current_dir(self.repo_dir())
+            .env_remove("GITHUB_REPOSITORY")
+            .env_remove("GITHUB_SERVER_URL")
+            .env_remove("CI_PROJECT_URL");
+        cmd
+    }
+
     pub fn repo_dir(&self) -> PathBuf {
         self.test_dir.path().join(&self.gitea.repo)
     }
@@ -1,9 +1,10 @@
 use anyhow::Context;
-use cargo_metadata::Package;
+use cargo_metadata::{semver::Version, Package};
 use crates_index::{Crate, GitIndex, SparseIndex};
 use tracing::{debug, info};
 
 use std::{
+    collections::BTreeMap,
     env,
     io::{BufRead, BufReader},
     path::Path,
@@ -22,13 +23,20 @@ fn cargo_cmd() -> Command {
 }
 
 pub fn run_cargo(root: &Path, args: &[&str]) -> anyhow::Result<(String, String)> {
+    run_cargo_with_env(root, args, &BTreeMap::new())
+}
+
+/// Run cargo with the given additional environment variables.
+pub fn run_cargo_with_env(
+    root: &Path,
+    args: &[&str],
+    env: &BTreeMap<String, String>,
+) -> anyhow::Result<(String, String)> {
     debug!("cargo {}", args.join(" "));
 
     let mut stderr_lines = vec![];
 
-    let mut child = cargo_cmd()
-        .current_dir(root)
-        .args(args)
+    let mut child = cargo_command(root, args, env)
         .stdout(Stdio::piped())
         .stderr(Stdio::piped())
         .spawn()
@@ -59,6 +67,28 @@ pub fn run_cargo(root: &Path, args: &[&str]) -> anyhow::Result<(String, String)>
     ))
 }
 
+/// Version of the Rust toolchain used by cargo in `root`,
+/// taking into account the `rust-toolchain.toml` file, if any.
+pub fn toolchain_version(root: &Path) -> anyhow::Result<Version> {
+    let (stdout, _) = run_cargo(root, &["--version"])?;
+    parse_toolchain_version(&stdout)
+}
+
+/// Parse the output of `cargo --version`, e.g. `cargo 1.76.0 (c84b36747 2024-01-18)`.
+fn parse_toolchain_version(cargo_version: &str) -> anyhow::Result<Version> {
+    let version = cargo_version
+        .split_whitespace()
+        .nth(1)
+        .with_context(|| format!("cannot find version in `{cargo_version}`"))?;
+    Version::parse(version).with_context(|| format!("cannot parse toolchain version `{version}`"))
+}
+
+fn cargo_command(root: &Path, args: &[&str], env: &BTreeMap<String, String>) -> Command {
+    let mut command = cargo_cmd();
+    command.current_dir(root).args(args).envs(env);
+    command
+}
+
 pub async fn is_published(
     index: &mut CargoIndex,
     package: &Package,
@@ -176,3 +206,42 @@ pub async fn wait_until_published(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::OsStr;
+
+    use super::*;
+
+    #[test]
+    fn env_is_applied_to_cargo_command() {
+        let env = BTreeMap::from([(
+            "CARGO_BUILD_TARGET".to_string(),
+            "x86_64-unknown-linux-gnu".to_string(),
+        )]);
+        let command = cargo_command(Path::new("."), &["publish"], &env);
+        let envs: Vec<_> = command.get_envs().collect();
+        assert_eq!(
+            envs,
+            vec![(
+                OsStr::new("CARGO_BUILD_TARGET"),
+                Some(OsStr::new("x86_64-unknown-linux-gnu"))
+            )]
+        );
+    }
+
+    #[test]
+    fn toolchain_version_is_parsed() {
+        assert_eq!(
+            parse_toolchain_version("cargo 1.76.0 (c84b36747 2024-01-18)").unwrap(),
+            Version::new(1, 76, 0)
+        );
+        assert_eq!(
+            parse_toolchain_version("cargo 1.78.0-nightly (7b7af3077 2024-02-17)")
+                .unwrap()
+                .to_string(),
+            "1.78.0-nightly"
+        );
+        assert!(parse_toolchain_version("cargo").is_err());
+    }
+}
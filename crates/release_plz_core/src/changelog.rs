@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use anyhow::Context;
 use chrono::{NaiveDate, TimeZone, Utc};
 use git_cliff_core::{
@@ -9,7 +11,7 @@ use git_cliff_core::{
 use regex::Regex;
 use tracing::warn;
 
-use crate::changelog_parser;
+use crate::{changelog_context, changelog_format, changelog_parser, ChangelogFormat};
 
 pub const CHANGELOG_HEADER: &str = r#"# Changelog
 All notable changes to this project will be documented in this file.
@@ -26,21 +28,40 @@ pub struct Changelog<'a> {
     release: Release<'a>,
     config: Option<Config>,
     release_link: Option<String>,
+    header_link: bool,
+    commit_link: Option<String>,
+    max_entries: Option<usize>,
+    context: HashMap<String, toml::Value>,
+    unreleased_section: bool,
+    dedup_existing: bool,
+    format: ChangelogFormat,
 }
 
 impl Changelog<'_> {
     /// Generate the full changelog.
-    pub fn generate(self) -> String {
-        let config = self
-            .config
-            .unwrap_or_else(|| default_git_cliff_config(None, self.release_link.as_deref()));
+    pub fn generate(self) -> anyhow::Result<String> {
+        match self.format {
+            ChangelogFormat::Markdown => {}
+            ChangelogFormat::Json => return Ok(changelog_format::generate_json(&self.release)),
+            ChangelogFormat::Rst => return Ok(changelog_format::generate_rst(&self.release)),
+        }
+        let config = self.config.unwrap_or_else(|| {
+            default_git_cliff_config(
+                None,
+                self.release_link.as_deref(),
+                self.header_link,
+                self.commit_link.as_deref(),
+                self.max_entries,
+            )
+        });
+        let config = without_commit_parsers(inline_context(config, &self.context)?);
         let changelog = GitCliffChangelog::new(vec![self.release], &config)
-            .expect("error while building changelog");
+            .context("error while building changelog")?;
         let mut out = Vec::new();
         changelog
             .generate(&mut out)
-            .expect("cannot generate changelog");
-        String::from_utf8(out).expect("cannot convert bytes to string")
+            .context("cannot generate changelog")?;
+        String::from_utf8(out).context("cannot convert bytes to string")
     }
 
     /// Update an existing changelog.
@@ -50,18 +71,148 @@ impl Changelog<'_> {
             // The changelog already contains this version, so we don't update the changelog.
             return Ok(old_changelog);
         }
+        match self.format {
+            ChangelogFormat::Markdown => {}
+            ChangelogFormat::Json => {
+                return changelog_format::prepend_json(&self.release, &old_changelog)
+            }
+            ChangelogFormat::Rst => {
+                return Ok(changelog_format::prepend_rst(&self.release, &old_changelog))
+            }
+        }
+        let mut release = self.release;
+        if self.dedup_existing {
+            let (_, existing_notes) = changelog_parser::take_unreleased_notes(&old_changelog);
+            let existing_entries = existing_entries(&existing_notes);
+            release
+                .commits
+                .retain(|commit| !is_entry_present(&existing_entries, commit));
+        }
+        let (old_changelog, unreleased_notes) = if self.unreleased_section {
+            changelog_parser::take_unreleased_notes(&old_changelog)
+        } else {
+            (old_changelog, String::new())
+        };
         let old_header = changelog_parser::parse_header(&old_changelog);
-        let config = self
-            .config
-            .unwrap_or_else(|| default_git_cliff_config(old_header, self.release_link.as_deref()));
-        let changelog = GitCliffChangelog::new(vec![self.release], &config)
+        let config = self.config.unwrap_or_else(|| {
+            default_git_cliff_config(
+                old_header,
+                self.release_link.as_deref(),
+                self.header_link,
+                self.commit_link.as_deref(),
+                self.max_entries,
+            )
+        });
+        let config = without_commit_parsers(inline_context(config, &self.context)?);
+        let changelog = GitCliffChangelog::new(vec![release], &config)
             .context("error while building changelog")?;
         let mut out = Vec::new();
         changelog
             .prepend(old_changelog, &mut out)
             .context("cannot update changelog")?;
-        String::from_utf8(out).context("cannot convert bytes to string")
+        let new_changelog = String::from_utf8(out).context("cannot convert bytes to string")?;
+        if self.unreleased_section {
+            Ok(promote_unreleased_notes(&new_changelog, &unreleased_notes))
+        } else {
+            Ok(new_changelog)
+        }
+    }
+}
+
+/// git-cliff processes the commits again when building the changelog.
+/// The commits were already parsed in [`ChangelogBuilder::build`], so the commit parsers
+/// are skipped, to keep the groups set with [`ChangelogBuilder::with_commit_groups`].
+fn without_commit_parsers(mut config: Config) -> Config {
+    config.git.commit_parsers = None;
+    config
+}
+
+/// Replace the references to the `context` variables in the body template with their values.
+fn inline_context(
+    mut config: Config,
+    context: &HashMap<String, toml::Value>,
+) -> anyhow::Result<Config> {
+    if context.is_empty() {
+        return Ok(config);
+    }
+    if let Some(body) = &config.changelog.body {
+        let body = changelog_context::render(body, context)
+            .context("cannot add the context variables to the changelog template")?;
+        config.changelog.body = Some(body);
+    }
+    Ok(config)
+}
+
+/// Add the notes of the `Unreleased` section to the new release, i.e. the first release
+/// of the changelog. If the changelog doesn't have an `Unreleased` section, add an empty one.
+fn promote_unreleased_notes(changelog: &str, notes: &str) -> String {
+    let mut out = String::new();
+    let mut has_unreleased_section = false;
+    let mut is_promoted = false;
+    for line in changelog.split_inclusive('\n') {
+        if !is_promoted && line.starts_with("## ") {
+            if changelog_parser::is_unreleased_heading(line) {
+                has_unreleased_section = true;
+            } else {
+                if !has_unreleased_section {
+                    out.push_str("## [Unreleased]\n\n");
+                }
+                out.push_str(line);
+                if !notes.is_empty() {
+                    out.push('\n');
+                    out.push_str(notes);
+                    out.push('\n');
+                }
+                is_promoted = true;
+                continue;
+            }
+        }
+        out.push_str(line);
+    }
+    out
+}
+
+/// Entries of the changelog `notes`, normalized to be compared with the commit summaries.
+/// E.g. `- *(api)* Add endpoint ([abc1234](...))` becomes `add endpoint ([abc1234](...))`.
+fn existing_entries(notes: &str) -> Vec<String> {
+    lazy_static::lazy_static! {
+        static ref DECORATIONS_RE: Regex =
+            Regex::new(r"^(\*\([^)]*\)\*\s*)?(\[\*\*breaking\*\*\]\s*)?").unwrap();
+    }
+    notes
+        .lines()
+        .filter_map(|line| {
+            let line = line.trim_start();
+            line.strip_prefix("- ").or_else(|| line.strip_prefix("* "))
+        })
+        .map(|entry| DECORATIONS_RE.replace(entry.trim(), "").to_lowercase())
+        .collect()
+}
+
+/// Whether one of the `entries` refers to the commit, by short hash or by summary.
+fn is_entry_present(entries: &[String], commit: &Commit) -> bool {
+    lazy_static::lazy_static! {
+        static ref COMMIT_LINK_RE: Regex =
+            Regex::new(r"\s*\(\[[0-9a-f]{7,40}\]\([^)]*\)\s*\)$").unwrap();
     }
+    let short_id = (commit.id != crate::NO_COMMIT_ID && commit.id.len() >= 7)
+        .then(|| commit.id[..7].to_lowercase());
+    let summary = commit
+        .conv
+        .as_ref()
+        .map(|conv| conv.description())
+        .unwrap_or_else(|| commit.message.lines().next().unwrap_or_default());
+    let summary = normalize_summary(summary);
+    entries.iter().any(|entry| {
+        let is_same_commit = short_id
+            .as_ref()
+            .map_or(false, |id| entry.contains(id.as_str()));
+        is_same_commit || normalize_summary(&COMMIT_LINK_RE.replace(entry, "")) == summary
+    })
+}
+
+fn normalize_summary(summary: &str) -> String {
+    summary.trim().trim_end_matches('.').to_lowercase()
 }
 
 fn is_version_unchanged(release: &Release) -> bool {
@@ -70,13 +221,43 @@ fn is_version_unchanged(release: &Release) -> bool {
     previous_version == new_version
 }
 
-fn default_git_cliff_config(header: Option<String>, release_link: Option<&str>) -> Config {
+fn default_git_cliff_config(
+    header: Option<String>,
+    release_link: Option<&str>,
+    header_link: bool,
+    commit_link: Option<&str>,
+    max_entries: Option<usize>,
+) -> Config {
     Config {
-        changelog: default_changelog_config(header, release_link),
+        changelog: default_changelog_config(
+            header,
+            release_link,
+            header_link,
+            commit_link,
+            max_entries,
+        ),
         git: default_git_config(),
     }
 }
 
+/// Order of the entries of each changelog section.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommitSort {
+    /// Newest commits first.
+    Newest,
+    /// Oldest commits first.
+    Oldest,
+}
+
+impl CommitSort {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Newest => "newest",
+            Self::Oldest => "oldest",
+        }
+    }
+}
+
 pub struct ChangelogBuilder<'a> {
     commits: Vec<Commit<'a>>,
     version: String,
@@ -84,6 +265,18 @@ pub struct ChangelogBuilder<'a> {
     config: Option<Config>,
     release_date: Option<NaiveDate>,
     release_link: Option<String>,
+    header_link: bool,
+    commit_link: Option<String>,
+    max_entries: Option<usize>,
+    context: HashMap<String, toml::Value>,
+    commit_summary_prefix: Option<Regex>,
+    sort_by_scope: bool,
+    commit_sort: Option<CommitSort>,
+    skip_ci_commits: bool,
+    unreleased_section: bool,
+    dedup_existing: bool,
+    commit_groups: HashMap<String, Vec<String>>,
+    format: ChangelogFormat,
 }
 
 impl<'a> ChangelogBuilder<'a> {
@@ -95,6 +288,18 @@ impl<'a> ChangelogBuilder<'a> {
             config: None,
             release_date: None,
             release_link: None,
+            header_link: true,
+            commit_link: None,
+            max_entries: None,
+            context: HashMap::new(),
+            commit_summary_prefix: None,
+            sort_by_scope: false,
+            commit_sort: None,
+            skip_ci_commits: false,
+            unreleased_section: false,
+            dedup_existing: false,
+            commit_groups: HashMap::new(),
+            format: ChangelogFormat::default(),
         }
     }
 
@@ -119,6 +324,111 @@ impl<'a> ChangelogBuilder<'a> {
         }
     }
 
+    /// If `false`, the version heading doesn't link to the release link.
+    /// Enabled by default.
+    pub fn with_header_link(self, header_link: bool) -> Self {
+        Self {
+            header_link,
+            ..self
+        }
+    }
+
+    /// Append the short hash of the commit to each changelog entry,
+    /// linking to `{commit_link}/{commit_id}`.
+    pub fn with_commit_link(self, commit_link: impl Into<String>) -> Self {
+        Self {
+            commit_link: Some(commit_link.into()),
+            ..self
+        }
+    }
+
+    /// Show at most `max_entries` entries in each section of the release.
+    /// The other entries are summarized in a "…and X more" line, linking to the release link.
+    pub fn with_max_entries(self, max_entries: usize) -> Self {
+        Self {
+            max_entries: Some(max_entries),
+            ..self
+        }
+    }
+
+    /// Variables available in the changelog template as `context`,
+    /// e.g. `{{ context.product_name }}`.
+    pub fn with_context(self, context: HashMap<String, toml::Value>) -> Self {
+        Self { context, ..self }
+    }
+
+    /// Remove the prefix matching this regex from the summary of the commits.
+    pub fn with_commit_summary_prefix(self, prefix: Regex) -> Self {
+        Self {
+            commit_summary_prefix: Some(prefix),
+            ..self
+        }
+    }
+
+    /// Sort the commits of each changelog section by scope.
+    /// Commits without scope are placed last.
+    pub fn with_sort_by_scope(self, sort_by_scope: bool) -> Self {
+        Self {
+            sort_by_scope,
+            ..self
+        }
+    }
+
+    /// Order of the entries of each changelog section.
+    /// It overrides the `sort_commits` field of the git-cliff configuration.
+    /// The commits must be sorted from newest to oldest.
+    pub fn with_commit_sort(self, commit_sort: CommitSort) -> Self {
+        Self {
+            commit_sort: Some(commit_sort),
+            ..self
+        }
+    }
+
+    /// Don't add the `ci`, `build` and `chore(ci)` commits to the changelog.
+    /// It applies to custom git-cliff configurations, too.
+    pub fn with_skip_ci_commits(self, skip_ci_commits: bool) -> Self {
+        Self {
+            skip_ci_commits,
+            ..self
+        }
+    }
+
+    /// Move the notes of the `Unreleased` section of the old changelog to the new release,
+    /// leaving an empty `Unreleased` section.
+    pub fn with_unreleased_section(self, unreleased_section: bool) -> Self {
+        Self {
+            unreleased_section,
+            ..self
+        }
+    }
+
+    /// Don't add the commits that are already listed in the `Unreleased` section
+    /// of the old changelog, e.g. because the section was edited by hand.
+    /// A commit is listed if an entry contains its short hash or its summary.
+    pub fn with_dedup_existing(self, dedup_existing: bool) -> Self {
+        Self {
+            dedup_existing,
+            ..self
+        }
+    }
+
+    /// Changelog groups of the commits, by commit id.
+    /// They replace the groups assigned by the commit parsers.
+    /// A commit with multiple groups appears in each of them.
+    pub fn with_commit_groups(self, commit_groups: HashMap<String, Vec<String>>) -> Self {
+        Self {
+            commit_groups,
+            ..self
+        }
+    }
+
+    /// Output format of the changelog. Markdown by default.
+    /// The git-cliff template and the options about the Markdown sections, like the
+    /// `Unreleased` section, only apply to the Markdown format.
+    pub fn with_format(self, format: ChangelogFormat) -> Self {
+        Self { format, ..self }
+    }
+
     pub fn with_config(self, config: Config) -> Self {
         Self {
             config: Some(config),
@@ -133,13 +443,36 @@ impl<'a> ChangelogBuilder<'a> {
             .map(|c| c.git)
             .unwrap_or_else(default_git_config);
         let release_date = self.release_timestamp();
+        let commit_summary_prefix = self.commit_summary_prefix;
+        let commit_groups = self.commit_groups;
         let mut commits: Vec<_> = self
             .commits
             .into_iter()
+            .map(|mut c| {
+                if let Some(prefix) = &commit_summary_prefix {
+                    c.message = strip_summary_prefix(&c.message, prefix);
+                }
+                c
+            })
             .filter_map(|c| c.process(&git_config).ok())
+            .filter(|c| !(self.skip_ci_commits && is_ci_commit(c)))
+            .flat_map(|c| match commit_groups.get(&c.id) {
+                Some(groups) => groups
+                    .iter()
+                    .map(|group| Commit {
+                        group: Some(group.clone()),
+                        ..c.clone()
+                    })
+                    .collect(),
+                None => vec![c],
+            })
             .collect();
 
-        match git_config.sort_commits.map(|s| s.to_lowercase()).as_deref() {
+        let sort_commits = match self.commit_sort {
+            Some(commit_sort) => Some(commit_sort.as_str().to_string()),
+            None => git_config.sort_commits.map(|s| s.to_lowercase()),
+        };
+        match sort_commits.as_deref() {
             Some("oldest") => {
                 commits.reverse();
             }
@@ -151,6 +484,10 @@ impl<'a> ChangelogBuilder<'a> {
             }
         }
 
+        if self.sort_by_scope {
+            sort_by_scope(&mut commits);
+        }
+
         let previous = self.previous_version.map(|ver| Release {
             version: Some(ver),
             commits: vec![],
@@ -168,7 +505,14 @@ impl<'a> ChangelogBuilder<'a> {
                 previous: previous.map(Box::new),
             },
             release_link: self.release_link,
+            header_link: self.header_link,
+            commit_link: self.commit_link,
+            max_entries: self.max_entries,
+            context: self.context,
             config: self.config,
+            unreleased_section: self.unreleased_section,
+            dedup_existing: self.dedup_existing,
+            format: self.format,
         }
     }
 
@@ -183,6 +527,53 @@ impl<'a> ChangelogBuilder<'a> {
     }
 }
 
+/// Scope of the commit, as shown in the changelog.
+pub(crate) fn commit_scope<'a>(commit: &'a Commit) -> Option<&'a str> {
+    commit
+        .scope
+        .as_deref()
+        .or_else(|| {
+            commit
+                .conv
+                .as_ref()
+                .and_then(|conv| conv.scope().map(|s| s.as_str()))
+        })
+        .or(commit.default_scope.as_deref())
+}
+
+/// Whether the commit is a conventional commit about CI or the build system,
+/// i.e. of type `ci` or `build`, or of type `chore` with scope `ci`.
+fn is_ci_commit(commit: &Commit) -> bool {
+    commit.conv.as_ref().is_some_and(|conv| {
+        let commit_type = conv.type_().as_str();
+        commit_type.eq_ignore_ascii_case("ci")
+            || commit_type.eq_ignore_ascii_case("build")
+            || (commit_type.eq_ignore_ascii_case("chore")
+                && conv
+                    .scope()
+                    .is_some_and(|scope| scope.as_str().eq_ignore_ascii_case("ci")))
+    })
+}
+
+/// Stable sort of the commits by scope. Commits without scope are placed last.
+fn sort_by_scope(commits: &mut [Commit]) {
+    commits.sort_by(|a, b| {
+        let (a, b) = (commit_scope(a), commit_scope(b));
+        a.is_none().cmp(&b.is_none()).then(a.cmp(&b))
+    });
+}
+
+/// Remove the prefix from the summary (first line) of the commit message.
+/// The prefix is removed only if the regex matches at the start of the message.
+fn strip_summary_prefix(message: &str, prefix: &Regex) -> String {
+    match prefix.find(message) {
+        Some(m) if m.start() == 0 && !message[..m.end()].contains('\n') => {
+            message[m.end()..].to_string()
+        }
+        _ => message.to_string(),
+    }
+}
+
 fn default_git_config() -> GitConfig {
     GitConfig {
         conventional_commits: Some(true),
@@ -228,35 +619,85 @@ fn commit_parsers() -> Vec<CommitParser> {
     ]
 }
 
-fn default_changelog_config(header: Option<String>, release_link: Option<&str>) -> ChangelogConfig {
+fn default_changelog_config(
+    header: Option<String>,
+    release_link: Option<&str>,
+    header_link: bool,
+    commit_link: Option<&str>,
+    max_entries: Option<usize>,
+) -> ChangelogConfig {
     ChangelogConfig {
         header: Some(header.unwrap_or(String::from(CHANGELOG_HEADER))),
-        body: Some(default_changelog_body_config(release_link)),
+        body: Some(default_changelog_body_config(
+            release_link,
+            header_link,
+            commit_link,
+            max_entries,
+        )),
         footer: None,
         postprocessors: None,
         trim: Some(true),
     }
 }
 
-fn default_changelog_body_config(release_link: Option<&str>) -> String {
+/// - `release_link`: link to the release, e.g. the comparison with the previous release.
+/// - `header_link`: if `true`, the version heading links to the `release_link`.
+fn default_changelog_body_config(
+    release_link: Option<&str>,
+    header_link: bool,
+    commit_link: Option<&str>,
+    max_entries: Option<usize>,
+) -> String {
     let pre = r#"
     ## [{{ version | trim_start_matches(pat="v") }}]"#;
-    let post = r#" - {{ timestamp | date(format="%Y-%m-%d") }}
+    let group_header = r#" - {{ timestamp | date(format="%Y-%m-%d") }}
 {% for group, commits in commits | group_by(attribute="group") %}
 ### {{ group | upper_first }}
-{% for commit in commits %}
+"#;
+    let entries_loop = match max_entries {
+        Some(max_entries) => format!("{{% for commit in commits | slice(end={max_entries}) %}}"),
+        None => "{% for commit in commits %}".to_string(),
+    };
+    let post = r#"
 {%- if commit.scope -%}
-- *({{commit.scope}})* {% if commit.breaking %}[**breaking**] {% endif %}{{ commit.message }}{%- if commit.links %} ({% for link in commit.links %}[{{link.text}}]({{link.href}}) {% endfor -%}){% endif %}
+- *({{commit.scope}})* {% if commit.breaking %}[**breaking**] {% endif %}{{ commit.message }}{%- if commit.links %} ({% for link in commit.links %}[{{link.text}}]({{link.href}}) {% endfor -%}){% endif %}"#;
+    let scopeless_entry = r#"
 {% else -%}
-- {% if commit.breaking %}[**breaking**] {% endif %}{{ commit.message }}
+- {% if commit.breaking %}[**breaking**] {% endif %}{{ commit.message }}"#;
+    let entries_end = r#"
 {% endif -%}
-{% endfor -%}
+{% endfor -%}"#;
+    let end = r#"
 {% endfor %}"#;
 
-    match release_link {
-        Some(link) => format!("{pre}({link}){post}"),
-        None => format!("{pre}{post}"),
-    }
+    let more_entries = max_entries
+        .map(|max_entries| more_entries_template(max_entries, release_link))
+        .unwrap_or_default();
+    let pre = match release_link.filter(|_| header_link) {
+        Some(link) => format!("{pre}({link})"),
+        None => pre.to_string(),
+    };
+    let commit_link = commit_link.map(commit_link_template).unwrap_or_default();
+    format!("{pre}{group_header}{entries_loop}{post}{commit_link}{scopeless_entry}{commit_link}{entries_end}{more_entries}{end}")
+}
+
+/// Template of the line summarizing the entries of a section exceeding `max_entries`,
+/// e.g. `- …and 3 more`.
+fn more_entries_template(max_entries: usize, release_link: Option<&str>) -> String {
+    let more = format!("…and {{{{ commits | length - {max_entries} }}}} more");
+    let more = match release_link {
+        Some(link) => format!("[{more}]({link})"),
+        None => more,
+    };
+    format!("{{% if commits | length > {max_entries} %}}- {more}\n{{% endif -%}}")
+}
+
+/// Template of the short commit hash linking to the commit, e.g. ` ([abc1234](<commit_link>/abc1234...))`.
+fn commit_link_template(commit_link: &str) -> String {
+    let no_commit_id = crate::NO_COMMIT_ID;
+    format!(
+        r#"{{% if commit.id != "{no_commit_id}" %}} ([{{{{ commit.id | truncate(length=7, end="") }}}}]({commit_link}/{{{{ commit.id }}}})){{% endif %}}"#
+    )
 }
 
 #[cfg(test)]
@@ -292,18 +733,35 @@ mod tests {
             ### Other
             - simple update
         "####]]
-        .assert_eq(&changelog.generate());
+        .assert_eq(&changelog.generate().unwrap());
     }
 
     #[test]
-    fn changelog_entry_with_link_is_generated() {
-        let commits = vec![Commit::new(
-            NO_COMMIT_ID.to_string(),
-            "fix: myfix".to_string(),
-        )];
+    fn commit_summary_prefix_is_stripped() {
+        let prefix = Regex::new(r"PROJ-\d+:\s*").unwrap();
+        assert_eq!(
+            strip_summary_prefix("PROJ-123: fix: myfix", &prefix),
+            "fix: myfix"
+        );
+        assert_eq!(
+            strip_summary_prefix("fix: myfix for PROJ-123: bug", &prefix),
+            "fix: myfix for PROJ-123: bug"
+        );
+        assert_eq!(
+            strip_summary_prefix("simple update", &prefix),
+            "simple update"
+        );
+    }
+
+    #[test]
+    fn changelog_entries_without_summary_prefix_are_generated() {
+        let commits = vec![
+            Commit::new(NO_COMMIT_ID.to_string(), "PROJ-1: fix: myfix".to_string()),
+            Commit::new(NO_COMMIT_ID.to_string(), "simple update".to_string()),
+        ];
         let changelog = ChangelogBuilder::new(commits, "1.1.1")
             .with_release_date(NaiveDate::from_ymd_opt(2015, 5, 15).unwrap())
-            .with_release_link("https://github.com/MarcoIeni/release-plz/compare/release-plz-v0.2.24...release-plz-v0.2.25")
+            .with_commit_summary_prefix(Regex::new(r"PROJ-\d+:\s*").unwrap())
             .build();
 
         expect_test::expect![[r####"
@@ -315,35 +773,79 @@ mod tests {
 
             ## [Unreleased]
 
-            ## [1.1.1](https://github.com/MarcoIeni/release-plz/compare/release-plz-v0.2.24...release-plz-v0.2.25) - 2015-05-15
+            ## [1.1.1] - 2015-05-15
 
             ### Fixed
             - myfix
+
+            ### Other
+            - simple update
         "####]]
-        .assert_eq(&changelog.generate());
+        .assert_eq(&changelog.generate().unwrap());
     }
 
     #[test]
-    fn generated_changelog_is_updated_correctly() {
+    fn changelog_entries_are_grouped_by_commit_groups() {
         let commits = vec![
-            Commit::new(NO_COMMIT_ID.to_string(), "fix: myfix".to_string()),
-            Commit::new(NO_COMMIT_ID.to_string(), "simple update".to_string()),
+            Commit::new("a".to_string(), "feat: parse floats".to_string()),
+            Commit::new("b".to_string(), "fix: fix stack overflow".to_string()),
+            Commit::new("c".to_string(), "fix: fix typo".to_string()),
         ];
+        let commit_groups = HashMap::from([
+            ("a".to_string(), vec!["parser".to_string()]),
+            (
+                "b".to_string(),
+                vec!["parser".to_string(), "runtime".to_string()],
+            ),
+        ]);
         let changelog = ChangelogBuilder::new(commits, "1.1.1")
             .with_release_date(NaiveDate::from_ymd_opt(2015, 5, 15).unwrap())
+            .with_commit_groups(commit_groups)
             .build();
 
-        let generated_changelog = changelog.generate();
+        expect_test::expect![[r#"
+            # Changelog
+            All notable changes to this project will be documented in this file.
+
+            The format is based on [Keep a Changelog](https://keepachangelog.com/en/1.0.0/),
+            and this project adheres to [Semantic Versioning](https://semver.org/spec/v2.0.0.html).
+
+            ## [Unreleased]
+
+            ## [1.1.1] - 2015-05-15
+
+            ### Fixed
+            - fix typo
+
+            ### Parser
+            - parse floats
+            - fix stack overflow
 
+            ### Runtime
+            - fix stack overflow
+        "#]]
+        .assert_eq(&changelog.generate().unwrap());
+    }
+
+    #[test]
+    fn ci_commits_are_skipped() {
         let commits = vec![
-            Commit::new(NO_COMMIT_ID.to_string(), "fix: myfix2".to_string()),
-            Commit::new(NO_COMMIT_ID.to_string(), "complex update".to_string()),
+            Commit::new(NO_COMMIT_ID.to_string(), "ci: add workflow".to_string()),
+            Commit::new(NO_COMMIT_ID.to_string(), "build: bump msrv".to_string()),
+            Commit::new(
+                NO_COMMIT_ID.to_string(),
+                "chore(ci): cache deps".to_string(),
+            ),
+            Commit::new(NO_COMMIT_ID.to_string(), "feat: new api".to_string()),
+            Commit::new(NO_COMMIT_ID.to_string(), "fix: myfix".to_string()),
+            Commit::new(NO_COMMIT_ID.to_string(), "chore: tidy up".to_string()),
         ];
-        let changelog = ChangelogBuilder::new(commits, "1.1.2")
+        let changelog = ChangelogBuilder::new(commits, "1.1.1")
             .with_release_date(NaiveDate::from_ymd_opt(2015, 5, 15).unwrap())
+            .with_skip_ci_commits(true)
             .build();
 
-        expect_test::expect![[r####"
+        expect_test::expect![[r#"
             # Changelog
             All notable changes to this project will be documented in this file.
 
@@ -352,44 +854,35 @@ mod tests {
 
             ## [Unreleased]
 
-            ## [1.1.2] - 2015-05-15
-
-            ### Fixed
-            - myfix2
-
-            ### Other
-            - complex update
-
             ## [1.1.1] - 2015-05-15
 
+            ### Added
+            - new api
+
             ### Fixed
             - myfix
 
             ### Other
-            - simple update
-        "####]]
-        .assert_eq(&changelog.prepend(generated_changelog).unwrap());
+            - tidy up
+        "#]]
+        .assert_eq(&changelog.generate().unwrap());
     }
 
     #[test]
-    fn changelog_is_updated() {
+    fn changelog_entries_are_sorted_by_scope() {
         let commits = vec![
-            Commit::new(NO_COMMIT_ID.to_string(), "fix: myfix".to_string()),
-            Commit::new(NO_COMMIT_ID.to_string(), "simple update".to_string()),
+            Commit::new(NO_COMMIT_ID.to_string(), "fix: unscoped fix".to_string()),
+            Commit::new(NO_COMMIT_ID.to_string(), "fix(parser): fix 1".to_string()),
+            Commit::new(NO_COMMIT_ID.to_string(), "fix(cli): fix 2".to_string()),
+            Commit::new(NO_COMMIT_ID.to_string(), "fix(parser): fix 3".to_string()),
+            Commit::new(NO_COMMIT_ID.to_string(), "feat: unscoped feat".to_string()),
+            Commit::new(NO_COMMIT_ID.to_string(), "feat(cli): feat 1".to_string()),
         ];
         let changelog = ChangelogBuilder::new(commits, "1.1.1")
             .with_release_date(NaiveDate::from_ymd_opt(2015, 5, 15).unwrap())
+            .with_sort_by_scope(true)
             .build();
-        let old_body = r#"## [1.1.0] - 1970-01-01
-
-### fix bugs
-- my awesomefix
 
-### other
-- complex update
-"#;
-        let old = format!("{CHANGELOG_HEADER}\n{old_body}");
-        let new = changelog.prepend(old).unwrap();
         expect_test::expect![[r####"
             # Changelog
             All notable changes to this project will be documented in this file.
@@ -401,41 +894,30 @@ mod tests {
 
             ## [1.1.1] - 2015-05-15
 
-            ### Fixed
-            - myfix
-
-            ### Other
-            - simple update
-
-            ## [1.1.0] - 1970-01-01
-
-            ### fix bugs
-            - my awesomefix
+            ### Added
+            - *(cli)* feat 1
+            - unscoped feat
 
-            ### other
-            - complex update
+            ### Fixed
+            - *(cli)* fix 2
+            - *(parser)* fix 1
+            - *(parser)* fix 3
+            - unscoped fix
         "####]]
-        .assert_eq(&new);
+        .assert_eq(&changelog.generate().unwrap());
     }
 
     #[test]
-    fn changelog_without_header_is_updated() {
-        let commits = vec![
-            Commit::new(NO_COMMIT_ID.to_string(), "fix: myfix".to_string()),
-            Commit::new(NO_COMMIT_ID.to_string(), "simple update".to_string()),
-        ];
+    fn changelog_entry_with_link_is_generated() {
+        let commits = vec![Commit::new(
+            NO_COMMIT_ID.to_string(),
+            "fix: myfix".to_string(),
+        )];
         let changelog = ChangelogBuilder::new(commits, "1.1.1")
             .with_release_date(NaiveDate::from_ymd_opt(2015, 5, 15).unwrap())
+            .with_release_link("https://github.com/MarcoIeni/release-plz/compare/release-plz-v0.2.24...release-plz-v0.2.25")
             .build();
-        let old = r#"## [1.1.0] - 1970-01-01
-
-### fix bugs
-- my awesomefix
 
-### other
-- complex update
-"#;
-        let new = changelog.prepend(old);
         expect_test::expect![[r####"
             # Changelog
             All notable changes to this project will be documented in this file.
@@ -445,56 +927,579 @@ mod tests {
 
             ## [Unreleased]
 
-            ## [1.1.1] - 2015-05-15
+            ## [1.1.1](https://github.com/MarcoIeni/release-plz/compare/release-plz-v0.2.24...release-plz-v0.2.25) - 2015-05-15
 
             ### Fixed
             - myfix
-
-            ### Other
-            - simple update
-            ## [1.1.0] - 1970-01-01
-
-            ### fix bugs
-            - my awesomefix
-
-            ### other
-            - complex update
         "####]]
-        .assert_eq(&new.unwrap());
+        .assert_eq(&changelog.generate().unwrap());
     }
 
     #[test]
-    fn changelog_has_commit_id() {
+    fn changelog_entries_have_commit_links() {
         let commits = vec![
-            Commit::new("0000000".to_string(), "fix: myfix".to_string()),
             Commit::new(
-                NO_COMMIT_ID.to_string(),
-                "chore: something else".to_string(),
+                "abc1234def5678".to_string(),
+                "feat(parser): new parser".to_string(),
             ),
+            Commit::new("0123456789abc".to_string(), "fix: myfix".to_string()),
+            Commit::new(NO_COMMIT_ID.to_string(), "simple update".to_string()),
         ];
         let changelog = ChangelogBuilder::new(commits, "1.1.1")
             .with_release_date(NaiveDate::from_ymd_opt(2015, 5, 15).unwrap())
-            .with_config(Config {
-                changelog: ChangelogConfig {
-                    body: Some(
-                        r"{%- for commit in commits %}
-                            {{ commit.message }} - {{ commit.id }}
-                        {% endfor -%}"
-                            .to_string(),
-                    ),
-                    ..Default::default()
-                },
-                git: GitConfig::default(),
-            })
+            .with_commit_link("https://github.com/MarcoIeni/release-plz/commit")
             .build();
 
         expect_test::expect![[r####"
+            # Changelog
+            All notable changes to this project will be documented in this file.
 
-            myfix - 0000000
+            The format is based on [Keep a Changelog](https://keepachangelog.com/en/1.0.0/),
+            and this project adheres to [Semantic Versioning](https://semver.org/spec/v2.0.0.html).
 
-            something else - N/A
+            ## [Unreleased]
+
+            ## [1.1.1] - 2015-05-15
+
+            ### Added
+            - *(parser)* new parser ([abc1234](https://github.com/MarcoIeni/release-plz/commit/abc1234def5678))
+
+            ### Fixed
+            - myfix ([0123456](https://github.com/MarcoIeni/release-plz/commit/0123456789abc))
+
+            ### Other
+            - simple update
+        "####]]
+        .assert_eq(&changelog.generate().unwrap());
+    }
+
+    fn changelog_with_release_link(release_link: String) -> ChangelogBuilder<'static> {
+        let commits = vec![Commit::new(
+            NO_COMMIT_ID.to_string(),
+            "fix: myfix".to_string(),
+        )];
+        ChangelogBuilder::new(commits, "1.2.3")
+            .with_release_date(NaiveDate::from_ymd_opt(2015, 5, 15).unwrap())
+            .with_release_link(release_link)
+    }
+
+    #[test]
+    fn version_heading_links_to_comparison_with_previous_release() {
+        let repo = crate::RepoUrl::new("https://github.com/owner/repo").unwrap();
+        let changelog =
+            changelog_with_release_link(repo.git_release_link("v1.2.2", "v1.2.3")).build();
+        expect_test::expect![[r#"
+            # Changelog
+            All notable changes to this project will be documented in this file.
+
+            The format is based on [Keep a Changelog](https://keepachangelog.com/en/1.0.0/),
+            and this project adheres to [Semantic Versioning](https://semver.org/spec/v2.0.0.html).
+
+            ## [Unreleased]
+
+            ## [1.2.3](https://github.com/owner/repo/compare/v1.2.2...v1.2.3) - 2015-05-15
+
+            ### Fixed
+            - myfix
+        "#]]
+        .assert_eq(&changelog.generate().unwrap());
+    }
+
+    #[test]
+    fn version_heading_of_first_release_links_to_tag() {
+        let repo = crate::RepoUrl::new("https://github.com/owner/repo").unwrap();
+        let changelog =
+            changelog_with_release_link(repo.git_release_link("v1.2.3", "v1.2.3")).build();
+        expect_test::expect![[r#"
+            # Changelog
+            All notable changes to this project will be documented in this file.
+
+            The format is based on [Keep a Changelog](https://keepachangelog.com/en/1.0.0/),
+            and this project adheres to [Semantic Versioning](https://semver.org/spec/v2.0.0.html).
+
+            ## [Unreleased]
+
+            ## [1.2.3](https://github.com/owner/repo/releases/tag/v1.2.3) - 2015-05-15
+
+            ### Fixed
+            - myfix
+        "#]]
+        .assert_eq(&changelog.generate().unwrap());
+    }
+
+    #[test]
+    fn version_heading_link_can_be_disabled() {
+        let repo = crate::RepoUrl::new("https://github.com/owner/repo").unwrap();
+        let changelog = changelog_with_release_link(repo.git_release_link("v1.2.2", "v1.2.3"))
+            .with_header_link(false)
+            .build();
+        expect_test::expect![[r#"
+            # Changelog
+            All notable changes to this project will be documented in this file.
+
+            The format is based on [Keep a Changelog](https://keepachangelog.com/en/1.0.0/),
+            and this project adheres to [Semantic Versioning](https://semver.org/spec/v2.0.0.html).
+
+            ## [Unreleased]
+
+            ## [1.2.3] - 2015-05-15
+
+            ### Fixed
+            - myfix
+        "#]]
+        .assert_eq(&changelog.generate().unwrap());
+    }
+
+    #[test]
+    fn changelog_entries_are_truncated() {
+        let commits = vec![
+            Commit::new(NO_COMMIT_ID.to_string(), "feat: feature 1".to_string()),
+            Commit::new(NO_COMMIT_ID.to_string(), "fix: fix 1".to_string()),
+            Commit::new(NO_COMMIT_ID.to_string(), "feat: feature 2".to_string()),
+            Commit::new(NO_COMMIT_ID.to_string(), "fix: fix 2".to_string()),
+            Commit::new(NO_COMMIT_ID.to_string(), "feat: feature 3".to_string()),
+            Commit::new(NO_COMMIT_ID.to_string(), "feat: feature 4".to_string()),
+        ];
+        let changelog = ChangelogBuilder::new(commits, "1.1.1")
+            .with_release_date(NaiveDate::from_ymd_opt(2015, 5, 15).unwrap())
+            .with_release_link("https://github.com/MarcoIeni/release-plz/compare/v1.1.0...v1.1.1")
+            .with_max_entries(2)
+            .build();
+
+        expect_test::expect![[r#"
+            # Changelog
+            All notable changes to this project will be documented in this file.
+
+            The format is based on [Keep a Changelog](https://keepachangelog.com/en/1.0.0/),
+            and this project adheres to [Semantic Versioning](https://semver.org/spec/v2.0.0.html).
+
+            ## [Unreleased]
+
+            ## [1.1.1](https://github.com/MarcoIeni/release-plz/compare/v1.1.0...v1.1.1) - 2015-05-15
+
+            ### Added
+            - feature 1
+            - feature 2
+            - […and 2 more](https://github.com/MarcoIeni/release-plz/compare/v1.1.0...v1.1.1)
+
+            ### Fixed
+            - fix 1
+            - fix 2
+        "#]]
+        .assert_eq(&changelog.generate().unwrap());
+    }
+
+    #[test]
+    fn changelog_entries_are_truncated_without_release_link() {
+        let commits = vec![
+            Commit::new(NO_COMMIT_ID.to_string(), "fix: fix 1".to_string()),
+            Commit::new(NO_COMMIT_ID.to_string(), "fix: fix 2".to_string()),
+        ];
+        let changelog = ChangelogBuilder::new(commits, "1.1.1")
+            .with_release_date(NaiveDate::from_ymd_opt(2015, 5, 15).unwrap())
+            .with_max_entries(1)
+            .build();
+
+        expect_test::expect![[r#"
+            # Changelog
+            All notable changes to this project will be documented in this file.
+
+            The format is based on [Keep a Changelog](https://keepachangelog.com/en/1.0.0/),
+            and this project adheres to [Semantic Versioning](https://semver.org/spec/v2.0.0.html).
+
+            ## [Unreleased]
+
+            ## [1.1.1] - 2015-05-15
+
+            ### Fixed
+            - fix 1
+            - …and 1 more
+        "#]]
+        .assert_eq(&changelog.generate().unwrap());
+    }
+
+    #[test]
+    fn changelog_entries_have_no_commit_links_by_default() {
+        let commits = vec![Commit::new(
+            "abc1234def5678".to_string(),
+            "fix: myfix".to_string(),
+        )];
+        let changelog = ChangelogBuilder::new(commits, "1.1.1")
+            .with_release_date(NaiveDate::from_ymd_opt(2015, 5, 15).unwrap())
+            .build()
+            .generate()
+            .unwrap();
+        assert!(changelog.contains("- myfix\n"));
+        assert!(!changelog.contains("abc1234"));
+    }
+
+    #[test]
+    fn generated_changelog_is_updated_correctly() {
+        let commits = vec![
+            Commit::new(NO_COMMIT_ID.to_string(), "fix: myfix".to_string()),
+            Commit::new(NO_COMMIT_ID.to_string(), "simple update".to_string()),
+        ];
+        let changelog = ChangelogBuilder::new(commits, "1.1.1")
+            .with_release_date(NaiveDate::from_ymd_opt(2015, 5, 15).unwrap())
+            .build();
+
+        let generated_changelog = changelog.generate().unwrap();
+
+        let commits = vec![
+            Commit::new(NO_COMMIT_ID.to_string(), "fix: myfix2".to_string()),
+            Commit::new(NO_COMMIT_ID.to_string(), "complex update".to_string()),
+        ];
+        let changelog = ChangelogBuilder::new(commits, "1.1.2")
+            .with_release_date(NaiveDate::from_ymd_opt(2015, 5, 15).unwrap())
+            .build();
+
+        expect_test::expect![[r####"
+            # Changelog
+            All notable changes to this project will be documented in this file.
+
+            The format is based on [Keep a Changelog](https://keepachangelog.com/en/1.0.0/),
+            and this project adheres to [Semantic Versioning](https://semver.org/spec/v2.0.0.html).
+
+            ## [Unreleased]
+
+            ## [1.1.2] - 2015-05-15
+
+            ### Fixed
+            - myfix2
+
+            ### Other
+            - complex update
+
+            ## [1.1.1] - 2015-05-15
+
+            ### Fixed
+            - myfix
+
+            ### Other
+            - simple update
+        "####]]
+        .assert_eq(&changelog.prepend(generated_changelog).unwrap());
+    }
+
+    #[test]
+    fn changelog_is_updated() {
+        let commits = vec![
+            Commit::new(NO_COMMIT_ID.to_string(), "fix: myfix".to_string()),
+            Commit::new(NO_COMMIT_ID.to_string(), "simple update".to_string()),
+        ];
+        let changelog = ChangelogBuilder::new(commits, "1.1.1")
+            .with_release_date(NaiveDate::from_ymd_opt(2015, 5, 15).unwrap())
+            .build();
+        let old_body = r#"## [1.1.0] - 1970-01-01
+
+### fix bugs
+- my awesomefix
+
+### other
+- complex update
+"#;
+        let old = format!("{CHANGELOG_HEADER}\n{old_body}");
+        let new = changelog.prepend(old).unwrap();
+        expect_test::expect![[r####"
+            # Changelog
+            All notable changes to this project will be documented in this file.
+
+            The format is based on [Keep a Changelog](https://keepachangelog.com/en/1.0.0/),
+            and this project adheres to [Semantic Versioning](https://semver.org/spec/v2.0.0.html).
+
+            ## [Unreleased]
+
+            ## [1.1.1] - 2015-05-15
+
+            ### Fixed
+            - myfix
+
+            ### Other
+            - simple update
+
+            ## [1.1.0] - 1970-01-01
+
+            ### fix bugs
+            - my awesomefix
+
+            ### other
+            - complex update
+        "####]]
+        .assert_eq(&new);
+    }
+
+    #[test]
+    fn unreleased_section_is_promoted() {
+        let commits = vec![Commit::new(
+            NO_COMMIT_ID.to_string(),
+            "fix: myfix".to_string(),
+        )];
+        let changelog = ChangelogBuilder::new(commits, "1.1.1")
+            .with_release_date(NaiveDate::from_ymd_opt(2015, 5, 15).unwrap())
+            .with_unreleased_section(true)
+            .build();
+        let old_body = r#"
+### Added
+- manual entry
+
+## [1.1.0] - 1970-01-01
+
+### fix bugs
+- my awesomefix
+"#;
+        let old = format!("{CHANGELOG_HEADER}{old_body}");
+        let new = changelog.prepend(old).unwrap();
+        expect_test::expect![[r####"
+            # Changelog
+            All notable changes to this project will be documented in this file.
+
+            The format is based on [Keep a Changelog](https://keepachangelog.com/en/1.0.0/),
+            and this project adheres to [Semantic Versioning](https://semver.org/spec/v2.0.0.html).
+
+            ## [Unreleased]
+
+            ## [1.1.1] - 2015-05-15
+
+            ### Added
+            - manual entry
+
+            ### Fixed
+            - myfix
+
+            ## [1.1.0] - 1970-01-01
+
+            ### fix bugs
+            - my awesomefix
+        "####]]
+        .assert_eq(&new);
+    }
+
+    #[test]
+    fn existing_entries_are_not_duplicated() {
+        let commits = vec![
+            Commit::new(NO_COMMIT_ID.to_string(), "fix: myfix".to_string()),
+            Commit::new(
+                "1234567890abcdef1234567890abcdef12345678".to_string(),
+                "fix: reword crash fix".to_string(),
+            ),
+            Commit::new(NO_COMMIT_ID.to_string(), "feat: new api".to_string()),
+        ];
+        let changelog = ChangelogBuilder::new(commits, "1.1.1")
+            .with_release_date(NaiveDate::from_ymd_opt(2015, 5, 15).unwrap())
+            .with_unreleased_section(true)
+            .with_dedup_existing(true)
+            .build();
+        let old_body = r#"
+### Fixed
+- MyFix.
+- Fix crash on startup ([1234567](https://github.com/me/proj/commit/1234567890abcdef1234567890abcdef12345678))
+
+## [1.1.0] - 1970-01-01
+
+### fix bugs
+- my awesomefix
+"#;
+        let old = format!("{CHANGELOG_HEADER}{old_body}");
+        let new = changelog.prepend(old).unwrap();
+        expect_test::expect![[r####"
+            # Changelog
+            All notable changes to this project will be documented in this file.
+
+            The format is based on [Keep a Changelog](https://keepachangelog.com/en/1.0.0/),
+            and this project adheres to [Semantic Versioning](https://semver.org/spec/v2.0.0.html).
+
+            ## [Unreleased]
+
+            ## [1.1.1] - 2015-05-15
+
+            ### Fixed
+            - MyFix.
+            - Fix crash on startup ([1234567](https://github.com/me/proj/commit/1234567890abcdef1234567890abcdef12345678))
+
+            ### Added
+            - new api
+
+            ## [1.1.0] - 1970-01-01
+
+            ### fix bugs
+            - my awesomefix
+        "####]]
+        .assert_eq(&new);
+    }
+
+    #[test]
+    fn unreleased_section_is_created() {
+        let commits = vec![Commit::new(
+            NO_COMMIT_ID.to_string(),
+            "fix: myfix".to_string(),
+        )];
+        let changelog = ChangelogBuilder::new(commits, "1.1.1")
+            .with_release_date(NaiveDate::from_ymd_opt(2015, 5, 15).unwrap())
+            .with_unreleased_section(true)
+            .build();
+        let old = r#"# Changelog
+
+## [1.1.0] - 1970-01-01
+
+### fix bugs
+- my awesomefix
+"#;
+        let new = changelog.prepend(old).unwrap();
+        expect_test::expect![[r####"
+            # Changelog
+
+            ## [Unreleased]
+
+            ## [1.1.1] - 2015-05-15
+
+            ### Fixed
+            - myfix
+
+            ## [1.1.0] - 1970-01-01
+
+            ### fix bugs
+            - my awesomefix
+        "####]]
+        .assert_eq(&new);
+    }
+
+    #[test]
+    fn changelog_without_header_is_updated() {
+        let commits = vec![
+            Commit::new(NO_COMMIT_ID.to_string(), "fix: myfix".to_string()),
+            Commit::new(NO_COMMIT_ID.to_string(), "simple update".to_string()),
+        ];
+        let changelog = ChangelogBuilder::new(commits, "1.1.1")
+            .with_release_date(NaiveDate::from_ymd_opt(2015, 5, 15).unwrap())
+            .build();
+        let old = r#"## [1.1.0] - 1970-01-01
+
+### fix bugs
+- my awesomefix
+
+### other
+- complex update
+"#;
+        let new = changelog.prepend(old);
+        expect_test::expect![[r####"
+            # Changelog
+            All notable changes to this project will be documented in this file.
+
+            The format is based on [Keep a Changelog](https://keepachangelog.com/en/1.0.0/),
+            and this project adheres to [Semantic Versioning](https://semver.org/spec/v2.0.0.html).
+
+            ## [Unreleased]
+
+            ## [1.1.1] - 2015-05-15
+
+            ### Fixed
+            - myfix
+
+            ### Other
+            - simple update
+            ## [1.1.0] - 1970-01-01
+
+            ### fix bugs
+            - my awesomefix
+
+            ### other
+            - complex update
         "####]]
-        .assert_eq(&changelog.generate());
+        .assert_eq(&new.unwrap());
+    }
+
+    #[test]
+    fn changelog_has_commit_id() {
+        let commits = vec![
+            Commit::new("0000000".to_string(), "fix: myfix".to_string()),
+            Commit::new(
+                NO_COMMIT_ID.to_string(),
+                "chore: something else".to_string(),
+            ),
+        ];
+        let changelog = ChangelogBuilder::new(commits, "1.1.1")
+            .with_release_date(NaiveDate::from_ymd_opt(2015, 5, 15).unwrap())
+            .with_config(Config {
+                changelog: ChangelogConfig {
+                    body: Some(
+                        r"{%- for commit in commits %}
+                            {{ commit.message }} - {{ commit.id }}
+                        {% endfor -%}"
+                            .to_string(),
+                    ),
+                    ..Default::default()
+                },
+                git: GitConfig::default(),
+            })
+            .build();
+
+        expect_test::expect![[r####"
+
+            myfix - 0000000
+
+            something else - N/A
+        "####]]
+        .assert_eq(&changelog.generate().unwrap());
+    }
+
+    #[test]
+    fn changelog_context_is_available_in_template() {
+        let commits = vec![Commit::new(
+            NO_COMMIT_ID.to_string(),
+            "fix: myfix".to_string(),
+        )];
+        let context = toml::from_str(
+            r#"
+            product_name = "Acme"
+            platforms = ["linux", "macos"]
+            [support]
+            url = "https://acme.com/support"
+            "#,
+        )
+        .unwrap();
+        let changelog = ChangelogBuilder::new(commits, "1.1.1")
+            .with_release_date(NaiveDate::from_ymd_opt(2015, 5, 15).unwrap())
+            .with_context(context)
+            .with_config(Config {
+                changelog: ChangelogConfig {
+                    body: Some(
+                        r#"{{ context.product_name }} {{ version }} ({{ context.platforms | join(sep=", ") }})
+                        {% for commit in commits %}- {{ commit.message }}
+                        {% endfor %}Support: {{ context.support.url }}"#
+                            .to_string(),
+                    ),
+                    ..Default::default()
+                },
+                git: GitConfig::default(),
+            })
+            .build();
+
+        expect_test::expect![[r#"
+            Acme 1.1.1 (linux, macos)
+            - myfix
+            Support: https://acme.com/support"#]]
+        .assert_eq(&changelog.generate().unwrap());
+    }
+
+    #[test]
+    fn missing_changelog_context_variable_is_an_error() {
+        let commits = vec![Commit::new(
+            NO_COMMIT_ID.to_string(),
+            "fix: myfix".to_string(),
+        )];
+        let context = toml::from_str(r#"product_name = "Acme""#).unwrap();
+        let changelog = ChangelogBuilder::new(commits, "1.1.1")
+            .with_context(context)
+            .with_config(Config {
+                changelog: ChangelogConfig {
+                    body: Some("{{ context.missing }} {{ version }}".to_string()),
+                    ..Default::default()
+                },
+                git: GitConfig::default(),
+            })
+            .build();
+        let error = changelog.generate().unwrap_err();
+        assert!(format!("{error:#}").contains("`context.missing` not found"));
     }
 
     #[test]
@@ -506,7 +1511,7 @@ mod tests {
         let changelog = ChangelogBuilder::new(commits, "1.1.1")
             .with_release_date(NaiveDate::from_ymd_opt(2015, 5, 15).unwrap())
             .with_config(Config {
-                changelog: default_changelog_config(None, None),
+                changelog: default_changelog_config(None, None, true, None, None),
                 git: GitConfig {
                     sort_commits: Some("oldest".to_string()),
                     ..Default::default()
@@ -529,7 +1534,339 @@ mod tests {
             - another fix
             - myfix
         "#]]
-        .assert_eq(&changelog.generate());
+        .assert_eq(&changelog.generate().unwrap());
+    }
+
+    /// Entries of the `Fixed` section of the changelog, sorted with `commit_sort`.
+    fn fixed_entries(commit_sort: Option<CommitSort>) -> Vec<String> {
+        // Commits are sorted from newest to oldest, like in the git log.
+        let commits = [
+            ("2015-05-14", "fix: third"),
+            ("2015-05-13", "fix: second"),
+            ("2015-05-12", "fix: first"),
+        ]
+        .map(|(date, message)| {
+            let timestamp = NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                .unwrap()
+                .and_hms_opt(0, 0, 0)
+                .unwrap()
+                .and_utc()
+                .timestamp();
+            let mut commit = Commit::new(NO_COMMIT_ID.to_string(), message.to_string());
+            commit.committer.timestamp = timestamp;
+            commit
+        })
+        .to_vec();
+        let mut builder = ChangelogBuilder::new(commits, "1.1.1")
+            .with_release_date(NaiveDate::from_ymd_opt(2015, 5, 15).unwrap());
+        if let Some(commit_sort) = commit_sort {
+            builder = builder.with_commit_sort(commit_sort);
+        }
+        builder
+            .build()
+            .generate()
+            .unwrap()
+            .lines()
+            .filter_map(|line| line.strip_prefix("- "))
+            .map(str::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn commit_sort_flips_the_order_of_the_entries() {
+        assert_eq!(fixed_entries(None), ["third", "second", "first"]);
+        assert_eq!(
+            fixed_entries(Some(CommitSort::Newest)),
+            ["third", "second", "first"]
+        );
+        assert_eq!(
+            fixed_entries(Some(CommitSort::Oldest)),
+            ["first", "second", "third"]
+        );
+    }
+
+    #[test]
+    fn commit_sort_overrides_git_cliff_sort_commits() {
+        let commits = vec![
+            Commit::new("1111111".to_string(), "fix: myfix".to_string()),
+            Commit::new("0000000".to_string(), "fix: another fix".to_string()),
+        ];
+        let changelog = ChangelogBuilder::new(commits, "1.1.1")
+            .with_config(Config {
+                changelog: default_changelog_config(None, None, true, None, None),
+                git: GitConfig {
+                    sort_commits: Some("oldest".to_string()),
+                    ..Default::default()
+                },
+            })
+            .with_commit_sort(CommitSort::Newest)
+            .build();
+        let changelog = changelog.generate().unwrap();
+        assert!(changelog.find("- myfix").unwrap() < changelog.find("- another fix").unwrap());
+    }
+
+    fn changelog_with_format(version: &str, format: ChangelogFormat) -> Changelog<'static> {
+        let commits = vec![
+            Commit::new(
+                "1111111".to_string(),
+                "feat(api)!: add endpoint".to_string(),
+            ),
+            Commit::new(NO_COMMIT_ID.to_string(), "fix: myfix".to_string()),
+            Commit::new(NO_COMMIT_ID.to_string(), "simple update".to_string()),
+        ];
+        ChangelogBuilder::new(commits, version)
+            .with_release_date(NaiveDate::from_ymd_opt(2015, 5, 15).unwrap())
+            .with_format(format)
+            .build()
+    }
+
+    #[test]
+    fn json_changelog_is_generated() {
+        let changelog = changelog_with_format("1.1.1", ChangelogFormat::Json);
+        expect_test::expect![[r#"
+            [
+              {
+                "version": "1.1.1",
+                "date": "2015-05-15",
+                "sections": [
+                  {
+                    "title": "Added",
+                    "entries": [
+                      {
+                        "message": "add endpoint",
+                        "scope": "api",
+                        "breaking": true,
+                        "commit_id": "1111111"
+                      }
+                    ]
+                  },
+                  {
+                    "title": "Fixed",
+                    "entries": [
+                      {
+                        "message": "myfix",
+                        "breaking": false
+                      }
+                    ]
+                  },
+                  {
+                    "title": "Other",
+                    "entries": [
+                      {
+                        "message": "simple update",
+                        "breaking": false
+                      }
+                    ]
+                  }
+                ]
+              }
+            ]
+        "#]]
+        .assert_eq(&changelog.generate().unwrap());
+    }
+
+    #[test]
+    fn json_changelog_is_updated() {
+        let old = changelog_with_format("1.1.0", ChangelogFormat::Json)
+            .generate()
+            .unwrap();
+        let changelog = changelog_with_format("1.1.1", ChangelogFormat::Json);
+        let new = changelog.prepend(old).unwrap();
+        let releases: Vec<serde_json::Value> = serde_json::from_str(&new).unwrap();
+        let versions: Vec<&str> = releases
+            .iter()
+            .map(|r| r["version"].as_str().unwrap())
+            .collect();
+        assert_eq!(versions, ["1.1.1", "1.1.0"]);
+    }
+
+    #[test]
+    fn json_changelog_with_same_version_is_not_updated() {
+        let old = changelog_with_format("1.1.1", ChangelogFormat::Json)
+            .generate()
+            .unwrap();
+        let changelog = changelog_with_format("1.1.1", ChangelogFormat::Json);
+        assert_eq!(changelog.prepend(old.clone()).unwrap(), old);
+    }
+
+    #[test]
+    fn invalid_json_changelog_is_an_error() {
+        let changelog = changelog_with_format("1.1.1", ChangelogFormat::Json);
+        assert!(changelog.prepend("# Changelog").is_err());
+    }
+
+    #[test]
+    fn last_release_of_json_changelog_is_markdown() {
+        let old = changelog_with_format("1.1.0", ChangelogFormat::Json)
+            .generate()
+            .unwrap();
+        let new = changelog_with_format("1.1.1", ChangelogFormat::Json)
+            .prepend(old)
+            .unwrap();
+        let release = ChangelogFormat::Json.last_release(&new).unwrap().unwrap();
+        assert_eq!(release.title(), "1.1.1 - 2015-05-15");
+        expect_test::expect![[r#"
+            ### Added
+
+            - *(api)* [**breaking**] add endpoint
+
+            ### Fixed
+
+            - myfix
+
+            ### Other
+
+            - simple update"#]]
+        .assert_eq(release.notes());
+    }
+
+    #[test]
+    fn last_release_of_rst_changelog_is_markdown() {
+        let old = changelog_with_format("1.1.0", ChangelogFormat::Rst)
+            .generate()
+            .unwrap();
+        let new = changelog_with_format("1.1.1", ChangelogFormat::Rst)
+            .prepend(old)
+            .unwrap();
+        let release = ChangelogFormat::Rst.last_release(&new).unwrap().unwrap();
+        assert_eq!(release.title(), "1.1.1 - 2015-05-15");
+        expect_test::expect![[r#"
+            ### Added
+
+            - *(api)* [**breaking**] add endpoint
+
+            ### Fixed
+
+            - myfix
+
+            ### Other
+
+            - simple update"#]]
+        .assert_eq(release.notes());
+    }
+
+    #[test]
+    fn pr_numbers_are_linked_in_the_syntax_of_the_changelog() {
+        let pr_link = "https://github.com/me/proj/pull";
+        let message = "fix: myfix (#12)";
+        assert_eq!(
+            ChangelogFormat::Markdown.link_pr_numbers(message, pr_link),
+            "fix: myfix ([#12](https://github.com/me/proj/pull/12))"
+        );
+        assert_eq!(
+            ChangelogFormat::Rst.link_pr_numbers(message, pr_link),
+            "fix: myfix (`#12 <https://github.com/me/proj/pull/12>`_)"
+        );
+        assert_eq!(
+            ChangelogFormat::Json.link_pr_numbers(message, pr_link),
+            message
+        );
+    }
+
+    #[test]
+    fn links_of_rst_changelog_are_markdown_in_release_notes() {
+        let message = ChangelogFormat::Rst
+            .link_pr_numbers("fix: myfix (#12)", "https://github.com/me/proj/pull");
+        let changelog = ChangelogBuilder::new(
+            vec![Commit::new(NO_COMMIT_ID.to_string(), message)],
+            "1.1.1",
+        )
+        .with_release_date(NaiveDate::from_ymd_opt(2015, 5, 15).unwrap())
+        .with_format(ChangelogFormat::Rst)
+        .build()
+        .generate()
+        .unwrap();
+        assert!(changelog.contains("- myfix (`#12 <https://github.com/me/proj/pull/12>`_)"));
+        let release = ChangelogFormat::Rst
+            .last_release(&changelog)
+            .unwrap()
+            .unwrap();
+        expect_test::expect![[r#"
+            ### Fixed
+
+            - myfix ([#12](https://github.com/me/proj/pull/12))"#]]
+        .assert_eq(release.notes());
+    }
+
+    #[test]
+    fn rst_changelog_is_generated() {
+        let changelog = changelog_with_format("1.1.1", ChangelogFormat::Rst);
+        expect_test::expect![[r#"
+            Changelog
+            =========
+
+            All notable changes to this project will be documented in this file.
+
+            1.1.1 - 2015-05-15
+            ------------------
+
+            Added
+            ~~~~~
+
+            - *(api)* [**breaking**] add endpoint
+
+            Fixed
+            ~~~~~
+
+            - myfix
+
+            Other
+            ~~~~~
+
+            - simple update
+        "#]]
+        .assert_eq(&changelog.generate().unwrap());
+    }
+
+    #[test]
+    fn rst_changelog_is_updated() {
+        let old = changelog_with_format("1.1.0", ChangelogFormat::Rst)
+            .generate()
+            .unwrap();
+        let changelog = changelog_with_format("1.1.1", ChangelogFormat::Rst);
+        expect_test::expect![[r#"
+            Changelog
+            =========
+
+            All notable changes to this project will be documented in this file.
+
+            1.1.1 - 2015-05-15
+            ------------------
+
+            Added
+            ~~~~~
+
+            - *(api)* [**breaking**] add endpoint
+
+            Fixed
+            ~~~~~
+
+            - myfix
+
+            Other
+            ~~~~~
+
+            - simple update
+
+            1.1.0 - 2015-05-15
+            ------------------
+
+            Added
+            ~~~~~
+
+            - *(api)* [**breaking**] add endpoint
+
+            Fixed
+            ~~~~~
+
+            - myfix
+
+            Other
+            ~~~~~
+
+            - simple update
+        "#]]
+        .assert_eq(&changelog.prepend(old).unwrap());
     }
 }
 
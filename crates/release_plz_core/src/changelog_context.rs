@@ -0,0 +1,151 @@
+use std::collections::HashMap;
+
+use anyhow::Context;
+use regex::{Captures, Regex};
+
+/// Name of the template variable containing the user-defined context.
+const CONTEXT: &str = "context";
+
+/// Replace the references to the `context` variables in the tera tags of `template`
+/// with their values, e.g. `{{ context.product_name }}` becomes `{{ "my-product" }}`.
+///
+/// git-cliff renders the template with the release only, so the
+/// values are inlined in the template as tera literals.
+/// Nested values are referenced with their full path, e.g. `context.support.url`.
+pub fn render(template: &str, context: &HashMap<String, toml::Value>) -> anyhow::Result<String> {
+    lazy_static::lazy_static! {
+        // tera expressions `{{ ... }}` and statements `{% ... %}`
+        static ref TAG_RE: Regex = Regex::new(r"(?s)\{\{.*?\}\}|\{%.*?%\}").unwrap();
+        // `context.a.b`, but not `commit.context.a`
+        static ref VARIABLE_RE: Regex =
+            Regex::new(r"(^|[^.\w])context((?:\.[A-Za-z_][A-Za-z0-9_]*)+)").unwrap();
+    }
+
+    let mut error = None;
+    let rendered = TAG_RE.replace_all(template, |tag: &Captures| {
+        VARIABLE_RE
+            .replace_all(&tag[0], |variable: &Captures| {
+                let path = &variable[2][1..];
+                match resolve(context, path).and_then(tera_literal) {
+                    Ok(literal) => format!("{}{literal}", &variable[1]),
+                    Err(e) => {
+                        error.get_or_insert(e);
+                        variable[0].to_string()
+                    }
+                }
+            })
+            .into_owned()
+    });
+    match error {
+        Some(e) => Err(e),
+        None => Ok(rendered.into_owned()),
+    }
+}
+
+/// Find the value of the dotted `path`, e.g. `support.url`.
+fn resolve<'a>(
+    context: &'a HashMap<String, toml::Value>,
+    path: &str,
+) -> anyhow::Result<&'a toml::Value> {
+    let mut keys = path.split('.');
+    let first = keys.next().expect("path is not empty");
+    let mut value = context.get(first);
+    for key in keys {
+        value = value.and_then(|v| v.get(key));
+    }
+    value.with_context(|| format!("changelog context variable `{CONTEXT}.{path}` not found"))
+}
+
+/// Convert `value` to a literal that tera can parse.
+fn tera_literal(value: &toml::Value) -> anyhow::Result<String> {
+    let literal = match value {
+        toml::Value::String(s) => string_literal(s)?,
+        toml::Value::Integer(i) => i.to_string(),
+        toml::Value::Float(f) => {
+            anyhow::ensure!(
+                f.is_finite(),
+                "changelog context value `{f}` is not a finite number"
+            );
+            format!("{f:?}")
+        }
+        toml::Value::Boolean(b) => b.to_string(),
+        toml::Value::Datetime(d) => string_literal(&d.to_string())?,
+        toml::Value::Array(values) => {
+            let values = values
+                .iter()
+                .map(tera_literal)
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            format!("[{}]", values.join(", "))
+        }
+        toml::Value::Table(_) => {
+            anyhow::bail!("changelog context value is a table: reference one of its fields instead")
+        }
+    };
+    Ok(literal)
+}
+
+/// Tera strings don't support escape sequences, so use a quote that isn't in the string.
+fn string_literal(s: &str) -> anyhow::Result<String> {
+    let quote = ['"', '\'', '`']
+        .into_iter()
+        .find(|q| !s.contains(*q))
+        .with_context(|| {
+            format!("changelog context value `{s}` can't contain all of the characters \", ' and `")
+        })?;
+    Ok(format!("{quote}{s}{quote}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context() -> HashMap<String, toml::Value> {
+        toml::from_str(
+            r#"
+            product_name = "Acme"
+            year = 2024
+            platforms = ["linux", "macos"]
+            [support]
+            url = "https://acme.com/support"
+            "#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn variables_are_replaced_with_literals() {
+        let template = "{{ context.product_name }} {% if context.year > 2000 %}{{ context.platforms | join(sep=\", \") }}{% endif %}";
+        assert_eq!(
+            render(template, &context()).unwrap(),
+            "{{ \"Acme\" }} {% if 2024 > 2000 %}{{ [\"linux\", \"macos\"] | join(sep=\", \") }}{% endif %}"
+        );
+    }
+
+    #[test]
+    fn nested_variables_are_replaced() {
+        assert_eq!(
+            render("{{ context.support.url }}", &context()).unwrap(),
+            "{{ \"https://acme.com/support\" }}"
+        );
+    }
+
+    #[test]
+    fn text_outside_of_tags_and_other_variables_are_unchanged() {
+        let template = "context.product_name {{ commit.context.product_name }}";
+        assert_eq!(render(template, &context()).unwrap(), template);
+    }
+
+    #[test]
+    fn missing_variable_is_an_error() {
+        let error = render("{{ context.support.email }}", &context()).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "changelog context variable `context.support.email` not found"
+        );
+    }
+
+    #[test]
+    fn string_quote_is_not_contained_in_string() {
+        assert_eq!(string_literal("say \"hi\"").unwrap(), "'say \"hi\"'");
+    }
+}
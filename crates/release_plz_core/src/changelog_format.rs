@@ -0,0 +1,364 @@
+use std::collections::BTreeMap;
+
+use anyhow::Context;
+use chrono::{TimeZone, Utc};
+use git_cliff_core::release::Release;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    changelog,
+    changelog_parser::{self, ChangelogRelease},
+    CHANGELOG_FILENAME, NO_COMMIT_ID,
+};
+
+const RST_HEADER: &str = "Changelog
+=========
+
+All notable changes to this project will be documented in this file.
+";
+
+/// Output format of the changelog.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ChangelogFormat {
+    /// Markdown, rendered with the git-cliff template.
+    #[default]
+    Markdown,
+    /// JSON list of the releases, from the newest to the oldest.
+    Json,
+    /// reStructuredText.
+    Rst,
+}
+
+impl ChangelogFormat {
+    /// Name of the changelog file, if the changelog path isn't configured.
+    pub fn file_name(self) -> &'static str {
+        match self {
+            Self::Markdown => CHANGELOG_FILENAME,
+            Self::Json => "CHANGELOG.json",
+            Self::Rst => "CHANGELOG.rst",
+        }
+    }
+
+    /// Last release of the `changelog`, with the notes in Markdown,
+    /// e.g. to use them as the body of the git release.
+    pub fn last_release(self, changelog: &str) -> anyhow::Result<Option<ChangelogRelease>> {
+        match self {
+            Self::Markdown => changelog_parser::last_release_from_str(changelog),
+            Self::Json => json_release(changelog, None),
+            Self::Rst => Ok(rst_release_notes(changelog, None)),
+        }
+    }
+
+    /// Replace the PR/issue numbers of the commit `message`, e.g. `#123`,
+    /// with a link to `pr_link`, in the link syntax of the changelog.
+    /// The JSON changelog stores the messages as they are.
+    pub(crate) fn link_pr_numbers(self, message: &str, pr_link: &str) -> String {
+        lazy_static::lazy_static! {
+            // match PR/issue numbers, e.g. `#123`
+            static ref PR_RE: Regex = Regex::new("#(\\d+)").unwrap();
+        }
+        let link = match self {
+            Self::Markdown => format!("[#$1]({pr_link}/$1)"),
+            Self::Rst => format!("`#$1 <{pr_link}/$1>`_"),
+            Self::Json => return message.to_string(),
+        };
+        PR_RE.replace_all(message, link).to_string()
+    }
+
+    /// Release of `version` in the `changelog`, with the notes in Markdown,
+    /// e.g. to create the git release of a past version.
+    pub fn release(
+        self,
+        changelog: &str,
+        version: &str,
+    ) -> anyhow::Result<Option<ChangelogRelease>> {
+        match self {
+            Self::Markdown => changelog_parser::release_from_str(changelog, version),
+            Self::Json => json_release(changelog, Some(version)),
+            Self::Rst => Ok(rst_release_notes(changelog, Some(version))),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct JsonRelease {
+    version: String,
+    date: String,
+    sections: Vec<JsonSection>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct JsonSection {
+    title: String,
+    entries: Vec<JsonEntry>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct JsonEntry {
+    message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    scope: Option<String>,
+    #[serde(default)]
+    breaking: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    commit_id: Option<String>,
+}
+
+impl JsonRelease {
+    /// Group the commits of the release by changelog section.
+    /// Sections are sorted by title, like in the Markdown changelog.
+    fn new(release: &Release) -> Self {
+        let mut sections: BTreeMap<String, Vec<JsonEntry>> = BTreeMap::new();
+        for commit in &release.commits {
+            let group = commit
+                .group
+                .clone()
+                .or_else(|| commit.conv.as_ref().map(|conv| conv.type_().to_string()))
+                .unwrap_or_else(|| "other".to_string());
+            let message = commit
+                .conv
+                .as_ref()
+                .map(|conv| conv.description().to_string())
+                .unwrap_or_else(|| {
+                    commit
+                        .message
+                        .lines()
+                        .next()
+                        .unwrap_or_default()
+                        .to_string()
+                });
+            let entry = JsonEntry {
+                message,
+                scope: changelog::commit_scope(commit).map(String::from),
+                breaking: commit.conv.as_ref().is_some_and(|conv| conv.breaking()),
+                commit_id: (commit.id != NO_COMMIT_ID).then(|| commit.id.clone()),
+            };
+            sections.entry(upper_first(&group)).or_default().push(entry);
+        }
+        Self {
+            version: release_version(release),
+            date: release_date(release),
+            sections: sections
+                .into_iter()
+                .map(|(title, entries)| JsonSection { title, entries })
+                .collect(),
+        }
+    }
+}
+
+/// Changelog containing the `release` only.
+pub(crate) fn generate_json(release: &Release) -> String {
+    to_json(&[JsonRelease::new(release)])
+}
+
+/// Add the `release` at the beginning of the list of releases of the old changelog.
+/// The old releases are kept as they are, including fields unknown to release-plz.
+pub(crate) fn prepend_json(release: &Release, old_changelog: &str) -> anyhow::Result<String> {
+    if old_changelog.trim().is_empty() {
+        return Ok(generate_json(release));
+    }
+    let mut releases: Vec<serde_json::Value> =
+        serde_json::from_str(old_changelog).context("cannot parse the JSON changelog")?;
+    let version = release_version(release);
+    let last_version = releases
+        .first()
+        .and_then(|r| r.get("version"))
+        .and_then(|v| v.as_str());
+    if last_version == Some(version.as_str()) {
+        return Ok(old_changelog.to_string());
+    }
+    let new_release =
+        serde_json::to_value(JsonRelease::new(release)).context("cannot serialize release")?;
+    releases.insert(0, new_release);
+    Ok(to_json(&releases))
+}
+
+/// Release of `version`, or the last release if `version` is `None`.
+fn json_release(
+    changelog: &str,
+    version: Option<&str>,
+) -> anyhow::Result<Option<ChangelogRelease>> {
+    let releases: Vec<JsonRelease> =
+        serde_json::from_str(changelog).context("cannot parse the JSON changelog")?;
+    let release = releases
+        .into_iter()
+        .find(|release| version.map_or(true, |version| release.version == version));
+    let release = release.map(|release| {
+        let mut notes = String::new();
+        for section in &release.sections {
+            notes.push_str(&format!("### {}\n\n", section.title));
+            for entry in &section.entries {
+                notes.push_str(&entry_line(entry));
+            }
+            notes.push('\n');
+        }
+        ChangelogRelease::new(
+            format!("{} - {}", release.version, release.date),
+            notes.trim().to_string(),
+        )
+    });
+    Ok(release)
+}
+
+fn to_json(releases: &[impl Serialize]) -> String {
+    let mut json = serde_json::to_string_pretty(releases).expect("cannot serialize changelog");
+    json.push('\n');
+    json
+}
+
+/// Changelog containing the `release` only.
+pub(crate) fn generate_rst(release: &Release) -> String {
+    format!("{RST_HEADER}\n{}", rst_release(release))
+}
+
+/// Add the `release` before the first release of the old changelog.
+/// If the old changelog doesn't contain releases, the `release` is appended.
+pub(crate) fn prepend_rst(release: &Release, old_changelog: &str) -> String {
+    if old_changelog.trim().is_empty() {
+        return generate_rst(release);
+    }
+    let lines: Vec<&str> = old_changelog.split_inclusive('\n').collect();
+    let first_release = (0..lines.len().saturating_sub(1))
+        .find(|&i| is_rst_release_heading(lines[i], lines[i + 1]));
+    match first_release {
+        Some(i) => {
+            let last_version = lines[i].split(" - ").next().unwrap_or_default().trim();
+            if last_version == release_version(release) {
+                return old_changelog.to_string();
+            }
+            format!(
+                "{}{}\n{}",
+                lines[..i].concat(),
+                rst_release(release),
+                lines[i..].concat()
+            )
+        }
+        None => {
+            let mut changelog = old_changelog.trim_end().to_string();
+            changelog.push_str("\n\n");
+            changelog.push_str(&rst_release(release));
+            changelog
+        }
+    }
+}
+
+/// Release headings are underlined with `-`, e.g.
+/// ```text
+/// 1.1.0 - 2015-05-15
+/// ------------------
+/// ```
+fn is_rst_release_heading(line: &str, next_line: &str) -> bool {
+    let title = line.trim_end();
+    let underline = next_line.trim_end();
+    !title.is_empty()
+        && underline.len() >= title.chars().count()
+        && underline.chars().all(|c| c == '-')
+}
+
+/// Release of `version`, or the last release if `version` is `None`.
+/// The section headings of the release, underlined with `~`, become Markdown headings.
+fn rst_release_notes(changelog: &str, version: Option<&str>) -> Option<ChangelogRelease> {
+    let lines: Vec<&str> = changelog.lines().collect();
+    let is_release_heading =
+        |i: usize| i + 1 < lines.len() && is_rst_release_heading(lines[i], lines[i + 1]);
+    // The heading is `<version> - <date>`.
+    let is_version_heading = |i: usize| {
+        version.map_or(true, |version| {
+            lines[i]
+                .split_whitespace()
+                .next()
+                .is_some_and(|v| v.trim_start_matches('v') == version)
+        })
+    };
+    let start = (0..lines.len()).find(|&i| is_release_heading(i) && is_version_heading(i))?;
+    let end = (start + 2..lines.len())
+        .find(|&i| is_release_heading(i))
+        .unwrap_or(lines.len());
+    let mut notes = vec![];
+    let mut i = start + 2;
+    while i < end {
+        let is_section_heading = i + 1 < end
+            && !lines[i].trim().is_empty()
+            && !lines[i + 1].trim().is_empty()
+            && lines[i + 1].trim_end().chars().all(|c| c == '~');
+        if is_section_heading {
+            notes.push(format!("### {}", lines[i].trim_end()));
+            i += 2;
+        } else {
+            notes.push(rst_links_to_markdown(lines[i]));
+            i += 1;
+        }
+    }
+    Some(ChangelogRelease::new(
+        lines[start].trim().to_string(),
+        notes.join("\n").trim().to_string(),
+    ))
+}
+
+/// Convert the reStructuredText links of `line`, e.g. `` `#1 <https://pr/1>`_ ``,
+/// to Markdown links, e.g. `[#1](https://pr/1)`.
+fn rst_links_to_markdown(line: &str) -> String {
+    lazy_static::lazy_static! {
+        static ref RST_LINK_RE: Regex = Regex::new(r"`([^`<]+) <([^`>]+)>`__?").unwrap();
+    }
+    RST_LINK_RE.replace_all(line, "[$1]($2)").to_string()
+}
+
+/// Changelog entry, e.g. `- *(api)* [**breaking**] add endpoint`.
+/// The scope and the breaking marker are valid both in Markdown and in reStructuredText.
+/// The links of the message are already in the syntax of the changelog,
+/// see [`ChangelogFormat::link_pr_numbers`].
+fn entry_line(entry: &JsonEntry) -> String {
+    let mut line = "- ".to_string();
+    if let Some(scope) = &entry.scope {
+        line.push_str(&format!("*({scope})* "));
+    }
+    if entry.breaking {
+        line.push_str("[**breaking**] ");
+    }
+    line.push_str(&entry.message);
+    line.push('\n');
+    line
+}
+
+fn rst_release(release: &Release) -> String {
+    let json_release = JsonRelease::new(release);
+    let heading = format!("{} - {}", json_release.version, json_release.date);
+    let mut out = format!("{heading}\n{}\n", "-".repeat(heading.chars().count()));
+    for section in json_release.sections {
+        out.push_str(&format!(
+            "\n{}\n{}\n\n",
+            section.title,
+            "~".repeat(section.title.chars().count())
+        ));
+        for entry in &section.entries {
+            out.push_str(&entry_line(entry));
+        }
+    }
+    out
+}
+
+fn release_version(release: &Release) -> String {
+    release
+        .version
+        .as_deref()
+        .unwrap_or_default()
+        .trim_start_matches('v')
+        .to_string()
+}
+
+fn release_date(release: &Release) -> String {
+    Utc.timestamp_opt(release.timestamp, 0)
+        .single()
+        .map(|date| date.format("%Y-%m-%d").to_string())
+        .unwrap_or_default()
+}
+
+fn upper_first(s: &str) -> String {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
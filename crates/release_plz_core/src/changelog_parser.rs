@@ -1,5 +1,3 @@
-use std::{fs::read_to_string, path::Path};
-
 use anyhow::Context;
 use regex::Regex;
 
@@ -25,15 +23,39 @@ pub fn parse_header(changelog: &str) -> Option<String> {
     None
 }
 
-pub fn last_changes(changelog: &Path) -> anyhow::Result<Option<String>> {
-    let changelog = read_to_string(changelog).context("can't read changelog file")?;
-    last_changes_from_str(&changelog)
+/// Whether the line is the heading of the `Unreleased` section, e.g. `## [Unreleased]`.
+pub fn is_unreleased_heading(line: &str) -> bool {
+    line.strip_prefix("## ").map_or(false, |title| {
+        title
+            .trim()
+            .trim_start_matches('[')
+            .to_lowercase()
+            .starts_with("unreleased")
+    })
 }
 
-pub fn last_changes_from_str(changelog: &str) -> anyhow::Result<Option<String>> {
-    let parser = ChangelogParser::new(changelog)?;
-    let last_release = parser.last_release().map(|r| r.notes.to_string());
-    Ok(last_release)
+/// Remove the notes of the `Unreleased` section from the changelog, keeping its heading.
+/// Returns the changelog without the notes and the notes.
+/// If the changelog doesn't contain the `Unreleased` section, the notes are empty.
+pub fn take_unreleased_notes(changelog: &str) -> (String, String) {
+    let mut changelog_without_notes = String::new();
+    let mut notes = String::new();
+    let mut in_unreleased_section = false;
+    for line in changelog.split_inclusive('\n') {
+        if line.starts_with("## ") {
+            in_unreleased_section = is_unreleased_heading(line);
+            changelog_without_notes.push_str(line);
+            if in_unreleased_section {
+                // Leave an empty line between the heading and the next section.
+                changelog_without_notes.push('\n');
+            }
+        } else if in_unreleased_section {
+            notes.push_str(line);
+        } else {
+            changelog_without_notes.push_str(line);
+        }
+    }
+    (changelog_without_notes, notes.trim().to_string())
 }
 
 pub fn last_version_from_str(changelog: &str) -> anyhow::Result<Option<String>> {
@@ -48,12 +70,30 @@ pub fn last_release_from_str(changelog: &str) -> anyhow::Result<Option<Changelog
     Ok(last_release)
 }
 
+/// Release of `version` (e.g. `1.2.3`), whose heading can also contain `v1.2.3`.
+pub fn release_from_str(
+    changelog: &str,
+    version: &str,
+) -> anyhow::Result<Option<ChangelogRelease>> {
+    let parser = ChangelogParser::new(changelog)?;
+    let release = parser
+        .changelog
+        .get(version)
+        .or_else(|| parser.changelog.get(format!("v{version}").as_str()))
+        .map(ChangelogRelease::from_release);
+    Ok(release)
+}
+
 pub struct ChangelogRelease {
     title: String,
     notes: String,
 }
 
 impl ChangelogRelease {
+    pub(crate) fn new(title: String, notes: String) -> Self {
+        Self { title, notes }
+    }
+
     fn from_release(release: &parse_changelog::Release) -> Self {
         Self {
             title: release.title.to_string(),
@@ -104,7 +144,11 @@ mod tests {
     use super::*;
 
     fn last_changes_from_str_test(changelog: &str) -> String {
-        last_changes_from_str(changelog).unwrap().unwrap()
+        last_release_from_str(changelog)
+            .unwrap()
+            .unwrap()
+            .notes()
+            .to_string()
     }
 
     #[test]
@@ -127,6 +171,26 @@ My custom changelog header
         assert_eq!(header, expected_header);
     }
 
+    #[test]
+    fn unreleased_notes_are_taken() {
+        let changelog = "\
+# Changelog
+
+## [Unreleased]
+
+### Added
+- manual entry
+
+## [1.0.0] - 1970-01-01
+";
+        let (changelog, notes) = take_unreleased_notes(changelog);
+        assert_eq!(
+            changelog,
+            "# Changelog\n\n## [Unreleased]\n\n## [1.0.0] - 1970-01-01\n"
+        );
+        assert_eq!(notes, "### Added\n- manual entry");
+    }
+
     #[test]
     fn changelog_header_with_crlf_parsed_will_contain_crlf() {
         let changelog = "# Changelog\r\n\r\nMy custom changelog header\r\n\r\n## [Unreleased]\r\n";
@@ -16,6 +16,7 @@ pub struct ClonerBuilder {
     /// Cargo current working directory. You can use it to point to the right `.cargo/config.toml`.
     cargo_cwd: Option<PathBuf>,
     use_git: bool,
+    allow_yanked: bool,
 }
 
 impl ClonerBuilder {
@@ -47,6 +48,15 @@ impl ClonerBuilder {
         }
     }
 
+    /// Clone the latest version even if it was yanked.
+    /// By default, the latest non-yanked version is cloned.
+    pub fn with_allow_yanked(self, allow_yanked: bool) -> Self {
+        Self {
+            allow_yanked,
+            ..self
+        }
+    }
+
     /// Build the [`Cloner`].
     pub fn build(self) -> CargoResult<Cloner> {
         let config = match self.config {
@@ -70,6 +80,7 @@ impl ClonerBuilder {
             directory,
             srcid,
             use_git: self.use_git,
+            allow_yanked: self.allow_yanked,
         })
     }
 }
@@ -9,18 +9,19 @@ mod source;
 use cargo::util::cache_lock::CacheLockMode;
 pub use cloner_builder::*;
 pub use source::*;
-use tracing::warn;
+use tracing::{info, warn};
 
 use std::collections::HashSet;
 use std::fs;
 use std::path::Path;
 use std::path::PathBuf;
 use std::process::Command;
+use std::task::Poll;
 
 use anyhow::{bail, Context};
 
 use cargo::core::dependency::Dependency;
-use cargo::core::Package;
+use cargo::core::{Package, PackageId};
 use cargo::sources::source::{QueryKind, Source};
 use cargo::sources::{PathSource, SourceConfigMap};
 
@@ -39,13 +40,29 @@ use crate::strip_prefix::strip_prefix;
 pub struct Crate {
     name: String,
     version: Option<String>,
+    /// Version of the crate in the local project.
+    /// If it was published and then yanked, the registry returns it anyway,
+    /// so that the yanked version can be detected.
+    local_version: Option<String>,
 }
 
 impl Crate {
     /// Create a new [`Crate`].
     /// If `version` is not specified, the latest version is chosen.
     pub fn new(name: String, version: Option<String>) -> Crate {
-        Crate { name, version }
+        Crate {
+            name,
+            version,
+            local_version: None,
+        }
+    }
+
+    /// Set the version of the crate in the local project.
+    pub fn with_local_version(self, local_version: Option<String>) -> Crate {
+        Crate {
+            local_version,
+            ..self
+        }
     }
 }
 
@@ -60,6 +77,9 @@ pub struct Cloner {
     pub(crate) srcid: SourceId,
     /// If true, use `git` to clone the git repository present in the manifest metadata.
     pub(crate) use_git: bool,
+    /// If true, the latest version is cloned even if it was yanked.
+    /// Otherwise, the latest non-yanked version is cloned.
+    pub(crate) allow_yanked: bool,
 }
 
 impl Cloner {
@@ -133,7 +153,17 @@ impl Cloner {
     where
         T: Source + 'a,
     {
-        let pkg = match select_pkg(&self.config, src, &crate_.name, crate_.version.as_deref())? {
+        if let Some(local_version) = &crate_.local_version {
+            let local_pkg = PackageId::new(crate_.name.as_str(), local_version, src.source_id())?;
+            src.add_to_yanked_whitelist(&[local_pkg]);
+        }
+        let pkg = match select_pkg(
+            &self.config,
+            src,
+            &crate_.name,
+            crate_.version.as_deref(),
+            self.allow_yanked,
+        )? {
             Some(pkg) => {
                 if self.use_git {
                     let repo = &pkg.manifest().metadata().repository;
@@ -175,6 +205,7 @@ fn select_pkg<'a, T>(
     src: &mut T,
     name: &str,
     vers: Option<&str>,
+    allow_yanked: bool,
 ) -> CargoResult<Option<Package>>
 where
     T: Source + 'a,
@@ -186,13 +217,13 @@ where
             summaries.push(summary)
         });
         match query_result {
-            std::task::Poll::Ready(res) => match res {
+            Poll::Ready(res) => match res {
                 Ok(()) => break,
                 Err(err) => {
                     return package_from_query_err(err);
                 }
             },
-            std::task::Poll::Pending => match src.block_until_ready() {
+            Poll::Pending => match src.block_until_ready() {
                 Ok(()) => {}
                 Err(err) => {
                     return package_from_query_err(err);
@@ -201,7 +232,23 @@ where
         }
     }
 
-    let latest = summaries.iter().max_by_key(|s| s.version());
+    // The registry source only returns the yanked versions present in its yanked whitelist.
+    let mut candidates = vec![];
+    for summary in summaries {
+        let yanked = is_yanked(src, summary.package_id())?;
+        candidates.push((summary, yanked));
+    }
+    if let Some((latest, true)) = candidates.iter().max_by_key(|(s, _)| s.version()) {
+        if allow_yanked {
+            info!(
+                "the latest version of `{name}` ({}) was yanked, using it anyway",
+                latest.version()
+            );
+        } else {
+            info!("the latest version of `{name}` ({}) was yanked, using the latest non-yanked version", latest.version());
+        }
+    }
+    let latest = select_latest(&candidates, |s| s.version().clone(), allow_yanked);
 
     let pkg = match latest {
         Some(l) => {
@@ -219,6 +266,32 @@ where
     Ok(pkg)
 }
 
+fn is_yanked<'a, T>(src: &mut T, pkg: PackageId) -> CargoResult<bool>
+where
+    T: Source + 'a,
+{
+    loop {
+        match src.is_yanked(pkg) {
+            Poll::Ready(res) => return res,
+            Poll::Pending => src.block_until_ready()?,
+        }
+    }
+}
+
+/// Latest of the `(package, is_yanked)` candidates.
+/// If `allow_yanked` is false, the yanked candidates are ignored.
+fn select_latest<T, V: Ord>(
+    candidates: &[(T, bool)],
+    version: impl Fn(&T) -> V,
+    allow_yanked: bool,
+) -> Option<&T> {
+    candidates
+        .iter()
+        .filter(|(_, yanked)| allow_yanked || !yanked)
+        .map(|(c, _)| c)
+        .max_by_key(|c| version(c))
+}
+
 fn package_from_query_err(err: anyhow::Error) -> CargoResult<Option<Package>> {
     if err.to_string().contains("failed to fetch") {
         // I observed this error happens when the cargo registry contains no crates.
@@ -274,3 +347,28 @@ fn clone_git_repo(repo: &str, to: &Path) -> CargoResult<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn yanked_latest_version_is_skipped() {
+        let candidates = [("0.1.0", false), ("0.3.0", true), ("0.2.0", false)];
+        let latest = select_latest(&candidates, |v| v.to_string(), false);
+        assert_eq!(latest, Some(&"0.2.0"));
+    }
+
+    #[test]
+    fn yanked_latest_version_is_selected_if_allowed() {
+        let candidates = [("0.1.0", false), ("0.3.0", true), ("0.2.0", false)];
+        let latest = select_latest(&candidates, |v| v.to_string(), true);
+        assert_eq!(latest, Some(&"0.3.0"));
+    }
+
+    #[test]
+    fn no_version_is_selected_if_all_are_yanked() {
+        let candidates = [("0.1.0", true)];
+        assert_eq!(select_latest(&candidates, |v| v.to_string(), false), None);
+    }
+}
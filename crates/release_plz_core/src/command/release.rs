@@ -1,23 +1,29 @@
 use std::{
-    collections::BTreeMap,
+    collections::{BTreeMap, HashMap},
+    future::Future,
     path::{Path, PathBuf},
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 use anyhow::Context;
-use cargo_metadata::{Metadata, Package};
+use cargo_metadata::{semver::Version, Metadata, Package};
+use chrono::{NaiveDate, Utc};
 use crates_index::{GitIndex, SparseIndex};
-use git_cmd::Repo;
+use git_cmd::{GitIdentity, Repo};
+use regex::Regex;
 use secrecy::{ExposeSecret, SecretString};
-use tracing::{info, instrument, warn};
-use url::Url;
+use serde::Serialize;
+use tracing::{error, info, instrument, warn};
 
 use crate::{
-    cargo::{is_published, run_cargo, wait_until_published, CargoIndex},
-    changelog_parser,
+    cargo::{
+        is_published, run_cargo_with_env, toolchain_version, wait_until_published, CargoIndex,
+    },
     git::backend::GitClient,
-    release_order::release_order,
-    GitBackend, PackagePath, Project, RequestReleaseValidator, CHANGELOG_FILENAME,
+    plan_trace::PlanTrace,
+    release_order::{release_order, should_dep_be_released_before},
+    ChangelogFormat, GitBackend, GitTagName, PackagePath, Project, RequestReleaseValidator,
+    CARGO_TOML,
 };
 
 #[derive(Debug)]
@@ -33,6 +39,9 @@ pub struct ReleaseRequest {
     token: Option<SecretString>,
     /// Perform all checks without uploading.
     dry_run: bool,
+    /// If true, a step that was already done, e.g. because the tag already exists,
+    /// is skipped instead of failing.
+    idempotent: bool,
     /// Publishes GitHub release.
     git_release: Option<GitRelease>,
     /// GitHub/Gitea/Gitlab repository url where your project is hosted.
@@ -43,6 +52,82 @@ pub struct ReleaseRequest {
     packages_config: PackagesConfig,
     // publish timeout
     publish_timeout: Duration,
+    /// Whether `publish_timeout` applies to each package or to the whole publish phase.
+    publish_timeout_scope: PublishTimeoutScope,
+    /// Key of the commit trailer that overrides the release type of the git release.
+    /// E.g. `Release-Type`.
+    release_type_trailer: Option<String>,
+    /// Don't release the packages if the registry token isn't set.
+    publish_require_token: bool,
+    /// Environment variables containing the token of each registry.
+    /// The key is the registry name, e.g. `crates-io`.
+    publish_token_env: BTreeMap<String, String>,
+    /// Create the git releases only if the current branch matches one of these globs.
+    /// If empty, the git releases are created from any branch.
+    git_release_branches: Vec<glob::Pattern>,
+    /// Author and committer of the git tags.
+    git_identity: GitIdentity,
+    /// Remote where the git tags are pushed.
+    /// If `None`, the remote of the current branch is used.
+    git_push_remote: Option<String>,
+    /// If true, log whether each package is published, tagged and released, and why.
+    verbose_plan: bool,
+    /// Date of the release, used in the git tag messages.
+    /// If `None`, today's date is used.
+    release_date: Option<NaiveDate>,
+    /// What to do if the release of a package fails.
+    on_error: OnError,
+    /// Format of the changelogs, used to read the body of the git releases.
+    changelog_format: ChangelogFormat,
+}
+
+/// What to do if the release of a package fails, when releasing multiple packages.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OnError {
+    /// Don't release the remaining packages.
+    /// The packages released before the failure stay released.
+    #[default]
+    Stop,
+    /// Release the remaining packages, then report the failures.
+    Continue,
+}
+
+/// How the publish timeout is applied when releasing multiple packages.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PublishTimeoutScope {
+    /// The timeout resets for each package.
+    #[default]
+    PerPackage,
+    /// The timeout caps the whole publish phase.
+    Total,
+}
+
+/// Deadline of the publish phase, computed from the publish timeout and its scope.
+#[derive(Debug)]
+struct PublishDeadline {
+    scope: PublishTimeoutScope,
+    timeout: Duration,
+    start: Instant,
+}
+
+impl PublishDeadline {
+    fn new(scope: PublishTimeoutScope, timeout: Duration, start: Instant) -> Self {
+        Self {
+            scope,
+            timeout,
+            start,
+        }
+    }
+
+    /// Time available at `now` to wait for a package to be published.
+    fn timeout(&self, now: Instant) -> Duration {
+        match self.scope {
+            PublishTimeoutScope::PerPackage => self.timeout,
+            PublishTimeoutScope::Total => self
+                .timeout
+                .saturating_sub(now.saturating_duration_since(self.start)),
+        }
+    }
 }
 
 impl ReleaseRequest {
@@ -53,10 +138,22 @@ impl ReleaseRequest {
             registry: None,
             token: None,
             dry_run: false,
+            idempotent: false,
             git_release: None,
             repo_url: None,
             packages_config: PackagesConfig::default(),
             publish_timeout: minutes_30,
+            publish_timeout_scope: PublishTimeoutScope::default(),
+            release_type_trailer: None,
+            publish_require_token: false,
+            publish_token_env: BTreeMap::new(),
+            git_release_branches: vec![],
+            git_identity: GitIdentity::default(),
+            git_push_remote: None,
+            verbose_plan: false,
+            release_date: None,
+            on_error: OnError::default(),
+            changelog_format: ChangelogFormat::default(),
         }
     }
 
@@ -80,6 +177,11 @@ impl ReleaseRequest {
         self
     }
 
+    pub fn with_idempotent(mut self, idempotent: bool) -> Self {
+        self.idempotent = idempotent;
+        self
+    }
+
     pub fn with_git_release(mut self, git_release: GitRelease) -> Self {
         self.git_release = Some(git_release);
         self
@@ -100,6 +202,112 @@ impl ReleaseRequest {
         self
     }
 
+    pub fn with_publish_timeout_scope(mut self, scope: PublishTimeoutScope) -> Self {
+        self.publish_timeout_scope = scope;
+        self
+    }
+
+    pub fn with_publish_require_token(mut self, publish_require_token: bool) -> Self {
+        self.publish_require_token = publish_require_token;
+        self
+    }
+
+    /// Read the token of each registry from the environment variable named in `token_env`,
+    /// instead of `CARGO_REGISTRIES_<NAME>_TOKEN`.
+    /// The key is the registry name, the value is the name of the environment variable.
+    pub fn with_publish_token_env(mut self, token_env: BTreeMap<String, String>) -> Self {
+        self.publish_token_env = token_env;
+        self
+    }
+
+    /// Create the git tags with the given identity instead of the one of the git configuration.
+    pub fn with_git_identity(mut self, git_identity: GitIdentity) -> Self {
+        self.git_identity = git_identity;
+        self
+    }
+
+    /// Push the git tags to this remote, e.g. to release from a fork.
+    pub fn with_git_push_remote(mut self, git_push_remote: Option<String>) -> Self {
+        self.git_push_remote = git_push_remote;
+        self
+    }
+
+    pub fn with_release_date(mut self, release_date: NaiveDate) -> Self {
+        self.release_date = Some(release_date);
+        self
+    }
+
+    pub fn with_on_error(mut self, on_error: OnError) -> Self {
+        self.on_error = on_error;
+        self
+    }
+
+    pub fn with_changelog_format(mut self, changelog_format: ChangelogFormat) -> Self {
+        self.changelog_format = changelog_format;
+        self
+    }
+
+    pub fn with_verbose_plan(mut self, verbose_plan: bool) -> Self {
+        self.verbose_plan = verbose_plan;
+        self
+    }
+
+    /// Create the git releases only if the current branch matches one of the `branches` globs,
+    /// e.g. `release/*`.
+    pub fn with_git_release_branches(mut self, branches: &[String]) -> anyhow::Result<Self> {
+        self.git_release_branches = branches
+            .iter()
+            .map(|b| {
+                glob::Pattern::new(b).with_context(|| format!("invalid git_release_branches `{b}`"))
+            })
+            .collect::<anyhow::Result<_>>()?;
+        Ok(self)
+    }
+
+    /// Repository where the git tags are created and pushed.
+    fn git_repo(&self, directory: impl AsRef<Path>) -> anyhow::Result<Repo> {
+        let repo = Repo::new(directory)?.with_identity(self.git_identity.clone());
+        Ok(match &self.git_push_remote {
+            Some(git_push_remote) => repo.with_push_remote(git_push_remote),
+            None => repo,
+        })
+    }
+
+    /// Whether the git releases can be created from `branch`.
+    /// `branch` is `None` if HEAD is detached: it only matches if `git_release_branches` is empty.
+    fn is_git_release_branch(&self, branch: Option<&str>) -> bool {
+        self.git_release_branches.is_empty()
+            || branch
+                .is_some_and(|branch| self.git_release_branches.iter().any(|b| b.matches(branch)))
+    }
+
+    pub fn with_release_type_trailer(mut self, trailer: impl Into<String>) -> Self {
+        self.release_type_trailer = Some(trailer.into());
+        self
+    }
+
+    /// Release type of the git release.
+    /// The release type of the commit trailer takes precedence over the configured one,
+    /// unless the package is never released as pre-release.
+    fn git_release_type(&self, package: &str, commit_message: &str) -> ReleaseType {
+        let git_release = self.get_package_config(package).generic.git_release;
+        let release_type = self
+            .trailer_release_type(commit_message)
+            .unwrap_or(git_release.release_type);
+        git_release.without_prerelease(release_type)
+    }
+
+    /// Release type specified in the trailer of the commit message, if any.
+    fn trailer_release_type(&self, commit_message: &str) -> Option<ReleaseType> {
+        let key = self.release_type_trailer.as_deref()?;
+        let value = commit_trailer(commit_message, key)?;
+        let release_type = ReleaseType::from_trailer(value);
+        if release_type.is_none() {
+            warn!("ignoring unknown release type `{value}` of commit trailer `{key}`");
+        }
+        release_type
+    }
+
     /// Set release config for a specific package.
     pub fn with_package_config(
         mut self,
@@ -119,7 +327,7 @@ impl ReleaseRequest {
                 package
                     .package_path()
                     .expect("can't determine package path")
-                    .join(CHANGELOG_FILENAME)
+                    .join(self.changelog_format.file_name())
             })
     }
 
@@ -133,24 +341,240 @@ impl ReleaseRequest {
         config.generic.git_release.enabled
     }
 
+    fn is_git_release_backfill_enabled(&self, package: &str) -> bool {
+        let config = self.get_package_config(package);
+        config.generic.git_release.enabled && config.generic.git_release.backfill
+    }
+
     fn is_git_tag_enabled(&self, package: &str) -> bool {
         let config = self.get_package_config(package);
         config.generic.git_tag.enabled
     }
 
+    /// Whether the git release is created for a tag pushed by someone else.
+    fn is_git_release_expect_existing_tag(&self, package: &str) -> bool {
+        let config = self.get_package_config(package);
+        config.generic.git_release.enabled && config.generic.git_release.expect_existing_tag
+    }
+
     pub fn get_package_config(&self, package: &str) -> PackageReleaseConfig {
         self.packages_config.get(package)
     }
 
+    /// Today, unless the date is set with [`Self::with_release_date`].
+    fn release_date(&self) -> NaiveDate {
+        self.release_date.unwrap_or_else(|| Utc::now().date_naive())
+    }
+
+    /// Git tag of the current version of `package`.
+    /// If one of the existing `tags` matches the tag name template, it's returned,
+    /// because its date can differ from the release date.
+    /// `default_template` is the template used if `git_tag_name` isn't configured.
+    fn git_tag(&self, package: &Package, default_template: &str, tags: &[String]) -> String {
+        let name = self.get_package_config(&package.name).generic.git_tag.name;
+        let version = package.version.to_string();
+        match name.find(tags, default_template, &package.name, &version) {
+            Some(tag) => tag.to_string(),
+            None => name.render(
+                default_template,
+                &package.name,
+                &version,
+                self.release_date(),
+            ),
+        }
+    }
+
+    /// Git tags of the `packages` that create a git tag, together with the package name.
+    /// Packages with `git_tag_enable = false` are left out, so they can share a tag
+    /// template with the package that creates the tag.
+    fn created_git_tags<'a>(
+        &self,
+        packages: &[&'a Package],
+        default_template: &str,
+        tags: &[String],
+    ) -> Vec<(&'a str, String)> {
+        packages
+            .iter()
+            .filter(|p| self.is_git_tag_enabled(&p.name))
+            .map(|p| (p.name.as_str(), self.git_tag(p, default_template, tags)))
+            .collect()
+    }
+
     pub fn allow_dirty(&self, package: &str) -> bool {
         let config = self.get_package_config(package);
         config.generic.allow_dirty
     }
 
+    /// Whether to add the `--allow-dirty` flag to `cargo publish`.
+    /// With `allow_dirty_auto`, the flag is added only if the uncommitted
+    /// changes of the `repo` are all in files written by release-plz.
+    fn publish_allow_dirty(&self, package: &Package, repo: &Repo) -> anyhow::Result<bool> {
+        let config = self.get_package_config(&package.name);
+        if config.generic.allow_dirty {
+            return Ok(true);
+        }
+        if !config.generic.allow_dirty_auto {
+            return Ok(false);
+        }
+        let repo_root = repo.git(&["rev-parse", "--show-toplevel"])?;
+        let changed_files: Vec<PathBuf> = repo
+            .changes_except_typechanges()?
+            .iter()
+            .map(|file| Path::new(&repo_root).join(file))
+            .collect();
+        let allow_dirty =
+            only_release_plz_changes(&changed_files, &self.release_plz_files(package)?);
+        if allow_dirty {
+            info!(
+                "{}: adding --allow-dirty to cargo publish, because release-plz made the uncommitted changes",
+                package.name
+            );
+        }
+        Ok(allow_dirty)
+    }
+
+    /// Files that release-plz writes when updating `package`.
+    fn release_plz_files(&self, package: &Package) -> anyhow::Result<Vec<PathBuf>> {
+        let config = self.get_package_config(&package.name);
+        let workspace_root = self.metadata.workspace_root.as_std_path();
+        let package_dir = package.package_path()?;
+        let mut files = vec![
+            workspace_root.join(CARGO_TOML),
+            workspace_root.join("Cargo.lock"),
+            package.manifest_path.clone().into_std_path_buf(),
+            self.changelog_path(package),
+        ];
+        files.extend(config.generic.version_files.iter().map(|version_file| {
+            let path = version_file
+                .split_once(':')
+                .map_or(version_file.as_str(), |(path, _)| path);
+            package_dir.join(path)
+        }));
+        Ok(files)
+    }
+
     pub fn no_verify(&self, package: &str) -> bool {
         let config = self.get_package_config(package);
         config.generic.no_verify
     }
+
+    /// Registries where the package is published, in order.
+    /// The `registry` of the request takes precedence over the `publish_registries` of the
+    /// package configuration, which takes precedence over the `publish` field of the
+    /// package manifest.
+    /// If empty, the package is published to crates.io.
+    fn registries(&self, package: &Package) -> Vec<String> {
+        if let Some(registry) = &self.registry {
+            return vec![registry.clone()];
+        }
+        let publish_registries = self
+            .get_package_config(&package.name)
+            .generic
+            .publish_registries;
+        if !publish_registries.is_empty() {
+            return publish_registries;
+        }
+        package.publish.clone().unwrap_or_default()
+    }
+
+    /// Environment variable of the registry token required to publish the package,
+    /// if `publish_require_token` is enabled and the token isn't set.
+    fn missing_registry_token(&self, package: &Package) -> Option<String> {
+        if !self.publish_require_token || self.token.is_some() {
+            return None;
+        }
+        let package_env = self.get_package_config(&package.name).generic.env;
+        let lookup = |var: &str| std::env::var(var).ok();
+        // The token is read from the env of the package, then from the environment
+        // variable read by cargo, then from the one configured in `publish_token_env`.
+        // A token of the package env that references an unset variable is missing.
+        missing_registry_token(&self.registries(package), |registry| {
+            let var = registry_token_env_var(registry);
+            if let Some(value) = package_env.get(&var) {
+                return interpolate_env(value, lookup).ok();
+            }
+            lookup(&var).or_else(|| {
+                registry_token_env(registry, &self.publish_token_env, lookup)
+                    .map(|(_, token)| token)
+            })
+        })
+    }
+
+    /// Trace of the release steps of the package that isn't released yet.
+    fn plan_trace(
+        &self,
+        package: &Package,
+        git_tag: &str,
+        is_git_release_branch: bool,
+    ) -> PlanTrace {
+        let trace = PlanTrace::new(&package.name).step("version", &package.version);
+        let trace = if !self.is_publish_enabled(&package.name) {
+            trace.step_because("publish", "no", "`publish = false`")
+        } else if let Some(token_env_var) = self.missing_registry_token(package) {
+            trace.step_because(
+                "publish",
+                "no",
+                format!("the registry token isn't set in {token_env_var}"),
+            )
+        } else {
+            trace.step_because("publish", "yes", "`publish = true`")
+        };
+        let trace = if !self.is_git_tag_enabled(&package.name) {
+            trace.step_because("git tag", "no", "`git_tag_enable = false`")
+        } else if is_git_release_branch && self.is_git_release_expect_existing_tag(&package.name) {
+            trace.step_because("git tag", "no", "`git_release_expect_existing_tag = true`")
+        } else {
+            trace.step_because(
+                "git tag",
+                "yes",
+                format!("tag `{git_tag}` doesn't exist yet"),
+            )
+        };
+        if !self.is_git_release_enabled(&package.name) {
+            trace.step_because("git release", "no", "`git_release_enable = false`")
+        } else if !is_git_release_branch {
+            trace.step_because(
+                "git release",
+                "no",
+                "the current branch doesn't match `git_release_branches`",
+            )
+        } else {
+            trace.step_because("git release", "yes", "`git_release_enable = true`")
+        }
+    }
+
+    /// Environment variables of `cargo publish` for the `registry`.
+    /// Besides the [`Self::env`] of the package, it contains the token of the registry,
+    /// if configured with [`Self::with_publish_token_env`].
+    fn publish_env(
+        &self,
+        package: &str,
+        registry: Option<&str>,
+    ) -> anyhow::Result<BTreeMap<String, String>> {
+        let mut env = self.env(package)?;
+        if let Some((var, token)) = registry_token_env(registry, &self.publish_token_env, |var| {
+            std::env::var(var).ok()
+        }) {
+            env.entry(var).or_insert(token);
+        }
+        Ok(env)
+    }
+
+    /// Environment variables of the package, with the references to
+    /// existing environment variables replaced by their value.
+    fn env(&self, package: &str) -> anyhow::Result<BTreeMap<String, String>> {
+        let config = self.get_package_config(package);
+        config
+            .generic
+            .env
+            .into_iter()
+            .map(|(name, value)| {
+                let value = interpolate_env(&value, |var| std::env::var(var).ok())
+                    .with_context(|| format!("invalid value of environment variable {name}"))?;
+                Ok((name, value))
+            })
+            .collect()
+    }
 }
 
 impl RequestReleaseValidator for ReleaseRequest {
@@ -197,8 +621,23 @@ pub struct ReleaseConfig {
     /// Allow dirty working directories to be packaged.
     /// If true, `release-plz` adds the `--allow-dirty` flag to `cargo publish`.
     allow_dirty: bool,
+    /// If true, `release-plz` adds the `--allow-dirty` flag to `cargo publish`
+    /// only if the uncommitted changes are in files written by release-plz.
+    allow_dirty_auto: bool,
+    /// Files where release-plz writes the version of the package.
+    /// Format of [`crate::UpdateConfig::version_files`].
+    version_files: Vec<String>,
     /// High-level toggle to process this package or ignore it
     release: bool,
+    /// Environment variables set when running `cargo publish`.
+    /// Values can reference existing environment variables with `${VAR}`.
+    env: BTreeMap<String, String>,
+    /// Registries where the package is published, in order.
+    /// If empty, the `publish` field of the package manifest is used.
+    publish_registries: Vec<String>,
+    /// Before publishing, check that the `rust-version` of the package
+    /// isn't newer than the toolchain used to publish it.
+    check_msrv: bool,
 }
 
 impl ReleaseConfig {
@@ -227,11 +666,36 @@ impl ReleaseConfig {
         self
     }
 
+    pub fn with_allow_dirty_auto(mut self, allow_dirty_auto: bool) -> Self {
+        self.allow_dirty_auto = allow_dirty_auto;
+        self
+    }
+
+    pub fn with_version_files(mut self, version_files: Vec<String>) -> Self {
+        self.version_files = version_files;
+        self
+    }
+
     pub fn with_release(mut self, release: bool) -> Self {
         self.release = release;
         self
     }
 
+    pub fn with_env(mut self, env: BTreeMap<String, String>) -> Self {
+        self.env = env;
+        self
+    }
+
+    pub fn with_publish_registries(mut self, publish_registries: Vec<String>) -> Self {
+        self.publish_registries = publish_registries;
+        self
+    }
+
+    pub fn with_check_msrv(mut self, check_msrv: bool) -> Self {
+        self.check_msrv = check_msrv;
+        self
+    }
+
     pub fn publish(&self) -> &PublishConfig {
         &self.publish
     }
@@ -239,6 +703,10 @@ impl ReleaseConfig {
     pub fn git_release(&self) -> &GitReleaseConfig {
         &self.git_release
     }
+
+    pub fn git_tag(&self) -> &GitTagConfig {
+        &self.git_tag
+    }
 }
 
 impl Default for ReleaseConfig {
@@ -249,7 +717,12 @@ impl Default for ReleaseConfig {
             git_tag: GitTagConfig::default(),
             no_verify: false,
             allow_dirty: false,
+            allow_dirty_auto: false,
+            version_files: vec![],
             release: true,
+            env: BTreeMap::new(),
+            publish_registries: vec![],
+            check_msrv: false,
         }
     }
 }
@@ -279,6 +752,77 @@ impl PublishConfig {
 pub struct GitReleaseConfig {
     enabled: bool,
     draft: bool,
+    /// Create the git release of already existing tags that don't have one.
+    backfill: bool,
+    /// If the release is a draft, create it as published if the package is published
+    /// to the cargo registry.
+    publish_draft_on_success: bool,
+    release_type: ReleaseType,
+    /// If true, releasing a semver pre-release as ready for production is an error
+    /// instead of a warning.
+    release_type_strict: bool,
+    /// If true, the release is always marked as ready for production,
+    /// even if the version is a semver pre-release.
+    never_prerelease: bool,
+    /// If true, release-plz doesn't create the git tag, because another job pushes it.
+    /// The git release is created for the existing tag.
+    expect_existing_tag: bool,
+    /// Whether to mark the release as the latest one.
+    /// If `None`, the git forge decides.
+    latest: Option<bool>,
+    /// If true, the git release is created once the git forge knows the pushed tag.
+    /// Useful for forges that create a release placeholder when a tag is pushed.
+    after_tag_push: bool,
+}
+
+/// Flags of the git release, computed from the [`GitReleaseConfig`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct GitReleaseFlags {
+    draft: bool,
+    pre_release: bool,
+    latest: Option<bool>,
+}
+
+/// Whether the git release is marked as ready for production or not.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ReleaseType {
+    /// Mark the release as ready for production.
+    #[default]
+    Prod,
+    /// Mark the release as not ready for production, i.e. as pre-release.
+    Pre,
+    /// Mark the release as pre-release only if the version is a semver pre-release,
+    /// e.g. `1.0.0-rc1`.
+    Auto,
+    /// Create the release as draft, i.e. as with `git_release_draft = true`.
+    /// The release isn't marked as pre-release.
+    Draft,
+}
+
+impl ReleaseType {
+    /// Parse the value of a commit trailer, e.g. `pre`.
+    fn from_trailer(value: &str) -> Option<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "prod" => Some(Self::Prod),
+            "pre" => Some(Self::Pre),
+            "auto" => Some(Self::Auto),
+            "draft" => Some(Self::Draft),
+            _ => None,
+        }
+    }
+
+    fn is_pre_release(self, version: &Version) -> bool {
+        match self {
+            Self::Prod | Self::Draft => false,
+            Self::Pre => true,
+            Self::Auto => is_semver_pre_release(version),
+        }
+    }
+}
+
+/// Whether the version has a pre-release segment, e.g. `1.0.0-rc1`.
+fn is_semver_pre_release(version: &Version) -> bool {
+    !version.pre.is_empty()
 }
 
 impl Default for GitReleaseConfig {
@@ -292,6 +836,14 @@ impl GitReleaseConfig {
         Self {
             enabled,
             draft: false,
+            backfill: false,
+            publish_draft_on_success: false,
+            release_type: ReleaseType::default(),
+            release_type_strict: false,
+            never_prerelease: false,
+            expect_existing_tag: false,
+            latest: None,
+            after_tag_push: false,
         }
     }
 
@@ -303,11 +855,136 @@ impl GitReleaseConfig {
         self.draft = draft;
         self
     }
+
+    pub fn set_backfill(mut self, backfill: bool) -> Self {
+        self.backfill = backfill;
+        self
+    }
+
+    pub fn set_publish_draft_on_success(mut self, publish_draft_on_success: bool) -> Self {
+        self.publish_draft_on_success = publish_draft_on_success;
+        self
+    }
+
+    pub fn set_release_type(mut self, release_type: ReleaseType) -> Self {
+        self.release_type = release_type;
+        self
+    }
+
+    pub fn set_release_type_strict(mut self, release_type_strict: bool) -> Self {
+        self.release_type_strict = release_type_strict;
+        self
+    }
+
+    pub fn set_never_prerelease(mut self, never_prerelease: bool) -> Self {
+        self.never_prerelease = never_prerelease;
+        self
+    }
+
+    pub fn set_expect_existing_tag(mut self, expect_existing_tag: bool) -> Self {
+        self.expect_existing_tag = expect_existing_tag;
+        self
+    }
+
+    pub fn set_latest(mut self, latest: Option<bool>) -> Self {
+        self.latest = latest;
+        self
+    }
+
+    pub fn set_after_tag_push(mut self, after_tag_push: bool) -> Self {
+        self.after_tag_push = after_tag_push;
+        self
+    }
+
+    /// Configured release type, ignoring the commit trailer.
+    fn release_type(&self) -> ReleaseType {
+        self.without_prerelease(self.release_type)
+    }
+
+    /// Release type that doesn't mark the release as pre-release if `never_prerelease` is true.
+    fn without_prerelease(&self, release_type: ReleaseType) -> ReleaseType {
+        if self.never_prerelease && release_type != ReleaseType::Draft {
+            ReleaseType::Prod
+        } else {
+            release_type
+        }
+    }
+
+    /// Check that a semver pre-release, e.g. `1.0.0-rc1`, isn't marked as ready for production.
+    /// The mismatch is an error if `release_type_strict` is true, a warning otherwise.
+    fn check_release_type(
+        &self,
+        release_type: ReleaseType,
+        package: &str,
+        version: &Version,
+    ) -> anyhow::Result<()> {
+        if self.never_prerelease
+            || release_type != ReleaseType::Prod
+            || !is_semver_pre_release(version)
+        {
+            return Ok(());
+        }
+        let message = format!(
+            "{package} {version}: the version is a pre-release, but the git release type is `prod`, so the release will be marked as ready for production"
+        );
+        if self.release_type_strict {
+            anyhow::bail!(message);
+        }
+        warn!("{message}");
+        Ok(())
+    }
+
+    /// Whether the git release must be created as draft.
+    /// `is_package_published` is true if the package was published to the cargo registry.
+    fn is_draft(&self, release_type: ReleaseType, is_package_published: bool) -> bool {
+        (self.draft || release_type == ReleaseType::Draft)
+            && !(self.publish_draft_on_success && is_package_published)
+    }
+
+    /// Draft, pre-release and latest flags of the git release of the package `version`.
+    /// `is_package_published` is true if the package was published to the cargo registry.
+    /// Fails if the flags contradict each other, e.g. a draft release marked as latest.
+    fn flags(
+        &self,
+        release_type: ReleaseType,
+        package: &str,
+        version: &Version,
+        is_package_published: bool,
+    ) -> anyhow::Result<GitReleaseFlags> {
+        let flags = GitReleaseFlags {
+            draft: self.is_draft(release_type, is_package_published),
+            pre_release: release_type.is_pre_release(version),
+            latest: self.latest,
+        };
+        if flags.latest == Some(true) {
+            if flags.draft {
+                anyhow::bail!(
+                    "{package} {version}: `git_release_latest` is `true`, but the git release is a draft. A draft release can't be marked as latest"
+                );
+            }
+            if flags.pre_release {
+                anyhow::bail!(
+                    "{package} {version}: `git_release_latest` is `true`, but the git release is a pre-release. A pre-release can't be marked as latest"
+                );
+            }
+        }
+        Ok(flags)
+    }
 }
 
+/// Default message of annotated git tags.
+const GIT_TAG_MESSAGE_TEMPLATE: &str = "{{ package }} {{ version }}";
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct GitTagConfig {
     enabled: bool,
+    /// If true, create an annotated tag. Otherwise, create a lightweight tag.
+    annotated: bool,
+    /// Template of the message of annotated tags.
+    /// If `None`, [`GIT_TAG_MESSAGE_TEMPLATE`] is used.
+    message: Option<String>,
+    /// Name of the tag and format of its date.
+    name: GitTagName,
 }
 
 impl Default for GitTagConfig {
@@ -318,12 +995,75 @@ impl Default for GitTagConfig {
 
 impl GitTagConfig {
     pub fn enabled(enabled: bool) -> Self {
-        Self { enabled }
+        Self {
+            enabled,
+            annotated: true,
+            message: None,
+            name: GitTagName::default(),
+        }
+    }
+
+    pub fn set_annotated(mut self, annotated: bool) -> Self {
+        self.annotated = annotated;
+        self
+    }
+
+    pub fn set_message(mut self, message: Option<String>) -> Self {
+        self.message = message;
+        self
+    }
+
+    pub fn set_name(mut self, name: GitTagName) -> Self {
+        self.name = name;
+        self
+    }
+
+    pub fn name(&self) -> &GitTagName {
+        &self.name
     }
 
     pub fn is_enabled(&self) -> bool {
         self.enabled
     }
+
+    /// Message of the git tag of the given package version.
+    /// In the template, `{{ package }}`, `{{ version }}`, `{{ tag }}`, `{{ date }}` and
+    /// `{{ changelog }}` are replaced with the name, the version, the git tag,
+    /// the release date (e.g. `2024-06-01`, see [`GitTagName`] for its format)
+    /// and the changes of the package.
+    /// `changelog` is only called if the template uses it.
+    /// Lightweight tags don't have a message.
+    pub fn message(
+        &self,
+        package: &str,
+        version: &str,
+        tag: &str,
+        date: NaiveDate,
+        changelog: impl FnOnce() -> String,
+    ) -> Option<String> {
+        lazy_static::lazy_static! {
+            static ref VARIABLE_RE: Regex =
+                Regex::new(r"\{\{\s*(package|version|tag|date|changelog)\s*\}\}").unwrap();
+        }
+        if !self.annotated {
+            return None;
+        }
+        let template = self.message.as_deref().unwrap_or(GIT_TAG_MESSAGE_TEMPLATE);
+        let changelog = VARIABLE_RE
+            .captures_iter(template)
+            .any(|c| &c[1] == "changelog")
+            .then(changelog)
+            .unwrap_or_default();
+        let date = self.name.format_date(date);
+        let message = VARIABLE_RE.replace_all(template, |c: &regex::Captures| match &c[1] {
+            "package" => package,
+            "version" => version,
+            "tag" => tag,
+            "date" => &date,
+            _ => &changelog,
+        });
+        Some(message.into_owned())
+    }
 }
 
 impl From<ReleaseConfig> for PackageReleaseConfig {
@@ -349,9 +1089,33 @@ pub struct GitRelease {
     pub backend: GitBackend,
 }
 
+/// Packages released by [`release`].
+/// In dry-run mode, packages that would be released.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ReleasePlan {
+    pub releases: Vec<PackageRelease>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PackageRelease {
+    pub package_name: String,
+    pub version: Version,
+    pub tag: String,
+}
+
+impl ReleasePlan {
+    fn add(&mut self, package: &Package, tag: String) {
+        self.releases.push(PackageRelease {
+            package_name: package.name.clone(),
+            version: package.version.clone(),
+            tag,
+        });
+    }
+}
+
 /// Release the project as it is.
 #[instrument]
-pub async fn release(input: &ReleaseRequest) -> anyhow::Result<()> {
+pub async fn release(input: &ReleaseRequest) -> anyhow::Result<ReleasePlan> {
     let overrides = input.packages_config.overrides.keys().cloned().collect();
     let project = Project::new(
         &input.local_manifest(),
@@ -362,144 +1126,801 @@ pub async fn release(input: &ReleaseRequest) -> anyhow::Result<()> {
     )?;
     let packages = project.publishable_packages();
     let release_order = release_order(&packages).context("cannot determine release order")?;
-    for package in release_order {
-        let repo = Repo::new(&input.metadata.workspace_root)?;
-        let git_tag = project.git_tag(&package.name, &package.version.to_string());
-        if repo.tag_exists(&git_tag)? {
-            info!(
-                "{} {}: Already published - Tag {} already exists",
-                package.name, package.version, &git_tag
+    let repo = Repo::new(&input.metadata.workspace_root)?;
+    let existing_tags = repo.tags()?;
+    let git_tags =
+        input.created_git_tags(&release_order, project.git_tag_template(), &existing_tags);
+    check_unique_git_tags(&git_tags)?;
+    let branch = repo.current_branch().map(str::to_string);
+    let is_git_release_branch = input.is_git_release_branch(branch.as_deref());
+    if !is_git_release_branch {
+        match &branch {
+            Some(branch) => info!("skipping git releases: the current branch `{branch}` doesn't match `git_release_branches`"),
+            None => info!("skipping git releases: HEAD is detached, so it doesn't match `git_release_branches`"),
+        }
+    }
+    let deadline = PublishDeadline::new(
+        input.publish_timeout_scope,
+        input.publish_timeout,
+        Instant::now(),
+    );
+    let project = &project;
+    let deadline = &deadline;
+    release_in_order(&release_order, input.on_error, |package| async move {
+        release_workspace_package(input, project, package, is_git_release_branch, deadline).await
+    })
+    .await
+}
+
+/// Release the `packages` in order with `release_package`, which returns the git tag
+/// of the released package, or `None` if the package is skipped.
+/// If releasing a package fails, `on_error` decides whether to stop or to release
+/// the next packages. With [`OnError::Continue`], the packages that depend on a failed
+/// package, directly or transitively, are skipped, because they would depend on a version
+/// that isn't published. The failures are reported at the end, together with the status
+/// of each package.
+async fn release_in_order<'a, F, Fut>(
+    packages: &[&'a Package],
+    on_error: OnError,
+    mut release_package: F,
+) -> anyhow::Result<ReleasePlan>
+where
+    F: FnMut(&'a Package) -> Fut,
+    Fut: Future<Output = anyhow::Result<Option<String>>>,
+{
+    let mut plan = ReleasePlan::default();
+    let mut statuses = vec![];
+    // Packages that failed or were skipped because of a failed dependency.
+    // Since the packages are in release order, the dependencies are checked before
+    // their dependents, so this also covers transitive dependencies.
+    let mut unreleased: Vec<&str> = vec![];
+    for &package in packages {
+        if let Some(dependency) = failed_dependency(package, &unreleased) {
+            error!(
+                "{} {}: skipping release because dependency `{dependency}` failed",
+                package.name, package.version
             );
+            unreleased.push(&package.name);
+            statuses.push((
+                package,
+                ReleaseStatus::DependencyFailed(dependency.to_string()),
+            ));
             continue;
         }
-        let registry_indexes = registry_indexes(package, input.registry.clone())
-            .context("can't determine registry indexes")?;
-        for mut index in registry_indexes {
-            if is_published(&mut index, package, input.publish_timeout)
-                .await
-                .context("can't determine if package is published")?
-            {
-                info!("{} {}: already published", package.name, package.version);
-                continue;
+        match release_package(package).await {
+            Ok(Some(git_tag)) => {
+                plan.add(package, git_tag);
+                statuses.push((package, ReleaseStatus::Released));
             }
-            release_package(&mut index, package, input, git_tag.clone())
-                .await
-                .context("failed to release package")?;
+            Ok(None) => statuses.push((package, ReleaseStatus::Skipped)),
+            Err(e) => match on_error {
+                OnError::Stop => return Err(e),
+                OnError::Continue => {
+                    error!("{} {}: {e:#}", package.name, package.version);
+                    unreleased.push(&package.name);
+                    statuses.push((package, ReleaseStatus::Failed(format!("{e:#}"))));
+                }
+            },
         }
     }
-    Ok(())
-}
-
-/// Get the indexes where the package should be published.
-/// If `registry` is specified, it takes precedence over the `publish` field
-/// of the package manifest.
-fn registry_indexes(
-    package: &Package,
-    registry: Option<String>,
-) -> anyhow::Result<Vec<CargoIndex>> {
-    let registries = registry
-        .map(|r| vec![r])
-        .unwrap_or_else(|| package.publish.clone().unwrap_or_default());
-    let registry_urls = registries
-        .iter()
-        .map(|r| {
-            cargo_utils::registry_url(package.manifest_path.as_ref(), Some(r))
-                .context("failed to retrieve registry url")
-        })
-        .collect::<anyhow::Result<Vec<Url>>>()?;
-
-    let mut registry_indexes = registry_urls
+    if statuses
         .iter()
-        .map(|u| {
-            if u.to_string().starts_with("sparse+") {
-                SparseIndex::from_url(u.as_str()).map(CargoIndex::Sparse)
-            } else {
-                GitIndex::from_url(&format!("registry+{u}")).map(CargoIndex::Git)
-            }
-        })
-        .collect::<Result<Vec<CargoIndex>, crates_index::Error>>()?;
-    if registry_indexes.is_empty() {
-        registry_indexes.push(CargoIndex::Git(GitIndex::new_cargo_default()?))
+        .any(|(_, status)| matches!(status, ReleaseStatus::Failed(_)))
+    {
+        anyhow::bail!(release_report(&statuses));
     }
-    Ok(registry_indexes)
+    Ok(plan)
 }
 
-async fn release_package(
+/// Dependency of `package` that is in `unreleased`, if any.
+fn failed_dependency<'a>(package: &Package, unreleased: &[&'a str]) -> Option<&'a str> {
+    unreleased.iter().copied().find(|name| {
+        package
+            .dependencies
+            .iter()
+            .any(|d| d.name == *name && should_dep_be_released_before(d, package))
+    })
+}
+
+/// Whether the package is published to the registry of `index`, checking it until the
+/// `deadline` expires.
+async fn is_published_before(
     index: &mut CargoIndex,
     package: &Package,
-    input: &ReleaseRequest,
-    git_tag: String,
-) -> anyhow::Result<()> {
-    let workspace_root = &input.metadata.workspace_root;
-
-    let repo = Repo::new(workspace_root)?;
+    deadline: &PublishDeadline,
+) -> anyhow::Result<bool> {
+    is_published(index, package, deadline.timeout(Instant::now())).await
+}
 
-    let publish = input.is_publish_enabled(&package.name);
-    if publish {
-        let (_, stderr) = run_cargo_publish(package, input, workspace_root.as_std_path())
-            .context("failed to run cargo publish")?;
-        if !stderr.contains("Uploading") || stderr.contains("error:") {
-            anyhow::bail!("failed to publish {}: {}", package.name, stderr);
+/// Registries of `indexes` where the package isn't published yet, in order.
+/// `is_published` checks whether the package is published to an index.
+async fn unpublished_indexes<I, F, Fut>(
+    package: &Package,
+    indexes: Vec<(Option<String>, I)>,
+    mut is_published: F,
+) -> anyhow::Result<Vec<(Option<String>, I)>>
+where
+    F: FnMut(I) -> Fut,
+    Fut: Future<Output = (I, anyhow::Result<bool>)>,
+{
+    let mut unpublished_indexes = vec![];
+    for (registry, index) in indexes {
+        let (index, published) = is_published(index).await;
+        let registry_name = registry_name(registry.as_deref());
+        if published.with_context(|| {
+            format!("can't determine if package is published to registry {registry_name}")
+        })? {
+            info!(
+                "{} {}: already published to registry {registry_name}",
+                package.name, package.version,
+            );
+            continue;
         }
+        unpublished_indexes.push((registry, index));
     }
+    Ok(unpublished_indexes)
+}
 
-    if input.dry_run {
-        info!(
-            "{} {}: aborting upload due to dry run",
-            package.name, package.version
-        );
-    } else {
-        if publish {
-            wait_until_published(index, package, input.publish_timeout).await?;
-        }
-
-        if input.is_git_tag_enabled(&package.name) {
-            repo.tag(&git_tag)?;
-            repo.push(&git_tag)?;
-        }
+/// Outcome of the release of a package, used in the report of [`OnError::Continue`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ReleaseStatus {
+    Released,
+    /// E.g. because the package is already published.
+    Skipped,
+    Failed(String),
+    /// The package isn't released because this dependency wasn't released.
+    DependencyFailed(String),
+}
 
-        if input.is_git_release_enabled(&package.name) {
+/// Summary of the release, with one line for each package.
+fn release_report(statuses: &[(&Package, ReleaseStatus)]) -> String {
+    let failed = statuses
+        .iter()
+        .filter(|(_, status)| matches!(status, ReleaseStatus::Failed(_)))
+        .count();
+    let mut report = format!("failed to release {failed} of {} packages:", statuses.len());
+    for (package, status) in statuses {
+        let status = match status {
+            ReleaseStatus::Released => "released".to_string(),
+            ReleaseStatus::Skipped => "skipped".to_string(),
+            ReleaseStatus::Failed(error) => format!("failed: {error}"),
+            ReleaseStatus::DependencyFailed(dependency) => {
+                format!("skipped: dependency `{dependency}` failed")
+            }
+        };
+        report.push_str(&format!(
+            "\n- {} {}: {status}",
+            package.name, package.version
+        ));
+    }
+    report
+}
+
+/// Release a package of the workspace, unless it's already released.
+/// Returns the git tag of the package if it's released.
+async fn release_workspace_package(
+    input: &ReleaseRequest,
+    project: &Project,
+    package: &Package,
+    is_git_release_branch: bool,
+    deadline: &PublishDeadline,
+) -> anyhow::Result<Option<String>> {
+    let repo = Repo::new(&input.metadata.workspace_root)?;
+    let tags = repo.tags()?;
+    let git_tag = input.git_tag(package, project.git_tag_template(), &tags);
+    let expect_existing_tag =
+        is_git_release_branch && input.is_git_release_expect_existing_tag(&package.name);
+    if expect_existing_tag {
+        if git_release_exists(input, &git_tag).await? {
+            info!(
+                "{} {}: Already published - Git release of tag {} already exists",
+                package.name, package.version, &git_tag
+            );
+            return Ok(None);
+        }
+    } else if repo.tag_exists(&git_tag)? {
+        info!(
+            "{} {}: Already published - Tag {} already exists",
+            package.name, package.version, &git_tag
+        );
+        if is_git_release_branch && input.is_git_release_backfill_enabled(&package.name) {
+            backfill_git_releases(input, package, project.git_tag_template(), &tags)
+                .await
+                .context("failed to backfill git release")?;
+        }
+        return Ok(None);
+    }
+    if input.verbose_plan {
+        input
+            .plan_trace(package, &git_tag, is_git_release_branch)
+            .log();
+    }
+    if input.is_publish_enabled(&package.name) {
+        if let Some(token_env_var) = input.missing_registry_token(package) {
+            info!(
+                "{} {}: skipping release because the registry token isn't set. Set the {token_env_var} environment variable to publish the package",
+                package.name, package.version
+            );
+            return Ok(None);
+        }
+    } else {
+        // The package isn't published, so we don't need to check the registries:
+        // we only create the git tag and the git release.
+        release_package(vec![], package, input, git_tag.clone(), deadline)
+            .await
+            .context("failed to release package")?;
+        return Ok(Some(git_tag));
+    }
+    let registry_indexes = registry_indexes(package, &input.registries(package))
+        .context("can't determine registry indexes")?;
+    let unpublished_indexes = unpublished_indexes(package, registry_indexes, |mut index| async {
+        let published = is_published_before(&mut index, package, deadline).await;
+        (index, published)
+    })
+    .await?;
+    // The package is published, but its tag doesn't exist, e.g. because pushing it failed.
+    // If idempotent, complete the release by creating the tag and the git release.
+    if unpublished_indexes.is_empty() && !input.idempotent {
+        return Ok(None);
+    }
+    release_package(
+        unpublished_indexes,
+        package,
+        input,
+        git_tag.clone(),
+        deadline,
+    )
+    .await
+    .context("failed to release package")?;
+    Ok(Some(git_tag))
+}
+
+/// Name of the crates.io registry in the cargo configuration.
+pub(crate) const CRATES_IO_REGISTRY: &str = "crates-io";
+
+/// Fail if two packages have the same git tag,
+/// so that we don't release a package and then fail to tag the other one.
+fn check_unique_git_tags(git_tags: &[(&str, String)]) -> anyhow::Result<()> {
+    let mut packages_by_tag: HashMap<&str, &str> = HashMap::new();
+    for (package, git_tag) in git_tags {
+        if let Some(other_package) = packages_by_tag.insert(git_tag, package) {
+            anyhow::bail!(
+                "packages `{other_package}` and `{package}` have the same git tag `{git_tag}`. Make sure each package has a unique git tag"
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Name of the registry, as shown to the user. `None` is crates.io.
+fn registry_name(registry: Option<&str>) -> &str {
+    registry.unwrap_or(CRATES_IO_REGISTRY)
+}
+
+/// Get the indexes of the `registries` where the package should be published,
+/// together with the registry name.
+/// If `registries` is empty, the package is published to crates.io, which has no name.
+fn registry_indexes(
+    package: &Package,
+    registries: &[String],
+) -> anyhow::Result<Vec<(Option<String>, CargoIndex)>> {
+    let mut registry_indexes = registries
+        .iter()
+        .map(|r| {
+            let registry_url = if r == CRATES_IO_REGISTRY {
+                cargo_utils::registry_url(package.manifest_path.as_ref(), None)
+            } else {
+                cargo_utils::registry_url(package.manifest_path.as_ref(), Some(r))
+            }
+            .with_context(|| format!("failed to retrieve url of registry {r}"))?;
+            let index = if registry_url.to_string().starts_with("sparse+") {
+                SparseIndex::from_url(registry_url.as_str()).map(CargoIndex::Sparse)
+            } else {
+                GitIndex::from_url(&format!("registry+{registry_url}")).map(CargoIndex::Git)
+            }?;
+            Ok((Some(r.clone()), index))
+        })
+        .collect::<anyhow::Result<Vec<(Option<String>, CargoIndex)>>>()?;
+    if registry_indexes.is_empty() {
+        registry_indexes.push((None, CargoIndex::Git(GitIndex::new_cargo_default()?)))
+    }
+    Ok(registry_indexes)
+}
+
+/// Release the package.
+/// `indexes` are the registries where the package is published, in order.
+/// It's empty if publishing is disabled.
+async fn release_package(
+    indexes: Vec<(Option<String>, CargoIndex)>,
+    package: &Package,
+    input: &ReleaseRequest,
+    git_tag: String,
+    deadline: &PublishDeadline,
+) -> anyhow::Result<()> {
+    let workspace_root = &input.metadata.workspace_root;
+
+    let repo = input.git_repo(workspace_root)?;
+
+    let is_git_release_enabled = input.is_git_release_enabled(&package.name)
+        && input.is_git_release_branch(repo.current_branch());
+    let release_type = input.git_release_type(&package.name, &repo.current_commit_message()?);
+    let publish = input.is_publish_enabled(&package.name);
+    let git_release_type = if is_git_release_enabled {
+        // Check before publishing, so that a mismatch doesn't leave the release half done.
+        let git_release_config = input.get_package_config(&package.name).generic.git_release;
+        git_release_config.check_release_type(release_type, &package.name, &package.version)?;
+        // The draft flag depends on the outcome of `cargo publish`, so the flags are
+        // computed again after publishing.
+        git_release_config.flags(release_type, &package.name, &package.version, publish)?;
+        Some(release_type)
+    } else {
+        None
+    };
+    let expect_existing_tag =
+        is_git_release_enabled && input.is_git_release_expect_existing_tag(&package.name);
+    if expect_existing_tag {
+        // Check before publishing, so that a missing tag doesn't leave the release half done.
+        check_existing_tag(&repo, &package.name, &git_tag)?;
+    }
+    if publish && input.get_package_config(&package.name).generic.check_msrv {
+        let toolchain = toolchain_version(workspace_root.as_std_path())
+            .context("cannot determine the version of the Rust toolchain")?;
+        check_msrv(package, &toolchain)?;
+    }
+
+    let is_package_published =
+        publish && publish_package(indexes, package, input, &repo, deadline).await?;
+
+    if input.dry_run {
+        info!(
+            "{} {}: aborting upload due to dry run",
+            package.name, package.version
+        );
+    } else {
+        let push_tag = input.is_git_tag_enabled(&package.name) && !expect_existing_tag;
+        release_to_git(
+            input,
+            package,
+            &repo,
+            &git_tag,
+            push_tag,
+            git_release_type,
+            is_package_published,
+        )
+        .await?;
+        info!("published {} {}", package.name, package.version);
+    }
+
+    Ok(())
+}
+
+/// Run `cargo publish` for every registry of the package.
+/// Returns `true` if the package was uploaded to at least one registry, i.e. `false` if
+/// every registry already had the version or if it's a dry run.
+async fn publish_package(
+    indexes: Vec<(Option<String>, CargoIndex)>,
+    package: &Package,
+    input: &ReleaseRequest,
+    repo: &Repo,
+    deadline: &PublishDeadline,
+) -> anyhow::Result<bool> {
+    let workspace_root = &input.metadata.workspace_root;
+    let allow_dirty = input.publish_allow_dirty(package, repo)?;
+    let mut uploaded = false;
+    for (registry, mut index) in indexes {
+        let registry_name = registry_name(registry.as_deref());
+        let (_, stderr) = run_cargo_publish(
+            package,
+            registry.as_deref(),
+            input,
+            allow_dirty,
+            workspace_root.as_std_path(),
+        )
+        .with_context(|| format!("failed to run cargo publish to registry {registry_name}"))?;
+        // `cargo publish` runs with `--color always`, so the colors must be removed
+        // to find its messages.
+        let stderr = strip_ansi_escapes::strip_str(stderr);
+        if !stderr.contains("Uploading") || stderr.contains("error:") {
+            if input.idempotent && is_already_uploaded(&stderr) {
+                info!(
+                    "{} {}: already published to registry {registry_name}",
+                    package.name, package.version
+                );
+                continue;
+            }
+            anyhow::bail!(
+                "failed to publish {} to registry {registry_name}: {}",
+                package.name,
+                stderr
+            );
+        }
+        if !input.dry_run {
+            let timeout = deadline.timeout(Instant::now());
+            wait_until_published(&mut index, package, timeout)
+                .await
+                .with_context(|| format!("failed to publish to registry {registry_name}"))?;
+            uploaded = true;
+        }
+    }
+    Ok(uploaded)
+}
+
+/// Push the git tag and create the git release of the package.
+/// `git_release_type` is `None` if the git release is disabled.
+/// `is_package_published` is true if `cargo publish` uploaded the package.
+async fn release_to_git(
+    input: &ReleaseRequest,
+    package: &Package,
+    repo: &Repo,
+    git_tag: &str,
+    push_tag: bool,
+    git_release_type: Option<ReleaseType>,
+    is_package_published: bool,
+) -> anyhow::Result<()> {
+    let git_release_config = input.get_package_config(&package.name).generic.git_release;
+    let git_release = match git_release_type {
+        Some(release_type) => {
+            let flags = git_release_config.flags(
+                release_type,
+                &package.name,
+                &package.version,
+                is_package_published,
+            )?;
             let git_release = input
                 .git_release
                 .as_ref()
                 .context("git release not configured. Did you specify git-token and backend?")?;
-            let release_body = release_body(input, package);
-            let is_release_draft = input
-                .get_package_config(&package.name)
-                .generic
-                .git_release
-                .draft;
-            let release_info = GitReleaseInfo {
-                git_tag,
-                release_body,
-                draft: is_release_draft,
-            };
-            publish_git_release(&release_info, &git_release.backend).await?;
+            Some((GitClient::new(git_release.backend.clone())?, flags))
         }
+        None => None,
+    };
+    for step in git_steps(
+        push_tag,
+        git_release.is_some(),
+        git_release_config.after_tag_push,
+    ) {
+        match (step, &git_release) {
+            (GitStep::PushTag, _) => push_git_tag(input, package, repo, git_tag)?,
+            (GitStep::WaitForTag, Some((git_client, _))) => {
+                wait_for_tag(git_client, git_tag, TAG_WAIT_ATTEMPTS, TAG_WAIT_INTERVAL).await?;
+            }
+            (GitStep::CreateRelease, Some((git_client, flags))) => {
+                let release_info = GitReleaseInfo {
+                    git_tag: git_tag.to_string(),
+                    release_body: release_body(input, package),
+                    draft: flags.draft,
+                    pre_release: flags.pre_release,
+                    latest: flags.latest,
+                };
+                if !create_git_release(git_client, &release_info, input.idempotent).await? {
+                    info!(
+                        "{} {}: git release of tag {} already exists",
+                        package.name, package.version, release_info.git_tag
+                    );
+                }
+            }
+            // The git release steps are only returned if the git release is enabled.
+            (GitStep::WaitForTag | GitStep::CreateRelease, None) => {}
+        }
+    }
+    Ok(())
+}
 
-        info!("published {} {}", package.name, package.version);
+/// Times release-plz checks whether the git forge knows the pushed tag.
+const TAG_WAIT_ATTEMPTS: u32 = 10;
+const TAG_WAIT_INTERVAL: Duration = Duration::from_secs(3);
+
+/// Step run after publishing the package.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum GitStep {
+    PushTag,
+    /// Wait until the git forge knows the tag.
+    WaitForTag,
+    CreateRelease,
+}
+
+/// Git steps of the release, in order.
+/// The tag is always pushed before the release is created. If `release_after_tag_push` is true,
+/// release-plz waits for the tag to be known by the git forge before creating the release.
+fn git_steps(push_tag: bool, create_release: bool, release_after_tag_push: bool) -> Vec<GitStep> {
+    let mut steps = vec![];
+    if push_tag {
+        steps.push(GitStep::PushTag);
     }
+    if create_release {
+        if release_after_tag_push {
+            steps.push(GitStep::WaitForTag);
+        }
+        steps.push(GitStep::CreateRelease);
+    }
+    steps
+}
+
+/// Wait until the git forge knows the tag, checking it up to `attempts` times.
+async fn wait_for_tag(
+    git_client: &GitClient,
+    git_tag: &str,
+    attempts: u32,
+    interval: Duration,
+) -> anyhow::Result<()> {
+    for attempt in 1..=attempts {
+        if git_client.tag_exists(git_tag).await? {
+            return Ok(());
+        }
+        if attempt < attempts {
+            info!("waiting for the git forge to know the tag {git_tag}");
+            tokio::time::sleep(interval).await;
+        }
+    }
+    anyhow::bail!("the git forge doesn't know the tag {git_tag} after {attempts} attempts")
+}
 
+/// Whether `cargo publish` failed because the version was already uploaded to the registry,
+/// based on its `stderr`.
+/// Cargo fails before uploading if the version is in the registry index, otherwise the
+/// registry rejects the upload.
+fn is_already_uploaded(stderr: &str) -> bool {
+    lazy_static::lazy_static! {
+        static ref ALREADY_UPLOADED_RE: Regex = Regex::new(
+            r"crate \S+@\S+ already exists on .*index|crate version `[^`]+` is already uploaded"
+        )
+        .unwrap();
+    }
+    ALREADY_UPLOADED_RE.is_match(stderr)
+}
+
+/// Create the git tag of the package and push it.
+/// If the request is idempotent and the tag already exists, it's only pushed.
+fn push_git_tag(
+    input: &ReleaseRequest,
+    package: &Package,
+    repo: &Repo,
+    git_tag: &str,
+) -> anyhow::Result<()> {
+    if input.idempotent && repo.tag_exists(git_tag)? {
+        info!(
+            "{} {}: git tag {git_tag} already exists",
+            package.name, package.version
+        );
+    } else {
+        let git_tag_config = input.get_package_config(&package.name).generic.git_tag;
+        let message = git_tag_config.message(
+            &package.name,
+            &package.version.to_string(),
+            git_tag,
+            input.release_date(),
+            || release_body(input, package),
+        );
+        match message {
+            Some(message) => repo.annotated_tag(git_tag, &message)?,
+            None => repo.tag(git_tag)?,
+        };
+    }
+    // Pushing a tag that the remote already has is a no-op.
+    repo.push(git_tag)?;
+    Ok(())
+}
+
+/// Create the git release.
+/// If `idempotent` is true and the release already exists, it isn't created.
+/// Returns `true` if the release was created.
+async fn create_git_release(
+    git_client: &GitClient,
+    release_info: &GitReleaseInfo,
+    idempotent: bool,
+) -> anyhow::Result<bool> {
+    if idempotent {
+        return create_missing_release(git_client, release_info).await;
+    }
+    git_client
+        .create_release(release_info)
+        .await
+        .context("Failed to create release")?;
+    Ok(true)
+}
+
+/// Create the git releases of the tags of the package that were pushed without one,
+/// e.g. because a previous run failed after pushing the tag.
+/// `tags` are the tags of the repository.
+async fn backfill_git_releases(
+    input: &ReleaseRequest,
+    package: &Package,
+    default_tag_template: &str,
+    tags: &[String],
+) -> anyhow::Result<()> {
+    let git_release = input
+        .git_release
+        .as_ref()
+        .context("git release not configured. Did you specify git-token and backend?")?;
+    let git_client = GitClient::new(git_release.backend.clone())?;
+    let config = input.get_package_config(&package.name).generic;
+    let git_release_config = &config.git_release;
+    let release_type = git_release_config.release_type();
+    let package_tags = config
+        .git_tag
+        .name
+        .versions(tags, default_tag_template, &package.name);
+    for (git_tag, version) in package_tags {
+        git_release_config.check_release_type(release_type, &package.name, &version)?;
+        let mut flags = git_release_config.flags(release_type, &package.name, &version, false)?;
+        if version < package.version {
+            // Don't replace the latest release with an older one.
+            flags.latest = Some(false);
+        }
+        let release_info = GitReleaseInfo {
+            release_body: release_notes(input, package, Some(&version)),
+            draft: flags.draft,
+            pre_release: flags.pre_release,
+            latest: flags.latest,
+            git_tag: git_tag.to_string(),
+        };
+        if input.dry_run {
+            info!(
+                "{} {version}: skipping git release backfill of tag {git_tag} due to dry run",
+                package.name
+            );
+            continue;
+        }
+        if create_missing_release(&git_client, &release_info).await? {
+            info!(
+                "{} {version}: created missing git release for tag {git_tag}",
+                package.name
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Whether the git release of the tag exists.
+async fn git_release_exists(input: &ReleaseRequest, git_tag: &str) -> anyhow::Result<bool> {
+    let git_release = input
+        .git_release
+        .as_ref()
+        .context("git release not configured. Did you specify git-token and backend?")?;
+    let git_client = GitClient::new(git_release.backend.clone())?;
+    git_client.release_exists(git_tag).await
+}
+
+/// Fail if the git tag that should have been pushed by someone else doesn't exist.
+fn check_existing_tag(repo: &Repo, package: &str, git_tag: &str) -> anyhow::Result<()> {
+    if !repo.tag_exists(git_tag)? {
+        anyhow::bail!(
+            "{package}: git tag `{git_tag}` doesn't exist, but `git_release_expect_existing_tag` is enabled. Push the tag before running release-plz, or disable `git_release_expect_existing_tag`"
+        );
+    }
     Ok(())
 }
 
+/// Fail if the `rust-version` of the package is newer than the `toolchain` version.
+/// Like cargo, the pre-release of the toolchain, e.g. `nightly`, is ignored.
+fn check_msrv(package: &Package, toolchain: &Version) -> anyhow::Result<()> {
+    let Some(msrv) = &package.rust_version else {
+        return Ok(());
+    };
+    let toolchain = Version::new(toolchain.major, toolchain.minor, toolchain.patch);
+    if msrv > &toolchain {
+        anyhow::bail!(
+            "{}: the package requires Rust {msrv} (`rust-version`), but the toolchain used to publish it is {toolchain}. Update the toolchain or disable `check_msrv`",
+            package.name
+        );
+    }
+    Ok(())
+}
+
+/// Create the git release if it doesn't exist.
+/// Returns `true` if the release was created.
+async fn create_missing_release(
+    git_client: &GitClient,
+    release_info: &GitReleaseInfo,
+) -> anyhow::Result<bool> {
+    if git_client.release_exists(&release_info.git_tag).await? {
+        return Ok(false);
+    }
+    git_client
+        .create_release(release_info)
+        .await
+        .context("Failed to create release")?;
+    Ok(true)
+}
+
 pub struct GitReleaseInfo {
     pub git_tag: String,
     pub release_body: String,
     pub draft: bool,
+    pub pre_release: bool,
+    /// Whether to mark the release as the latest one. If `None`, the git forge decides.
+    pub latest: Option<bool>,
+}
+
+/// Name of the environment variable containing the token of the registry.
+/// `None` is crates.io.
+fn registry_token_env_var(registry: Option<&str>) -> String {
+    match registry.filter(|r| *r != CRATES_IO_REGISTRY) {
+        Some(registry) => format!(
+            "CARGO_REGISTRIES_{}_TOKEN",
+            registry.to_uppercase().replace('-', "_")
+        ),
+        None => "CARGO_REGISTRY_TOKEN".to_string(),
+    }
+}
+
+/// Token of the `registry` read from the environment variable configured in `token_env`,
+/// with the name of the environment variable where cargo looks for it.
+/// Returns `None` if the registry doesn't have a configured environment variable,
+/// so that cargo looks for the token in its default locations.
+fn registry_token_env(
+    registry: Option<&str>,
+    token_env: &BTreeMap<String, String>,
+    lookup: impl Fn(&str) -> Option<String>,
+) -> Option<(String, String)> {
+    let registry = registry.unwrap_or(CRATES_IO_REGISTRY);
+    let var = token_env.get(registry)?;
+    match lookup(var).filter(|token| !token.is_empty()) {
+        Some(token) => Some((registry_token_env_var(Some(registry)), token)),
+        None => {
+            warn!("environment variable {var} with the token of registry {registry} is not set");
+            None
+        }
+    }
+}
+
+/// Environment variable of the token of the first registry without token.
+/// `token` returns the token of a registry, where `None` is crates.io.
+/// If `registries` is empty, the package is published to crates.io.
+fn missing_registry_token(
+    registries: &[String],
+    token: impl Fn(Option<&str>) -> Option<String>,
+) -> Option<String> {
+    let registries: Vec<Option<&str>> = if registries.is_empty() {
+        vec![None]
+    } else {
+        registries.iter().map(|r| Some(r.as_str())).collect()
+    };
+    registries
+        .into_iter()
+        .find(|registry| token(*registry).map_or(true, |token| token.is_empty()))
+        .map(registry_token_env_var)
+}
+
+/// Value of the trailer with the given `key` (case-insensitive) in the commit message.
+/// Trailers are the `Key: value` lines of the last paragraph of the message.
+fn commit_trailer<'a>(message: &'a str, key: &str) -> Option<&'a str> {
+    let last_paragraph = message.trim_end().rsplit("\n\n").next()?;
+    last_paragraph.lines().find_map(|line| {
+        let (line_key, value) = line.split_once(':')?;
+        line_key
+            .trim()
+            .eq_ignore_ascii_case(key)
+            .then_some(value.trim())
+    })
 }
 
 fn run_cargo_publish(
     package: &Package,
+    registry: Option<&str>,
     input: &ReleaseRequest,
+    allow_dirty: bool,
     workspace_root: &Path,
 ) -> anyhow::Result<(String, String)> {
+    let args = cargo_publish_args(package, registry, input, allow_dirty);
+    let env = input.publish_env(&package.name, registry)?;
+    run_cargo_with_env(workspace_root, &args, &env)
+}
+
+/// Arguments of `cargo publish`.
+/// `registry` is the registry where the package is published. `None` is crates.io.
+fn cargo_publish_args<'a>(
+    package: &'a Package,
+    registry: Option<&'a str>,
+    input: &'a ReleaseRequest,
+    allow_dirty: bool,
+) -> Vec<&'a str> {
     let mut args = vec!["publish"];
     args.push("--color");
     args.push("always");
     args.push("--manifest-path");
     args.push(package.manifest_path.as_ref());
-    if let Some(registry) = &input.registry {
+    if let Some(registry) = registry {
         args.push("--registry");
         args.push(registry);
     }
@@ -510,23 +1931,70 @@ fn run_cargo_publish(
     if input.dry_run {
         args.push("--dry-run");
     }
-    if input.allow_dirty(&package.name) {
+    if allow_dirty {
         args.push("--allow-dirty");
     }
     if input.no_verify(&package.name) {
         args.push("--no-verify");
     }
-    run_cargo(workspace_root, &args)
+    args
+}
+
+/// Whether the working directory is dirty only because of release-plz,
+/// i.e. there are `changed_files` and all of them are `release_plz_files`.
+fn only_release_plz_changes(changed_files: &[PathBuf], release_plz_files: &[PathBuf]) -> bool {
+    !changed_files.is_empty()
+        && changed_files
+            .iter()
+            .all(|file| release_plz_files.contains(file))
+}
+
+/// Replace `${VAR}` with the value returned by `lookup` for `VAR`.
+fn interpolate_env(value: &str, lookup: impl Fn(&str) -> Option<String>) -> anyhow::Result<String> {
+    let mut result = String::new();
+    let mut rest = value;
+    while let Some(start) = rest.find("${") {
+        result.push_str(&rest[..start]);
+        let after_start = &rest[start + 2..];
+        let end = after_start
+            .find('}')
+            .with_context(|| format!("unclosed `${{` in `{value}`"))?;
+        let var = &after_start[..end];
+        let var_value =
+            lookup(var).with_context(|| format!("environment variable {var} is not set"))?;
+        result.push_str(&var_value);
+        rest = &after_start[end + 1..];
+    }
+    result.push_str(rest);
+    Ok(result)
 }
 
 /// Return an empty string if the changelog cannot be parsed.
 fn release_body(req: &ReleaseRequest, package: &Package) -> String {
+    release_notes(req, package, None)
+}
+
+/// Notes of the release of `version` in the changelog of the package,
+/// or of the last release if `version` is `None`.
+fn release_notes(req: &ReleaseRequest, package: &Package, version: Option<&Version>) -> String {
     let changelog_path = req.changelog_path(package);
-    match changelog_parser::last_changes(&changelog_path) {
-        Ok(Some(changes)) => changes,
+    let release = std::fs::read_to_string(&changelog_path)
+        .context("can't read changelog file")
+        .and_then(|changelog: String| match version {
+            Some(version) => req
+                .changelog_format
+                .release(&changelog, &version.to_string()),
+            None => req.changelog_format.last_release(&changelog),
+        });
+    match release {
+        Ok(Some(release)) => release.notes().to_string(),
         Ok(None) => {
+            let release = match version {
+                Some(version) => format!("release {version}"),
+                None => "last change".to_string(),
+            };
             warn!(
-                "{}: last change not fuond in changelog at path {:?}. The git release body will be empty.",
+                "{}: {release} not fuond in changelog at path {:?}. The git release body will be empty.",
                 package.name, &changelog_path
             );
             String::new()
@@ -541,19 +2009,1474 @@ fn release_body(req: &ReleaseRequest, package: &Package) -> String {
     }
 }
 
-async fn publish_git_release(
-    release_info: &GitReleaseInfo,
-    backend: &GitBackend,
-) -> anyhow::Result<()> {
-    let backend = match backend {
-        GitBackend::Github(github) => GitBackend::Github(github.clone()),
-        GitBackend::Gitea(gitea) => GitBackend::Gitea(gitea.clone()),
-        GitBackend::Gitlab(gitlab) => GitBackend::Gitlab(gitlab.clone()),
+#[cfg(test)]
+mod tests {
+    use secrecy::SecretString;
+    use wiremock::{
+        matchers::{body_partial_json, method, path},
+        Mock, MockServer, ResponseTemplate,
     };
-    let git_client = GitClient::new(backend)?;
-    git_client
-        .create_release(release_info)
+
+    use crate::{GitHub, CHANGELOG_FILENAME};
+
+    use super::*;
+
+    #[test]
+    fn draft_release_is_published_only_if_package_is_published() {
+        let config = GitReleaseConfig::enabled(true)
+            .set_draft(true)
+            .set_publish_draft_on_success(true);
+        assert!(config.is_draft(ReleaseType::Prod, false));
+        assert!(!config.is_draft(ReleaseType::Prod, true));
+    }
+
+    #[test]
+    fn draft_release_stays_draft_without_publish_draft_on_success() {
+        let config = GitReleaseConfig::enabled(true).set_draft(true);
+        assert!(config.is_draft(ReleaseType::Prod, true));
+        assert!(!GitReleaseConfig::enabled(true).is_draft(ReleaseType::Prod, true));
+    }
+
+    #[test]
+    fn git_release_flags_are_computed_from_config_and_release_type() {
+        let version = Version::parse("1.0.0").unwrap();
+        let pre_version = Version::parse("1.0.0-rc.1").unwrap();
+        let flags = |config: &GitReleaseConfig, release_type, version, published| {
+            config
+                .flags(release_type, "my-pkg", version, published)
+                .unwrap()
+        };
+        let config = GitReleaseConfig::enabled(true).set_latest(Some(false));
+        assert_eq!(
+            flags(&config, ReleaseType::Auto, &pre_version, true),
+            GitReleaseFlags {
+                draft: false,
+                pre_release: true,
+                latest: Some(false)
+            }
+        );
+        assert_eq!(
+            flags(&config, ReleaseType::Draft, &pre_version, false),
+            GitReleaseFlags {
+                draft: true,
+                pre_release: false,
+                latest: Some(false)
+            }
+        );
+        let config = GitReleaseConfig::enabled(true)
+            .set_publish_draft_on_success(true)
+            .set_latest(Some(true));
+        assert_eq!(
+            flags(&config, ReleaseType::Draft, &version, true),
+            GitReleaseFlags {
+                draft: false,
+                pre_release: false,
+                latest: Some(true)
+            }
+        );
+    }
+
+    #[test]
+    fn draft_release_type_is_kept_with_never_prerelease() {
+        let config = GitReleaseConfig::enabled(true)
+            .set_release_type(ReleaseType::Draft)
+            .set_never_prerelease(true);
+        assert_eq!(config.release_type(), ReleaseType::Draft);
+        let config = config.set_release_type(ReleaseType::Pre);
+        assert_eq!(config.release_type(), ReleaseType::Prod);
+    }
+
+    #[test]
+    fn draft_release_marked_as_latest_is_an_error() {
+        let version = Version::parse("1.0.0").unwrap();
+        let config = GitReleaseConfig::enabled(true)
+            .set_draft(true)
+            .set_latest(Some(true));
+        let error = config
+            .flags(ReleaseType::Prod, "my-pkg", &version, true)
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "my-pkg 1.0.0: `git_release_latest` is `true`, but the git release is a draft. A draft release can't be marked as latest"
+        );
+        let config = GitReleaseConfig::enabled(true).set_latest(Some(true));
+        assert!(config
+            .flags(ReleaseType::Draft, "my-pkg", &version, true)
+            .is_err());
+    }
+
+    #[test]
+    fn pre_release_marked_as_latest_is_an_error() {
+        let config = GitReleaseConfig::enabled(true).set_latest(Some(true));
+        let error = config
+            .flags(ReleaseType::Pre, "my-pkg", &Version::new(1, 0, 0), true)
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "my-pkg 1.0.0: `git_release_latest` is `true`, but the git release is a pre-release. A pre-release can't be marked as latest"
+        );
+    }
+
+    #[test]
+    fn unique_git_tags_are_accepted() {
+        let git_tags = [
+            ("pkg-a", "pkg-a-v1.0.0".to_string()),
+            ("pkg-b", "pkg-b-v1.0.0".to_string()),
+        ];
+        check_unique_git_tags(&git_tags).unwrap();
+    }
+
+    #[test]
+    fn colliding_git_tags_are_rejected() {
+        let git_tags = [
+            ("pkg-a", "v1.0.0".to_string()),
+            ("pkg-b", "pkg-b-v1.0.0".to_string()),
+            ("pkg-c", "v1.0.0".to_string()),
+        ];
+        let err = check_unique_git_tags(&git_tags).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "packages `pkg-a` and `pkg-c` have the same git tag `v1.0.0`. Make sure each package has a unique git tag"
+        );
+    }
+
+    #[test]
+    fn shared_git_tag_is_accepted_if_only_one_package_creates_it() {
+        let shared_name = GitTagName::default().set_template(Some("v{{ version }}".to_string()));
+        let request = ReleaseRequest::new(fake_package::metadata::fake_metadata())
+            .with_default_package_config(
+                ReleaseConfig::default()
+                    .with_git_tag(GitTagConfig::enabled(true).set_name(shared_name.clone())),
+            )
+            .with_package_config(
+                "bbb",
+                PackageReleaseConfig {
+                    generic: ReleaseConfig::default()
+                        .with_git_tag(GitTagConfig::enabled(false).set_name(shared_name)),
+                    changelog_path: None,
+                },
+            );
+        let packages: Vec<Package> = ["aaa", "bbb"]
+            .into_iter()
+            .map(|name| fake_package::FakePackage::new(name).into())
+            .collect();
+        let packages: Vec<&Package> = packages.iter().collect();
+
+        let git_tags = request.created_git_tags(&packages, "{{ package }}-v{{ version }}", &[]);
+
+        assert_eq!(git_tags, [("aaa", "v0.1.0".to_string())]);
+        check_unique_git_tags(&git_tags).unwrap();
+    }
+
+    fn date() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 6, 1).unwrap()
+    }
+
+    #[test]
+    fn git_tag_message_contains_release_date() {
+        let config = GitTagConfig::default()
+            .set_message(Some("{{ package }} {{ version }} ({{ date }})".to_string()));
+        assert_eq!(
+            config
+                .message("my-pkg", "1.2.3", "v1.2.3", date(), || unreachable!())
+                .as_deref(),
+            Some("my-pkg 1.2.3 (2024-06-01)")
+        );
+    }
+
+    #[test]
+    fn git_tag_is_created_with_release_date() {
+        let (_dir, repo) = repo_with_pushed_tag();
+        let config = ReleaseConfig::default().with_git_tag(
+            GitTagConfig::default().set_message(Some("released on {{ date }}".to_string())),
+        );
+        let request = ReleaseRequest::new(fake_package::metadata::fake_metadata())
+            .with_default_package_config(config)
+            .with_release_date(date());
+        let package: Package = fake_package::FakePackage::new("aaa").into();
+
+        push_git_tag(&request, &package, &repo, "v2.0.0").unwrap();
+
+        let message = repo
+            .git(&["tag", "-l", "--format=%(contents:subject)", "v2.0.0"])
+            .unwrap();
+        assert_eq!(message, "released on 2024-06-01");
+    }
+
+    #[test]
+    fn git_tag_name_contains_release_date() {
+        let (_dir, repo) = repo_with_pushed_tag();
+        let name = GitTagName::default()
+            .set_template(Some("v{{ version }}-{{ date }}".to_string()))
+            .set_date_format(Some("%Y.%m".to_string()));
+        let config = ReleaseConfig::default().with_git_tag(
+            GitTagConfig::default()
+                .set_message(Some("{{ tag }} ({{ date }})".to_string()))
+                .set_name(name),
+        );
+        let request = ReleaseRequest::new(fake_package::metadata::fake_metadata())
+            .with_default_package_config(config)
+            .with_release_date(date());
+        let package: Package = fake_package::FakePackage::new("aaa").into();
+
+        let git_tag = request.git_tag(&package, "v{{ version }}", &[]);
+        assert_eq!(git_tag, "v0.1.0-2024.06");
+        push_git_tag(&request, &package, &repo, &git_tag).unwrap();
+        let message = repo
+            .git(&["tag", "-l", "--format=%(contents:subject)", &git_tag])
+            .unwrap();
+        assert_eq!(message, "v0.1.0-2024.06 (2024.06)");
+
+        // On a later run, the existing tag is found even if the date changed.
+        let request = request.with_release_date(NaiveDate::from_ymd_opt(2024, 7, 1).unwrap());
+        assert_eq!(
+            request.git_tag(&package, "v{{ version }}", &repo.tags().unwrap()),
+            "v0.1.0-2024.06"
+        );
+    }
+
+    #[test]
+    fn annotated_git_tag_has_message() {
+        assert_eq!(
+            GitTagConfig::default()
+                .message("my-pkg", "1.0.0", "v1.0.0", date(), String::new)
+                .as_deref(),
+            Some("my-pkg 1.0.0")
+        );
+    }
+
+    #[test]
+    fn lightweight_git_tag_has_no_message() {
+        let config = GitTagConfig::enabled(true).set_annotated(false);
+        assert_eq!(
+            config.message("my-pkg", "1.0.0", "v1.0.0", date(), String::new),
+            None
+        );
+    }
+
+    #[test]
+    fn git_tag_message_template_is_rendered() {
+        let config = GitTagConfig::default().set_message(Some(
+            "{{tag}}: {{ package }} {{version}}\n\n{{ changelog }}".to_string(),
+        ));
+        assert_eq!(
+            config
+                .message("my-pkg", "1.0.0", "my-pkg-v1.0.0", date(), || {
+                    "### Fixed\n- myfix".to_string()
+                })
+                .as_deref(),
+            Some("my-pkg-v1.0.0: my-pkg 1.0.0\n\n### Fixed\n- myfix")
+        );
+    }
+
+    #[test]
+    fn changelog_is_read_only_if_git_tag_message_uses_it() {
+        let config = GitTagConfig::default().set_message(Some("{{ tag }}".to_string()));
+        assert_eq!(
+            config
+                .message("my-pkg", "1.0.0", "v1.0.0", date(), || unreachable!())
+                .as_deref(),
+            Some("v1.0.0")
+        );
+    }
+
+    #[test]
+    fn git_tag_message_template_is_ignored_for_lightweight_tags() {
+        let config = GitTagConfig::enabled(true)
+            .set_annotated(false)
+            .set_message(Some("{{ changelog }}".to_string()));
+        assert_eq!(
+            config.message("my-pkg", "1.0.0", "v1.0.0", date(), || unreachable!()),
+            None
+        );
+    }
+
+    fn release_request_with_trailer(release_type: ReleaseType) -> ReleaseRequest {
+        ReleaseRequest::new(fake_package::metadata::fake_metadata())
+            .with_default_package_config(
+                ReleaseConfig::default().with_git_release(
+                    GitReleaseConfig::enabled(true).set_release_type(release_type),
+                ),
+            )
+            .with_release_type_trailer("Release-Type")
+    }
+
+    #[test]
+    fn pre_trailer_forces_pre_release() {
+        let request = release_request_with_trailer(ReleaseType::Prod);
+        let message = "chore: release\n\nSome details.\n\nRelease-Type: pre\nSigned-off-by: me";
+        assert_eq!(request.git_release_type("aaa", message), ReleaseType::Pre);
+    }
+
+    #[test]
+    fn configured_release_type_is_used_without_trailer() {
+        let request = release_request_with_trailer(ReleaseType::Prod);
+        let message =
+            "chore: release\n\nRelease-Type: pre is mentioned in the body.\n\nSigned-off-by: me";
+        assert_eq!(request.git_release_type("aaa", message), ReleaseType::Prod);
+        let request = release_request_with_trailer(ReleaseType::Auto);
+        assert_eq!(
+            request.git_release_type("aaa", "chore: release"),
+            ReleaseType::Auto
+        );
+    }
+
+    #[test]
+    fn never_prerelease_overrides_auto_release_type_and_trailer() {
+        let request = ReleaseRequest::new(fake_package::metadata::fake_metadata())
+            .with_default_package_config(
+                ReleaseConfig::default().with_git_release(
+                    GitReleaseConfig::enabled(true)
+                        .set_release_type(ReleaseType::Auto)
+                        .set_release_type_strict(true)
+                        .set_never_prerelease(true),
+                ),
+            )
+            .with_release_type_trailer("Release-Type");
+        let version = Version::parse("1.0.0-rc.1").unwrap();
+        for message in ["chore: release", "chore: release\n\nRelease-Type: pre"] {
+            let release_type = request.git_release_type("aaa", message);
+            assert!(!release_type.is_pre_release(&version));
+            let git_release = request.get_package_config("aaa").generic.git_release;
+            git_release
+                .check_release_type(release_type, "aaa", &version)
+                .unwrap();
+        }
+    }
+
+    #[test]
+    fn trailer_is_ignored_if_not_configured() {
+        let request = ReleaseRequest::new(fake_package::metadata::fake_metadata());
+        let message = "chore: release\n\nRelease-Type: pre";
+        assert_eq!(request.git_release_type("aaa", message), ReleaseType::Prod);
+    }
+
+    #[test]
+    fn auto_release_type_depends_on_version() {
+        assert!(ReleaseType::Auto.is_pre_release(&Version::parse("1.0.0-rc1").unwrap()));
+        assert!(!ReleaseType::Auto.is_pre_release(&Version::parse("1.0.0").unwrap()));
+    }
+
+    #[test]
+    fn git_release_is_created_from_any_branch_by_default() {
+        let request = ReleaseRequest::new(fake_package::metadata::fake_metadata());
+        assert!(request.is_git_release_branch(Some("main")));
+        assert!(request.is_git_release_branch(Some("feature/preview")));
+        assert!(request.is_git_release_branch(None));
+    }
+
+    #[test]
+    fn git_release_is_created_only_from_matching_branches() {
+        let request = ReleaseRequest::new(fake_package::metadata::fake_metadata())
+            .with_git_release_branches(&["main".to_string(), "release/*".to_string()])
+            .unwrap();
+        assert!(request.is_git_release_branch(Some("main")));
+        assert!(request.is_git_release_branch(Some("release/1.x")));
+        assert!(!request.is_git_release_branch(Some("feature/preview")));
+        assert!(!request.is_git_release_branch(Some("main-preview")));
+        // Detached HEAD, e.g. in CI checkouts.
+        assert!(!request.is_git_release_branch(None));
+    }
+
+    #[test]
+    fn invalid_git_release_branches_glob_is_an_error() {
+        let error = ReleaseRequest::new(fake_package::metadata::fake_metadata())
+            .with_git_release_branches(&["release/[".to_string()])
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "invalid git_release_branches `release/[`"
+        );
+    }
+
+    #[test]
+    fn prod_pre_release_is_an_error_if_strict() {
+        let config = GitReleaseConfig::enabled(true).set_release_type_strict(true);
+        let error = config
+            .check_release_type(
+                ReleaseType::Prod,
+                "my-pkg",
+                &Version::parse("1.0.0-rc1").unwrap(),
+            )
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "my-pkg 1.0.0-rc1: the version is a pre-release, but the git release type is `prod`, so the release will be marked as ready for production"
+        );
+    }
+
+    #[test]
+    fn prod_pre_release_is_allowed_if_not_strict() {
+        let config = GitReleaseConfig::enabled(true);
+        let version = Version::parse("1.0.0-beta.1").unwrap();
+        config
+            .check_release_type(ReleaseType::Prod, "my-pkg", &version)
+            .unwrap();
+    }
+
+    #[test]
+    fn strict_release_type_allows_consistent_versions() {
+        let config = GitReleaseConfig::enabled(true).set_release_type_strict(true);
+        let pre_release = Version::parse("1.0.0-rc1").unwrap();
+        let prod_release = Version::parse("1.0.0").unwrap();
+        for (release_type, version) in [
+            (ReleaseType::Prod, &prod_release),
+            (ReleaseType::Pre, &pre_release),
+            (ReleaseType::Auto, &pre_release),
+        ] {
+            config
+                .check_release_type(release_type, "my-pkg", version)
+                .unwrap();
+        }
+    }
+
+    #[test]
+    fn crates_io_token_is_missing_if_env_var_is_absent() {
+        assert_eq!(
+            missing_registry_token(&[], |_| None),
+            Some("CARGO_REGISTRY_TOKEN".to_string())
+        );
+        assert_eq!(
+            missing_registry_token(&[], |registry| registry
+                .is_none()
+                .then(|| "token".to_string())),
+            None
+        );
+    }
+
+    #[test]
+    fn registry_token_is_missing_if_env_var_is_absent() {
+        let registries = ["my-registry".to_string()];
+        assert_eq!(
+            missing_registry_token(&registries, |registry| registry
+                .is_none()
+                .then(|| "token".to_string())),
+            Some("CARGO_REGISTRIES_MY_REGISTRY_TOKEN".to_string())
+        );
+        assert_eq!(
+            missing_registry_token(&registries, |registry| (registry == Some("my-registry"))
+                .then(|| "token".to_string())),
+            None
+        );
+    }
+
+    #[test]
+    fn plan_trace_explains_release_steps() {
+        let request = ReleaseRequest::new(fake_package::metadata::fake_metadata())
+            .with_default_package_config(ReleaseConfig::default())
+            .with_package_config(
+                "bbb",
+                PackageReleaseConfig {
+                    generic: ReleaseConfig::default()
+                        .with_publish(PublishConfig::enabled(false))
+                        .with_git_release(GitReleaseConfig::enabled(false)),
+                    changelog_path: None,
+                },
+            );
+        let traces = ["aaa", "bbb"]
+            .map(|name| {
+                let package: Package = fake_package::FakePackage::new(name).into();
+                let git_tag = format!("{name}-v{}", package.version);
+                request.plan_trace(&package, &git_tag, false).to_string()
+            })
+            .join("\n");
+        expect_test::expect![[r#"
+            aaa: plan
+              version: 0.1.0
+              publish: yes (`publish = true`)
+              git tag: yes (tag `aaa-v0.1.0` doesn't exist yet)
+              git release: no (the current branch doesn't match `git_release_branches`)
+            bbb: plan
+              version: 0.1.0
+              publish: no (`publish = false`)
+              git tag: yes (tag `bbb-v0.1.0` doesn't exist yet)
+              git release: no (`git_release_enable = false`)"#]]
+        .assert_eq(&traces);
+    }
+
+    fn package_with_msrv(msrv: &str) -> Package {
+        let mut package: Package = fake_package::FakePackage::new("aaa").into();
+        package.rust_version = Some(Version::parse(msrv).unwrap());
+        package
+    }
+
+    #[test]
+    fn msrv_newer_than_toolchain_fails() {
+        let package = package_with_msrv("1.78.0");
+        let error = check_msrv(&package, &Version::new(1, 76, 0)).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "aaa: the package requires Rust 1.78.0 (`rust-version`), but the toolchain used to publish it is 1.76.0. Update the toolchain or disable `check_msrv`"
+        );
+    }
+
+    #[test]
+    fn satisfied_msrv_passes() {
+        let package = package_with_msrv("1.70.0");
+        check_msrv(&package, &Version::new(1, 76, 0)).unwrap();
+        check_msrv(&package, &Version::new(1, 70, 0)).unwrap();
+        let nightly = Version::parse("1.78.0-nightly").unwrap();
+        check_msrv(&package_with_msrv("1.78.0"), &nightly).unwrap();
+    }
+
+    #[test]
+    fn package_without_msrv_passes() {
+        let package: Package = fake_package::FakePackage::new("aaa").into();
+        check_msrv(&package, &Version::new(1, 0, 0)).unwrap();
+    }
+
+    #[test]
+    fn token_is_not_required_by_default() {
+        let request = ReleaseRequest::new(fake_package::metadata::fake_metadata());
+        let package = fake_package::FakePackage::new("aaa").into();
+        assert_eq!(request.missing_registry_token(&package), None);
+    }
+
+    #[test]
+    fn token_passed_to_the_request_is_used() {
+        let request = ReleaseRequest::new(fake_package::metadata::fake_metadata())
+            .with_publish_require_token(true)
+            .with_token(SecretString::from("token".to_string()));
+        let package = fake_package::FakePackage::new("aaa").into();
+        assert_eq!(request.missing_registry_token(&package), None);
+    }
+
+    #[test]
+    fn token_referencing_unset_variable_is_missing() {
+        let env = BTreeMap::from([(
+            "CARGO_REGISTRY_TOKEN".to_string(),
+            "${RELEASE_PLZ_TEST_UNSET_TOKEN}".to_string(),
+        )]);
+        let request = ReleaseRequest::new(fake_package::metadata::fake_metadata())
+            .with_publish_require_token(true)
+            .with_default_package_config(ReleaseConfig::default().with_env(env));
+        let package = fake_package::FakePackage::new("aaa").into();
+        assert_eq!(
+            request.missing_registry_token(&package).as_deref(),
+            Some("CARGO_REGISTRY_TOKEN")
+        );
+    }
+
+    fn release_request_with_registries(registries: &[&str]) -> ReleaseRequest {
+        ReleaseRequest::new(fake_package::metadata::fake_metadata()).with_default_package_config(
+            ReleaseConfig::default()
+                .with_publish_registries(registries.iter().map(|r| r.to_string()).collect()),
+        )
+    }
+
+    #[test]
+    fn package_is_published_to_each_registry() {
+        let request = release_request_with_registries(&["crates-io", "internal"]);
+        let package: Package = fake_package::FakePackage::new("aaa").into();
+        let registries = request.registries(&package);
+        assert_eq!(registries, ["crates-io", "internal"]);
+        let publish_commands: Vec<Vec<&str>> = registries
+            .iter()
+            .map(|r| cargo_publish_args(&package, Some(r), &request, false))
+            .collect();
+        assert_eq!(
+            publish_commands,
+            [
+                [
+                    "publish",
+                    "--color",
+                    "always",
+                    "--manifest-path",
+                    "aaa/Cargo.toml",
+                    "--registry",
+                    "crates-io"
+                ],
+                [
+                    "publish",
+                    "--color",
+                    "always",
+                    "--manifest-path",
+                    "aaa/Cargo.toml",
+                    "--registry",
+                    "internal"
+                ]
+            ]
+        );
+    }
+
+    /// Repository with the package `aaa`, that has the `VERSION` version file.
+    fn repo_with_version_file(
+        allow_dirty_auto: bool,
+    ) -> (tempfile::TempDir, Repo, ReleaseRequest, Package) {
+        let dir = tempfile::tempdir().unwrap();
+        let package_dir = dir.path().join("aaa");
+        std::fs::create_dir(&package_dir).unwrap();
+        std::fs::write(package_dir.join("VERSION"), "0.1.0\n").unwrap();
+        std::fs::write(package_dir.join("lib.rs"), "").unwrap();
+        let repo = Repo::init(dir.path());
+        let mut metadata = fake_package::metadata::fake_metadata();
+        metadata.workspace_root = dir.path().to_path_buf().try_into().unwrap();
+        let request = ReleaseRequest::new(metadata).with_default_package_config(
+            ReleaseConfig::default()
+                .with_allow_dirty_auto(allow_dirty_auto)
+                .with_version_files(vec!["VERSION".to_string()]),
+        );
+        let mut package: Package = fake_package::FakePackage::new("aaa").into();
+        package.manifest_path = package_dir.join(CARGO_TOML).try_into().unwrap();
+        (dir, repo, request, package)
+    }
+
+    #[test]
+    fn allow_dirty_is_added_when_release_plz_made_the_changes() {
+        let (dir, repo, request, package) = repo_with_version_file(true);
+        std::fs::write(dir.path().join("aaa/VERSION"), "0.2.0\n").unwrap();
+        std::fs::write(
+            dir.path().join("aaa").join(CHANGELOG_FILENAME),
+            "# Changelog",
+        )
+        .unwrap();
+        assert!(request.publish_allow_dirty(&package, &repo).unwrap());
+    }
+
+    #[test]
+    fn allow_dirty_is_not_added_when_other_files_changed() {
+        let (dir, repo, request, package) = repo_with_version_file(true);
+        std::fs::write(dir.path().join("aaa/VERSION"), "0.2.0\n").unwrap();
+        std::fs::write(dir.path().join("aaa/lib.rs"), "pub fn f() {}").unwrap();
+        assert!(!request.publish_allow_dirty(&package, &repo).unwrap());
+    }
+
+    #[test]
+    fn release_body_is_read_from_json_changelog() {
+        let (dir, _repo, request, package) = repo_with_version_file(false);
+        let request = request.with_changelog_format(ChangelogFormat::Json);
+        std::fs::write(
+            dir.path().join("aaa/CHANGELOG.json"),
+            r#"[{"version": "0.2.0", "date": "2024-01-01", "sections": [
+                {"title": "Fixed", "entries": [{"message": "fix bug", "breaking": false}]}
+            ]}]"#,
+        )
+        .unwrap();
+        assert_eq!(release_body(&request, &package), "### Fixed\n\n- fix bug");
+    }
+
+    #[test]
+    fn release_notes_of_past_versions_are_read_from_changelog() {
+        let (dir, _repo, request, package) = repo_with_version_file(false);
+        let request = request.with_changelog_format(ChangelogFormat::Rst);
+        std::fs::write(
+            dir.path().join("aaa/CHANGELOG.rst"),
+            "Changelog\n=========\n\n0.2.0 - 2024-02-01\n------------------\n\nFixed\n~~~~~\n\n- fix bug\n\n0.1.0 - 2024-01-01\n------------------\n\nAdded\n~~~~~\n\n- add api\n",
+        )
+        .unwrap();
+        let notes = release_notes(&request, &package, Some(&Version::new(0, 1, 0)));
+        assert_eq!(notes, "### Added\n\n- add api");
+        let notes = release_notes(&request, &package, Some(&Version::new(0, 0, 1)));
+        assert_eq!(notes, "");
+
+        let request = request.with_changelog_format(ChangelogFormat::Json);
+        std::fs::write(
+            dir.path().join("aaa/CHANGELOG.json"),
+            r#"[{"version": "0.2.0", "date": "2024-02-01", "sections": []},
+                {"version": "0.1.0", "date": "2024-01-01", "sections": [
+                {"title": "Added", "entries": [{"message": "add api", "breaking": false}]}
+            ]}]"#,
+        )
+        .unwrap();
+        let notes = release_notes(&request, &package, Some(&Version::new(0, 1, 0)));
+        assert_eq!(notes, "### Added\n\n- add api");
+    }
+
+    #[test]
+    fn allow_dirty_is_not_added_when_there_are_no_changes() {
+        let (_dir, repo, request, package) = repo_with_version_file(true);
+        assert!(!request.publish_allow_dirty(&package, &repo).unwrap());
+    }
+
+    #[test]
+    fn allow_dirty_is_not_added_when_auto_is_disabled() {
+        let (dir, repo, request, package) = repo_with_version_file(false);
+        std::fs::write(dir.path().join("aaa/VERSION"), "0.2.0\n").unwrap();
+        assert!(!request.publish_allow_dirty(&package, &repo).unwrap());
+    }
+
+    #[test]
+    fn registry_of_the_request_overrides_publish_registries() {
+        let request =
+            release_request_with_registries(&["crates-io", "internal"]).with_registry("other");
+        let package = fake_package::FakePackage::new("aaa").into();
+        assert_eq!(request.registries(&package), ["other"]);
+    }
+
+    #[test]
+    fn crates_io_token_is_read_from_cargo_registry_token() {
+        assert_eq!(
+            registry_token_env_var(Some("crates-io")),
+            "CARGO_REGISTRY_TOKEN"
+        );
+    }
+
+    fn token_env() -> BTreeMap<String, String> {
+        BTreeMap::from([
+            ("internal".to_string(), "INTERNAL_TOKEN".to_string()),
+            ("crates-io".to_string(), "CRATES_IO_TOKEN".to_string()),
+        ])
+    }
+
+    fn token_lookup(var: &str) -> Option<String> {
+        match var {
+            "INTERNAL_TOKEN" => Some("internal-token".to_string()),
+            "CRATES_IO_TOKEN" => Some("crates-io-token".to_string()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn configured_token_env_is_exported_for_the_registry() {
+        assert_eq!(
+            registry_token_env(Some("internal"), &token_env(), token_lookup),
+            Some((
+                "CARGO_REGISTRIES_INTERNAL_TOKEN".to_string(),
+                "internal-token".to_string()
+            ))
+        );
+        assert_eq!(
+            registry_token_env(None, &token_env(), token_lookup),
+            Some((
+                "CARGO_REGISTRY_TOKEN".to_string(),
+                "crates-io-token".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn registry_without_token_env_uses_cargo_defaults() {
+        assert_eq!(
+            registry_token_env(Some("other"), &token_env(), token_lookup),
+            None
+        );
+        let token_env = BTreeMap::from([("other".to_string(), "UNSET_TOKEN".to_string())]);
+        assert_eq!(
+            registry_token_env(Some("other"), &token_env, token_lookup),
+            None
+        );
+    }
+
+    const MINUTES_30: Duration = Duration::from_secs(30 * 60);
+
+    #[test]
+    fn per_package_timeout_resets_for_each_package() {
+        let start = Instant::now();
+        let deadline = PublishDeadline::new(PublishTimeoutScope::PerPackage, MINUTES_30, start);
+        // first package
+        assert_eq!(deadline.timeout(start), MINUTES_30);
+        // second package, published after the first one took 20 minutes
+        let second_package_start = start + Duration::from_secs(20 * 60);
+        assert_eq!(deadline.timeout(second_package_start), MINUTES_30);
+    }
+
+    #[test]
+    fn total_timeout_caps_the_whole_publish_phase() {
+        let start = Instant::now();
+        let deadline = PublishDeadline::new(PublishTimeoutScope::Total, MINUTES_30, start);
+        // first package
+        assert_eq!(deadline.timeout(start), MINUTES_30);
+        // second package, published after the first one took 20 minutes
+        let second_package_start = start + Duration::from_secs(20 * 60);
+        assert_eq!(
+            deadline.timeout(second_package_start),
+            Duration::from_secs(10 * 60)
+        );
+        // the deadline is already elapsed
+        let late = start + Duration::from_secs(40 * 60);
+        assert_eq!(deadline.timeout(late), Duration::ZERO);
+    }
+
+    fn lookup(var: &str) -> Option<String> {
+        (var == "HOME").then(|| "/home/me".to_string())
+    }
+
+    #[test]
+    fn env_variables_are_interpolated() {
+        assert_eq!(
+            interpolate_env("${HOME}/target:${HOME}", lookup).unwrap(),
+            "/home/me/target:/home/me"
+        );
+    }
+
+    #[test]
+    fn env_value_without_variables_is_untouched() {
+        assert_eq!(
+            interpolate_env("x86_64-unknown-linux-gnu", lookup).unwrap(),
+            "x86_64-unknown-linux-gnu"
+        );
+    }
+
+    #[test]
+    fn missing_env_variable_is_an_error() {
+        let error = interpolate_env("${MISSING}", lookup).unwrap_err();
+        assert_eq!(error.to_string(), "environment variable MISSING is not set");
+    }
+
+    const RELEASE_TAG_PATH: &str = "/repos/owner/repo/releases/tags/v1.0.0";
+    const RELEASES_PATH: &str = "/repos/owner/repo/releases";
+
+    fn git_client(server: &MockServer) -> GitClient {
+        let github = GitHub::new(
+            "owner".to_string(),
+            "repo".to_string(),
+            SecretString::from("token".to_string()),
+        )
+        .with_base_url(format!("{}/", server.uri()).parse().unwrap());
+        GitClient::new(GitBackend::Github(github)).unwrap()
+    }
+
+    fn release_info() -> GitReleaseInfo {
+        GitReleaseInfo {
+            git_tag: "v1.0.0".to_string(),
+            release_body: "### Fixed\n- myfix".to_string(),
+            draft: false,
+            pre_release: false,
+            latest: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn release_is_created_for_tag_without_release() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path(RELEASE_TAG_PATH))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path(RELEASES_PATH))
+            .respond_with(ResponseTemplate::new(201))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let created = create_missing_release(&git_client(&server), &release_info())
+            .await
+            .unwrap();
+        assert!(created);
+    }
+
+    #[tokio::test]
+    async fn missing_git_releases_of_all_package_tags_are_backfilled() {
+        let (dir, _repo, request, package) = repo_with_version_file(false);
+        std::fs::write(
+            dir.path().join("aaa").join(CHANGELOG_FILENAME),
+            "# Changelog\n\n## [0.1.0] - 2024-03-01\n\n- third\n\n## [0.0.2] - 2024-02-01\n\n- second\n\n## [0.0.1] - 2024-01-01\n\n- first\n",
+        )
+        .unwrap();
+        let server = MockServer::start().await;
+        let github = GitHub::new(
+            "owner".to_string(),
+            "repo".to_string(),
+            SecretString::from("token".to_string()),
+        )
+        .with_base_url(format!("{}/", server.uri()).parse().unwrap());
+        let request = request.with_git_release(GitRelease {
+            backend: GitBackend::Github(github),
+        });
+        Mock::given(method("GET"))
+            .and(path("/repos/owner/repo/releases/tags/v0.1.0"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+        for (tag, body) in [("v0.0.1", "- first"), ("v0.0.2", "- second")] {
+            Mock::given(method("GET"))
+                .and(path(format!("/repos/owner/repo/releases/tags/{tag}")))
+                .respond_with(ResponseTemplate::new(404))
+                .mount(&server)
+                .await;
+            Mock::given(method("POST"))
+                .and(path(RELEASES_PATH))
+                .and(body_partial_json(serde_json::json!({
+                    "tag_name": tag,
+                    "body": body,
+                    "make_latest": "false",
+                })))
+                .respond_with(ResponseTemplate::new(201))
+                .expect(1)
+                .mount(&server)
+                .await;
+        }
+        let tags = ["v0.0.1", "v0.0.2", "v0.1.0", "bbb-v0.0.3"].map(String::from);
+
+        backfill_git_releases(&request, &package, "v{{ version }}", &tags)
+            .await
+            .unwrap();
+    }
+
+    /// Request that creates the git release of `aaa` as a draft that is published
+    /// if the package is published.
+    fn draft_release_request(
+        server: &MockServer,
+    ) -> (tempfile::TempDir, Repo, ReleaseRequest, Package) {
+        let (dir, repo, request, package) = repo_with_version_file(false);
+        let github = GitHub::new(
+            "owner".to_string(),
+            "repo".to_string(),
+            SecretString::from("token".to_string()),
+        )
+        .with_base_url(format!("{}/", server.uri()).parse().unwrap());
+        let request = request
+            .with_git_release(GitRelease {
+                backend: GitBackend::Github(github),
+            })
+            .with_default_package_config(
+                ReleaseConfig::default()
+                    .with_git_tag(GitTagConfig::enabled(false))
+                    .with_git_release(
+                        GitReleaseConfig::enabled(true)
+                            .set_draft(true)
+                            .set_publish_draft_on_success(true),
+                    ),
+            );
+        (dir, repo, request, package)
+    }
+
+    async fn mock_release_creation(server: &MockServer, draft: bool, times: u64) {
+        Mock::given(method("POST"))
+            .and(path(RELEASES_PATH))
+            .and(body_partial_json(serde_json::json!({
+                "tag_name": "v0.1.0",
+                "draft": draft,
+            })))
+            .respond_with(ResponseTemplate::new(201))
+            .expect(times)
+            .mount(server)
+            .await;
+    }
+
+    fn publish_deadline() -> PublishDeadline {
+        PublishDeadline::new(
+            PublishTimeoutScope::Total,
+            Duration::from_secs(60),
+            Instant::now(),
+        )
+    }
+
+    #[tokio::test]
+    async fn published_check_stops_at_the_publish_deadline() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(404).set_delay(Duration::from_secs(30)))
+            .mount(&server)
+            .await;
+        let dir = tempfile::tempdir().unwrap();
+        let index =
+            SparseIndex::with_path(dir.path(), format!("sparse+{}/", server.uri())).unwrap();
+        let package: Package = fake_package::FakePackage::new("aaa").into();
+        // The whole publish phase had 1 second, and it's already over.
+        let start = Instant::now() - Duration::from_secs(2);
+        let deadline =
+            PublishDeadline::new(PublishTimeoutScope::Total, Duration::from_secs(1), start);
+
+        let check_start = Instant::now();
+        let published =
+            is_published_before(&mut CargoIndex::Sparse(index), &package, &deadline).await;
+        assert_eq!(published.unwrap_err().to_string(), "deadline has elapsed");
+        assert!(check_start.elapsed() < Duration::from_secs(10));
+    }
+
+    #[tokio::test]
+    async fn draft_release_is_published_after_upload() {
+        let server = MockServer::start().await;
+        let (_dir, repo, request, package) = draft_release_request(&server);
+        mock_release_creation(&server, false, 1).await;
+
+        release_to_git(
+            &request,
+            &package,
+            &repo,
+            "v0.1.0",
+            false,
+            Some(ReleaseType::Prod),
+            true,
+        )
         .await
-        .context("Failed to create release")?;
-    Ok(())
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn draft_release_stays_draft_if_no_registry_is_uploaded() {
+        let server = MockServer::start().await;
+        let (_dir, _repo, request, package) = draft_release_request(&server);
+        mock_release_creation(&server, true, 1).await;
+        mock_release_creation(&server, false, 0).await;
+
+        release_package(
+            vec![],
+            &package,
+            &request,
+            "v0.1.0".to_string(),
+            &publish_deadline(),
+        )
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn draft_release_stays_draft_if_publish_is_disabled() {
+        let server = MockServer::start().await;
+        let (_dir, _repo, request, package) = draft_release_request(&server);
+        let request = request.with_default_package_config(
+            ReleaseConfig::default()
+                .with_publish(PublishConfig::enabled(false))
+                .with_git_tag(GitTagConfig::enabled(false))
+                .with_git_release(
+                    GitReleaseConfig::enabled(true)
+                        .set_draft(true)
+                        .set_publish_draft_on_success(true),
+                ),
+        );
+        mock_release_creation(&server, true, 1).await;
+        mock_release_creation(&server, false, 0).await;
+
+        release_package(
+            vec![],
+            &package,
+            &request,
+            "v0.1.0".to_string(),
+            &publish_deadline(),
+        )
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn git_release_is_not_created_if_publish_fails() {
+        let server = MockServer::start().await;
+        let (dir, _repo, request, package) = draft_release_request(&server);
+        mock_release_creation(&server, true, 0).await;
+        mock_release_creation(&server, false, 0).await;
+        // `aaa` has no manifest, so `cargo publish` fails before contacting the registry.
+        let index = SparseIndex::with_path(dir.path(), "sparse+http://127.0.0.1/index/").unwrap();
+
+        let result = release_package(
+            vec![(None, CargoIndex::Sparse(index))],
+            &package,
+            &request,
+            "v0.1.0".to_string(),
+            &publish_deadline(),
+        )
+        .await;
+        assert!(format!("{:?}", result.unwrap_err()).contains("failed to publish aaa"));
+    }
+
+    #[tokio::test]
+    async fn release_is_not_created_for_tag_with_release() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path(RELEASE_TAG_PATH))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path(RELEASES_PATH))
+            .respond_with(ResponseTemplate::new(201))
+            .expect(0)
+            .mount(&server)
+            .await;
+        let created = create_missing_release(&git_client(&server), &release_info())
+            .await
+            .unwrap();
+        assert!(!created);
+    }
+
+    #[tokio::test]
+    async fn release_is_created_for_existing_tag() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = Repo::init(dir.path());
+        repo.tag("v1.0.0").unwrap();
+        check_existing_tag(&repo, "aaa", "v1.0.0").unwrap();
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path(RELEASE_TAG_PATH))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path(RELEASES_PATH))
+            .respond_with(ResponseTemplate::new(201))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let created = create_missing_release(&git_client(&server), &release_info())
+            .await
+            .unwrap();
+        assert!(created);
+    }
+
+    #[test]
+    fn release_is_created_right_after_tag_push_by_default() {
+        assert_eq!(
+            git_steps(true, true, false),
+            [GitStep::PushTag, GitStep::CreateRelease]
+        );
+    }
+
+    #[test]
+    fn release_is_created_once_the_pushed_tag_is_known() {
+        assert_eq!(
+            git_steps(true, true, true),
+            [
+                GitStep::PushTag,
+                GitStep::WaitForTag,
+                GitStep::CreateRelease
+            ]
+        );
+    }
+
+    #[test]
+    fn release_waits_for_tag_pushed_by_someone_else() {
+        assert_eq!(
+            git_steps(false, true, true),
+            [GitStep::WaitForTag, GitStep::CreateRelease]
+        );
+    }
+
+    #[test]
+    fn tag_is_pushed_without_git_release() {
+        assert_eq!(git_steps(true, false, true), [GitStep::PushTag]);
+    }
+
+    const TAG_REF_PATH: &str = "/repos/owner/repo/git/ref/tags/v1.0.0";
+
+    #[tokio::test]
+    async fn tag_is_awaited_until_the_forge_knows_it() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path(TAG_REF_PATH))
+            .respond_with(ResponseTemplate::new(404))
+            .up_to_n_times(1)
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path(TAG_REF_PATH))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+        wait_for_tag(&git_client(&server), "v1.0.0", 3, Duration::ZERO)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn unknown_tag_is_an_error() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path(TAG_REF_PATH))
+            .respond_with(ResponseTemplate::new(404))
+            .expect(2)
+            .mount(&server)
+            .await;
+        let error = wait_for_tag(&git_client(&server), "v1.0.0", 2, Duration::ZERO)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "the git forge doesn't know the tag v1.0.0 after 2 attempts"
+        );
+    }
+
+    /// Repository with the tag `v1.0.0` already pushed to its `origin` remote.
+    fn repo_with_pushed_tag() -> (tempfile::TempDir, Repo) {
+        let dir = tempfile::tempdir().unwrap();
+        let remote_dir = dir.path().join("remote");
+        let repo_dir = dir.path().join("repo");
+        std::fs::create_dir(&repo_dir).unwrap();
+        git_cmd::git_in_dir(dir.path(), &["init", "--bare", "remote"]).unwrap();
+        let repo = Repo::init(&repo_dir);
+        repo.git(&["remote", "add", "origin", remote_dir.to_str().unwrap()])
+            .unwrap();
+        repo.tag("v1.0.0").unwrap();
+        repo.push("v1.0.0").unwrap();
+        (dir, repo)
+    }
+
+    #[test]
+    fn already_uploaded_version_is_detected() {
+        assert!(is_already_uploaded(
+            "error: failed to publish to registry at https://crates.io\n\nCaused by:\n  the remote server responded with an error: crate version `aaa@1.0.0` is already uploaded"
+        ));
+        assert!(is_already_uploaded(
+            "error: crate aaa@1.0.0 already exists on crates.io index"
+        ));
+        assert!(!is_already_uploaded(
+            "error: failed to publish to registry at https://crates.io\n\nCaused by:\n  the remote server responded with an error: this crate exists but you don't seem to be an owner"
+        ));
+        assert!(!is_already_uploaded(
+            "error: failed to prepare local package for uploading\n\nCaused by:\n  file `README.md` already exists"
+        ));
+    }
+
+    #[tokio::test]
+    async fn idempotent_release_rerun_skips_already_uploaded_version() {
+        let server = MockServer::start().await;
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join(".cargo")).unwrap();
+        std::fs::write(
+            dir.path().join(".cargo/config.toml"),
+            format!(
+                "[registries.test]\nindex = \"sparse+{}/index/\"\n",
+                server.uri()
+            ),
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join(CARGO_TOML),
+            "[package]\nname = \"aaa\"\nversion = \"0.1.0\"\nedition = \"2021\"\nlicense = \"MIT\"\ndescription = \"aaa\"\npublish = [\"test\"]\n",
+        )
+        .unwrap();
+        std::fs::create_dir(dir.path().join("src")).unwrap();
+        std::fs::write(dir.path().join("src/lib.rs"), "").unwrap();
+        std::fs::write(dir.path().join(".gitignore"), "target\nCargo.lock\n").unwrap();
+        Repo::init(dir.path());
+
+        Mock::given(method("GET"))
+            .and(path("/index/config.json"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "dl": format!("{}/dl", server.uri()),
+                "api": server.uri(),
+            })))
+            .mount(&server)
+            .await;
+        // The previous run uploaded the package, but the index doesn't show it yet,
+        // so `cargo publish` runs again and the registry rejects the upload.
+        Mock::given(method("GET"))
+            .and(path("/index/3/a/aaa"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&server)
+            .await;
+        Mock::given(method("PUT"))
+            .and(path("/api/v1/crates/new"))
+            .respond_with(ResponseTemplate::new(400).set_body_json(serde_json::json!({
+                "errors": [{"detail": "crate version `0.1.0` is already uploaded"}]
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let metadata = cargo_metadata::MetadataCommand::new()
+            .manifest_path(dir.path().join(CARGO_TOML))
+            .exec()
+            .unwrap();
+        let config = ReleaseConfig::default()
+            .with_git_tag(GitTagConfig::enabled(false))
+            .with_git_release(GitReleaseConfig::enabled(false))
+            .with_no_verify(true);
+        let request = ReleaseRequest::new(metadata)
+            .with_default_package_config(config)
+            .with_token(SecretString::from("token".to_string()))
+            .with_idempotent(true);
+
+        release(&request).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn idempotent_rerun_of_released_package_does_nothing() {
+        let (_dir, repo) = repo_with_pushed_tag();
+        let tag_commit = repo.get_tag_commit("v1.0.0").unwrap();
+        let request =
+            ReleaseRequest::new(fake_package::metadata::fake_metadata()).with_idempotent(true);
+        let package: Package = fake_package::FakePackage::new("aaa").into();
+
+        push_git_tag(&request, &package, &repo, "v1.0.0").unwrap();
+        assert_eq!(repo.get_tag_commit("v1.0.0").unwrap(), tag_commit);
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path(RELEASE_TAG_PATH))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path(RELEASES_PATH))
+            .respond_with(ResponseTemplate::new(201))
+            .expect(0)
+            .mount(&server)
+            .await;
+        let created = create_git_release(&git_client(&server), &release_info(), true)
+            .await
+            .unwrap();
+        assert!(!created);
+    }
+
+    #[test]
+    fn git_tag_is_pushed_to_configured_remote() {
+        let (dir, _repo) = repo_with_pushed_tag();
+        let fork_dir = dir.path().join("fork");
+        git_cmd::git_in_dir(dir.path(), &["init", "--bare", "fork"]).unwrap();
+        let repo_dir = dir.path().join("repo");
+        git_cmd::git_in_dir(
+            &repo_dir,
+            &["remote", "add", "fork", fork_dir.to_str().unwrap()],
+        )
+        .unwrap();
+        let request = ReleaseRequest::new(fake_package::metadata::fake_metadata())
+            .with_git_push_remote(Some("fork".to_string()));
+        let package: Package = fake_package::FakePackage::new("aaa").into();
+
+        let repo = request.git_repo(&repo_dir).unwrap();
+        push_git_tag(&request, &package, &repo, "v2.0.0").unwrap();
+
+        let fork_tags = git_cmd::git_in_dir(&fork_dir, &["tag"]).unwrap();
+        assert_eq!(fork_tags, "v2.0.0");
+        let origin_tags = git_cmd::git_in_dir(&dir.path().join("remote"), &["tag"]).unwrap();
+        assert_eq!(origin_tags, "v1.0.0");
+    }
+
+    #[test]
+    fn existing_tag_is_an_error_if_not_idempotent() {
+        let (_dir, repo) = repo_with_pushed_tag();
+        let request = ReleaseRequest::new(fake_package::metadata::fake_metadata());
+        let package: Package = fake_package::FakePackage::new("aaa").into();
+        assert!(push_git_tag(&request, &package, &repo, "v1.0.0").is_err());
+    }
+
+    #[test]
+    fn missing_expected_tag_is_an_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = Repo::init(dir.path());
+        let error = check_existing_tag(&repo, "aaa", "v1.0.0").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "aaa: git tag `v1.0.0` doesn't exist, but `git_release_expect_existing_tag` is enabled. Push the tag before running release-plz, or disable `git_release_expect_existing_tag`"
+        );
+    }
+
+    /// Release the packages `a`..`e`: `b` is already released, `c` fails.
+    /// Returns the attempted packages and the result of the release.
+    async fn release_with_failing_package(
+        on_error: OnError,
+    ) -> (Vec<String>, anyhow::Result<ReleasePlan>) {
+        let packages: Vec<Package> = ["a", "b", "c", "d", "e"]
+            .into_iter()
+            .map(|name| fake_package::FakePackage::new(name).into())
+            .collect();
+        let packages: Vec<&Package> = packages.iter().collect();
+        let mut attempted = vec![];
+        let result = release_in_order(&packages, on_error, |package| {
+            attempted.push(package.name.clone());
+            let name = package.name.clone();
+            async move {
+                match name.as_str() {
+                    "b" => Ok(None),
+                    "c" => Err(anyhow::anyhow!("cargo publish failed")),
+                    _ => Ok(Some(format!("{name}-v0.1.0"))),
+                }
+            }
+        })
+        .await;
+        (attempted, result)
+    }
+
+    #[tokio::test]
+    async fn release_stops_at_first_failure() {
+        let (attempted, result) = release_with_failing_package(OnError::Stop).await;
+        assert_eq!(attempted, ["a", "b", "c"]);
+        assert_eq!(result.unwrap_err().to_string(), "cargo publish failed");
+    }
+
+    #[tokio::test]
+    async fn release_continues_after_failure_and_reports_each_package() {
+        let (attempted, result) = release_with_failing_package(OnError::Continue).await;
+        assert_eq!(attempted, ["a", "b", "c", "d", "e"]);
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "failed to release 1 of 5 packages:
+- a 0.1.0: released
+- b 0.1.0: skipped
+- c 0.1.0: failed: cargo publish failed
+- d 0.1.0: released
+- e 0.1.0: released"
+        );
+    }
+
+    #[tokio::test]
+    async fn release_skips_dependents_of_failed_package() {
+        let packages: Vec<Package> = vec![
+            fake_package::FakePackage::new("a").into(),
+            fake_package::FakePackage::new("b")
+                .with_dependencies(vec![fake_package::FakeDependency::new("a")])
+                .into(),
+            fake_package::FakePackage::new("c")
+                .with_dependencies(vec![fake_package::FakeDependency::new("b")])
+                .into(),
+            fake_package::FakePackage::new("d")
+                .with_dependencies(vec![fake_package::FakeDependency::new("a").dev()])
+                .into(),
+        ];
+        let packages: Vec<&Package> = packages.iter().collect();
+        let mut attempted = vec![];
+        let result = release_in_order(&packages, OnError::Continue, |package| {
+            attempted.push(package.name.clone());
+            let name = package.name.clone();
+            async move {
+                match name.as_str() {
+                    "a" => Err(anyhow::anyhow!("cargo publish failed")),
+                    _ => Ok(Some(format!("{name}-v0.1.0"))),
+                }
+            }
+        })
+        .await;
+        assert_eq!(attempted, ["a", "d"]);
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "failed to release 1 of 4 packages:
+- a 0.1.0: failed: cargo publish failed
+- b 0.1.0: skipped: dependency `a` failed
+- c 0.1.0: skipped: dependency `b` failed
+- d 0.1.0: released"
+        );
+    }
+
+    #[tokio::test]
+    async fn release_without_failures_returns_released_packages() {
+        let packages: Vec<Package> = ["a", "b"]
+            .into_iter()
+            .map(|name| fake_package::FakePackage::new(name).into())
+            .collect();
+        let packages: Vec<&Package> = packages.iter().collect();
+        let plan = release_in_order(&packages, OnError::Continue, |package| {
+            let tag = format!("{}-v0.1.0", package.name);
+            async move { Ok(Some(tag)) }
+        })
+        .await
+        .unwrap();
+        let tags: Vec<&str> = plan.releases.iter().map(|r| r.tag.as_str()).collect();
+        assert_eq!(tags, ["a-v0.1.0", "b-v0.1.0"]);
+    }
+
+    #[tokio::test]
+    async fn package_is_published_to_registries_where_it_is_missing() {
+        let package: Package = fake_package::FakePackage::new("aaa").into();
+        let indexes = vec![
+            (Some("first".to_string()), true),
+            (Some("second".to_string()), false),
+        ];
+        let unpublished = unpublished_indexes(&package, indexes, |published| async move {
+            (published, Ok(published))
+        })
+        .await
+        .unwrap();
+        let registries: Vec<Option<&str>> = unpublished
+            .iter()
+            .map(|(registry, _)| registry.as_deref())
+            .collect();
+        assert_eq!(registries, [Some("second")]);
+    }
+
+    #[tokio::test]
+    async fn package_published_to_all_registries_has_no_unpublished_index() {
+        let package: Package = fake_package::FakePackage::new("aaa").into();
+        let indexes = vec![(Some("first".to_string()), true), (None, true)];
+        let unpublished = unpublished_indexes(&package, indexes, |published| async move {
+            (published, Ok(published))
+        })
+        .await
+        .unwrap();
+        assert!(unpublished.is_empty());
+    }
 }
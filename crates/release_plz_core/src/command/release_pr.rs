@@ -1,17 +1,23 @@
-use std::path::{Path, PathBuf};
+use std::{collections::HashMap, path::Path, process::Command};
 
-use git_cmd::Repo;
+use cargo_metadata::Package;
+use chrono::{DateTime, FixedOffset};
+use git_cmd::{GitIdentity, Repo};
 
 use anyhow::{anyhow, Context};
-use tracing::{info, instrument};
+use tracing::{info, instrument, warn};
 
-use crate::git::backend::{contributors_from_commits, GitClient, GitPr, PrEdit};
+use crate::git::backend::{contributors_from_commits, GitClient, GitPr, MergedPr, PrEdit};
+use crate::next_ver::default_git_tag_template;
 use crate::pr::{Pr, BRANCH_PREFIX, OLD_BRANCH_PREFIX};
 use crate::{
     copy_to_temp_dir, publishable_packages_from_manifest, update, GitBackend, PackagesUpdate,
     UpdateRequest, CARGO_TOML,
 };
 
+/// Default label that identifies the release PR.
+pub const DEFAULT_PR_MARKER_LABEL: &str = "release-plz";
+
 #[derive(Debug)]
 pub struct ReleasePrRequest {
     pub git: GitBackend,
@@ -19,15 +25,60 @@ pub struct ReleasePrRequest {
     draft: bool,
     /// Labels to add to the release PR.
     labels: Vec<String>,
+    /// Label added to every release PR, used to find the open release PR.
+    marker_label: String,
+    /// If `false`, the packages are updated, but the release PR isn't opened.
+    create_pr: bool,
+    /// Shell command run after the packages are updated, before committing the changes.
+    pre_hook: Option<String>,
+    /// If `false`, the changes of the `Cargo.lock` of the workspace aren't committed
+    /// in the release commit.
+    commit_lockfile: bool,
+    /// If `true`, the breaking changes found by cargo-semver-checks are added to the PR body.
+    semver_check_report: bool,
+    /// Base branch of the release PR.
+    /// If `None`, the current branch is used. If HEAD is detached,
+    /// the default branch of the remote is used.
+    base_branch: Option<String>,
+    /// Author and committer of the release commit.
+    git_identity: GitIdentity,
+    /// Remote where the release branch is pushed.
+    /// If `None`, the remote of the current branch is used.
+    git_push_remote: Option<String>,
+    /// Issues closed when the release PR is merged.
+    closed_issues: Vec<u64>,
+    /// Where the entries of the changelog come from.
+    changelog_source: ChangelogSource,
     pub update_request: UpdateRequest,
 }
 
+/// Source of the entries of the changelog.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ChangelogSource {
+    /// Use the commit messages.
+    #[default]
+    Commits,
+    /// Use the titles of the Pull Requests merged since the last tag.
+    /// Commits that don't belong to a merged Pull Request keep their message.
+    PullRequests,
+}
+
 impl ReleasePrRequest {
     pub fn new(git: GitBackend, update_request: UpdateRequest) -> Self {
         Self {
             git,
             draft: false,
             labels: vec![],
+            marker_label: DEFAULT_PR_MARKER_LABEL.to_string(),
+            create_pr: true,
+            pre_hook: None,
+            commit_lockfile: true,
+            semver_check_report: true,
+            base_branch: None,
+            git_identity: GitIdentity::default(),
+            git_push_remote: None,
+            closed_issues: vec![],
+            changelog_source: ChangelogSource::default(),
             update_request,
         }
     }
@@ -37,49 +88,240 @@ impl ReleasePrRequest {
         self
     }
 
+    pub fn with_marker_label(mut self, marker_label: impl Into<String>) -> Self {
+        self.marker_label = marker_label.into();
+        self
+    }
+
     pub fn mark_as_draft(mut self, draft: bool) -> Self {
         self.draft = draft;
         self
     }
+
+    pub fn with_create_pr(mut self, create_pr: bool) -> Self {
+        self.create_pr = create_pr;
+        self
+    }
+
+    pub fn with_pre_hook(mut self, pre_hook: Option<String>) -> Self {
+        self.pre_hook = pre_hook;
+        self
+    }
+
+    pub fn with_commit_lockfile(mut self, commit_lockfile: bool) -> Self {
+        self.commit_lockfile = commit_lockfile;
+        self
+    }
+
+    pub fn with_semver_check_report(mut self, semver_check_report: bool) -> Self {
+        self.semver_check_report = semver_check_report;
+        self
+    }
+
+    pub fn with_base_branch(mut self, base_branch: Option<String>) -> Self {
+        self.base_branch = base_branch;
+        self
+    }
+
+    /// Create the release commit with the given identity instead of the one of the git configuration.
+    pub fn with_git_identity(mut self, git_identity: GitIdentity) -> Self {
+        self.git_identity = git_identity;
+        self
+    }
+
+    /// Push the release branch to this remote, e.g. to open the release PR from a fork.
+    pub fn with_git_push_remote(mut self, git_push_remote: Option<String>) -> Self {
+        self.git_push_remote = git_push_remote;
+        self
+    }
+
+    /// Add closing keywords for these issues to the body of the release PR,
+    /// so that the issues are closed when the release PR is merged.
+    pub fn with_closed_issues(mut self, closed_issues: Vec<u64>) -> Self {
+        self.closed_issues = closed_issues;
+        self
+    }
+
+    pub fn with_changelog_source(mut self, changelog_source: ChangelogSource) -> Self {
+        self.changelog_source = changelog_source;
+        self
+    }
 }
 
-/// Open a pull request with the next packages versions of a local rust project
+/// Open a pull request with the next packages versions of a local rust project.
+/// Return the opened (or updated) pull request, if any.
 #[instrument(skip_all)]
-pub async fn release_pr(input: &ReleasePrRequest) -> anyhow::Result<()> {
+pub async fn release_pr(input: &ReleasePrRequest) -> anyhow::Result<Option<GitPr>> {
     let manifest_dir = input.update_request.local_manifest_dir()?;
-    let tmp_project_root = copy_to_temp_dir(manifest_dir)?;
-    let manifest_dir_name = manifest_dir
-        .iter()
-        .last()
-        .ok_or_else(|| anyhow!("wrong local manifest path"))?;
-    let manifest_dir_name = PathBuf::from(manifest_dir_name);
-    let new_manifest_dir = tmp_project_root.as_ref().join(manifest_dir_name);
+    // The release PR is committed from a copy of the project, so that the project isn't modified.
+    // If the release PR isn't created, the project itself is updated.
+    let tmp_project_root = if input.create_pr {
+        Some(copy_to_temp_dir(manifest_dir)?)
+    } else {
+        None
+    };
+    let new_manifest_dir = match &tmp_project_root {
+        Some(tmp_project_root) => {
+            let manifest_dir_name = manifest_dir
+                .iter()
+                .last()
+                .ok_or_else(|| anyhow!("wrong local manifest path"))?;
+            tmp_project_root.as_ref().join(manifest_dir_name)
+        }
+        None => manifest_dir.to_path_buf(),
+    };
     let local_manifest = new_manifest_dir.join(CARGO_TOML);
-    let new_update_request = input
+    let mut new_update_request = input
         .update_request
         .clone()
         .set_local_manifest(&local_manifest)
         .context("can't find temporary project")?;
+    let use_pr_titles = input.changelog_source == ChangelogSource::PullRequests;
+    let credit_authors = new_update_request.changelog_credit_authors();
+    if use_pr_titles || credit_authors {
+        match merged_prs_since_last_tag(&input.git, &new_update_request, &new_manifest_dir).await {
+            Ok(merged_prs) => {
+                if use_pr_titles {
+                    new_update_request =
+                        new_update_request.with_merged_prs(merged_pr_entries(&merged_prs));
+                }
+                if credit_authors {
+                    new_update_request =
+                        new_update_request.with_commit_authors(merged_pr_authors(&merged_prs));
+                }
+            }
+            Err(e) => warn!(
+                "cannot retrieve the merged Pull Requests, the changelog uses the commit messages and the git author names: {e:?}"
+            ),
+        }
+    }
     let (packages_to_update, _temp_repository) =
         update(&new_update_request).context("failed to update packages")?;
+    if let Some(pre_hook) = &input.pre_hook {
+        if !packages_to_update.updates().is_empty() {
+            run_pre_hook(pre_hook, &new_manifest_dir)?;
+        }
+    }
+    if !input.create_pr {
+        info!(
+            "release PR creation is disabled: skipping the release PR of {} packages",
+            packages_to_update.updates().len()
+        );
+        return Ok(None);
+    }
     let git_client = GitClient::new(input.git.clone())?;
+    let mut release_pr = None;
     if !packages_to_update.updates().is_empty() {
-        let repo = Repo::new(new_manifest_dir)?;
-        let there_are_commits_to_push = repo.is_clean().is_err();
+        let repo = release_pr_repo(
+            &new_manifest_dir,
+            input.base_branch.as_deref(),
+            input.git_push_remote.as_deref(),
+        )?
+        .with_identity(input.git_identity.clone());
+        let there_are_commits_to_push = !release_changes(&repo, input.commit_lockfile)?.is_empty();
         if there_are_commits_to_push {
-            open_or_update_release_pr(
+            let pr = open_or_update_release_pr(
                 &local_manifest,
                 &packages_to_update,
                 &git_client,
                 &repo,
-                input.draft,
-                input.labels.clone(),
+                input,
             )
             .await?;
+            release_pr = Some(pr);
         }
     }
 
-    Ok(())
+    Ok(release_pr)
+}
+
+/// Pull Requests merged since the last tag of the packages.
+async fn merged_prs_since_last_tag(
+    git: &GitBackend,
+    update_request: &UpdateRequest,
+    directory: &Path,
+) -> anyhow::Result<Vec<MergedPr>> {
+    let repo = Repo::new(directory)?;
+    let packages = publishable_packages_from_manifest(update_request.local_manifest())?;
+    let since = last_release_date(&repo, update_request, &packages)?;
+    GitClient::new(git.clone())?.merged_prs(since).await
+}
+
+/// Date of the oldest among the last tags of the `packages`, so that the Pull Requests
+/// merged since the last release of each package are listed.
+/// `None` if a package has no tag yet.
+fn last_release_date(
+    repo: &Repo,
+    update_request: &UpdateRequest,
+    packages: &[Package],
+) -> anyhow::Result<Option<DateTime<FixedOffset>>> {
+    let tags = repo.tags_merged_into_head()?;
+    let default_template = default_git_tag_template(packages.len() > 1);
+    let mut since: Option<DateTime<FixedOffset>> = None;
+    for package in packages {
+        let git_tag_name = update_request
+            .get_package_config(&package.name)
+            .generic
+            .git_tag_name;
+        // Tags are sorted from the most recent.
+        let Some((tag, _)) = git_tag_name
+            .versions(&tags, default_template, &package.name)
+            .into_iter()
+            .next()
+        else {
+            info!(
+                "{}: no git tag found, only the most recent merged Pull Requests are listed",
+                package.name
+            );
+            return Ok(None);
+        };
+        let date = repo.commit_date(tag)?;
+        let date = DateTime::parse_from_rfc3339(&date)
+            .with_context(|| format!("cannot parse date `{date}` of tag `{tag}`"))?;
+        since = Some(since.map_or(date, |since| since.min(date)));
+    }
+    Ok(since)
+}
+
+/// Changelog entries of the merged Pull Requests, e.g. `feat: add api (#12)`.
+/// The key is the hash of the commit created by merging the Pull Request.
+fn merged_pr_entries(merged_prs: &[MergedPr]) -> HashMap<String, String> {
+    merged_prs
+        .iter()
+        .map(|pr| {
+            (
+                pr.merge_commit_sha.clone(),
+                format!("{} (#{})", pr.title, pr.number),
+            )
+        })
+        .collect()
+}
+
+/// Usernames of the authors of the merged Pull Requests.
+/// The key is the hash of the commit created by merging the Pull Request.
+fn merged_pr_authors(merged_prs: &[MergedPr]) -> HashMap<String, String> {
+    merged_prs
+        .iter()
+        .filter_map(|pr| Some((pr.merge_commit_sha.clone(), pr.author.clone()?)))
+        .collect()
+}
+
+/// Repository of the release PR.
+/// The original branch of the repository is the base branch of the PR.
+/// The release branch is pushed to `push_remote`, if any.
+fn release_pr_repo(
+    directory: &Path,
+    base_branch: Option<&str>,
+    push_remote: Option<&str>,
+) -> anyhow::Result<Repo> {
+    let mut repo = Repo::new(directory)?;
+    if let Some(base_branch) = base_branch {
+        repo = repo.with_original_branch(base_branch);
+    }
+    if let Some(push_remote) = push_remote {
+        repo = repo.with_push_remote(push_remote);
+    }
+    Ok(repo)
 }
 
 async fn open_or_update_release_pr(
@@ -87,9 +329,8 @@ async fn open_or_update_release_pr(
     packages_to_update: &PackagesUpdate,
     git_client: &GitClient,
     repo: &Repo,
-    draft: bool,
-    pr_labels: Vec<String>,
-) -> anyhow::Result<()> {
+    input: &ReleasePrRequest,
+) -> anyhow::Result<GitPr> {
     let mut opened_release_prs = git_client
         .opened_prs(BRANCH_PREFIX)
         .await
@@ -104,6 +345,7 @@ async fn open_or_update_release_pr(
             .await
             .context("cannot get opened release-plz prs")?;
     }
+    let opened_release_prs = select_release_prs(opened_release_prs, &input.marker_label);
 
     // Close all release-plz prs, except one.
     let old_release_prs = opened_release_prs.iter().skip(1);
@@ -121,11 +363,15 @@ async fn open_or_update_release_pr(
             repo.original_branch(),
             packages_to_update,
             project_contains_multiple_pub_packages,
+            input.semver_check_report,
         )
-        .mark_as_draft(draft)
-        .with_labels(pr_labels)
+        .mark_as_draft(input.draft)
+        .with_labels(input.labels.clone())
+        .with_marker_label(Some(input.marker_label.clone()))
+        .with_commit_marker(input.update_request.release_commit_marker())
+        .with_closed_issues(input.update_request.repo_url(), &input.closed_issues)
     };
-    match opened_release_prs.first() {
+    let release_pr = match opened_release_prs.first() {
         Some(opened_pr) => {
             let pr_commits = git_client
                 .pr_commits(opened_pr.number)
@@ -135,15 +381,34 @@ async fn open_or_update_release_pr(
             if pr_contributors.is_empty() {
                 // There are no contributors, so we can force-push
                 // in this PR, because we don't care about the git history.
-                let update_outcome =
-                    update_pr(git_client, opened_pr, pr_commits.len(), repo, &new_pr).await;
-                if let Err(e) = update_outcome {
-                    tracing::error!("cannot update release pr {}: {:?}. I'm closing the old release pr and opening a new one", opened_pr.number, e);
-                    git_client
-                        .close_pr(opened_pr.number)
-                        .await
-                        .context("cannot close old release-plz prs")?;
-                    create_pr(git_client, repo, &new_pr).await?
+                let update_outcome = update_pr(
+                    git_client,
+                    opened_pr,
+                    pr_commits.len(),
+                    repo,
+                    &new_pr,
+                    input.commit_lockfile,
+                )
+                .await;
+                match update_outcome {
+                    Ok(()) => {
+                        if !opened_pr.has_label(&input.marker_label) {
+                            // E.g. the PR was opened before the marker label was configured.
+                            git_client
+                                .add_labels(&new_pr, opened_pr.number)
+                                .await
+                                .context("Failed to add labels")?;
+                        }
+                        opened_pr.clone()
+                    }
+                    Err(e) => {
+                        tracing::error!("cannot update release pr {}: {:?}. I'm closing the old release pr and opening a new one", opened_pr.number, e);
+                        git_client
+                            .close_pr(opened_pr.number)
+                            .await
+                            .context("cannot close old release-plz prs")?;
+                        create_pr(git_client, repo, &new_pr, input.commit_lockfile).await?
+                    }
                 }
             } else {
                 // There's a contributor, so we don't want to force-push in this PR.
@@ -155,18 +420,38 @@ async fn open_or_update_release_pr(
                     .close_pr(opened_pr.number)
                     .await
                     .context("cannot close old release-plz prs")?;
-                create_pr(git_client, repo, &new_pr).await?
+                create_pr(git_client, repo, &new_pr, input.commit_lockfile).await?
             }
         }
-        None => create_pr(git_client, repo, &new_pr).await?,
+        None => create_pr(git_client, repo, &new_pr, input.commit_lockfile).await?,
+    };
+    Ok(release_pr)
+}
+
+/// Release PRs among the `opened_prs` of the release-plz branches.
+/// If some of them have the `marker_label`, the others are ignored, e.g. because
+/// they belong to another release-plz configuration of the same repository.
+/// Otherwise, e.g. if the PRs were opened before the marker label existed,
+/// or the git backend doesn't return labels, all of them are release PRs.
+fn select_release_prs(opened_prs: Vec<GitPr>, marker_label: &str) -> Vec<GitPr> {
+    if opened_prs.iter().any(|pr| pr.has_label(marker_label)) {
+        opened_prs
+            .into_iter()
+            .filter(|pr| pr.has_label(marker_label))
+            .collect()
+    } else {
+        opened_prs
     }
-    Ok(())
 }
 
-async fn create_pr(git_client: &GitClient, repo: &Repo, pr: &Pr) -> anyhow::Result<()> {
-    create_release_branch(repo, &pr.branch)?;
-    git_client.open_pr(pr).await.context("Failed to open PR")?;
-    Ok(())
+async fn create_pr(
+    git_client: &GitClient,
+    repo: &Repo,
+    pr: &Pr,
+    commit_lockfile: bool,
+) -> anyhow::Result<GitPr> {
+    create_release_branch(repo, &pr.branch, &pr.commit_message, commit_lockfile)?;
+    git_client.open_pr(pr).await.context("Failed to open PR")
 }
 
 async fn update_pr(
@@ -175,6 +460,7 @@ async fn update_pr(
     commits_number: usize,
     repository: &Repo,
     new_pr: &Pr,
+    commit_lockfile: bool,
 ) -> anyhow::Result<()> {
     // save local work
     repository.git(&["stash", "--include-untracked"])?;
@@ -187,7 +473,12 @@ async fn update_pr(
         e
     })?;
     repository.stash_pop()?;
-    force_push(opened_pr, repository)?;
+    force_push(
+        opened_pr,
+        repository,
+        &new_pr.commit_message,
+        commit_lockfile,
+    )?;
     let pr_edit = {
         let mut pr_edit = PrEdit::new();
         if opened_pr.title != new_pr.title {
@@ -232,22 +523,279 @@ fn reset_branch(pr: &GitPr, commits_number: usize, repository: &Repo) -> anyhow:
     Ok(())
 }
 
-fn force_push(pr: &GitPr, repository: &Repo) -> anyhow::Result<()> {
-    add_changes_and_commit(repository)?;
+fn force_push(
+    pr: &GitPr,
+    repository: &Repo,
+    commit_message: &str,
+    commit_lockfile: bool,
+) -> anyhow::Result<()> {
+    add_changes_and_commit(repository, commit_message, commit_lockfile)?;
     repository.force_push(pr.branch())?;
     Ok(())
 }
 
-fn create_release_branch(repository: &Repo, release_branch: &str) -> anyhow::Result<()> {
+fn create_release_branch(
+    repository: &Repo,
+    release_branch: &str,
+    commit_message: &str,
+    commit_lockfile: bool,
+) -> anyhow::Result<()> {
     repository.checkout_new_branch(release_branch)?;
-    add_changes_and_commit(repository)?;
+    add_changes_and_commit(repository, commit_message, commit_lockfile)?;
     repository.push(release_branch)?;
     Ok(())
 }
 
-fn add_changes_and_commit(repository: &Repo) -> anyhow::Result<()> {
-    let changes_expect_typechanges = repository.changes_except_typechanges()?;
-    repository.add(&changes_expect_typechanges)?;
-    repository.commit_signed("chore: release")?;
+fn add_changes_and_commit(
+    repository: &Repo,
+    commit_message: &str,
+    commit_lockfile: bool,
+) -> anyhow::Result<()> {
+    let changes = release_changes(repository, commit_lockfile)?;
+    repository.add(&changes)?;
+    repository.commit_signed(commit_message)?;
     Ok(())
 }
+
+/// Changed files to commit in the release commit, except typechanges.
+/// If `commit_lockfile` is `false`, the `Cargo.lock` of the workspace is left out.
+fn release_changes(repository: &Repo, commit_lockfile: bool) -> anyhow::Result<Vec<String>> {
+    let mut changes = repository.changes_except_typechanges()?;
+    if !commit_lockfile {
+        // `git status` shows the paths relative to the root of the repository.
+        let workspace_dir = repository.git(&["rev-parse", "--show-prefix"])?;
+        let lockfile = format!("{workspace_dir}Cargo.lock");
+        changes.retain(|file| *file != lockfile);
+    }
+    Ok(changes)
+}
+
+/// Run the `pre_hook` shell command in `dir`, so that the files it modifies
+/// are committed together with the updated packages.
+fn run_pre_hook(pre_hook: &str, dir: &Path) -> anyhow::Result<()> {
+    info!("running release PR pre hook `{pre_hook}`");
+    let mut command = if cfg!(windows) {
+        let mut command = Command::new("cmd");
+        command.arg("/C");
+        command
+    } else {
+        let mut command = Command::new("sh");
+        command.arg("-c");
+        command
+    };
+    let output = command
+        .arg(pre_hook)
+        .current_dir(dir)
+        .output()
+        .with_context(|| format!("cannot run release PR pre hook `{pre_hook}`"))?;
+    anyhow::ensure!(
+        output.status.success(),
+        "release PR pre hook `{pre_hook}` failed with {}: {}",
+        output.status,
+        String::from_utf8_lossy(&output.stderr).trim()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn pre_hook_changes_are_committed() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = Repo::init(&dir);
+        fs::write(dir.path().join("Cargo.toml"), "version = \"0.1.0\"").unwrap();
+        repo.add_all_and_commit("init").unwrap();
+
+        fs::write(dir.path().join("Cargo.toml"), "version = \"0.1.1\"").unwrap();
+        run_pre_hook("cp Cargo.toml generated.txt", dir.path()).unwrap();
+        add_changes_and_commit(&repo, "chore: release", true).unwrap();
+
+        assert!(repo.is_clean().is_ok());
+        assert_eq!(
+            repo.git(&["show", "--name-only", "--format=", "HEAD"])
+                .unwrap(),
+            "Cargo.toml\ngenerated.txt"
+        );
+        assert_eq!(
+            fs::read_to_string(dir.path().join("generated.txt")).unwrap(),
+            "version = \"0.1.1\""
+        );
+    }
+
+    #[test]
+    fn configured_base_branch_is_used_for_pr_with_detached_head() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = Repo::init(&dir);
+        repo.git(&["checkout", "--detach"]).unwrap();
+        let packages_to_update = PackagesUpdate::new(vec![(
+            fake_package::FakePackage::new("foo").into(),
+            crate::UpdateResult {
+                version: cargo_metadata::semver::Version::new(0, 2, 0),
+                changelog: None,
+                changelog_format: crate::ChangelogFormat::Markdown,
+                semver_check: crate::semver_check::SemverCheck::Compatible,
+            },
+        )]);
+
+        let repo = release_pr_repo(dir.path(), Some("release/1.x"), None).unwrap();
+        let pr = Pr::new(repo.original_branch(), &packages_to_update, false, true);
+        assert_eq!(pr.base_branch, "release/1.x");
+    }
+
+    #[test]
+    fn release_branch_is_pushed_to_configured_remote() {
+        let dir = tempfile::tempdir().unwrap();
+        let fork_dir = tempfile::tempdir().unwrap();
+        git_cmd::git_in_dir(fork_dir.path(), &["init", "--bare"]).unwrap();
+        let repo = Repo::init(&dir);
+        repo.git(&["remote", "add", "fork", fork_dir.path().to_str().unwrap()])
+            .unwrap();
+        fs::write(dir.path().join("CHANGELOG.md"), "# Changelog").unwrap();
+
+        let repo = release_pr_repo(dir.path(), None, Some("fork")).unwrap();
+        create_release_branch(&repo, "release-plz-test", "chore: release", true).unwrap();
+
+        let fork_branches = git_cmd::git_in_dir(fork_dir.path(), &["branch"]).unwrap();
+        assert_eq!(fork_branches, "release-plz-test");
+    }
+
+    #[test]
+    fn failing_pre_hook_is_an_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let error = run_pre_hook("echo oops >&2; exit 3", dir.path()).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "release PR pre hook `echo oops >&2; exit 3` failed with exit status: 3: oops"
+        );
+    }
+
+    /// Create an empty commit with the given committer date and tag it with `tag`.
+    fn tagged_commit(dir: &Path, date: &str, tag: &str) {
+        let output = Command::new("git")
+            .args(["commit", "--allow-empty", "-m", "chore: release"])
+            .env("GIT_COMMITTER_DATE", date)
+            .current_dir(dir)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        git_cmd::git_in_dir(dir, &["tag", tag]).unwrap();
+    }
+
+    #[test]
+    fn merged_prs_are_listed_since_the_oldest_last_tag_of_the_packages() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = Repo::init(&dir);
+        tagged_commit(dir.path(), "2024-01-01T10:00:00+00:00", "b-v0.1.0");
+        tagged_commit(dir.path(), "2024-02-01T10:00:00+00:00", "a-v0.1.0");
+        tagged_commit(dir.path(), "2024-03-01T10:00:00+00:00", "a-v0.2.0");
+        let request = UpdateRequest::new(fake_package::metadata::fake_metadata()).unwrap();
+        let packages: Vec<Package> = vec![
+            fake_package::FakePackage::new("a").into(),
+            fake_package::FakePackage::new("b").into(),
+        ];
+
+        let since = last_release_date(&repo, &request, &packages).unwrap();
+        assert_eq!(
+            since,
+            Some(DateTime::parse_from_rfc3339("2024-01-01T10:00:00+00:00").unwrap())
+        );
+    }
+
+    #[test]
+    fn merged_prs_are_not_limited_by_date_if_a_package_has_no_tag() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = Repo::init(&dir);
+        tagged_commit(dir.path(), "2024-02-01T10:00:00+00:00", "a-v0.1.0");
+        let request = UpdateRequest::new(fake_package::metadata::fake_metadata()).unwrap();
+        let packages: Vec<Package> = vec![
+            fake_package::FakePackage::new("a").into(),
+            fake_package::FakePackage::new("b").into(),
+        ];
+
+        assert_eq!(last_release_date(&repo, &request, &packages).unwrap(), None);
+    }
+
+    /// Repository with a committed `Cargo.toml` and `Cargo.lock`, both modified.
+    fn repo_with_modified_lockfile() -> (tempfile::TempDir, Repo) {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = Repo::init(&dir);
+        fs::write(dir.path().join("Cargo.toml"), "version = \"0.1.0\"").unwrap();
+        fs::write(dir.path().join("Cargo.lock"), "version = \"0.1.0\"").unwrap();
+        repo.add_all_and_commit("init").unwrap();
+        fs::write(dir.path().join("Cargo.toml"), "version = \"0.1.1\"").unwrap();
+        fs::write(dir.path().join("Cargo.lock"), "version = \"0.1.1\"").unwrap();
+        (dir, repo)
+    }
+
+    #[test]
+    fn lockfile_is_committed_by_default() {
+        let (_dir, repo) = repo_with_modified_lockfile();
+        add_changes_and_commit(&repo, "chore: release", true).unwrap();
+        assert_eq!(
+            repo.git(&["show", "--name-only", "--format=", "HEAD"])
+                .unwrap(),
+            "Cargo.lock\nCargo.toml"
+        );
+        assert!(repo.is_clean().is_ok());
+    }
+
+    #[test]
+    fn lockfile_is_not_committed_if_disabled() {
+        let (_dir, repo) = repo_with_modified_lockfile();
+        add_changes_and_commit(&repo, "chore: release", false).unwrap();
+        assert_eq!(
+            repo.git(&["show", "--name-only", "--format=", "HEAD"])
+                .unwrap(),
+            "Cargo.toml"
+        );
+        assert_eq!(repo.changes_except_typechanges().unwrap(), ["Cargo.lock"]);
+    }
+
+    #[test]
+    fn lockfile_changes_alone_are_not_released_if_disabled() {
+        let (dir, repo) = repo_with_modified_lockfile();
+        fs::write(dir.path().join("Cargo.toml"), "version = \"0.1.0\"").unwrap();
+        assert!(release_changes(&repo, false).unwrap().is_empty());
+        assert_eq!(release_changes(&repo, true).unwrap(), ["Cargo.lock"]);
+    }
+
+    fn opened_pr(number: u64, labels: &[&str]) -> GitPr {
+        let labels: Vec<serde_json::Value> = labels
+            .iter()
+            .map(|name| serde_json::json!({ "name": name }))
+            .collect();
+        serde_json::from_value(serde_json::json!({
+            "number": number,
+            "html_url": format!("https://github.com/owner/repo/pull/{number}"),
+            "head": { "ref": format!("release-plz-{number}"), "sha": "abc" },
+            "title": "chore: release",
+            "body": null,
+            "labels": labels,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn release_prs_are_detected_by_marker_label() {
+        let prs = vec![
+            opened_pr(1, &["release-plz-docs"]),
+            opened_pr(2, &["release", "release-plz-api"]),
+            opened_pr(3, &[]),
+        ];
+        let release_prs = select_release_prs(prs, "release-plz-api");
+        let numbers: Vec<u64> = release_prs.iter().map(|pr| pr.number).collect();
+        assert_eq!(numbers, [2]);
+    }
+
+    #[test]
+    fn release_prs_without_marker_label_are_detected_by_branch() {
+        let prs = vec![opened_pr(1, &[]), opened_pr(2, &["release"])];
+        let release_prs = select_release_prs(prs, DEFAULT_PR_MARKER_LABEL);
+        let numbers: Vec<u64> = release_prs.iter().map(|pr| pr.number).collect();
+        assert_eq!(numbers, [1, 2]);
+    }
+}
@@ -1,10 +1,14 @@
+use crate::changelog_parser;
+use crate::line_ending::write_changelog;
 use crate::semver_check::SemverCheck;
+use crate::version_file::update_version_file;
 use crate::CARGO_TOML;
 use crate::{tmp_repo::TempRepo, PackagePath, UpdateRequest, UpdateResult};
 use anyhow::Context;
 use cargo_metadata::{semver::Version, Package};
 use cargo_utils::upgrade_requirement;
 use cargo_utils::LocalManifest;
+use chrono::{NaiveDate, Utc};
 use git_cmd::Repo;
 use std::{fs, path::Path};
 use tracing::{info, warn};
@@ -53,6 +57,19 @@ impl PackagesUpdate {
         format!("{updates}\n{breaking_changes}")
     }
 
+    /// Summary of the updates for the body of the release PR.
+    /// If `semver_check_report` is true, the breaking changes found by cargo-semver-checks
+    /// are reported in a collapsible section for each package.
+    pub fn pr_summary(&self, semver_check_report: bool) -> String {
+        let updates = self.updates_summary();
+        let report = if semver_check_report {
+            self.semver_check_report()
+        } else {
+            "".to_string()
+        };
+        format!("{updates}\n{report}")
+    }
+
     fn updates_summary(&self) -> String {
         self.updates
             .iter()
@@ -122,6 +139,22 @@ impl PackagesUpdate {
             })
             .collect()
     }
+
+    fn semver_check_report(&self) -> String {
+        self.updates
+            .iter()
+            .map(|(package, update)| match &update.semver_check {
+                SemverCheck::Incompatible(incompatibilities) => {
+                    format!(
+                        "\n<details><summary><b>⚠️ `{}` breaking changes</b></summary>\n\n```\n{}\n```\n</details>\n",
+                        package.name,
+                        incompatibilities.trim()
+                    )
+                }
+                SemverCheck::Compatible | SemverCheck::Skipped => "".to_string(),
+            })
+            .collect()
+    }
 }
 
 /// Update a local rust project
@@ -136,9 +169,16 @@ pub fn update(input: &UpdateRequest) -> anyhow::Result<(PackagesUpdate, TempRepo
     let all_packages: Vec<Package> = cargo_utils::workspace_members(&local_metadata)?.collect();
     update_manifests(&packages_to_update, local_manifest_path, &all_packages)?;
     update_changelogs(input, &packages_to_update)?;
+    update_version_files(input, &packages_to_update)?;
+    update_workspace_changelog(input, &packages_to_update)?;
     if !packages_to_update.updates.is_empty() {
         let local_manifest_dir = input.local_manifest_dir()?;
-        update_cargo_lock(local_manifest_dir, input.should_update_dependencies())?;
+        if let Some(args) = cargo_update_args(
+            input.should_update_lockfile(),
+            input.should_update_dependencies(),
+        ) {
+            update_cargo_lock(local_manifest_dir, &args)?;
+        }
 
         let there_are_commits_to_push = Repo::new(local_manifest_dir)?.is_clean().is_err();
         if !there_are_commits_to_push {
@@ -149,6 +189,54 @@ pub fn update(input: &UpdateRequest) -> anyhow::Result<(PackagesUpdate, TempRepo
     Ok((packages_to_update, repository))
 }
 
+/// Unified diff of the changelogs that [`update`] would write, compared to the current
+/// changelogs. Nothing is written to the local project.
+#[instrument(skip_all)]
+pub fn changelog_diff(input: &UpdateRequest) -> anyhow::Result<String> {
+    let (packages_to_update, _repository) =
+        crate::next_versions(input).context("failed to determine next versions")?;
+    let project_dir = input.local_manifest_dir()?;
+    let mut diff = String::new();
+    for (package, update) in &packages_to_update.updates {
+        if let Some(changelog) = update.changelog.as_ref() {
+            let changelog_path = input.changelog_path(package);
+            let old_changelog = fs::read_to_string(&changelog_path).ok();
+            let file_name = changelog_path
+                .strip_prefix(project_dir)
+                .unwrap_or(&changelog_path)
+                .display()
+                .to_string();
+            diff.push_str(&unified_diff(
+                &file_name,
+                old_changelog.as_deref(),
+                changelog,
+            ));
+        }
+    }
+    Ok(diff)
+}
+
+/// Diff in the format of `git diff`, with 3 lines of context.
+/// If the file doesn't exist, i.e. `old` is `None`, it's compared to `/dev/null`.
+fn unified_diff(file_name: &str, old: Option<&str>, new: &str) -> String {
+    let old_lines: Vec<&str> = old.unwrap_or_default().lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+    let hunks: Vec<String> = difflib::unified_diff(&old_lines, &new_lines, "", "", "", "", 3)
+        .into_iter()
+        // Skip the file names, which are written below without the file dates.
+        .skip(2)
+        .map(|line| line.trim_end_matches('\n').to_string())
+        .collect();
+    if hunks.is_empty() {
+        return String::new();
+    }
+    let old_file = match old {
+        Some(_) => format!("a/{file_name}"),
+        None => "/dev/null".to_string(),
+    };
+    format!("--- {old_file}\n+++ b/{file_name}\n{}\n", hunks.join("\n"))
+}
+
 fn update_manifests(
     packages_to_update: &PackagesUpdate,
     local_manifest_path: &Path,
@@ -203,20 +291,142 @@ fn update_changelogs(
     for (package, update) in &local_packages.updates {
         if let Some(changelog) = update.changelog.as_ref() {
             let changelog_path = update_request.changelog_path(package);
-            fs::write(&changelog_path, changelog)
-                .with_context(|| format!("cannot write changelog to {:?}", &changelog_path))?;
+            write_changelog(
+                &changelog_path,
+                changelog,
+                update_request.changelog_line_ending(),
+            )?;
         }
     }
     Ok(())
 }
 
-#[instrument(skip_all)]
-fn update_cargo_lock(root: &Path, update_all_dependencies: bool) -> anyhow::Result<()> {
+/// Write the new versions in the `version_files` of the updated packages.
+fn update_version_files(
+    update_request: &UpdateRequest,
+    local_packages: &PackagesUpdate,
+) -> anyhow::Result<()> {
+    for (package, update) in &local_packages.updates {
+        let package_path = package.package_path()?;
+        let config = update_request.get_package_config(&package.name);
+        for version_file in &config.generic.version_files {
+            update_version_file(
+                package_path,
+                version_file,
+                &package.version,
+                &update.version,
+            )
+            .with_context(|| format!("cannot update the version of package {}", package.name))?;
+        }
+    }
+    Ok(())
+}
+
+/// Add the changes of the updated packages to the workspace changelog, if configured.
+fn update_workspace_changelog(
+    update_request: &UpdateRequest,
+    local_packages: &PackagesUpdate,
+) -> anyhow::Result<()> {
+    let Some(changelog_path) = update_request.workspace_changelog_path() else {
+        return Ok(());
+    };
+    let release_date = update_request
+        .release_date()
+        .unwrap_or_else(|| Utc::now().date_naive());
+    let Some(entry) = workspace_changelog_entry(&local_packages.updates, release_date) else {
+        return Ok(());
+    };
+    let old_changelog = fs::read_to_string(&changelog_path).ok();
+    let changelog = prepend_workspace_changelog(old_changelog.as_deref(), &entry);
+    write_changelog(
+        &changelog_path,
+        &changelog,
+        update_request.changelog_line_ending(),
+    )
+}
+
+/// Entry of the workspace changelog, containing a section for each bumped package.
+/// Packages are sorted by name.
+/// Returns `None` if no package was bumped.
+fn workspace_changelog_entry(
+    updates: &[(Package, UpdateResult)],
+    release_date: NaiveDate,
+) -> Option<String> {
+    let mut updates: Vec<&(Package, UpdateResult)> = updates
+        .iter()
+        .filter(|(package, update)| package.version != update.version)
+        .collect();
+    if updates.is_empty() {
+        return None;
+    }
+    updates.sort_by(|(a, _), (b, _)| a.name.cmp(&b.name));
+    let mut entry = format!("## {release_date}\n");
+    for (package, update) in updates {
+        entry.push_str(&format!("\n### `{}` - {}\n", package.name, update.version));
+        let notes = match update.last_changes() {
+            Ok(Some(release)) => release.notes().trim().to_string(),
+            _ => String::new(),
+        };
+        if !notes.is_empty() {
+            // Nest the sections of the package changelog under the package heading.
+            let notes = notes
+                .lines()
+                .map(|line| match line.strip_prefix("### ") {
+                    Some(title) => format!("#### {title}"),
+                    None => line.to_string(),
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            entry.push_str(&format!("\n{notes}\n"));
+        }
+    }
+    Some(entry)
+}
+
+/// Add the `entry` to the workspace changelog, before the previous releases.
+fn prepend_workspace_changelog(old_changelog: Option<&str>, entry: &str) -> String {
+    let old_changelog = old_changelog.unwrap_or("# Changelog\n");
+    let mut changelog = String::new();
+    let mut is_prepended = false;
+    for line in old_changelog.split_inclusive('\n') {
+        if !is_prepended
+            && line.starts_with("## ")
+            && !changelog_parser::is_unreleased_heading(line)
+        {
+            changelog.push_str(entry);
+            changelog.push('\n');
+            is_prepended = true;
+        }
+        changelog.push_str(line);
+    }
+    if !is_prepended {
+        if !changelog.ends_with("\n\n") {
+            changelog.push('\n');
+        }
+        changelog.push_str(entry);
+    }
+    changelog
+}
+
+/// Arguments of the `cargo` command that updates the Cargo.lock file.
+/// Returns `None` if the Cargo.lock file must not be updated.
+fn cargo_update_args(
+    update_lockfile: bool,
+    update_all_dependencies: bool,
+) -> Option<Vec<&'static str>> {
+    if !update_lockfile {
+        return None;
+    }
     let mut args = vec!["update"];
     if !update_all_dependencies {
         args.push("--workspace")
     }
-    crate::cargo::run_cargo(root, &args)
+    Some(args)
+}
+
+#[instrument(skip_all)]
+fn update_cargo_lock(root: &Path, args: &[&str]) -> anyhow::Result<()> {
+    crate::cargo::run_cargo(root, args)
         .context("error while running cargo to update the Cargo.lock file")?;
     Ok(())
 }
@@ -297,6 +507,122 @@ fn update_dependencies(
 mod tests {
     use super::*;
 
+    #[test]
+    fn new_changelog_is_diffed_with_dev_null() {
+        let diff = unified_diff("CHANGELOG.md", None, "# Changelog\n\n## [0.1.0]\n");
+        expect_test::expect![[r#"
+            --- /dev/null
+            +++ b/CHANGELOG.md
+            @@ -0,0 +1,3 @@
+            +# Changelog
+            +
+            +## [0.1.0]
+        "#]]
+        .assert_eq(&diff);
+    }
+
+    #[test]
+    fn existing_changelog_is_diffed_with_the_new_release() {
+        let old = "# Changelog\n\nAll notable changes to this project will be documented in this file.\n\n## [Unreleased]\n\n## [0.1.0] - 2015-05-14\n\n### Added\n- first release\n";
+        let new = "# Changelog\n\nAll notable changes to this project will be documented in this file.\n\n## [Unreleased]\n\n## [0.2.0] - 2015-05-15\n\n### Fixed\n- fix bug\n\n## [0.1.0] - 2015-05-14\n\n### Added\n- first release\n";
+        let diff = unified_diff("crates/aaa/CHANGELOG.md", Some(old), new);
+        // The empty context lines are a single space.
+        let expected = "--- a/crates/aaa/CHANGELOG.md\n+++ b/crates/aaa/CHANGELOG.md\n@@ -4,6 +4,11 @@\n \n ## [Unreleased]\n \n+## [0.2.0] - 2015-05-15\n+\n+### Fixed\n+- fix bug\n+\n ## [0.1.0] - 2015-05-14\n \n ### Added\n";
+        assert_eq!(diff, expected);
+    }
+
+    #[test]
+    fn unchanged_changelog_has_no_diff() {
+        let changelog = "# Changelog\n\n## [0.1.0]\n";
+        assert_eq!(unified_diff("CHANGELOG.md", Some(changelog), changelog), "");
+    }
+
+    fn updated_package(name: &str, version: &str, notes: &str) -> (Package, UpdateResult) {
+        let changelog = format!(
+            "# Changelog\n\n## [Unreleased]\n\n## [{version}] - 2015-05-15\n\n{notes}\n## [0.1.0] - 2015-05-14\n\n### Added\n- first release\n"
+        );
+        let update = UpdateResult {
+            version: Version::parse(version).unwrap(),
+            changelog: Some(changelog),
+            changelog_format: crate::ChangelogFormat::Markdown,
+            semver_check: SemverCheck::Skipped,
+        };
+        (fake_package::FakePackage::new(name).into(), update)
+    }
+
+    #[test]
+    fn workspace_changelog_contains_each_bumped_package() {
+        let updates = [
+            updated_package("crate-b", "0.2.0", "### Added\n- feature b\n"),
+            updated_package("crate-a", "0.1.1", "### Fixed\n- fix a\n"),
+            // not bumped
+            updated_package("crate-c", "0.1.0", "### Fixed\n- fix c\n"),
+        ];
+        let release_date = NaiveDate::from_ymd_opt(2015, 5, 15).unwrap();
+        let entry = workspace_changelog_entry(&updates, release_date).unwrap();
+        let old_changelog = "# Changelog\n\n## 2015-05-14\n\n### `crate-a` - 0.1.0\n";
+        expect_test::expect![[r#"
+            # Changelog
+
+            ## 2015-05-15
+
+            ### `crate-a` - 0.1.1
+
+            #### Fixed
+            - fix a
+
+            ### `crate-b` - 0.2.0
+
+            #### Added
+            - feature b
+
+            ## 2015-05-14
+
+            ### `crate-a` - 0.1.0
+        "#]]
+        .assert_eq(&prepend_workspace_changelog(Some(old_changelog), &entry));
+    }
+
+    #[test]
+    fn workspace_changelog_is_created() {
+        let updates = [updated_package("crate-a", "0.1.1", "### Fixed\n- fix a\n")];
+        let release_date = NaiveDate::from_ymd_opt(2015, 5, 15).unwrap();
+        let entry = workspace_changelog_entry(&updates, release_date).unwrap();
+        expect_test::expect![[r#"
+            # Changelog
+
+            ## 2015-05-15
+
+            ### `crate-a` - 0.1.1
+
+            #### Fixed
+            - fix a
+        "#]]
+        .assert_eq(&prepend_workspace_changelog(None, &entry));
+    }
+
+    #[test]
+    fn workspace_changelog_is_not_updated_without_bumped_packages() {
+        let updates = [updated_package("crate-c", "0.1.0", "### Fixed\n- fix c\n")];
+        let release_date = NaiveDate::from_ymd_opt(2015, 5, 15).unwrap();
+        assert_eq!(workspace_changelog_entry(&updates, release_date), None);
+    }
+
+    #[test]
+    fn cargo_update_is_not_run_if_lockfile_update_is_disabled() {
+        assert_eq!(cargo_update_args(false, false), None);
+        assert_eq!(cargo_update_args(false, true), None);
+    }
+
+    #[test]
+    fn cargo_update_args_depend_on_dependencies_update() {
+        assert_eq!(
+            cargo_update_args(true, false),
+            Some(vec!["update", "--workspace"])
+        );
+        assert_eq!(cargo_update_args(true, true), Some(vec!["update"]));
+    }
+
     #[test]
     fn changelog_is_printed_correctly_in_workspace() {
         test_logs::init();
@@ -332,6 +658,7 @@ and this project adheres to [Semantic Versioning](https://semver.org/spec/v2.0.0
                 UpdateResult {
                     version: Version::parse("0.2.0").unwrap(),
                     changelog: Some(changelog.clone()),
+                    changelog_format: crate::ChangelogFormat::Markdown,
                     semver_check: SemverCheck::Compatible,
                 },
             ),
@@ -340,6 +667,7 @@ and this project adheres to [Semantic Versioning](https://semver.org/spec/v2.0.0
                 UpdateResult {
                     version: Version::parse("0.2.0").unwrap(),
                     changelog: Some(changelog),
+                    changelog_format: crate::ChangelogFormat::Markdown,
                     semver_check: SemverCheck::Compatible,
                 },
             ),
@@ -407,6 +735,7 @@ and this project adheres to [Semantic Versioning](https://semver.org/spec/v2.0.0
             UpdateResult {
                 version: Version::parse("0.2.0").unwrap(),
                 changelog: Some(changelog),
+                changelog_format: crate::ChangelogFormat::Markdown,
                 semver_check: SemverCheck::Compatible,
             },
         )]);
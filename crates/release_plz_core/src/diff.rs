@@ -1,3 +1,6 @@
+use std::collections::HashMap;
+
+use cargo_metadata::semver::Version;
 use git_cliff_core::commit::Commit;
 
 use crate::semver_check::SemverCheck;
@@ -14,6 +17,12 @@ pub(crate) struct Diff<'a> {
     pub is_version_published: bool,
     /// Semver incompatible changes.
     pub semver_check: SemverCheck,
+    /// Changelog groups of the commits, by commit id.
+    /// Only the commits matching `changelog_group_by_path` are present.
+    pub commit_groups: HashMap<String, Vec<String>>,
+    /// Whether no commit matches the `release_commits` pattern,
+    /// so the package must not be released.
+    pub release_skipped: bool,
 }
 
 impl<'a> Diff<'a> {
@@ -23,6 +32,8 @@ impl<'a> Diff<'a> {
             registry_package_exists,
             is_version_published: true,
             semver_check: SemverCheck::Skipped,
+            commit_groups: HashMap::new(),
+            release_skipped: false,
         }
     }
 
@@ -30,6 +41,18 @@ impl<'a> Diff<'a> {
         self.registry_package_exists && !self.commits.is_empty()
     }
 
+    /// Whether the package must be released with `next_version`, i.e. its version changed
+    /// or it isn't published yet, unless its release is skipped.
+    pub fn is_release_needed(&self, current_version: &Version, next_version: &Version) -> bool {
+        !self.release_skipped && (next_version != current_version || !self.registry_package_exists)
+    }
+
+    /// Don't release the package, because no commit matches the `release_commits` pattern.
+    pub fn skip_release(&mut self) {
+        self.commits.clear();
+        self.release_skipped = true;
+    }
+
     pub fn set_version_unpublished(&mut self) {
         self.is_version_published = false
     }
@@ -1,6 +1,9 @@
 //! Download packages from cargo registry, similar to the `git clone` behavior.
 
-use std::path::{Path, PathBuf};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
 
 use anyhow::{anyhow, Context};
 use cargo_metadata::Package;
@@ -17,6 +20,11 @@ pub struct PackageDownloader {
     directory: String,
     registry: Option<String>,
     cargo_cwd: Option<PathBuf>,
+    /// Versions of the packages in the local project. The key is the package name.
+    /// Used to detect if the latest version was yanked.
+    local_versions: BTreeMap<String, String>,
+    /// If true, download the latest version even if it was yanked.
+    allow_yanked: bool,
 }
 
 impl PackageDownloader {
@@ -29,6 +37,8 @@ impl PackageDownloader {
             directory: directory.into(),
             registry: None,
             cargo_cwd: None,
+            local_versions: BTreeMap::new(),
+            allow_yanked: false,
         }
     }
 
@@ -46,6 +56,20 @@ impl PackageDownloader {
         }
     }
 
+    pub fn with_local_versions(self, local_versions: BTreeMap<String, String>) -> Self {
+        Self {
+            local_versions,
+            ..self
+        }
+    }
+
+    pub fn with_allow_yanked(self, allow_yanked: bool) -> Self {
+        Self {
+            allow_yanked,
+            ..self
+        }
+    }
+
     #[instrument]
     pub fn download(&self) -> anyhow::Result<Vec<Package>> {
         info!("downloading packages from cargo registry");
@@ -56,11 +80,15 @@ impl PackageDownloader {
         let crates: Vec<Crate> = self
             .packages
             .iter()
-            .map(|package_name| Crate::new(package_name.to_string(), None))
+            .map(|package_name| {
+                Crate::new(package_name.to_string(), None)
+                    .with_local_version(self.local_versions.get(package_name).cloned())
+            })
             .collect();
         let mut cloner_builder = Cloner::builder()
             .with_directory(&self.directory)
-            .with_source(source);
+            .with_source(source)
+            .with_allow_yanked(self.allow_yanked);
         if let Some(cwd) = &self.cargo_cwd {
             cloner_builder = cloner_builder.with_cargo_cwd(cwd.clone());
         }
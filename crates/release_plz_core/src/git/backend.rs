@@ -2,8 +2,12 @@ use crate::git::{gitea_client::Gitea, gitlab_client::GitLab};
 use crate::{GitHub, GitReleaseInfo};
 
 use crate::pr::Pr;
+use std::collections::HashMap;
+use std::time::Duration;
+
 use anyhow::Context;
-use reqwest::header::HeaderMap;
+use chrono::{DateTime, FixedOffset};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
 use reqwest::Url;
 use reqwest_middleware::ClientBuilder;
 use reqwest_retry::{policies::ExponentialBackoff, RetryTransientMiddleware};
@@ -21,11 +25,57 @@ pub enum GitBackend {
 
 impl GitBackend {
     fn default_headers(&self) -> anyhow::Result<HeaderMap> {
-        match self {
+        let mut headers = match self {
             GitBackend::Github(g) => g.default_headers(),
             GitBackend::Gitea(g) => g.default_headers(),
             GitBackend::Gitlab(g) => g.default_headers(),
+        }?;
+        for (name, value) in &self.remote().custom_headers {
+            let name: HeaderName = name
+                .parse()
+                .with_context(|| format!("invalid header name `{name}`"))?;
+            let value: HeaderValue = value
+                .parse()
+                .with_context(|| format!("invalid value for header `{name}`"))?;
+            headers.insert(name, value);
         }
+        Ok(headers)
+    }
+
+    fn remote(&self) -> &Remote {
+        match self {
+            GitBackend::Github(g) => &g.remote,
+            GitBackend::Gitea(g) => &g.remote,
+            GitBackend::Gitlab(g) => &g.remote,
+        }
+    }
+
+    fn remote_mut(&mut self) -> &mut Remote {
+        match self {
+            GitBackend::Github(g) => &mut g.remote,
+            GitBackend::Gitea(g) => &mut g.remote,
+            GitBackend::Gitlab(g) => &mut g.remote,
+        }
+    }
+
+    /// Additional headers sent with every request to the git forge API.
+    /// E.g. to authenticate with a proxy in front of a self-hosted Gitea.
+    pub fn with_custom_headers(mut self, custom_headers: HashMap<String, String>) -> Self {
+        self.remote_mut().custom_headers = custom_headers;
+        self
+    }
+
+    /// Maximum number of times a request to the git forge API is retried
+    /// on server or network errors.
+    pub fn with_api_retries(mut self, retries: u32) -> Self {
+        self.remote_mut().api_retries = retries;
+        self
+    }
+
+    /// Timeout of each request to the git forge API.
+    pub fn with_api_timeout(mut self, timeout: Duration) -> Self {
+        self.remote_mut().api_timeout = Some(timeout);
+        self
     }
 }
 
@@ -49,8 +99,20 @@ pub struct Remote {
     pub repo: String,
     pub token: SecretString,
     pub base_url: Url,
+    /// Headers added to the default ones of the backend.
+    pub custom_headers: HashMap<String, String>,
+    /// Maximum number of retries of failed requests.
+    pub api_retries: u32,
+    /// Timeout of each request. If `None`, requests don't time out.
+    pub api_timeout: Option<Duration>,
 }
 
+/// Default number of retries of failed requests to the git forge API.
+pub const DEFAULT_API_RETRIES: u32 = 3;
+
+/// Maximum number of pages of closed Pull Requests listed when there's no date to stop at.
+const MAX_CLOSED_PRS_PAGES: i32 = 10;
+
 #[derive(Deserialize)]
 pub struct PrCommit {
     pub author: Option<Author>,
@@ -72,24 +134,62 @@ pub struct CreateReleaseOption<'a> {
     body: &'a str,
     name: &'a str,
     draft: &'a bool,
+    prerelease: &'a bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    make_latest: Option<&'a str>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Clone, Debug)]
 pub struct GitPr {
     pub number: u64,
     pub html_url: Url,
     pub head: Commit,
     pub title: String,
     pub body: Option<String>,
+    /// Labels of the PR. Empty if the git backend doesn't return them.
+    #[serde(default)]
+    pub labels: Vec<Label>,
+}
+
+#[derive(Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Label {
+    pub name: String,
 }
 
 impl GitPr {
     pub fn branch(&self) -> &str {
         self.head.ref_field.as_str()
     }
+
+    pub fn has_label(&self, label: &str) -> bool {
+        self.labels.iter().any(|l| l.name == label)
+    }
 }
 
+/// Pull Request returned when listing the closed Pull Requests.
 #[derive(Deserialize)]
+struct ClosedPr {
+    number: u64,
+    title: String,
+    merge_commit_sha: Option<String>,
+    merged_at: Option<String>,
+    updated_at: String,
+    user: Option<Author>,
+}
+
+/// Pull Request merged into the repository.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MergedPr {
+    pub number: u64,
+    pub title: String,
+    /// Commit created by merging the Pull Request.
+    /// In squash-merge repositories, it's the commit containing all the changes of the PR.
+    pub merge_commit_sha: String,
+    /// Username of the author of the Pull Request.
+    pub author: Option<String>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
 pub struct Commit {
     #[serde(rename = "ref")]
     pub ref_field: String,
@@ -135,13 +235,17 @@ impl GitClient {
     pub fn new(backend: GitBackend) -> anyhow::Result<Self> {
         let client = {
             let headers = backend.default_headers()?;
-            let reqwest_client = reqwest::Client::builder()
+            let remote = backend.remote();
+            let mut client_builder = reqwest::Client::builder()
                 .user_agent("release-plz")
-                .default_headers(headers)
-                .build()
-                .context("can't build Git client")?;
+                .default_headers(headers);
+            if let Some(timeout) = remote.api_timeout {
+                client_builder = client_builder.timeout(timeout);
+            }
+            let reqwest_client = client_builder.build().context("can't build Git client")?;
 
-            let retry_policy = ExponentialBackoff::builder().build_with_max_retries(3);
+            let retry_policy =
+                ExponentialBackoff::builder().build_with_max_retries(remote.api_retries);
             ClientBuilder::new(reqwest_client)
                 // Retry failed requests.
                 .with(RetryTransientMiddleware::new_with_policy(retry_policy))
@@ -187,6 +291,10 @@ impl GitClient {
             body: &release_info.release_body,
             name: &release_info.git_tag,
             draft: &release_info.draft,
+            prerelease: &release_info.pre_release,
+            make_latest: release_info
+                .latest
+                .map(|latest| if latest { "true" } else { "false" }),
         };
         self.client
             .post(format!("{}/releases", self.repo_url()))
@@ -197,6 +305,53 @@ impl GitClient {
         Ok(())
     }
 
+    /// Check if a GitHub/Gitea/GitLab release exists for the given tag.
+    pub async fn release_exists(&self, git_tag: &str) -> anyhow::Result<bool> {
+        let url = match self.backend {
+            BackendType::Github | BackendType::Gitea => {
+                format!("{}/releases/tags/{git_tag}", self.repo_url())
+            }
+            BackendType::Gitlab => format!(
+                "{}/projects/{}%2F{}/releases/{git_tag}",
+                self.remote.base_url, self.remote.owner, self.remote.repo
+            ),
+        };
+        let response = self
+            .client
+            .get(url)
+            .send()
+            .await
+            .with_context(|| format!("cannot retrieve release of tag {git_tag}"))?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(false);
+        }
+        response.error_for_status()?;
+        Ok(true)
+    }
+
+    /// Check if the git forge knows the given tag, e.g. because its push propagated.
+    pub async fn tag_exists(&self, git_tag: &str) -> anyhow::Result<bool> {
+        let url = match self.backend {
+            BackendType::Github => format!("{}/git/ref/tags/{git_tag}", self.repo_url()),
+            BackendType::Gitea => format!("{}/tags/{git_tag}", self.repo_url()),
+            BackendType::Gitlab => format!(
+                "{}/projects/{}%2F{}/repository/tags/{git_tag}",
+                self.remote.base_url, self.remote.owner, self.remote.repo
+            ),
+        };
+        let response = self
+            .client
+            .get(url)
+            .send()
+            .await
+            .with_context(|| format!("cannot retrieve tag {git_tag}"))?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(false);
+        }
+        response.error_for_status()?;
+        Ok(true)
+    }
+
     pub async fn create_gitlab_release(&self, release_info: &GitReleaseInfo) -> anyhow::Result<()> {
         #[derive(Serialize)]
         pub struct GitlabReleaseOption<'a> {
@@ -299,7 +454,7 @@ impl GitClient {
     }
 
     #[instrument(skip(self, pr))]
-    pub async fn open_pr(&self, pr: &Pr) -> anyhow::Result<()> {
+    pub async fn open_pr(&self, pr: &Pr) -> anyhow::Result<GitPr> {
         debug!("Opening PR in {}/{}", self.remote.owner, self.remote.repo);
         let git_pr: GitPr = self
             .client
@@ -322,29 +477,114 @@ impl GitClient {
         self.add_labels(pr, git_pr.number)
             .await
             .context("Failed to add labels")?;
-        Ok(())
+        Ok(git_pr)
     }
 
+    /// Add the marker label and the labels of `pr` to the PR `pr_number`.
+    /// Only GitHub is supported.
     #[instrument(skip(self, pr))]
-    async fn add_labels(&self, pr: &Pr, pr_number: u64) -> anyhow::Result<()> {
-        if pr.labels.is_empty() {
+    pub(crate) async fn add_labels(&self, pr: &Pr, pr_number: u64) -> anyhow::Result<()> {
+        if self.backend != BackendType::Github {
+            if !pr.labels.is_empty() {
+                warn!("PR labels are only supported on Github");
+            }
             return Ok(());
         }
-        if self.backend != BackendType::Github {
-            warn!("PR labels are only supported on Github");
+        let labels = pr.all_labels();
+        if labels.is_empty() {
             return Ok(());
         }
         self.client
             .post(format!("{}/{}/labels", self.issues_url(), pr_number))
-            .json(&json!({
-                "labels": pr.labels
-            }))
+            .json(&json!({ "labels": labels }))
             .send()
             .await?
             .error_for_status()?;
         Ok(())
     }
 
+    /// Pull Requests merged after `since`, e.g. the date of the last tag.
+    /// If `since` is `None`, e.g. because there's no tag yet, only the Pull Requests of the
+    /// first [`MAX_CLOSED_PRS_PAGES`] pages of closed Pull Requests are listed.
+    /// Only GitHub and Gitea are supported.
+    pub async fn merged_prs(
+        &self,
+        since: Option<DateTime<FixedOffset>>,
+    ) -> anyhow::Result<Vec<MergedPr>> {
+        anyhow::ensure!(
+            self.backend != BackendType::Gitlab,
+            "listing the merged Pull Requests isn't supported on GitLab"
+        );
+        let parse_date = |date: &str| {
+            DateTime::parse_from_rfc3339(date)
+                .with_context(|| format!("cannot parse date `{date}` of Pull Request"))
+        };
+        let mut page = 1;
+        let page_size = 30;
+        let mut merged_prs = vec![];
+        loop {
+            let prs = self
+                .closed_prs_page(page, page_size)
+                .await
+                .context("failed to retrieve closed PRs")?;
+            let prs_len = prs.len();
+            let mut older_than_since = false;
+            for pr in prs {
+                if let Some(since) = since {
+                    // PRs are sorted from the most recently updated, and a PR can't be merged
+                    // after its last update, so the next PRs were merged before `since`, too.
+                    if parse_date(&pr.updated_at)? < since {
+                        older_than_since = true;
+                        break;
+                    }
+                }
+                let (Some(merged_at), Some(merge_commit_sha)) = (pr.merged_at, pr.merge_commit_sha)
+                else {
+                    continue;
+                };
+                let merged_after_since = match since {
+                    Some(since) => parse_date(&merged_at)? >= since,
+                    None => true,
+                };
+                if merged_after_since {
+                    merged_prs.push(MergedPr {
+                        number: pr.number,
+                        title: pr.title,
+                        merge_commit_sha,
+                        author: pr.user.map(|user| user.login),
+                    });
+                }
+            }
+            if older_than_since
+                || prs_len < page_size
+                || (since.is_none() && page >= MAX_CLOSED_PRS_PAGES)
+            {
+                break;
+            }
+            page += 1;
+        }
+        Ok(merged_prs)
+    }
+
+    async fn closed_prs_page(&self, page: i32, page_size: usize) -> anyhow::Result<Vec<ClosedPr>> {
+        let sort: &[(&str, &str)] = match self.backend {
+            BackendType::Gitea => &[("sort", "recentupdate")],
+            _ => &[("sort", "updated"), ("direction", "desc")],
+        };
+        self.client
+            .get(self.pulls_url())
+            .query(&[("state", "closed")])
+            .query(sort)
+            .query(&[("page", page)])
+            .query(&[(self.per_page(), page_size)])
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await
+            .context("failed to parse pr")
+    }
+
     pub async fn pr_commits(&self, pr_number: u64) -> anyhow::Result<Vec<PrCommit>> {
         self.client
             .get(format!("{}/{}/commits", self.pulls_url(), pr_number))
@@ -398,4 +638,286 @@ mod tests {
         let contributors = contributors_from_commits(&commits);
         assert_eq!(contributors, vec!["marco"]);
     }
+
+    #[test]
+    fn custom_headers_are_added_to_default_headers() {
+        let github = GitHub::new(
+            "MarcoIeni".to_string(),
+            "release-plz".to_string(),
+            SecretString::from("token".to_string()),
+        );
+        let backend = GitBackend::Github(github).with_custom_headers(HashMap::from([(
+            "X-Proxy-Auth".to_string(),
+            "secret".to_string(),
+        )]));
+        let headers = backend.default_headers().unwrap();
+        assert_eq!(headers["x-proxy-auth"], "secret");
+        assert!(headers.contains_key(reqwest::header::AUTHORIZATION));
+    }
+
+    fn github_release_info() -> GitReleaseInfo {
+        GitReleaseInfo {
+            git_tag: "v1.0.0".to_string(),
+            release_body: String::new(),
+            draft: false,
+            pre_release: false,
+            latest: None,
+        }
+    }
+
+    fn github_backend(base_url: &str) -> GitBackend {
+        let github = GitHub::new(
+            "MarcoIeni".to_string(),
+            "release-plz".to_string(),
+            SecretString::from("token".to_string()),
+        )
+        .with_base_url(base_url.parse().unwrap());
+        GitBackend::Github(github)
+    }
+
+    #[tokio::test]
+    async fn failed_requests_are_retried_the_configured_number_of_times() {
+        let server = wiremock::MockServer::start().await;
+        wiremock::Mock::given(wiremock::matchers::method("POST"))
+            .respond_with(wiremock::ResponseTemplate::new(500))
+            // first attempt + 1 retry
+            .expect(2)
+            .mount(&server)
+            .await;
+        let backend = github_backend(&server.uri()).with_api_retries(1);
+        let client = GitClient::new(backend).unwrap();
+        let result = client.create_release(&github_release_info()).await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn existing_release_is_detected() {
+        let server = wiremock::MockServer::start().await;
+        wiremock::Mock::given(wiremock::matchers::method("GET"))
+            .and(wiremock::matchers::path(
+                "/repos/MarcoIeni/release-plz/releases/tags/v1.0.0",
+            ))
+            .respond_with(wiremock::ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+        let client = GitClient::new(github_backend(&server.uri())).unwrap();
+        assert!(client.release_exists("v1.0.0").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn missing_release_is_detected() {
+        let server = wiremock::MockServer::start().await;
+        wiremock::Mock::given(wiremock::matchers::method("GET"))
+            .and(wiremock::matchers::path(
+                "/repos/MarcoIeni/release-plz/releases/tags/v1.0.0",
+            ))
+            .respond_with(wiremock::ResponseTemplate::new(404))
+            .mount(&server)
+            .await;
+        let client = GitClient::new(github_backend(&server.uri())).unwrap();
+        assert!(!client.release_exists("v1.0.0").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn client_errors_are_not_retried() {
+        let server = wiremock::MockServer::start().await;
+        wiremock::Mock::given(wiremock::matchers::method("POST"))
+            .respond_with(wiremock::ResponseTemplate::new(404))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let backend = github_backend(&server.uri()).with_api_retries(3);
+        let client = GitClient::new(backend).unwrap();
+        let result = client.create_release(&github_release_info()).await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn requests_time_out_after_the_configured_timeout() {
+        let server = wiremock::MockServer::start().await;
+        wiremock::Mock::given(wiremock::matchers::method("POST"))
+            .respond_with(wiremock::ResponseTemplate::new(201).set_delay(Duration::from_secs(5)))
+            .mount(&server)
+            .await;
+        let backend = github_backend(&server.uri())
+            .with_api_retries(0)
+            .with_api_timeout(Duration::from_millis(100));
+        let client = GitClient::new(backend).unwrap();
+        let start = std::time::Instant::now();
+        let result = client.create_release(&github_release_info()).await;
+        assert!(result.is_err());
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn invalid_custom_header_name_is_rejected() {
+        let github = GitHub::new(
+            "MarcoIeni".to_string(),
+            "release-plz".to_string(),
+            SecretString::from("token".to_string()),
+        );
+        let backend = GitBackend::Github(github).with_custom_headers(HashMap::from([(
+            "invalid header".to_string(),
+            "value".to_string(),
+        )]));
+        let err = backend.default_headers().unwrap_err();
+        assert_eq!(err.to_string(), "invalid header name `invalid header`");
+    }
+
+    #[tokio::test]
+    async fn only_prs_merged_since_the_given_date_are_listed() {
+        let server = wiremock::MockServer::start().await;
+        let prs = serde_json::json!([
+            {
+                "number": 3,
+                "title": "feat: add api",
+                "merge_commit_sha": "ccc",
+                "merged_at": "2024-03-01T10:00:00Z",
+                "updated_at": "2024-03-01T10:00:00Z"
+            },
+            {
+                "number": 2,
+                "title": "closed without merging",
+                "merge_commit_sha": null,
+                "merged_at": null,
+                "updated_at": "2024-02-20T10:00:00Z"
+            },
+            {
+                "number": 1,
+                "title": "fix: merged before the tag",
+                "merge_commit_sha": "aaa",
+                "merged_at": "2024-01-01T10:00:00Z",
+                "updated_at": "2024-01-01T10:00:00Z"
+            }
+        ]);
+        wiremock::Mock::given(wiremock::matchers::method("GET"))
+            .and(wiremock::matchers::path(
+                "/repos/MarcoIeni/release-plz/pulls",
+            ))
+            .and(wiremock::matchers::query_param("state", "closed"))
+            .respond_with(wiremock::ResponseTemplate::new(200).set_body_json(prs))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let client = GitClient::new(github_backend(&server.uri())).unwrap();
+        let since = DateTime::parse_from_rfc3339("2024-02-01T00:00:00+00:00").unwrap();
+        let merged_prs = client.merged_prs(Some(since)).await.unwrap();
+        assert_eq!(
+            merged_prs,
+            vec![MergedPr {
+                number: 3,
+                title: "feat: add api".to_string(),
+                merge_commit_sha: "ccc".to_string(),
+                author: None,
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn closed_prs_pages_are_capped_without_since() {
+        let server = wiremock::MockServer::start().await;
+        let prs: Vec<_> = (1..=30)
+            .map(|number| {
+                serde_json::json!({
+                    "number": number,
+                    "title": "feat: add api",
+                    "merge_commit_sha": format!("sha{number}"),
+                    "merged_at": "2020-01-01T10:00:00Z",
+                    "updated_at": "2020-01-01T10:00:00Z"
+                })
+            })
+            .collect();
+        wiremock::Mock::given(wiremock::matchers::method("GET"))
+            .and(wiremock::matchers::query_param("state", "closed"))
+            .respond_with(wiremock::ResponseTemplate::new(200).set_body_json(prs))
+            .expect(MAX_CLOSED_PRS_PAGES as u64)
+            .mount(&server)
+            .await;
+        let client = GitClient::new(github_backend(&server.uri())).unwrap();
+        let merged_prs = client.merged_prs(None).await.unwrap();
+        assert_eq!(merged_prs.len(), 30 * MAX_CLOSED_PRS_PAGES as usize);
+    }
+
+    #[tokio::test]
+    async fn author_of_merged_pr_is_listed() {
+        let server = wiremock::MockServer::start().await;
+        let prs = serde_json::json!([{
+            "number": 3,
+            "title": "feat: add api",
+            "merge_commit_sha": "ccc",
+            "merged_at": "2024-03-01T10:00:00Z",
+            "updated_at": "2024-03-01T10:00:00Z",
+            "user": { "login": "octocat" }
+        }]);
+        wiremock::Mock::given(wiremock::matchers::method("GET"))
+            .and(wiremock::matchers::query_param("state", "closed"))
+            .respond_with(wiremock::ResponseTemplate::new(200).set_body_json(prs))
+            .mount(&server)
+            .await;
+        let client = GitClient::new(github_backend(&server.uri())).unwrap();
+        let since = DateTime::parse_from_rfc3339("2024-02-01T00:00:00+00:00").unwrap();
+        let merged_prs = client.merged_prs(Some(since)).await.unwrap();
+        assert_eq!(merged_prs[0].author.as_deref(), Some("octocat"));
+    }
+
+    fn release_pr(labels: &[&str]) -> Pr {
+        Pr {
+            base_branch: "main".to_string(),
+            branch: "release-plz-2024-01-01".to_string(),
+            title: "chore: release".to_string(),
+            body: "body".to_string(),
+            draft: false,
+            labels: labels.iter().map(|l| l.to_string()).collect(),
+            marker_label: Some("release-plz".to_string()),
+            commit_message: "chore: release".to_string(),
+        }
+    }
+
+    async fn mock_labels(server: &wiremock::MockServer, labels: serde_json::Value) {
+        wiremock::Mock::given(wiremock::matchers::method("POST"))
+            .and(wiremock::matchers::path(
+                "/repos/MarcoIeni/release-plz/issues/7/labels",
+            ))
+            .and(wiremock::matchers::body_json(
+                serde_json::json!({ "labels": labels }),
+            ))
+            .respond_with(wiremock::ResponseTemplate::new(200))
+            .expect(1)
+            .mount(server)
+            .await;
+    }
+
+    #[tokio::test]
+    async fn marker_label_is_always_applied_to_release_pr() {
+        let server = wiremock::MockServer::start().await;
+        wiremock::Mock::given(wiremock::matchers::method("POST"))
+            .and(wiremock::matchers::path(
+                "/repos/MarcoIeni/release-plz/pulls",
+            ))
+            .respond_with(
+                wiremock::ResponseTemplate::new(201).set_body_json(serde_json::json!({
+                    "number": 7,
+                    "html_url": "https://github.com/MarcoIeni/release-plz/pull/7",
+                    "head": { "ref": "release-plz-2024-01-01", "sha": "abc" },
+                    "title": "chore: release",
+                    "body": "body",
+                })),
+            )
+            .mount(&server)
+            .await;
+        mock_labels(&server, serde_json::json!(["release-plz"])).await;
+        let client = GitClient::new(github_backend(&server.uri())).unwrap();
+        client.open_pr(&release_pr(&[])).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn marker_label_is_applied_before_user_labels() {
+        let server = wiremock::MockServer::start().await;
+        mock_labels(&server, serde_json::json!(["release-plz", "release"])).await;
+        let client = GitClient::new(github_backend(&server.uri())).unwrap();
+        client
+            .add_labels(&release_pr(&["release", "release-plz"]), 7)
+            .await
+            .unwrap();
+    }
 }
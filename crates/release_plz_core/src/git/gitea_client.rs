@@ -1,4 +1,6 @@
-use crate::git::backend::Remote;
+use std::collections::HashMap;
+
+use crate::git::backend::{Remote, DEFAULT_API_RETRIES};
 use crate::RepoUrl;
 use anyhow::{bail, Context};
 use reqwest::header::HeaderMap;
@@ -30,6 +32,9 @@ impl Gitea {
                 owner: url.owner,
                 repo: url.name,
                 token,
+                custom_headers: HashMap::new(),
+                api_retries: DEFAULT_API_RETRIES,
+                api_timeout: None,
             },
         })
     }
@@ -1,9 +1,11 @@
+use std::collections::HashMap;
+
 use anyhow::Context;
 use reqwest::header::{HeaderMap, HeaderValue};
 use secrecy::{ExposeSecret, SecretString};
 use url::Url;
 
-use crate::git::backend::Remote;
+use crate::git::backend::{Remote, DEFAULT_API_RETRIES};
 
 #[derive(Debug, Clone)]
 pub struct GitHub {
@@ -18,6 +20,9 @@ impl GitHub {
                 repo,
                 token,
                 base_url: "https://api.github.com".parse().unwrap(),
+                custom_headers: HashMap::new(),
+                api_retries: DEFAULT_API_RETRIES,
+                api_timeout: None,
             },
         }
     }
@@ -1,8 +1,10 @@
+use std::collections::HashMap;
+
 use anyhow::Context;
 use reqwest::header::{HeaderMap, HeaderValue};
 use secrecy::{ExposeSecret, SecretString};
 
-use crate::git::backend::Remote;
+use crate::git::backend::{Remote, DEFAULT_API_RETRIES};
 
 #[derive(Debug, Clone)]
 pub struct GitLab {
@@ -17,6 +19,9 @@ impl GitLab {
                 repo,
                 token,
                 base_url: "https://gitlab.com/api/v4".parse().unwrap(),
+                custom_headers: HashMap::new(),
+                api_retries: DEFAULT_API_RETRIES,
+                api_timeout: None,
             },
         }
     }
@@ -0,0 +1,218 @@
+use cargo_metadata::semver::Version;
+use chrono::NaiveDate;
+use regex::Regex;
+
+/// Format of `{{ date }}` if no date format is configured.
+/// Same format of the dates of the changelog.
+const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// Name of the git tag of a package version.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GitTagName {
+    /// Template of the tag name.
+    /// `{{ package }}`, `{{ version }}` and `{{ date }}` are replaced with the
+    /// package name, the version and the release date.
+    /// If `None`, the default template of the project is used, see
+    /// [`crate::Project::git_tag_template`].
+    template: Option<String>,
+    /// [chrono format](https://docs.rs/chrono/latest/chrono/format/strftime/index.html)
+    /// of `{{ date }}`, in both the tag name and the tag message.
+    /// If `None`, [`DEFAULT_DATE_FORMAT`] is used.
+    date_format: Option<String>,
+}
+
+/// Semantic version, e.g. `1.2.3-rc.1`.
+const VERSION_PATTERN: &str =
+    r"(?P<version>\d+\.\d+\.\d+(?:-[0-9A-Za-z.-]+)?(?:\+[0-9A-Za-z.-]+)?)";
+
+lazy_static::lazy_static! {
+    static ref VARIABLE_RE: Regex =
+        Regex::new(r"\{\{\s*(package|version|date)\s*\}\}").unwrap();
+}
+
+impl GitTagName {
+    pub fn set_template(mut self, template: Option<String>) -> Self {
+        self.template = template;
+        self
+    }
+
+    pub fn set_date_format(mut self, date_format: Option<String>) -> Self {
+        self.date_format = date_format;
+        self
+    }
+
+    pub(crate) fn format_date(&self, date: NaiveDate) -> String {
+        let format = self.date_format.as_deref().unwrap_or(DEFAULT_DATE_FORMAT);
+        date.format(format).to_string()
+    }
+
+    fn template<'a>(&'a self, default_template: &'a str) -> &'a str {
+        self.template.as_deref().unwrap_or(default_template)
+    }
+
+    /// Name of the tag of a new release of `package`.
+    /// `default_template` is the template used if there's no configured one.
+    pub(crate) fn render(
+        &self,
+        default_template: &str,
+        package: &str,
+        version: &str,
+        date: NaiveDate,
+    ) -> String {
+        let date = self.format_date(date);
+        VARIABLE_RE
+            .replace_all(
+                self.template(default_template),
+                |c: &regex::Captures| match &c[1] {
+                    "package" => package,
+                    "version" => version,
+                    _ => &date,
+                },
+            )
+            .into_owned()
+    }
+
+    /// Tag of an existing release of `package`, among `tags`.
+    /// The release date of an existing tag is unknown, so `{{ date }}` matches any text.
+    pub(crate) fn find<'a>(
+        &self,
+        tags: &'a [String],
+        default_template: &str,
+        package: &str,
+        version: &str,
+    ) -> Option<&'a str> {
+        let pattern = self.pattern(default_template, package, Some(version));
+        tags.iter()
+            .map(String::as_str)
+            .find(|tag| pattern.is_match(tag))
+    }
+
+    /// Tags of `package` among `tags`, with the version they refer to.
+    pub(crate) fn versions<'a>(
+        &self,
+        tags: &'a [String],
+        default_template: &str,
+        package: &str,
+    ) -> Vec<(&'a str, Version)> {
+        let pattern = self.pattern(default_template, package, None);
+        tags.iter()
+            .filter_map(|tag| {
+                let version = pattern.captures(tag)?.name("version")?.as_str();
+                Some((tag.as_str(), Version::parse(version).ok()?))
+            })
+            .collect()
+    }
+
+    /// Regex matching the tags of `package`.
+    /// If `version` is `None`, the `version` group captures the version of the tag.
+    fn pattern(&self, default_template: &str, package: &str, version: Option<&str>) -> Regex {
+        let template = self.template(default_template);
+        let mut pattern = String::from("^");
+        let mut last = 0;
+        for c in VARIABLE_RE.captures_iter(template) {
+            let m = c.get(0).expect("the match is always present");
+            pattern.push_str(&regex::escape(&template[last..m.start()]));
+            match (&c[1], version) {
+                ("package", _) => pattern.push_str(&regex::escape(package)),
+                ("version", Some(version)) => pattern.push_str(&regex::escape(version)),
+                ("version", None) => pattern.push_str(VERSION_PATTERN),
+                _ => pattern.push_str(".+"),
+            }
+            last = m.end();
+        }
+        pattern.push_str(&regex::escape(&template[last..]));
+        pattern.push('$');
+        Regex::new(&pattern).expect("the pattern is escaped")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WORKSPACE_TEMPLATE: &str = "{{ package }}-v{{ version }}";
+
+    fn date() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 6, 1).unwrap()
+    }
+
+    fn dated_name() -> GitTagName {
+        GitTagName::default()
+            .set_template(Some("v{{ version }}-{{ date }}".to_string()))
+            .set_date_format(Some("%Y.%m".to_string()))
+    }
+
+    #[test]
+    fn default_tag_name_is_used_without_template() {
+        let name = GitTagName::default();
+        assert_eq!(
+            name.render(WORKSPACE_TEMPLATE, "pkg", "1.2.3", date()),
+            "pkg-v1.2.3"
+        );
+        let tags = ["pkg-v1.2.3".to_string(), "pkg-v1.2.30".to_string()];
+        assert_eq!(
+            name.find(&tags, WORKSPACE_TEMPLATE, "pkg", "1.2.3"),
+            Some("pkg-v1.2.3")
+        );
+    }
+
+    #[test]
+    fn tag_name_contains_formatted_date() {
+        assert_eq!(
+            dated_name().render(WORKSPACE_TEMPLATE, "pkg", "1.2.3", date()),
+            "v1.2.3-2024.06"
+        );
+        let name = GitTagName::default()
+            .set_template(Some("{{package}}/{{ version }}/{{ date }}".to_string()));
+        assert_eq!(
+            name.render(WORKSPACE_TEMPLATE, "pkg", "1.2.3", date()),
+            "pkg/1.2.3/2024-06-01"
+        );
+    }
+
+    #[test]
+    fn existing_tag_is_found_regardless_of_its_date() {
+        let tags = [
+            "v1.2.30-2024.07".to_string(),
+            "v1.2.3-2023.12".to_string(),
+            "v1.2.4-2024.06".to_string(),
+        ];
+        assert_eq!(
+            dated_name().find(&tags, WORKSPACE_TEMPLATE, "pkg", "1.2.3"),
+            Some("v1.2.3-2023.12")
+        );
+        assert_eq!(
+            dated_name().find(&tags, WORKSPACE_TEMPLATE, "pkg", "1.2.5"),
+            None
+        );
+    }
+
+    #[test]
+    fn versions_of_package_tags_are_parsed() {
+        let tags = [
+            "pkg-v1.0.0".to_string(),
+            "pkg-b-v1.1.0".to_string(),
+            "pkg-v1.1.0-rc.1".to_string(),
+            "pkg-vfoo".to_string(),
+        ];
+        let versions = GitTagName::default().versions(&tags, WORKSPACE_TEMPLATE, "pkg");
+        assert_eq!(
+            versions,
+            [
+                ("pkg-v1.0.0", Version::new(1, 0, 0)),
+                ("pkg-v1.1.0-rc.1", Version::parse("1.1.0-rc.1").unwrap()),
+            ]
+        );
+        let tags = [
+            "v1.2.3-2023.12".to_string(),
+            "v1.3.0-rc.1-2024.06".to_string(),
+        ];
+        assert_eq!(
+            dated_name().versions(&tags, WORKSPACE_TEMPLATE, "pkg"),
+            [
+                ("v1.2.3-2023.12", Version::new(1, 2, 3)),
+                ("v1.3.0-rc.1-2024.06", Version::parse("1.3.0-rc.1").unwrap()),
+            ]
+        );
+    }
+}
@@ -1,5 +1,7 @@
 mod cargo;
 mod changelog;
+mod changelog_context;
+mod changelog_format;
 mod changelog_parser;
 mod clone;
 mod command;
@@ -7,10 +9,14 @@ mod copy_dir;
 mod diff;
 mod download;
 mod git;
+mod git_tag_name;
+mod line_ending;
 mod lock_compare;
+mod merge_commit;
 mod next_ver;
 mod package_compare;
 mod package_path;
+mod plan_trace;
 mod pr;
 mod registry_packages;
 mod release_order;
@@ -20,18 +26,25 @@ mod strip_prefix;
 mod tmp_repo;
 mod toml_compare;
 mod version;
+mod version_file;
 
 pub use changelog::*;
+pub use changelog_format::ChangelogFormat;
 pub use command::*;
 pub use download::{read_package, PackageDownloader};
-pub use git::backend::{GitBackend, GitClient, GitPr};
+pub use git::backend::{GitBackend, GitClient, GitPr, Label, MergedPr};
 pub use git::gitea_client::Gitea;
 pub use git::github_client::GitHub;
 pub use git::gitlab_client::GitLab;
+pub use git_tag_name::GitTagName;
+pub use line_ending::LineEnding;
 pub use next_ver::*;
+pub use next_version::VersionIncrement;
 pub use package_compare::*;
 pub use package_path::*;
 pub use pr::BRANCH_PREFIX;
 pub use repo_url::*;
+pub use semver_check::SemverCheckMissing;
+pub use version::{next_version_from_commits, VersionBumpOverrides};
 
 pub const CARGO_TOML: &str = "Cargo.toml";
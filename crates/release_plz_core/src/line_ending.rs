@@ -0,0 +1,110 @@
+use std::{fs, path::Path};
+
+use anyhow::Context;
+
+/// Line ending of the changelogs written by release-plz.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LineEnding {
+    /// `\n`.
+    #[default]
+    Lf,
+    /// `\r\n`.
+    Crlf,
+    /// Line ending used by most lines of the existing file.
+    /// If the file doesn't exist, `\n` is used.
+    Auto,
+}
+
+impl LineEnding {
+    /// Convert the line endings of `content`.
+    /// `existing` is the content of the file before the update, used by [`LineEnding::Auto`].
+    fn apply(self, content: &str, existing: Option<&str>) -> String {
+        let content = content.replace("\r\n", "\n");
+        let line_ending = match self {
+            Self::Auto => existing.map_or(Self::Lf, dominant_line_ending),
+            line_ending => line_ending,
+        };
+        match line_ending {
+            Self::Crlf => content.replace('\n', "\r\n"),
+            _ => content,
+        }
+    }
+}
+
+/// Line ending used by most lines of `content`.
+fn dominant_line_ending(content: &str) -> LineEnding {
+    let lines = content.matches('\n').count();
+    let crlf_lines = content.matches("\r\n").count();
+    if crlf_lines * 2 > lines {
+        LineEnding::Crlf
+    } else {
+        LineEnding::Lf
+    }
+}
+
+/// Write the changelog to `path`, converting its line endings.
+pub fn write_changelog(
+    path: &Path,
+    changelog: &str,
+    line_ending: LineEnding,
+) -> anyhow::Result<()> {
+    let existing = match line_ending {
+        LineEnding::Auto => fs::read_to_string(path).ok(),
+        _ => None,
+    };
+    fs::write(path, line_ending.apply(changelog, existing.as_deref()))
+        .with_context(|| format!("cannot write changelog to {path:?}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHANGELOG: &str = "# Changelog\n\n## [0.1.1]\n\n- fix\n";
+
+    fn write_and_read(existing: Option<&str>, line_ending: LineEnding) -> String {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("CHANGELOG.md");
+        if let Some(existing) = existing {
+            fs::write(&path, existing).unwrap();
+        }
+        write_changelog(&path, CHANGELOG, line_ending).unwrap();
+        fs::read_to_string(&path).unwrap()
+    }
+
+    #[test]
+    fn auto_preserves_crlf_of_existing_file() {
+        let existing = "# Changelog\r\n\r\n## [0.1.0]\r\n\r\n- feat\r\n";
+        assert_eq!(
+            write_and_read(Some(existing), LineEnding::Auto),
+            "# Changelog\r\n\r\n## [0.1.1]\r\n\r\n- fix\r\n"
+        );
+    }
+
+    #[test]
+    fn auto_preserves_lf_of_existing_file() {
+        let existing = "# Changelog\n\n## [0.1.0]\r\n\n- feat\n";
+        assert_eq!(write_and_read(Some(existing), LineEnding::Auto), CHANGELOG);
+    }
+
+    #[test]
+    fn auto_uses_lf_for_new_file() {
+        assert_eq!(write_and_read(None, LineEnding::Auto), CHANGELOG);
+    }
+
+    #[test]
+    fn lf_is_forced() {
+        let changelog = "# Changelog\r\n\r\n## [0.1.1]\r\n\r\n- fix\r\n";
+        assert_eq!(LineEnding::Lf.apply(changelog, None), CHANGELOG);
+        let existing = "# Changelog\r\n";
+        assert_eq!(write_and_read(Some(existing), LineEnding::Lf), CHANGELOG);
+    }
+
+    #[test]
+    fn crlf_is_forced() {
+        assert_eq!(
+            write_and_read(Some(CHANGELOG), LineEnding::Crlf),
+            "# Changelog\r\n\r\n## [0.1.1]\r\n\r\n- fix\r\n"
+        );
+    }
+}
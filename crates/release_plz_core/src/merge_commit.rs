@@ -0,0 +1,92 @@
+use regex::Regex;
+
+lazy_static::lazy_static! {
+    // e.g. `Merge pull request #12 from owner/branch` or `Merge branch 'feature' into 'main'`
+    static ref MERGE_RE: Regex =
+        Regex::new(r"^Merge (pull request|branch|remote-tracking branch) ").unwrap();
+    // Gitea, e.g. `Merge pull request 'Add api' (#12) from feature into main`
+    static ref GITEA_TITLE_RE: Regex = Regex::new(r"^Merge pull request '(.+)' \(#\d+\)").unwrap();
+    static ref PR_NUMBER_RE: Regex = Regex::new(r"#(\d+)").unwrap();
+    // GitLab, e.g. `See merge request group/project!12`
+    static ref MERGE_REQUEST_RE: Regex = Regex::new(r"^See merge request \S*!(\d+)$").unwrap();
+}
+
+/// Whether the commit was created by merging a branch or a Pull Request.
+pub fn is_merge_commit(message: &str) -> bool {
+    MERGE_RE.is_match(message)
+}
+
+/// Changelog entry of a merge commit: the title of the merged Pull Request,
+/// followed by its number, e.g. `feat: add api (#12)`.
+/// If the message doesn't contain the title, the summary of the commit is used.
+/// Returns `None` if the commit isn't a merge commit.
+pub fn merge_commit_entry(message: &str) -> Option<String> {
+    let mut lines = message.lines();
+    let summary = lines.next()?;
+    if !is_merge_commit(summary) {
+        return None;
+    }
+    let body: Vec<&str> = lines.map(str::trim).filter(|l| !l.is_empty()).collect();
+    let merge_request = body.iter().find_map(|line| MERGE_REQUEST_RE.captures(line));
+    let title = GITEA_TITLE_RE
+        .captures(summary)
+        .map(|c| c[1].to_string())
+        .or_else(|| {
+            body.iter()
+                .find(|line| !MERGE_REQUEST_RE.is_match(line))
+                .map(|line| line.to_string())
+        });
+    let Some(title) = title else {
+        return Some(summary.to_string());
+    };
+    let entry = match (PR_NUMBER_RE.captures(summary), merge_request) {
+        (Some(pr), _) => format!("{title} (#{})", &pr[1]),
+        (None, Some(merge_request)) => format!("{title} (!{})", &merge_request[1]),
+        (None, None) => title,
+    };
+    Some(entry)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn github_merge_commit_is_converted_to_pr_title() {
+        let message = "Merge pull request #12 from owner/feature\n\nfeat: add api";
+        assert_eq!(
+            merge_commit_entry(message).as_deref(),
+            Some("feat: add api (#12)")
+        );
+    }
+
+    #[test]
+    fn gitea_merge_commit_is_converted_to_pr_title() {
+        let message = "Merge pull request 'feat: add api' (#12) from feature into main";
+        assert_eq!(
+            merge_commit_entry(message).as_deref(),
+            Some("feat: add api (#12)")
+        );
+    }
+
+    #[test]
+    fn gitlab_merge_commit_is_converted_to_merge_request_title() {
+        let message = "Merge branch 'feature' into 'main'\n\nfeat: add api\n\nSee merge request group/project!7";
+        assert_eq!(
+            merge_commit_entry(message).as_deref(),
+            Some("feat: add api (!7)")
+        );
+    }
+
+    #[test]
+    fn merge_commit_without_title_keeps_summary() {
+        let message = "Merge branch 'feature'";
+        assert_eq!(merge_commit_entry(message).as_deref(), Some(message));
+    }
+
+    #[test]
+    fn regular_commit_is_not_a_merge_commit() {
+        assert!(!is_merge_commit("feat: merge the configs"));
+        assert_eq!(merge_commit_entry("fix: merge pull request handling"), None);
+    }
+}
@@ -2,29 +2,36 @@ use crate::{
     changelog_parser::{self, ChangelogRelease},
     copy_dir::copy_dir,
     diff::Diff,
-    lock_compare,
+    line_ending::LineEnding,
+    lock_compare, merge_commit,
     package_compare::are_packages_equal,
     package_path::{manifest_dir, PackagePath},
+    plan_trace::version_trace,
     registry_packages::{self, PackagesCollection},
+    release_order::release_order,
     repo_url::RepoUrl,
-    semver_check::{self, SemverCheck},
+    semver_check::{self, SemverCheck, SemverCheckBaseline, SemverCheckMissing},
     strip_prefix::strip_prefix,
     tmp_repo::TempRepo,
     toml_compare::are_toml_dependencies_updated,
-    version::NextVersionFromDiff,
-    ChangelogBuilder, PackagesToUpdate, PackagesUpdate, CARGO_TOML, CHANGELOG_FILENAME,
+    version::{NextVersionFromDiff, VersionBumpOverrides},
+    ChangelogBuilder, ChangelogFormat, CommitSort, GitTagName, PackagesToUpdate, PackagesUpdate,
+    CARGO_TOML,
 };
 use anyhow::Context;
 use cargo_metadata::{semver::Version, Metadata, Package};
 use cargo_utils::{upgrade_requirement, LocalManifest};
-use chrono::NaiveDate;
+use chrono::{NaiveDate, Utc};
 use git_cliff_core::{commit::Commit, config::Config as GitCliffConfig};
 use git_cmd::{self, Repo};
-use next_version::NextVersion;
-use rayon::prelude::{IntoParallelRefMutIterator, ParallelIterator};
+use next_version::{NextVersion, VersionIncrement};
+use rayon::{
+    prelude::{IntoParallelIterator, IntoParallelRefMutIterator, ParallelIterator},
+    ThreadPool, ThreadPoolBuilder,
+};
 use regex::Regex;
 use std::{
-    collections::{BTreeMap, HashMap, HashSet},
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
     fs, io,
     path::{Path, PathBuf},
 };
@@ -34,6 +41,9 @@ use tracing::{debug, info, instrument, warn};
 // Used to indicate that this is a dummy commit with no corresponding ID available
 pub(crate) const NO_COMMIT_ID: &str = "N/A";
 
+/// Trailer of the release commit if `release_commit_marker` isn't configured.
+pub const DEFAULT_RELEASE_COMMIT_MARKER: &str = "release-plz: true";
+
 pub trait RequestReleaseValidator {
     fn is_release_enabled(&self, package_name: &str) -> bool;
 }
@@ -57,12 +67,54 @@ pub struct UpdateRequest {
     /// - If true, update all the dependencies in Cargo.lock by running `cargo update`.
     /// - If false, updates the workspace packages in Cargo.lock by running `cargo update --workspace`.
     dependencies_update: bool,
+    /// If false, don't touch the Cargo.lock file.
+    lockfile_update: bool,
     /// Allow dirty working directories to be updated.
     /// The uncommitted changes will be part of the update.
     allow_dirty: bool,
     /// Repository Url. If present, the new changelog entry contains a link to the diff between the old and new version.
     /// Format: `https://{repo_host}/{repo_owner}/{repo_name}/compare/{old_tag}...{new_tag}`.
     repo_url: Option<RepoUrl>,
+    /// Repository Url used to generate the links of the changelog.
+    /// If `None`, `repo_url` is used.
+    changelog_repo_url: Option<RepoUrl>,
+    /// Path of the changelog aggregating the changes of all the updated packages.
+    /// It needs to be relative to the Cargo.toml of the project.
+    /// If `None`, the aggregated changelog isn't written.
+    workspace_changelog_path: Option<PathBuf>,
+    /// Line ending of the written changelogs.
+    changelog_line_ending: LineEnding,
+    /// Version increment caused by the commits of each type, e.g. `perf` -> minor.
+    /// Commit types that aren't listed follow the conventional commits rules.
+    version_bump_rules: HashMap<String, VersionIncrement>,
+    /// Trailer added to the release commit, e.g. `release-plz: true`.
+    /// Commits containing this trailer aren't considered when computing the next version
+    /// and the changelog.
+    /// If `None`, [`DEFAULT_RELEASE_COMMIT_MARKER`] is used.
+    release_commit_marker: Option<String>,
+    /// Maximum number of packages processed in parallel, e.g. when running cargo-semver-checks
+    /// or generating the changelogs.
+    /// If `None`, use one thread per CPU.
+    jobs: Option<usize>,
+    /// What to do if cargo-semver-checks isn't installed.
+    semver_check_missing: SemverCheckMissing,
+    /// If true, log the decision trail of each package,
+    /// e.g. the commits considered and the computed version increment.
+    verbose_plan: bool,
+    /// Changelog entries of the commits created by merging a Pull Request,
+    /// e.g. `feat: add api (#12)`. The key is the hash of the commit.
+    /// These entries replace the commit messages in the changelog.
+    merged_prs: HashMap<String, String>,
+    /// If true, credit the author of each commit in its changelog entry, e.g. `fix bug (@user)`.
+    changelog_credit_authors: bool,
+    /// Usernames of the authors of the commits, resolved with the git forge API.
+    /// The key is the hash of the commit.
+    /// Commits that aren't listed are credited to their git author name.
+    commit_authors: HashMap<String, String>,
+    /// If true and the latest version of a package published in the registry was yanked,
+    /// compare the local package with the latest non-yanked version.
+    /// Otherwise, compare it with the yanked version.
+    skip_yanked_baseline: bool,
     /// Package-specific configurations.
     packages_config: PackagesConfig,
 }
@@ -82,11 +134,31 @@ impl From<UpdateConfig> for PackageUpdateConfig {
             generic: config,
             changelog_path: None,
             changelog_include: vec![],
+            changelog_exclude: vec![],
+            changelog_include_mode: ChangelogIncludeMode::default(),
+            changelog_include_order: ChangelogIncludeOrder::default(),
         }
     }
 }
 
 impl PackagesConfig {
+    /// Check that the packages of `changelog_include` are workspace members.
+    fn check_changelog_include(&self, packages: &[Package]) -> anyhow::Result<()> {
+        let mut members: Vec<&str> = packages.iter().map(|p| p.name.as_str()).collect();
+        members.sort_unstable();
+        for (package, config) in &self.overrides {
+            config.check_changelog_include(package, &members)?;
+        }
+        Ok(())
+    }
+
+    /// Whether a package includes the changelogs of other packages.
+    fn has_changelog_include(&self) -> bool {
+        self.overrides
+            .values()
+            .any(|config| !config.changelog_include.is_empty())
+    }
+
     fn get(&self, package_name: &str) -> PackageUpdateConfig {
         self.overrides
             .get(package_name)
@@ -109,11 +181,46 @@ pub struct UpdateConfig {
     /// Note: You can only run cargo-semver-checks if the package contains a library.
     ///       For example, if it has a `lib.rs` file.
     pub semver_check: bool,
+    /// Git revision to run cargo-semver-checks against.
+    /// If `None`, the package published in the registry is used.
+    pub semver_check_baseline_ref: Option<String>,
+    /// Regex matching the tags that can be used as previous release of the package.
+    /// If `None`, the tag of the current package version is used.
+    pub changelog_tag_pattern: Option<String>,
+    /// Regex matching the commit messages that can trigger a release.
+    /// If `None`, every commit can trigger a release.
+    pub release_commits: Option<String>,
+    /// Git revision used as starting point of the release.
+    /// Only the commits after it are considered.
+    /// If `None`, the commits after the last release are considered.
+    pub since: Option<String>,
     /// Whether to create/update changelog or not.
     /// Default: `true`.
     pub changelog_update: bool,
+    /// Changelog entry of the releases caused only by the update of local dependencies.
+    /// `{{ dependencies }}` is replaced with the names of the updated dependencies.
+    /// If `None`, the entry lists the updated dependencies.
+    pub changelog_dependency_note: Option<String>,
+    /// Registry where the package is published.
+    /// Used to look up the versions of the package that are already published.
+    /// If `None`, the first registry of the `publish` field of the package manifest is used,
+    /// or crates.io.
+    pub publish_registry: Option<String>,
+    /// Files containing the version of the package, besides `Cargo.toml`.
+    /// See [`crate::version_file::update_version_file`] for the format.
+    pub version_files: Vec<String>,
+    /// Changelog groups of the commits, by glob of the files they change.
+    /// The globs are relative to the package directory, e.g. `src/parser/**`.
+    /// A commit changing files of multiple groups appears in each of them.
+    pub changelog_group_by_path: BTreeMap<String, String>,
+    /// If true, it's an error if the changelog section of the release has no entries.
+    pub changelog_require_nonempty: bool,
+    /// If true, `0.0.z` versions are only incremented in the last segment.
+    pub zero_based_versioning: bool,
     /// High-level toggle to process this package or ignore it.
     pub release: bool,
+    /// Name of the git tags, used to find the previous release and in the changelog links.
+    pub git_tag_name: GitTagName,
 }
 
 /// Package-specific config
@@ -126,12 +233,147 @@ pub struct PackageUpdateConfig {
     /// This path needs to be a relative path to the Cargo.toml of the project.
     /// I.e. if you have a workspace, it needs to be relative to the workspace root.
     pub changelog_path: Option<PathBuf>,
-    /// List of package names.
+    /// List of package names or globs of package names, e.g. `api-*`.
     /// Include the changelogs of these packages in the changelog of the current package.
     pub changelog_include: Vec<String>,
+    /// List of package names or globs of package names.
+    /// Exclude the changelogs of these packages from the ones included with `changelog_include`.
+    pub changelog_exclude: Vec<String>,
+    /// How the changelogs of `changelog_include` are included.
+    pub changelog_include_mode: ChangelogIncludeMode,
+    /// Order of the changelogs of `changelog_include`.
+    pub changelog_include_order: ChangelogIncludeOrder,
+}
+
+/// How the changelogs of other packages are included in the changelog of a package.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ChangelogIncludeMode {
+    /// Add the commits of the included packages to the changelog.
+    #[default]
+    Inline,
+    /// Add an entry linking to the release of each included package that is released.
+    Link,
+}
+
+/// Order of the changelogs included in the changelog of a package.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ChangelogIncludeOrder {
+    /// Order of `changelog_include`.
+    #[default]
+    Declaration,
+    /// Dependencies before the packages depending on them.
+    Dependencies,
+}
+
+/// Whether the `changelog_include` or `changelog_exclude` entry is a glob, e.g. `api-*`.
+fn is_package_glob(entry: &str) -> bool {
+    entry.contains(['*', '?', '['])
+}
+
+/// Names of the `members` matching the glob `entry` of `changelog_include` or
+/// `changelog_exclude`, sorted by name. An entry that isn't a glob, e.g. a path,
+/// is returned as it is, even if it isn't a member.
+/// An invalid glob doesn't match any member.
+pub fn expand_package_glob<'a>(entry: &'a str, members: &[&'a str]) -> Vec<&'a str> {
+    if !is_package_glob(entry) {
+        return vec![entry];
+    }
+    let Ok(pattern) = glob::Pattern::new(entry) else {
+        return vec![];
+    };
+    let mut matches: Vec<&str> = members
+        .iter()
+        .copied()
+        .filter(|member| pattern.matches(member))
+        .collect();
+    matches.sort_unstable();
+    matches.dedup();
+    matches
 }
 
 impl PackageUpdateConfig {
+    /// Packages whose changelogs are included in the changelog of `package`.
+    /// The globs of `changelog_include` and `changelog_exclude` are expanded to the
+    /// matching `members`, except `package` itself.
+    pub fn included_changelogs<'a>(&'a self, package: &str, members: &[&'a str]) -> Vec<&'a str> {
+        let members: Vec<&str> = members.iter().copied().filter(|m| *m != package).collect();
+        let excluded: Vec<&str> = self
+            .changelog_exclude
+            .iter()
+            .flat_map(|entry| expand_package_glob(entry, &members))
+            .collect();
+        let mut included = vec![];
+        for package in self
+            .changelog_include
+            .iter()
+            .flat_map(|entry| expand_package_glob(entry, &members))
+        {
+            if !excluded.contains(&package) && !included.contains(&package) {
+                included.push(package);
+            }
+        }
+        included
+    }
+
+    /// [`Self::included_changelogs`], sorted according to `changelog_include_order`.
+    /// `dependency_order` contains the workspace packages, placed after their dependencies.
+    /// Globs are matched against them.
+    /// Entries not in `dependency_order`, e.g. paths, are placed last.
+    pub fn ordered_included_changelogs<'a>(
+        &'a self,
+        package: &str,
+        dependency_order: &'a [String],
+    ) -> Vec<&'a str> {
+        let members: Vec<&str> = dependency_order.iter().map(String::as_str).collect();
+        let mut included = self.included_changelogs(package, &members);
+        if self.changelog_include_order == ChangelogIncludeOrder::Dependencies {
+            included.sort_by_key(|p| {
+                dependency_order
+                    .iter()
+                    .position(|d| d == p)
+                    .unwrap_or(usize::MAX)
+            });
+        }
+        included
+    }
+
+    /// Check that the packages of `changelog_include` of `package` are workspace `members`,
+    /// and that its globs are valid and match at least one member.
+    /// Entries in the path form, e.g. `crates/pkg1`, aren't package names, so they are not checked.
+    pub fn check_changelog_include(&self, package: &str, members: &[&str]) -> anyhow::Result<()> {
+        let is_path = |include: &str| include.contains(['/', '\\']);
+        for glob in self
+            .changelog_include
+            .iter()
+            .chain(&self.changelog_exclude)
+            .filter(|entry| is_package_glob(entry))
+        {
+            glob::Pattern::new(glob).with_context(|| {
+                format!("package `{package}` has the invalid glob `{glob}` in `changelog_include` or `changelog_exclude`")
+            })?;
+        }
+        if let Some(glob) = self.changelog_include.iter().find(|include| {
+            is_package_glob(include) && expand_package_glob(include, members).is_empty()
+        }) {
+            anyhow::bail!(
+                "package `{package}` has `{glob}` in `changelog_include`, but `{glob}` doesn't match any member of the workspace"
+            );
+        }
+        if let Some(include) = self.changelog_include.iter().find(|include| {
+            !is_path(include) && !is_package_glob(include) && !members.contains(&include.as_str())
+        }) {
+            let members = members
+                .iter()
+                .map(|m| format!("`{m}`"))
+                .collect::<Vec<_>>()
+                .join(", ");
+            anyhow::bail!(
+                "package `{package}` has `{include}` in `changelog_include`, but `{include}` is not a member of the workspace. Workspace members: {members}"
+            );
+        }
+        Ok(())
+    }
+
     pub fn semver_check(&self) -> bool {
         self.generic.semver_check
     }
@@ -145,13 +387,31 @@ impl Default for UpdateConfig {
     fn default() -> Self {
         Self {
             semver_check: true,
+            semver_check_baseline_ref: None,
+            changelog_tag_pattern: None,
+            release_commits: None,
+            since: None,
             changelog_update: true,
+            changelog_dependency_note: None,
+            publish_registry: None,
+            version_files: vec![],
+            changelog_group_by_path: BTreeMap::new(),
+            changelog_require_nonempty: false,
+            zero_based_versioning: false,
             release: true,
+            git_tag_name: GitTagName::default(),
         }
     }
 }
 
 impl UpdateConfig {
+    pub fn with_git_tag_name(self, git_tag_name: GitTagName) -> Self {
+        Self {
+            git_tag_name,
+            ..self
+        }
+    }
+
     pub fn with_semver_check(self, semver_check: bool) -> Self {
         Self {
             semver_check,
@@ -159,19 +419,141 @@ impl UpdateConfig {
         }
     }
 
+    pub fn with_semver_check_baseline_ref(self, baseline_ref: impl Into<String>) -> Self {
+        Self {
+            semver_check_baseline_ref: Some(baseline_ref.into()),
+            ..self
+        }
+    }
+
+    pub fn with_changelog_tag_pattern(self, changelog_tag_pattern: impl Into<String>) -> Self {
+        Self {
+            changelog_tag_pattern: Some(changelog_tag_pattern.into()),
+            ..self
+        }
+    }
+
+    pub fn with_release_commits(self, release_commits: impl Into<String>) -> Self {
+        Self {
+            release_commits: Some(release_commits.into()),
+            ..self
+        }
+    }
+
+    pub fn with_since(self, since: impl Into<String>) -> Self {
+        Self {
+            since: Some(since.into()),
+            ..self
+        }
+    }
+
     pub fn with_changelog_update(self, changelog_update: bool) -> Self {
         Self {
             changelog_update,
             ..self
         }
     }
+
+    pub fn with_changelog_dependency_note(self, note: impl Into<String>) -> Self {
+        Self {
+            changelog_dependency_note: Some(note.into()),
+            ..self
+        }
+    }
+
+    pub fn with_publish_registry(self, publish_registry: impl Into<String>) -> Self {
+        Self {
+            publish_registry: Some(publish_registry.into()),
+            ..self
+        }
+    }
+
+    pub fn with_version_files(self, version_files: Vec<String>) -> Self {
+        Self {
+            version_files,
+            ..self
+        }
+    }
+
+    pub fn with_changelog_require_nonempty(self, changelog_require_nonempty: bool) -> Self {
+        Self {
+            changelog_require_nonempty,
+            ..self
+        }
+    }
+
+    pub fn with_zero_based_versioning(self, zero_based_versioning: bool) -> Self {
+        Self {
+            zero_based_versioning,
+            ..self
+        }
+    }
+
+    pub fn with_changelog_group_by_path(
+        self,
+        changelog_group_by_path: BTreeMap<String, String>,
+    ) -> Self {
+        Self {
+            changelog_group_by_path,
+            ..self
+        }
+    }
 }
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone)]
 pub struct ChangelogRequest {
     /// When the new release is published. If unspecified, current date is used.
     pub release_date: Option<NaiveDate>,
     pub changelog_config: Option<GitCliffConfig>,
+    /// Prefix to remove from the commit summaries, e.g. `PROJ-\d+:\s*`.
+    pub commit_summary_prefix: Option<Regex>,
+    /// Sort the changelog entries of each section by scope.
+    pub sort_by_scope: bool,
+    /// Order of the changelog entries of each section.
+    /// If `None`, the `sort_commits` field of the git-cliff configuration is used.
+    pub commit_sort: Option<CommitSort>,
+    /// Don't add the `ci`, `build` and `chore(ci)` commits to the changelog.
+    pub skip_ci_commits: bool,
+    /// Move the notes of the `Unreleased` section to the new release.
+    pub unreleased_section: bool,
+    /// Don't add the commits already listed in the `Unreleased` section of the changelog.
+    pub dedup_existing: bool,
+    /// Add the merge commits to the changelog, with the title of the merged Pull Request.
+    /// The history is walked following the first parent of the merge commits,
+    /// so the commits of the merged branches aren't considered.
+    /// If false, the merge commits are excluded from the changelog.
+    pub include_merge_commits: bool,
+    /// Link the version heading of the release to the comparison with the previous release.
+    pub header_links: bool,
+    /// Append the short hash of the commit, linking to the commit, to each changelog entry.
+    pub commit_links: bool,
+    /// Maximum number of entries of each section of the release.
+    pub max_entries: Option<usize>,
+    /// Variables available in the changelog template as `context`.
+    pub context: HashMap<String, toml::Value>,
+    /// Output format of the changelog.
+    pub format: ChangelogFormat,
+}
+
+impl Default for ChangelogRequest {
+    fn default() -> Self {
+        Self {
+            release_date: None,
+            changelog_config: None,
+            commit_summary_prefix: None,
+            sort_by_scope: false,
+            commit_sort: None,
+            skip_ci_commits: false,
+            unreleased_section: false,
+            dedup_existing: false,
+            include_merge_commits: false,
+            header_links: true,
+            commit_links: false,
+            max_entries: None,
+            context: HashMap::new(),
+            format: ChangelogFormat::default(),
+        }
+    }
 }
 
 fn canonical_local_manifest(local_manifest: &Path) -> io::Result<PathBuf> {
@@ -194,8 +576,21 @@ impl UpdateRequest {
             changelog_req: ChangelogRequest::default(),
             registry: None,
             dependencies_update: false,
+            lockfile_update: true,
             allow_dirty: false,
             repo_url: None,
+            changelog_repo_url: None,
+            workspace_changelog_path: None,
+            changelog_line_ending: LineEnding::default(),
+            version_bump_rules: HashMap::new(),
+            release_commit_marker: None,
+            jobs: None,
+            semver_check_missing: SemverCheckMissing::default(),
+            verbose_plan: false,
+            merged_prs: HashMap::new(),
+            changelog_credit_authors: false,
+            commit_authors: HashMap::new(),
+            skip_yanked_baseline: true,
             packages_config: PackagesConfig::default(),
         })
     }
@@ -209,7 +604,7 @@ impl UpdateRequest {
                 package
                     .package_path()
                     .expect("can't determine package path")
-                    .join(CHANGELOG_FILENAME)
+                    .join(self.changelog_req.format.file_name())
             })
     }
 
@@ -266,6 +661,20 @@ impl UpdateRequest {
         }
     }
 
+    /// Registry where the published versions of the package are looked up. `None` is crates.io.
+    /// The `registry` of the request takes precedence over the `publish_registry` of the
+    /// package configuration, which takes precedence over the `publish` field of the
+    /// package manifest.
+    fn lookup_registry(&self, package: &Package) -> Option<String> {
+        if let Some(registry) = &self.registry {
+            return Some(registry.clone());
+        }
+        self.get_package_config(&package.name)
+            .generic
+            .publish_registry
+            .or_else(|| package.publish.as_ref()?.first().cloned())
+    }
+
     pub fn with_single_package(self, package: String) -> Self {
         Self {
             single_package: Some(package),
@@ -280,6 +689,125 @@ impl UpdateRequest {
         }
     }
 
+    pub fn with_changelog_repo_url(self, changelog_repo_url: RepoUrl) -> Self {
+        Self {
+            changelog_repo_url: Some(changelog_repo_url),
+            ..self
+        }
+    }
+
+    pub fn with_workspace_changelog_path(self, workspace_changelog_path: PathBuf) -> Self {
+        Self {
+            workspace_changelog_path: Some(workspace_changelog_path),
+            ..self
+        }
+    }
+
+    pub fn with_changelog_line_ending(self, changelog_line_ending: LineEnding) -> Self {
+        Self {
+            changelog_line_ending,
+            ..self
+        }
+    }
+
+    pub fn with_version_bump_rules(
+        self,
+        version_bump_rules: HashMap<String, VersionIncrement>,
+    ) -> Self {
+        Self {
+            version_bump_rules,
+            ..self
+        }
+    }
+
+    pub fn with_semver_check_missing(self, semver_check_missing: SemverCheckMissing) -> Self {
+        Self {
+            semver_check_missing,
+            ..self
+        }
+    }
+
+    pub fn with_jobs(self, jobs: usize) -> Self {
+        Self {
+            jobs: Some(jobs),
+            ..self
+        }
+    }
+
+    pub fn with_verbose_plan(self, verbose_plan: bool) -> Self {
+        Self {
+            verbose_plan,
+            ..self
+        }
+    }
+
+    /// Use these entries instead of the messages of the commits in the changelog.
+    /// The key is the hash of the commit, e.g. the merge commit of a Pull Request.
+    pub fn with_merged_prs(self, merged_prs: HashMap<String, String>) -> Self {
+        Self { merged_prs, ..self }
+    }
+
+    pub fn with_changelog_credit_authors(self, changelog_credit_authors: bool) -> Self {
+        Self {
+            changelog_credit_authors,
+            ..self
+        }
+    }
+
+    pub fn changelog_credit_authors(&self) -> bool {
+        self.changelog_credit_authors
+    }
+
+    pub fn with_skip_yanked_baseline(self, skip_yanked_baseline: bool) -> Self {
+        Self {
+            skip_yanked_baseline,
+            ..self
+        }
+    }
+
+    /// Credit these usernames instead of the git author names in the changelog.
+    /// The key is the hash of the commit.
+    pub fn with_commit_authors(self, commit_authors: HashMap<String, String>) -> Self {
+        Self {
+            commit_authors,
+            ..self
+        }
+    }
+
+    pub fn with_release_commit_marker(self, release_commit_marker: impl Into<String>) -> Self {
+        Self {
+            release_commit_marker: Some(release_commit_marker.into()),
+            ..self
+        }
+    }
+
+    pub fn changelog_line_ending(&self) -> LineEnding {
+        self.changelog_line_ending
+    }
+
+    fn version_bump_overrides(&self, package: &str) -> VersionBumpOverrides {
+        VersionBumpOverrides {
+            version_bump_rules: self.version_bump_rules.clone(),
+            zero_based_versioning: self
+                .get_package_config(package)
+                .generic
+                .zero_based_versioning,
+            ..Default::default()
+        }
+    }
+
+    /// Path of the changelog aggregating the changes of all the updated packages.
+    pub fn workspace_changelog_path(&self) -> Option<PathBuf> {
+        self.workspace_changelog_path
+            .as_ref()
+            .map(|p| self.local_manifest.parent().unwrap().join(p))
+    }
+
+    /// When the new release is published. If unspecified, current date is used.
+    pub fn release_date(&self) -> Option<NaiveDate> {
+        self.changelog_req.release_date
+    }
+
     pub fn local_manifest_dir(&self) -> anyhow::Result<&Path> {
         self.local_manifest
             .parent()
@@ -305,6 +833,17 @@ impl UpdateRequest {
         self.dependencies_update
     }
 
+    pub fn with_lockfile_update(self, lockfile_update: bool) -> Self {
+        Self {
+            lockfile_update,
+            ..self
+        }
+    }
+
+    pub fn should_update_lockfile(&self) -> bool {
+        self.lockfile_update
+    }
+
     pub fn with_allow_dirty(self, allow_dirty: bool) -> Self {
         Self {
             allow_dirty,
@@ -315,6 +854,17 @@ impl UpdateRequest {
     pub fn repo_url(&self) -> Option<&RepoUrl> {
         self.repo_url.as_ref()
     }
+
+    /// Repository Url used to generate the links of the changelog.
+    pub fn changelog_repo_url(&self) -> Option<&RepoUrl> {
+        self.changelog_repo_url.as_ref().or(self.repo_url.as_ref())
+    }
+
+    pub fn release_commit_marker(&self) -> &str {
+        self.release_commit_marker
+            .as_deref()
+            .unwrap_or(DEFAULT_RELEASE_COMMIT_MARKER)
+    }
 }
 
 impl RequestReleaseValidator for UpdateRequest {
@@ -327,6 +877,9 @@ impl RequestReleaseValidator for UpdateRequest {
 /// Determine next version of packages
 #[instrument(skip_all)]
 pub fn next_versions(input: &UpdateRequest) -> anyhow::Result<(PackagesUpdate, TempRepo)> {
+    input
+        .packages_config
+        .check_changelog_include(&workspace_packages(&input.metadata)?)?;
     let overrides = input.packages_config.overrides.keys().cloned().collect();
     let local_project = Project::new(
         &input.local_manifest,
@@ -335,25 +888,112 @@ pub fn next_versions(input: &UpdateRequest) -> anyhow::Result<(PackagesUpdate, T
         &input.metadata,
         input,
     )?;
-    let updater = Updater {
-        project: &local_project,
-        req: input,
-    };
     let registry_packages = registry_packages::get_registry_packages(
         input.registry_manifest.as_ref(),
         &local_project.publishable_packages(),
-        input.registry.as_deref(),
+        |p| input.lookup_registry(p),
+        !input.skip_yanked_baseline,
     )?;
 
-    let repository = local_project.get_repo()?;
+    let mut repository = local_project.get_repo()?;
+    repository.repo = repository
+        .repo
+        .with_first_parent(input.changelog_req.include_merge_commits);
     if !input.allow_dirty {
         repository.repo.is_clean()?;
     }
+    let updater = Updater {
+        project: &local_project,
+        req: input,
+        git_tags: repository.repo.tags()?,
+    };
     let packages_to_update =
         updater.packages_to_update(&registry_packages, &repository.repo, input.local_manifest())?;
     Ok((packages_to_update, repository))
 }
 
+/// Check that the changelog section of the release has at least one entry,
+/// i.e. a list item. An empty section usually means that no commit follows
+/// the commit conventions expected by the changelog configuration.
+/// The entries are read from the release notes, which are in Markdown for every `format`.
+fn check_changelog_entries(
+    package: &str,
+    version: &Version,
+    changelog: &str,
+    format: ChangelogFormat,
+) -> anyhow::Result<()> {
+    let notes = format
+        .last_release(changelog)?
+        .map(|release| release.notes().to_string())
+        .unwrap_or_default();
+    let has_entries = notes.lines().any(|line| {
+        let line = line.trim_start();
+        line.starts_with("- ") || line.starts_with("* ")
+    });
+    anyhow::ensure!(
+        has_entries,
+        "{package} {version}: the changelog of the release has no entries, but `changelog_require_nonempty` is enabled. Check that the commits follow the conventions of the changelog configuration"
+    );
+    Ok(())
+}
+
+/// Message of the commit describing the update of the local dependencies `deps`.
+/// `note` is the configured `changelog_dependency_note`, if any.
+fn dependency_change_message(note: Option<&str>, deps: &[&str]) -> String {
+    lazy_static::lazy_static! {
+        static ref DEPENDENCIES_RE: Regex = Regex::new(r"\{\{\s*dependencies\s*\}\}").unwrap();
+    }
+    let deps = deps.join(", ");
+    match note {
+        Some(note) => {
+            let note = DEPENDENCIES_RE.replace_all(note, regex::NoExpand(&deps));
+            format!("chore: {note}")
+        }
+        None => format!("chore: updated the following local packages: {deps}"),
+    }
+}
+
+/// Commits of the packages included with [`ChangelogIncludeMode::Inline`].
+fn inlined_commits<'a>(
+    package: &str,
+    config: &PackageUpdateConfig,
+    dependency_order: &[String],
+    packages_commits: &HashMap<String, Vec<Commit<'a>>>,
+) -> Vec<Commit<'a>> {
+    if config.changelog_include_mode != ChangelogIncludeMode::Inline {
+        return vec![];
+    }
+    config
+        .ordered_included_changelogs(package, dependency_order)
+        .into_iter()
+        .filter_map(|pkg_to_include| packages_commits.get(pkg_to_include))
+        .flatten()
+        .cloned()
+        .collect()
+}
+
+/// Changelog entries of the packages included with [`ChangelogIncludeMode::Link`]:
+/// one entry for each included package that is released, linking to its release
+/// if `release_link` returns a link.
+fn included_release_commits<'a>(
+    included: impl Iterator<Item = &'a str>,
+    next_versions: &HashMap<&str, Version>,
+    release_link: impl Fn(&str, &Version) -> Option<String>,
+) -> Vec<Commit<'static>> {
+    included
+        .filter_map(|package| {
+            let version = next_versions.get(package)?;
+            let message = match release_link(package, version) {
+                Some(link) => {
+                    format!("chore: updated `{package}` to {version} — see its [changelog]({link})")
+                }
+                None => format!("chore: updated `{package}` to {version}"),
+            };
+            Some(Commit::new(NO_COMMIT_ID.to_string(), message))
+        })
+        .collect()
+}
+
 /// Check for typos in the package names based on the overrides
 fn check_for_typos(packages: &HashSet<String>, overrides: &HashSet<String>) -> anyhow::Result<()> {
     let diff: Vec<_> = overrides.difference(packages).collect();
@@ -444,6 +1084,15 @@ impl Project {
         self.packages.iter().collect()
     }
 
+    /// Names of the publishable packages, placed after their dependencies,
+    /// as in the release order.
+    fn dependency_order(&self) -> anyhow::Result<Vec<String>> {
+        let packages = self.publishable_packages();
+        let order = release_order(&packages)
+            .context("cannot determine dependency order of the packages")?;
+        Ok(order.iter().map(|p| p.name.clone()).collect())
+    }
+
     /// Copy this project in a temporary repository and return the repository.
     /// We copy the project in another directory in order to avoid altering it.
     fn get_repo(&self) -> anyhow::Result<TempRepo> {
@@ -461,12 +1110,10 @@ impl Project {
         Ok(repository)
     }
 
-    pub fn git_tag(&self, package_name: &str, version: &str) -> String {
-        if self.contains_multiple_pub_packages {
-            format!("{package_name}-v{version}")
-        } else {
-            format!("v{version}")
-        }
+    /// Template of the git tags if `git_tag_name` isn't configured.
+    /// See [`GitTagName`] for the syntax.
+    pub fn git_tag_template(&self) -> &'static str {
+        default_git_tag_template(self.contains_multiple_pub_packages)
     }
 
     pub fn cargo_lock_path(&self) -> PathBuf {
@@ -507,13 +1154,15 @@ fn check_overrides_typos(
 pub struct UpdateResult {
     pub version: Version,
     pub changelog: Option<String>,
+    /// Format of `changelog`.
+    pub changelog_format: ChangelogFormat,
     pub semver_check: SemverCheck,
 }
 
 impl UpdateResult {
     pub fn last_changes(&self) -> anyhow::Result<Option<ChangelogRelease>> {
         match &self.changelog {
-            Some(c) => changelog_parser::last_release_from_str(c),
+            Some(c) => self.changelog_format.last_release(c),
             None => Ok(None),
         }
     }
@@ -522,9 +1171,29 @@ impl UpdateResult {
 pub struct Updater<'a> {
     pub project: &'a Project,
     pub req: &'a UpdateRequest,
+    /// Tags of the repository.
+    pub git_tags: Vec<String>,
 }
 
 impl Updater<'_> {
+    /// Git tag of `version` of `package`.
+    /// If the tag doesn't exist yet, it's the tag of a new release.
+    fn git_tag(&self, package: &str, version: &Version) -> String {
+        let name = self.req.get_package_config(package).generic.git_tag_name;
+        let version = version.to_string();
+        let default = self.project.git_tag_template();
+        match name.find(&self.git_tags, default, package, &version) {
+            Some(tag) => tag.to_string(),
+            None => {
+                let date = self
+                    .req
+                    .release_date()
+                    .unwrap_or_else(|| Utc::now().date_naive());
+                name.render(default, package, &version, date)
+            }
+        }
+    }
+
     #[instrument(skip_all)]
     fn packages_to_update(
         &self,
@@ -534,7 +1203,15 @@ impl Updater<'_> {
     ) -> anyhow::Result<PackagesUpdate> {
         debug!("calculating local packages");
 
-        let packages_diffs = self.get_packages_diffs(registry_packages, repository)?;
+        let pool = thread_pool(self.req.jobs)?;
+        // The dependency order is only used to order the included changelogs.
+        let dependency_order = if self.req.packages_config.has_changelog_include() {
+            self.project.dependency_order()?
+        } else {
+            vec![]
+        };
+        let packages_diffs =
+            self.get_packages_diffs(registry_packages, repository, &pool, &dependency_order)?;
         let mut packages_to_check_for_deps: Vec<&Package> = vec![];
         let mut packages_to_update = PackagesUpdate::default();
 
@@ -549,6 +1226,7 @@ impl Updater<'_> {
             .collect();
 
         let new_workspace_version = new_workspace_version(
+            self.req,
             local_manifest_path,
             &packages_diffs,
             &workspace_version_pkgs,
@@ -557,36 +1235,70 @@ impl Updater<'_> {
             packages_to_update.with_workspace_version(new_workspace_version.clone());
         }
 
+        let mut packages_to_release = vec![];
         for (p, diff) in packages_diffs {
+            let version_bump_overrides = self.req.version_bump_overrides(&p.name);
             // Calculate next version without taking into account workspace version
-            let next_version = if let Some(max_workspace_version) = &new_workspace_version {
-                if workspace_version_pkgs.contains(p.name.as_str()) {
+            let inherits_workspace_version =
+                new_workspace_version.is_some() && workspace_version_pkgs.contains(p.name.as_str());
+            let next_version = match &new_workspace_version {
+                Some(max_workspace_version) if inherits_workspace_version => {
                     max_workspace_version.clone()
-                } else {
-                    p.version.next_from_diff(&diff)
                 }
-            } else {
-                p.version.next_from_diff(&diff)
+                _ => p.version.next_from_diff(&diff, &version_bump_overrides),
             };
 
             debug!("diff: {:?}, next_version: {}", &diff, next_version);
-            let current_version = p.version.clone();
-            if next_version != current_version || !diff.registry_package_exists {
+            if self.req.verbose_plan {
+                version_trace(p, &diff, &next_version, inherits_workspace_version).log();
+            }
+            if diff.is_release_needed(&p.version, &next_version) {
                 info!(
                     "{}: next version is {next_version}{}",
                     p.name,
                     diff.semver_check.outcome_str()
                 );
-                let update_result =
-                    self.update_result(diff.commits, next_version, p, diff.semver_check)?;
-
-                packages_to_update
-                    .updates_mut()
-                    .push((p.clone(), update_result));
+                packages_to_release.push((p, diff, next_version));
             } else if diff.is_version_published {
                 packages_to_check_for_deps.push(p);
             }
         }
+        let next_versions: HashMap<&str, Version> = packages_to_release
+            .iter()
+            .map(|(p, _, next_version)| (p.name.as_str(), next_version.clone()))
+            .collect();
+        for (p, diff, _) in &mut packages_to_release {
+            let package_config = self.req.get_package_config(&p.name);
+            if package_config.changelog_include_mode == ChangelogIncludeMode::Link {
+                let release_link = |package: &str, version: &Version| {
+                    let tag = self.git_tag(package, version);
+                    self.req
+                        .changelog_repo_url()
+                        .map(|r| r.git_release_link(&tag, &tag))
+                };
+                diff.commits.extend(included_release_commits(
+                    package_config
+                        .ordered_included_changelogs(&p.name, &dependency_order)
+                        .into_iter(),
+                    &next_versions,
+                    release_link,
+                ));
+            }
+        }
+        // The changelogs of the packages are independent, so we generate them in parallel.
+        let update_results = par_map(&pool, packages_to_release, |(p, diff, next_version)| {
+            self.update_result(
+                diff.commits,
+                diff.commit_groups,
+                next_version,
+                p,
+                diff.semver_check,
+            )
+            .map(|update_result| (p.clone(), update_result))
+        });
+        for update_result in update_results {
+            packages_to_update.updates_mut().push(update_result?);
+        }
 
         let changed_packages: Vec<(&Package, &Version)> = packages_to_update
             .updates()
@@ -603,6 +1315,8 @@ impl Updater<'_> {
         &self,
         registry_packages: &PackagesCollection,
         repository: &Repo,
+        pool: &ThreadPool,
+        dependency_order: &[String],
     ) -> anyhow::Result<Vec<(&Package, Diff)>> {
         // Store diff for each package. This operation is not thread safe, so we do it in one
         // package at a time.
@@ -624,38 +1338,81 @@ impl Updater<'_> {
             .iter()
             .map(|(p, d)| (p.name.clone(), d.commits.clone()))
             .collect();
+        let release_commits = self.release_commits_patterns(&packages_diffs)?;
 
-        let semver_check_result: anyhow::Result<()> =
+        let semver_check_result: anyhow::Result<()> = pool.install(|| {
             packages_diffs.par_iter_mut().try_for_each(|(p, diff)| {
                 let registry_package = registry_packages.get_package(&p.name);
+                let package_config = self.req.get_package_config(&p.name);
+                if registry_package.is_some() {
+                    diff.add_commits(&inlined_commits(
+                        &p.name,
+                        &package_config,
+                        dependency_order,
+                        &packages_commits,
+                    ));
+                }
+                if let Some(pattern) = release_commits.get(p.name.as_str()) {
+                    if !has_release_commits(&diff.commits, pattern) {
+                        info!(
+                            "{}: no commit matches release_commits `{pattern}`, skipping release",
+                            p.name
+                        );
+                        diff.skip_release();
+                    }
+                }
                 if let Some(registry_package) = registry_package {
                     let package_path = get_package_path(p, repository, &self.project.root)
                         .context("can't retrieve package path")?;
-                    let package_config = self.req.get_package_config(&p.name);
-                    for pkg_to_include in &package_config.changelog_include {
-                        if let Some(commits) = packages_commits.get(pkg_to_include) {
-                            diff.add_commits(commits);
-                        }
-                    }
-                    if should_check_semver(p, package_config.semver_check())
-                        && diff.should_update_version()
+                    if diff.should_update_version()
+                        && should_check_semver(
+                            p,
+                            package_config.semver_check(),
+                            self.req.semver_check_missing,
+                            semver_check::is_cargo_semver_checks_installed,
+                        )?
                     {
                         let registry_package_path = registry_package
                             .package_path()
                             .context("can't retrieve registry package path")?;
-                        let semver_check =
-                            semver_check::run_semver_check(&package_path, registry_package_path)
-                                .context("error while running cargo-semver-checks")?;
+                        let baseline = match &package_config.generic.semver_check_baseline_ref {
+                            Some(baseline_ref) => SemverCheckBaseline::GitRev(baseline_ref),
+                            None => SemverCheckBaseline::Registry(registry_package_path),
+                        };
+                        let semver_check = semver_check::run_semver_check(&package_path, baseline)
+                            .context("error while running cargo-semver-checks")?;
                         diff.set_semver_check(semver_check);
                     }
                 }
                 Ok(())
-            });
+            })
+        });
         semver_check_result?;
 
         Ok(packages_diffs)
     }
 
+    /// `release_commits` pattern of each package that has one.
+    /// Fails if a pattern isn't a valid regex.
+    fn release_commits_patterns<'p>(
+        &self,
+        packages_diffs: &[(&'p Package, Diff)],
+    ) -> anyhow::Result<HashMap<&'p str, Regex>> {
+        packages_diffs
+            .iter()
+            .filter_map(|(p, _)| {
+                let pattern = self
+                    .req
+                    .get_package_config(&p.name)
+                    .generic
+                    .release_commits?;
+                let regex = Regex::new(&pattern)
+                    .with_context(|| format!("invalid release_commits `{pattern}`"));
+                Some(regex.map(|regex| (p.name.as_str(), regex)))
+            })
+            .collect()
+    }
+
     /// Return the packages that depend on the `changed_packages`.
     fn dependent_packages(
         &self,
@@ -676,10 +1433,12 @@ impl Updater<'_> {
             })
             .map(|(&p, deps)| {
                 let deps: Vec<&str> = deps.iter().map(|d| d.name.as_str()).collect();
-                let change = format!(
-                    "chore: updated the following local packages: {}",
-                    deps.join(", ")
-                );
+                let note = self
+                    .req
+                    .get_package_config(&p.name)
+                    .generic
+                    .changelog_dependency_note;
+                let change = dependency_change_message(note.as_deref(), &deps);
                 let next_version = { p.version.increment_patch() };
                 info!(
                     "{}: dependencies changed. Next version is {next_version}",
@@ -689,6 +1448,7 @@ impl Updater<'_> {
                     p.clone(),
                     self.update_result(
                         vec![Commit::new(NO_COMMIT_ID.to_string(), change)],
+                        HashMap::new(),
                         next_version,
                         p,
                         SemverCheck::Skipped,
@@ -702,35 +1462,42 @@ impl Updater<'_> {
     fn update_result(
         &self,
         commits: Vec<Commit>,
+        commit_groups: HashMap<String, Vec<String>>,
         version: Version,
         package: &Package,
         semver_check: SemverCheck,
     ) -> anyhow::Result<UpdateResult> {
         let release_link = {
-            let prev_tag = self
-                .project
-                .git_tag(&package.name, &package.version.to_string());
-            let next_tag = self.project.git_tag(&package.name, &version.to_string());
+            let prev_tag = self.git_tag(&package.name, &package.version);
+            let next_tag = self.git_tag(&package.name, &version);
             self.req
-                .repo_url
-                .as_ref()
+                .changelog_repo_url()
                 .map(|r| r.git_release_link(&prev_tag, &next_tag))
         };
 
-        let pr_link = self.req.repo_url.as_ref().map(|r| r.git_pr_link());
+        let pr_link = self.req.changelog_repo_url().map(|r| r.git_pr_link());
+        let commit_link = self
+            .req
+            .changelog_repo_url()
+            .filter(|_| self.req.changelog_req.commit_links)
+            .map(|r| r.git_commit_link());
 
-        lazy_static::lazy_static! {
-            // match PR/issue numbers, e.g. `#123`
-            static ref PR_RE: Regex = Regex::new("#(\\d+)").unwrap();
-        }
+        let cfg = self.req.get_package_config(package.name.as_str());
+        let local_manifest_dir = self.req.local_manifest_dir()?;
         let changelog = {
-            let cfg = self.req.get_package_config(package.name.as_str());
             let changelog_req = cfg
                 .should_update_changelog()
                 .then_some(self.req.changelog_req.clone());
             let old_changelog = fs::read_to_string(self.req.changelog_path(package)).ok();
             let commits: Vec<Commit> = commits
                 .into_iter()
+                // Use the title of the merged Pull Request, if any.
+                .map(|c| match self.req.merged_prs.get(&c.id) {
+                    Some(entry) => Commit::new(c.id, entry.clone()),
+                    None => c,
+                })
+                // Merge commits to include were already replaced with the title of the PR.
+                .filter(|c| !merge_commit::is_merge_commit(&c.message))
                 // If not conventional commit, only consider the first line of the commit message.
                 .filter_map(|c| {
                     if c.clone().into_conventional().is_ok() {
@@ -742,25 +1509,63 @@ impl Updater<'_> {
                             .map(|line| Commit::new(c.id.clone(), line.to_string()))
                     }
                 })
-                // replace #123 with [#123](https://link_to_pr).
+                // replace #123 with a link to the PR, e.g. [#123](https://link_to_pr).
                 // If the number refers to an issue, GitHub redirects the PR link to the issue link.
                 .map(|c| {
                     if let Some(pr_link) = &pr_link {
-                        let result = PR_RE.replace_all(&c.message, format!("[#$1]({pr_link}/$1)"));
-                        Commit::new(c.id, result.to_string())
+                        let message = self
+                            .req
+                            .changelog_req
+                            .format
+                            .link_pr_numbers(&c.message, pr_link);
+                        Commit::new(c.id, message)
                     } else {
                         c
                     }
                 })
+                // Credit the author of the commit, e.g. `fix bug (@user)`.
+                .map(|c| {
+                    if !self.req.changelog_credit_authors {
+                        return c;
+                    }
+                    match commit_author(&c.id, &self.req.commit_authors, local_manifest_dir) {
+                        Some(author) => {
+                            Commit::new(c.id.clone(), credit_author(&c.message, &author))
+                        }
+                        None => c,
+                    }
+                })
                 .collect();
             changelog_req
-                .map(|r| get_changelog(commits, &version, Some(r), old_changelog, release_link))
+                .map(|r| {
+                    get_changelog(
+                        commits,
+                        commit_groups,
+                        &version,
+                        Some(r),
+                        old_changelog,
+                        release_link,
+                        commit_link,
+                    )
+                })
                 .transpose()
         }?;
+        if let Some(changelog) = changelog
+            .as_deref()
+            .filter(|_| cfg.generic.changelog_require_nonempty)
+        {
+            check_changelog_entries(
+                &package.name,
+                &version,
+                changelog,
+                self.req.changelog_req.format,
+            )?;
+        }
 
         Ok(UpdateResult {
             version,
             changelog,
+            changelog_format: self.req.changelog_req.format,
             semver_check,
         })
     }
@@ -795,9 +1600,7 @@ impl Updater<'_> {
             }
         }
 
-        let git_tag = self
-            .project
-            .git_tag(&package.name, &package.version.to_string());
+        let git_tag = self.git_tag(&package.name, &package.version);
         let tag_commit = repository.get_tag_commit(&git_tag);
         if tag_commit.is_some() {
             let registry_package = registry_package.with_context(|| format!("package `{}` not found in the registry, but the git tag {git_tag} exists. Consider running `cargo publish` manually to publish this package.", package.name))?;
@@ -807,8 +1610,37 @@ impl Updater<'_> {
                 package.name, package.version, registry_package.version
             )
         }
+        let package_config = self.req.get_package_config(&package.name).generic;
+        let since_commit = package_config
+            .since
+            .as_deref()
+            .map(|since| {
+                repository
+                    .resolve_commit(since)
+                    .with_context(|| format!("invalid since `{since}`"))
+            })
+            .transpose()?;
+        let tag_commit = match (&since_commit, &package_config.changelog_tag_pattern) {
+            (Some(since_commit), _) => Some(since_commit.clone()),
+            (None, Some(pattern)) => {
+                let pattern = Regex::new(pattern)
+                    .with_context(|| format!("invalid changelog_tag_pattern `{pattern}`"))?;
+                let tags = repository.tags_merged_into_head()?;
+                previous_tag(&tags, &pattern).and_then(|tag| repository.get_tag_commit(tag))
+            }
+            (None, None) => tag_commit,
+        };
         loop {
             let current_commit_message = repository.current_commit_message()?;
+            // Release commits don't trigger a new release.
+            let is_release_commit = is_current_release_commit(
+                repository,
+                &current_commit_message,
+                self.req.release_commit_marker(),
+            );
+            let current_commit_message = merge_commit::merge_commit_entry(&current_commit_message)
+                .filter(|_| self.req.changelog_req.include_merge_commits)
+                .unwrap_or(current_commit_message);
             let current_commit_hash = repository.current_commit_hash()?;
             if let Some(registry_package) = registry_package {
                 debug!("package {} found in cargo registry", registry_package.name);
@@ -868,7 +1700,7 @@ impl Updater<'_> {
                     info!("{}: the local package has already a different version with respect to the registry package, so release-plz will not update it", package.name);
                     diff.set_version_unpublished();
                     break;
-                } else {
+                } else if !is_release_commit {
                     debug!("packages are different");
                     // At this point of the git history, the two packages are different,
                     // which means that this commit is not present in the published package.
@@ -878,16 +1710,31 @@ impl Updater<'_> {
                     ));
                 }
             } else {
-                diff.commits.push(Commit::new(
-                    current_commit_hash,
-                    current_commit_message.clone(),
-                ));
+                // The package was never published: consider the whole history,
+                // unless the user specified where to start from.
+                if is_commit_too_old(repository, since_commit.as_deref(), &current_commit_hash) {
+                    break;
+                }
+                if !is_release_commit {
+                    diff.commits.push(Commit::new(
+                        current_commit_hash,
+                        current_commit_message.clone(),
+                    ));
+                }
             }
             if let Err(_err) = repository.checkout_previous_commit_at_path(&package_path) {
                 debug!("there are no other commits");
                 break;
             }
         }
+        if !package_config.changelog_group_by_path.is_empty() {
+            diff.commit_groups = commit_path_groups(
+                &diff.commits,
+                repository,
+                &package_path,
+                &package_config.changelog_group_by_path,
+            )?;
+        }
         repository
             .checkout_head()
             .context("can't checkout to head after calculating diff")?;
@@ -910,6 +1757,7 @@ impl Updater<'_> {
 }
 
 fn new_workspace_version(
+    req: &UpdateRequest,
     local_manifest_path: &Path,
     packages_diffs: &[(&Package, Diff)],
     workspace_version_pkgs: &HashSet<String>,
@@ -923,7 +1771,9 @@ fn new_workspace_version(
         .filter_map(|workspace_package| {
             for (p, diff) in packages_diffs {
                 if workspace_package == &p.name {
-                    let next = p.version.next_from_diff(diff);
+                    let next = p
+                        .version
+                        .next_from_diff(diff, &req.version_bump_overrides(&p.name));
                     if let Some(workspace_version) = &workspace_version {
                         if &next >= workspace_version {
                             return Some(next);
@@ -939,12 +1789,15 @@ fn new_workspace_version(
 
 fn get_changelog(
     commits: Vec<Commit>,
+    commit_groups: HashMap<String, Vec<String>>,
     next_version: &Version,
     changelog_req: Option<ChangelogRequest>,
     old_changelog: Option<String>,
     release_link: Option<String>,
+    commit_link: Option<String>,
 ) -> anyhow::Result<String> {
-    let mut changelog_builder = ChangelogBuilder::new(commits, next_version.to_string());
+    let mut changelog_builder =
+        ChangelogBuilder::new(commits, next_version.to_string()).with_commit_groups(commit_groups);
     if let Some(changelog_req) = changelog_req {
         if let Some(release_date) = changelog_req.release_date {
             changelog_builder = changelog_builder.with_release_date(release_date)
@@ -952,9 +1805,29 @@ fn get_changelog(
         if let Some(config) = changelog_req.changelog_config {
             changelog_builder = changelog_builder.with_config(config)
         }
+        if let Some(prefix) = changelog_req.commit_summary_prefix {
+            changelog_builder = changelog_builder.with_commit_summary_prefix(prefix)
+        }
+        if let Some(commit_sort) = changelog_req.commit_sort {
+            changelog_builder = changelog_builder.with_commit_sort(commit_sort)
+        }
+        changelog_builder = changelog_builder
+            .with_sort_by_scope(changelog_req.sort_by_scope)
+            .with_skip_ci_commits(changelog_req.skip_ci_commits)
+            .with_unreleased_section(changelog_req.unreleased_section)
+            .with_dedup_existing(changelog_req.dedup_existing)
+            .with_header_link(changelog_req.header_links)
+            .with_format(changelog_req.format);
         if let Some(link) = release_link {
             changelog_builder = changelog_builder.with_release_link(link)
         }
+        if let Some(link) = commit_link {
+            changelog_builder = changelog_builder.with_commit_link(link)
+        }
+        if let Some(max_entries) = changelog_req.max_entries {
+            changelog_builder = changelog_builder.with_max_entries(max_entries)
+        }
+        changelog_builder = changelog_builder.with_context(changelog_req.context);
         if let Some(old_changelog) = &old_changelog {
             if let Ok(Some(last_version)) = changelog_parser::last_version_from_str(old_changelog) {
                 changelog_builder = changelog_builder.with_previous_version(last_version)
@@ -964,11 +1837,54 @@ fn get_changelog(
     let new_changelog = changelog_builder.build();
     let changelog = match &old_changelog {
         Some(old_changelog) => new_changelog.prepend(old_changelog)?,
-        None => new_changelog.generate(), // Old changelog doesn't exist.
+        None => new_changelog.generate()?, // Old changelog doesn't exist.
     };
     Ok(changelog)
 }
 
+/// Changelog groups of the `commits`, by commit id.
+/// A commit belongs to the group of each glob of `group_by_path` matching
+/// one of the files it changes. The globs are relative to `package_path`.
+fn commit_path_groups(
+    commits: &[Commit],
+    repository: &Repo,
+    package_path: &Path,
+    group_by_path: &BTreeMap<String, String>,
+) -> anyhow::Result<HashMap<String, Vec<String>>> {
+    let patterns = group_by_path
+        .iter()
+        .map(|(glob, group)| {
+            let pattern = glob::Pattern::new(glob)
+                .with_context(|| format!("invalid changelog_group_by_path glob `{glob}`"))?;
+            Ok((pattern, group.as_str()))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let package_dir = strip_prefix(package_path, repository.directory())?;
+    let mut commit_groups = HashMap::new();
+    for commit in commits.iter().filter(|c| c.id != NO_COMMIT_ID) {
+        let files = repository.commit_files(&commit.id)?;
+        let package_files: Vec<&Path> = files
+            .iter()
+            .filter_map(|file| Path::new(file).strip_prefix(package_dir).ok())
+            .collect();
+        let groups = path_groups(&patterns, &package_files);
+        if !groups.is_empty() {
+            commit_groups.insert(commit.id.clone(), groups);
+        }
+    }
+    Ok(commit_groups)
+}
+
+/// Groups of the `patterns` matching at least one of the `files`, without duplicates.
+fn path_groups(patterns: &[(glob::Pattern, &str)], files: &[&Path]) -> Vec<String> {
+    let groups: BTreeSet<&str> = patterns
+        .iter()
+        .filter(|(pattern, _)| files.iter().any(|file| pattern.matches_path(file)))
+        .map(|(_, group)| *group)
+        .collect();
+    groups.into_iter().map(String::from).collect()
+}
+
 fn get_package_path(
     package: &Package,
     repository: &Repo,
@@ -990,6 +1906,111 @@ fn get_repo_path(
     Ok(result_path)
 }
 
+/// Template of the git tags if `git_tag_name` isn't configured.
+/// The package name is only in the tag if the workspace contains multiple publishable packages.
+pub(crate) fn default_git_tag_template(contains_multiple_pub_packages: bool) -> &'static str {
+    if contains_multiple_pub_packages {
+        "{{ package }}-v{{ version }}"
+    } else {
+        "v{{ version }}"
+    }
+}
+
+/// Most recent tag matching the pattern.
+/// `tags` are sorted from the most recent to the oldest.
+fn previous_tag<'a>(tags: &'a [String], pattern: &Regex) -> Option<&'a str> {
+    tags.iter()
+        .map(|tag| tag.as_str())
+        .find(|tag| pattern.is_match(tag))
+}
+
+/// Thread pool running the operations of the packages that don't depend on each other.
+/// If `jobs` is `None`, use one thread per CPU.
+fn thread_pool(jobs: Option<usize>) -> anyhow::Result<ThreadPool> {
+    ThreadPoolBuilder::new()
+        .num_threads(jobs.unwrap_or_default())
+        .build()
+        .context("cannot build thread pool")
+}
+
+/// Apply `f` to the `items` in parallel on the `pool`.
+/// The results are in the same order as the items.
+fn par_map<T: Send, R: Send>(
+    pool: &ThreadPool,
+    items: Vec<T>,
+    f: impl Fn(T) -> R + Send + Sync,
+) -> Vec<R> {
+    pool.install(|| items.into_par_iter().map(f).collect())
+}
+
+/// Whether the commit was created by release-plz, i.e. a trailer paragraph of the
+/// commit message contains the `marker` trailer.
+/// Trailers aren't always in the last paragraph, e.g. when GitHub squashes the
+/// release PR, it appends the `Co-authored-by` trailers in a separate paragraph.
+fn is_release_commit(message: &str, marker: &str) -> bool {
+    lazy_static::lazy_static! {
+        // e.g. `Signed-off-by: me`
+        static ref TRAILER_RE: Regex = Regex::new(r"^[\w-]+: .+$").unwrap();
+    }
+    message
+        .trim_end()
+        .split("\n\n")
+        // The first paragraph is the summary.
+        .skip(1)
+        .any(|paragraph| {
+            let lines: Vec<&str> = paragraph.lines().map(str::trim).collect();
+            lines.contains(&marker)
+                && lines
+                    .iter()
+                    .all(|line| *line == marker || TRAILER_RE.is_match(line))
+        })
+}
+
+/// Whether the checked out commit is a release commit, or a merge commit that merges
+/// a release commit, e.g. when the release PR is merged with a merge commit.
+fn is_current_release_commit(repository: &Repo, message: &str, marker: &str) -> bool {
+    is_release_commit(message, marker)
+        || (merge_commit::is_merge_commit(message)
+            && repository
+                .commit_message("HEAD^2")
+                .is_ok_and(|merged_message| is_release_commit(&merged_message, marker)))
+}
+
+/// Author credited in the changelog entry of `commit`:
+/// the username in `commit_authors`, resolved with the git forge API, e.g. `@user`,
+/// or the name of the git author in the repository of `dir`.
+/// Returns `None` for commits that don't exist in the repository, e.g. dependency updates.
+fn commit_author(
+    commit: &str,
+    commit_authors: &HashMap<String, String>,
+    dir: &Path,
+) -> Option<String> {
+    if commit == NO_COMMIT_ID {
+        return None;
+    }
+    if let Some(username) = commit_authors.get(commit) {
+        return Some(format!("@{username}"));
+    }
+    git_cmd::git_in_dir(dir, &["log", "-1", "--format=%an", commit])
+        .ok()
+        .filter(|name| !name.is_empty())
+}
+
+/// Append the `author` to the first line of the commit `message`, e.g. `fix: fix bug (@user)`.
+fn credit_author(message: &str, author: &str) -> String {
+    match message.split_once('\n') {
+        Some((title, body)) => format!("{} ({author})\n{body}", title.trim_end()),
+        None => format!("{} ({author})", message.trim_end()),
+    }
+}
+
+/// Whether at least one of the commits matches the `release_commits` pattern.
+fn has_release_commits(commits: &[Commit], pattern: &Regex) -> bool {
+    commits
+        .iter()
+        .any(|commit| pattern.is_match(&commit.message))
+}
+
 /// Check if commit belongs to a previous version of the package.
 fn is_commit_too_old(
     repository: &Repo,
@@ -1007,9 +2028,26 @@ fn is_commit_too_old(
 
 /// Check if release-plz should check the semver compatibility of the package.
 /// - `run_semver_check` is true if the user wants to run the semver check.
-fn should_check_semver(package: &Package, run_semver_check: bool) -> bool {
-    let is_cargo_semver_checks_installed = semver_check::is_cargo_semver_checks_installed;
-    run_semver_check && is_library(package) && is_cargo_semver_checks_installed()
+/// - `missing` is what to do if cargo-semver-checks isn't installed.
+fn should_check_semver(
+    package: &Package,
+    run_semver_check: bool,
+    missing: SemverCheckMissing,
+    is_cargo_semver_checks_installed: impl Fn() -> bool,
+) -> anyhow::Result<bool> {
+    if !run_semver_check || !is_library(package) {
+        return Ok(false);
+    }
+    if is_cargo_semver_checks_installed() {
+        return Ok(true);
+    }
+    let message = semver_check::missing_cargo_semver_checks_message(&package.name);
+    match missing {
+        SemverCheckMissing::Error => anyhow::bail!(message),
+        SemverCheckMissing::Warn => warn!("{message}"),
+        SemverCheckMissing::Skip => debug!("{message}"),
+    }
+    Ok(false)
 }
 
 pub fn workspace_packages(metadata: &Metadata) -> anyhow::Result<Vec<Package>> {
@@ -1118,9 +2156,522 @@ mod tests {
 
     use super::*;
     use super::{check_for_typos, Project};
-    use crate::RequestReleaseValidator;
+    use crate::{next_version_from_commits, RequestReleaseValidator};
     use std::{collections::HashSet, path::Path};
 
+    #[test]
+    fn commits_are_grouped_by_the_paths_they_change() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = Repo::init(dir.path());
+        let mut commits = vec![];
+        for (files, message) in [
+            (&["parser/lib.rs"][..], "feat: parse floats"),
+            (&["runtime/lib.rs"], "fix: fix stack overflow"),
+            (&["parser/ast.rs", "runtime/gc.rs"], "fix: fix leak"),
+            (&["README.md"], "docs: update readme"),
+        ] {
+            for file in files {
+                let path = dir.path().join(file);
+                fs::create_dir_all(path.parent().unwrap()).unwrap();
+                fs::write(path, message).unwrap();
+            }
+            repo.add_all_and_commit(message).unwrap();
+            commits.push(Commit::new(
+                repo.current_commit_hash().unwrap(),
+                message.to_string(),
+            ));
+        }
+        let group_by_path = BTreeMap::from([
+            ("parser/**".to_string(), "Parser".to_string()),
+            ("runtime/**".to_string(), "Runtime".to_string()),
+        ]);
+        let groups = commit_path_groups(&commits, &repo, dir.path(), &group_by_path).unwrap();
+        let group_of = |i: usize| groups.get(&commits[i].id).cloned().unwrap_or_default();
+        assert_eq!(group_of(0), ["Parser"]);
+        assert_eq!(group_of(1), ["Runtime"]);
+        assert_eq!(group_of(2), ["Parser", "Runtime"]);
+        assert!(group_of(3).is_empty());
+    }
+
+    #[test]
+    fn only_tags_matching_pattern_are_used_as_previous_tag() {
+        let tags = [
+            "v2.0.0".to_string(),
+            "other-crate-v1.3.0".to_string(),
+            "my-crate-v1.2.0".to_string(),
+            "my-crate-v1.1.0".to_string(),
+        ];
+        let pattern = Regex::new(r"^my-crate-v\d+\.\d+\.\d+$").unwrap();
+        assert_eq!(previous_tag(&tags, &pattern), Some("my-crate-v1.2.0"));
+    }
+
+    #[test]
+    fn release_is_triggered_by_matching_commit() {
+        let commits = [
+            Commit::new(
+                crate::NO_COMMIT_ID.to_string(),
+                "chore: bump deps".to_string(),
+            ),
+            Commit::new(crate::NO_COMMIT_ID.to_string(), "feat: new api".to_string()),
+        ];
+        let pattern = Regex::new(r"^(feat|fix)").unwrap();
+        assert!(has_release_commits(&commits, &pattern));
+    }
+
+    #[test]
+    fn release_is_skipped_if_no_commit_matches() {
+        let commits = [
+            Commit::new(
+                crate::NO_COMMIT_ID.to_string(),
+                "chore: bump deps".to_string(),
+            ),
+            Commit::new(
+                crate::NO_COMMIT_ID.to_string(),
+                "docs: fix typo".to_string(),
+            ),
+        ];
+        let pattern = Regex::new(r"^(feat|fix)").unwrap();
+        assert!(!has_release_commits(&commits, &pattern));
+    }
+
+    #[test]
+    fn par_map_is_bounded_by_jobs() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let pool = thread_pool(Some(2)).unwrap();
+        let running = AtomicUsize::new(0);
+        let max_running = AtomicUsize::new(0);
+        par_map(&pool, (0..16).collect(), |_: u32| {
+            let now_running = running.fetch_add(1, Ordering::SeqCst) + 1;
+            max_running.fetch_max(now_running, Ordering::SeqCst);
+            std::thread::sleep(std::time::Duration::from_millis(5));
+            running.fetch_sub(1, Ordering::SeqCst);
+        });
+        assert!(max_running.load(Ordering::SeqCst) <= 2);
+        assert_eq!(pool.current_num_threads(), 2);
+    }
+
+    #[test]
+    fn par_map_with_one_job_matches_serial_map() {
+        let pool = thread_pool(Some(1)).unwrap();
+        let items: Vec<u32> = (0..32).collect();
+        let serial: Vec<String> = items.iter().map(|i| format!("item {i}")).collect();
+        let parallel = par_map(&pool, items, |i| format!("item {i}"));
+        assert_eq!(parallel, serial);
+    }
+
+    #[test]
+    fn since_ref_is_lower_bound_of_commits() {
+        let repository_dir = tempdir().unwrap();
+        let repo = Repo::init(&repository_dir);
+        let file = repository_dir.path().join("file.txt");
+        fs::write(&file, "old").unwrap();
+        repo.add_all_and_commit("feat: old feature").unwrap();
+        let old_commit = repo.current_commit_hash().unwrap();
+        repo.tag("since-ref").unwrap();
+        fs::write(&file, "new").unwrap();
+        repo.add_all_and_commit("feat: new feature").unwrap();
+        let new_commit = repo.current_commit_hash().unwrap();
+
+        let since_commit = repo.resolve_commit("since-ref").unwrap();
+        assert_eq!(since_commit, old_commit);
+        assert!(is_commit_too_old(&repo, Some(&since_commit), &old_commit));
+        assert!(!is_commit_too_old(&repo, Some(&since_commit), &new_commit));
+        assert!(!is_commit_too_old(&repo, None, &old_commit));
+    }
+
+    #[test]
+    fn missing_cargo_semver_checks_is_handled_according_to_policy() {
+        let library: Package = fake_package::FakePackage::new("aaa").library().into();
+        let not_installed = || false;
+
+        let error = should_check_semver(&library, true, SemverCheckMissing::Error, not_installed)
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "aaa: cargo-semver-checks isn't installed, so the API compatibility of the package can't be checked. Install it with `cargo install cargo-semver-checks --locked`, or disable `semver_check`"
+        );
+        assert!(
+            !should_check_semver(&library, true, SemverCheckMissing::Warn, not_installed).unwrap()
+        );
+        assert!(
+            !should_check_semver(&library, true, SemverCheckMissing::Skip, not_installed).unwrap()
+        );
+    }
+
+    #[test]
+    fn missing_cargo_semver_checks_is_ignored_if_semver_check_is_not_needed() {
+        let library: Package = fake_package::FakePackage::new("aaa").library().into();
+        let binary: Package = fake_package::FakePackage::new("bbb").into();
+        let not_installed = || false;
+        assert!(
+            !should_check_semver(&library, false, SemverCheckMissing::Error, not_installed)
+                .unwrap()
+        );
+        assert!(
+            !should_check_semver(&binary, true, SemverCheckMissing::Error, not_installed).unwrap()
+        );
+        assert!(should_check_semver(&library, true, SemverCheckMissing::Error, || true).unwrap());
+    }
+
+    #[test]
+    fn commits_with_marker_are_release_commits() {
+        let marker = "release-plz: true";
+        assert!(is_release_commit(
+            "chore: release\n\nrelease-plz: true",
+            marker
+        ));
+        assert!(is_release_commit(
+            "chore: release v1.0.0\n\nSigned-off-by: me\nrelease-plz: true\n",
+            marker
+        ));
+        assert!(!is_release_commit("feat: new feature", marker));
+        assert!(!is_release_commit(
+            "fix: typo\n\nrelease-plz: true is mentioned in the body.\n\nSigned-off-by: me",
+            marker
+        ));
+    }
+
+    #[test]
+    fn squashed_release_pr_is_release_commit() {
+        let marker = "release-plz: true";
+        assert!(is_release_commit(
+            "chore: release (#12)\n\n* chore: release\n\nrelease-plz: true\n\nCo-authored-by: bot <bot@example.com>",
+            marker
+        ));
+        assert!(!is_release_commit(
+            "release-plz: true\n\nCo-authored-by: bot <bot@example.com>",
+            marker
+        ));
+        assert!(!is_release_commit(
+            "fix: typo\n\nThe marker is\nrelease-plz: true",
+            marker
+        ));
+    }
+
+    #[test]
+    fn no_previous_tag_if_no_tag_matches_pattern() {
+        let tags = ["v2.0.0".to_string()];
+        let pattern = Regex::new(r"^my-crate-v").unwrap();
+        assert_eq!(previous_tag(&tags, &pattern), None);
+    }
+
+    #[test]
+    fn excluded_packages_are_not_included_in_changelog() {
+        let config = PackageUpdateConfig {
+            changelog_include: vec!["pkg1".to_string(), "pkg2".to_string()],
+            changelog_exclude: vec!["pkg2".to_string()],
+            ..Default::default()
+        };
+        let included = config.included_changelogs("my-crate", &["pkg1", "pkg2"]);
+        assert_eq!(included, vec!["pkg1"]);
+    }
+
+    #[test]
+    fn author_is_credited_in_first_line_of_commit_message() {
+        assert_eq!(
+            credit_author("fix: fix bug", "@user"),
+            "fix: fix bug (@user)"
+        );
+        assert_eq!(
+            credit_author(
+                "feat: add api\n\nBREAKING CHANGE: removed old api",
+                "Jane Doe"
+            ),
+            "feat: add api (Jane Doe)\n\nBREAKING CHANGE: removed old api"
+        );
+    }
+
+    #[test]
+    fn commit_author_is_resolved_with_forge_username_or_git_author() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = git_cmd::Repo::init(dir.path());
+        fs::write(dir.path().join("a.txt"), "a").unwrap();
+        repo.add_all_and_commit("fix: fix bug").unwrap();
+        let commit = repo.current_commit_hash().unwrap();
+
+        let forge_authors = HashMap::from([(commit.clone(), "octocat".to_string())]);
+        assert_eq!(
+            commit_author(&commit, &forge_authors, dir.path()).as_deref(),
+            Some("@octocat")
+        );
+        assert_eq!(
+            commit_author(&commit, &HashMap::new(), dir.path()).as_deref(),
+            Some("author_name")
+        );
+    }
+
+    #[test]
+    fn commit_author_is_omitted_if_unresolvable() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = git_cmd::Repo::init(dir.path());
+        fs::write(dir.path().join("a.txt"), "a").unwrap();
+        repo.add_all_and_commit("fix: fix bug").unwrap();
+
+        assert_eq!(
+            commit_author(NO_COMMIT_ID, &HashMap::new(), dir.path()),
+            None
+        );
+        let unknown_commit = "0123456789abcdef0123456789abcdef01234567";
+        assert_eq!(
+            commit_author(unknown_commit, &HashMap::new(), dir.path()),
+            None
+        );
+    }
+
+    #[test]
+    fn changelog_include_glob_expands_to_matching_members() {
+        let config = PackageUpdateConfig {
+            changelog_include: vec!["api-*".to_string()],
+            ..Default::default()
+        };
+        let included = config.included_changelogs("cli", &["cli", "api-server", "api-client"]);
+        assert_eq!(included, vec!["api-client", "api-server"]);
+    }
+
+    #[test]
+    fn changelog_include_glob_doesnt_match_the_package_itself() {
+        let config = PackageUpdateConfig {
+            changelog_include: vec!["api-*".to_string()],
+            ..Default::default()
+        };
+        let included = config.included_changelogs("api-server", &["api-server", "api-client"]);
+        assert_eq!(included, vec!["api-client"]);
+    }
+
+    #[test]
+    fn excluded_packages_are_removed_from_changelog_include_glob() {
+        let config = PackageUpdateConfig {
+            changelog_include: vec!["api-*".to_string(), "api-client".to_string()],
+            changelog_exclude: vec!["api-server".to_string()],
+            ..Default::default()
+        };
+        let included =
+            config.included_changelogs("cli", &["cli", "api-server", "api-client", "api-types"]);
+        assert_eq!(included, vec!["api-client", "api-types"]);
+    }
+
+    #[test]
+    fn published_versions_are_looked_up_in_publish_registry() {
+        let package: Package = fake_package::FakePackage::new("my-pkg").into();
+        let request = UpdateRequest::new(fake_package::metadata::fake_metadata()).unwrap();
+        assert_eq!(request.lookup_registry(&package), None);
+
+        let request = request.with_default_package_config(
+            UpdateConfig::default().with_publish_registry("my-registry"),
+        );
+        assert_eq!(
+            request.lookup_registry(&package).as_deref(),
+            Some("my-registry")
+        );
+
+        let request = request.with_registry("other-registry".to_string());
+        assert_eq!(
+            request.lookup_registry(&package).as_deref(),
+            Some("other-registry")
+        );
+    }
+
+    #[test]
+    fn zero_based_versioning_reaches_version_bump_overrides() {
+        let request = UpdateRequest::new(fake_package::metadata::fake_metadata()).unwrap();
+        assert!(
+            !request
+                .version_bump_overrides("my-pkg")
+                .zero_based_versioning
+        );
+
+        let request = request
+            .with_default_package_config(UpdateConfig::default().with_zero_based_versioning(true));
+        let overrides = VersionBumpOverrides {
+            min_version_bump: Some(VersionIncrement::Minor),
+            ..request.version_bump_overrides("my-pkg")
+        };
+        let (next, _) =
+            next_version_from_commits(&Version::new(0, 0, 3), ["feat!: remove api"], &overrides);
+        assert_eq!(next, Version::new(0, 0, 4));
+    }
+
+    #[test]
+    fn published_versions_are_looked_up_in_manifest_publish_registry() {
+        let mut package: Package = fake_package::FakePackage::new("my-pkg").into();
+        package.publish = Some(vec!["my-registry".to_string()]);
+        let request = UpdateRequest::new(fake_package::metadata::fake_metadata()).unwrap();
+        assert_eq!(
+            request.lookup_registry(&package).as_deref(),
+            Some("my-registry")
+        );
+    }
+
+    #[test]
+    fn dependency_note_lists_updated_dependencies() {
+        let note = "Update the {{ dependencies }} crates to their latest version";
+        assert_eq!(
+            dependency_change_message(Some(note), &["pkg1", "pkg2"]),
+            "chore: Update the pkg1, pkg2 crates to their latest version"
+        );
+        assert_eq!(
+            dependency_change_message(Some("deps: {{dependencies}}"), &["pkg1"]),
+            "chore: deps: pkg1"
+        );
+    }
+
+    #[test]
+    fn dependency_change_lists_updated_dependencies_by_default() {
+        assert_eq!(
+            dependency_change_message(None, &["pkg1", "pkg2"]),
+            "chore: updated the following local packages: pkg1, pkg2"
+        );
+    }
+
+    fn included_packages_commits() -> HashMap<String, Vec<Commit<'static>>> {
+        HashMap::from([
+            (
+                "core".to_string(),
+                vec![
+                    Commit::new("a".to_string(), "feat: add api".to_string()),
+                    Commit::new("b".to_string(), "fix: fix api".to_string()),
+                ],
+            ),
+            (
+                "utils".to_string(),
+                vec![Commit::new("c".to_string(), "fix: fix utils".to_string())],
+            ),
+        ])
+    }
+
+    #[test]
+    fn inline_mode_includes_the_commits_of_the_included_packages() {
+        let config = PackageUpdateConfig {
+            changelog_include: vec!["core".to_string()],
+            ..Default::default()
+        };
+        let commits = inlined_commits("my-crate", &config, &[], &included_packages_commits());
+        let messages: Vec<&str> = commits.iter().map(|c| c.message.as_str()).collect();
+        assert_eq!(messages, ["feat: add api", "fix: fix api"]);
+    }
+
+    /// A────►B────►C
+    #[test]
+    fn included_changelogs_are_sorted_by_dependencies() {
+        let dep = fake_package::FakeDependency::new;
+        let a: Package = fake_package::FakePackage::new("a")
+            .with_dependencies(vec![dep("b")])
+            .into();
+        let b: Package = fake_package::FakePackage::new("b")
+            .with_dependencies(vec![dep("c")])
+            .into();
+        let c: Package = fake_package::FakePackage::new("c").into();
+        let packages = [&a, &b, &c];
+        let dependency_order: Vec<String> = release_order(&packages)
+            .unwrap()
+            .iter()
+            .map(|p| p.name.clone())
+            .collect();
+        let mut config = PackageUpdateConfig {
+            changelog_include: vec!["a".to_string(), "b".to_string(), "c".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(
+            config.ordered_included_changelogs("my-crate", &dependency_order),
+            ["a", "b", "c"]
+        );
+
+        config.changelog_include_order = ChangelogIncludeOrder::Dependencies;
+        assert_eq!(
+            config.ordered_included_changelogs("my-crate", &dependency_order),
+            ["c", "b", "a"]
+        );
+        let packages_commits = HashMap::from(["a", "b", "c"].map(|p| {
+            let commit = Commit::new(p.to_string(), format!("feat: add {p}"));
+            (p.to_string(), vec![commit])
+        }));
+        let commits = inlined_commits("my-crate", &config, &dependency_order, &packages_commits);
+        let messages: Vec<&str> = commits.iter().map(|c| c.message.as_str()).collect();
+        assert_eq!(messages, ["feat: add c", "feat: add b", "feat: add a"]);
+    }
+
+    #[test]
+    fn link_mode_doesnt_inline_commits() {
+        let config = PackageUpdateConfig {
+            changelog_include: vec!["core".to_string()],
+            changelog_include_mode: ChangelogIncludeMode::Link,
+            ..Default::default()
+        };
+        assert!(inlined_commits("my-crate", &config, &[], &included_packages_commits()).is_empty());
+    }
+
+    #[test]
+    fn link_mode_adds_one_linked_entry_per_released_package() {
+        let included = ["core", "utils", "cli"];
+        let next_versions = HashMap::from([
+            ("core", Version::new(2, 0, 0)),
+            ("utils", Version::new(0, 3, 1)),
+        ]);
+        let commits = included_release_commits(included.into_iter(), &next_versions, |p, v| {
+            Some(format!(
+                "https://github.com/owner/repo/releases/tag/{p}-v{v}"
+            ))
+        });
+        let messages: Vec<&str> = commits.iter().map(|c| c.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            [
+                "chore: updated `core` to 2.0.0 — see its [changelog](https://github.com/owner/repo/releases/tag/core-v2.0.0)",
+                "chore: updated `utils` to 0.3.1 — see its [changelog](https://github.com/owner/repo/releases/tag/utils-v0.3.1)",
+            ]
+        );
+    }
+
+    #[test]
+    fn link_mode_entry_has_no_link_without_repo_url() {
+        let included = ["core"];
+        let next_versions = HashMap::from([("core", Version::new(2, 0, 0))]);
+        let commits = included_release_commits(included.into_iter(), &next_versions, |_, _| None);
+        assert_eq!(commits[0].message, "chore: updated `core` to 2.0.0");
+    }
+
+    #[test]
+    fn changelog_include_of_non_member_is_an_error() {
+        let config = PackageUpdateConfig {
+            changelog_include: vec!["pkg1".to_string(), "bogus".to_string()],
+            ..Default::default()
+        };
+        let error = config
+            .check_changelog_include("my-crate", &["my-crate", "pkg1"])
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "package `my-crate` has `bogus` in `changelog_include`, but `bogus` is not a member of the workspace. Workspace members: `my-crate`, `pkg1`"
+        );
+    }
+
+    #[test]
+    fn changelog_include_of_members_and_paths_is_valid() {
+        let config = PackageUpdateConfig {
+            changelog_include: vec!["pkg1".to_string(), "crates/pkg2".to_string()],
+            ..Default::default()
+        };
+        config
+            .check_changelog_include("my-crate", &["my-crate", "pkg1"])
+            .unwrap();
+    }
+
+    #[test]
+    fn changelog_include_glob_without_matches_is_an_error() {
+        let config = PackageUpdateConfig {
+            changelog_include: vec!["api-*".to_string()],
+            ..Default::default()
+        };
+        let error = config
+            .check_changelog_include("my-crate", &["my-crate", "pkg1"])
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "package `my-crate` has `api-*` in `changelog_include`, but `api-*` doesn't match any member of the workspace"
+        );
+    }
+
     fn get_project(
         local_manifest: &Path,
         single_package: Option<&str>,
@@ -1193,6 +2744,60 @@ mod tests {
         );
     }
 
+    fn changelog_of(commits: Vec<Commit>, format: ChangelogFormat) -> String {
+        let changelog_req = ChangelogRequest {
+            release_date: NaiveDate::from_ymd_opt(2015, 5, 15),
+            format,
+            ..Default::default()
+        };
+        get_changelog(
+            commits,
+            HashMap::new(),
+            &Version::new(1, 1, 0),
+            Some(changelog_req),
+            None,
+            None,
+            None,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn empty_changelog_section_is_an_error() {
+        for format in [
+            ChangelogFormat::Markdown,
+            ChangelogFormat::Json,
+            ChangelogFormat::Rst,
+        ] {
+            let changelog = changelog_of(vec![], format);
+            let error =
+                check_changelog_entries("my-crate", &Version::new(1, 1, 0), &changelog, format)
+                    .unwrap_err();
+            assert!(error
+                .to_string()
+                .starts_with("my-crate 1.1.0: the changelog of the release has no entries"));
+        }
+    }
+
+    #[test]
+    fn changelog_section_with_entries_is_accepted() {
+        for format in [
+            ChangelogFormat::Markdown,
+            ChangelogFormat::Json,
+            ChangelogFormat::Rst,
+        ] {
+            let changelog = changelog_of(
+                vec![Commit::new(
+                    NO_COMMIT_ID.to_string(),
+                    "fix: myfix".to_string(),
+                )],
+                format,
+            );
+            check_changelog_entries("my-crate", &Version::new(1, 1, 0), &changelog, format)
+                .unwrap();
+        }
+    }
+
     #[test]
     fn same_version_is_not_added_to_changelog() {
         let commits = vec![
@@ -1213,10 +2818,12 @@ mod tests {
 "#;
         let new = get_changelog(
             commits,
+            HashMap::new(),
             &next_version,
             Some(changelog_req),
             Some(old.to_string()),
             None,
+            None,
         )
         .unwrap();
         assert_eq!(old, new)
@@ -0,0 +1,244 @@
+use std::fmt;
+
+use cargo_metadata::{semver::Version, Package};
+use tracing::info;
+
+use crate::{diff::Diff, semver_check::SemverCheck};
+
+/// Decision trail of the release of a package, e.g. the computed version and why.
+/// Logged if `verbose_plan` is enabled.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct PlanTrace {
+    package: String,
+    steps: Vec<PlanStep>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct PlanStep {
+    name: &'static str,
+    outcome: String,
+    reason: Option<String>,
+    details: Vec<String>,
+}
+
+impl PlanTrace {
+    pub fn new(package: impl Into<String>) -> Self {
+        Self {
+            package: package.into(),
+            steps: vec![],
+        }
+    }
+
+    pub fn step(mut self, name: &'static str, outcome: impl fmt::Display) -> Self {
+        self.steps.push(PlanStep {
+            name,
+            outcome: outcome.to_string(),
+            reason: None,
+            details: vec![],
+        });
+        self
+    }
+
+    pub fn step_because(
+        self,
+        name: &'static str,
+        outcome: impl fmt::Display,
+        reason: impl Into<String>,
+    ) -> Self {
+        let mut trace = self.step(name, outcome);
+        if let Some(step) = trace.steps.last_mut() {
+            step.reason = Some(reason.into());
+        }
+        trace
+    }
+
+    /// Add the details of the last step, e.g. the commits considered.
+    pub fn with_details(mut self, details: Vec<String>) -> Self {
+        if let Some(step) = self.steps.last_mut() {
+            step.details = details;
+        }
+        self
+    }
+
+    pub fn log(&self) {
+        info!("{self}");
+    }
+}
+
+impl fmt::Display for PlanTrace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: plan", self.package)?;
+        for step in &self.steps {
+            write!(f, "\n  {}: {}", step.name, step.outcome)?;
+            if let Some(reason) = &step.reason {
+                write!(f, " ({reason})")?;
+            }
+            for detail in &step.details {
+                write!(f, "\n    - {detail}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Trace of the computation of the next version of the package.
+/// `inherits_workspace_version` is true if the next version is the new workspace version.
+pub(crate) fn version_trace(
+    package: &Package,
+    diff: &Diff,
+    next_version: &Version,
+    inherits_workspace_version: bool,
+) -> PlanTrace {
+    let previous_version_source = if diff.registry_package_exists {
+        "from Cargo.toml, the package is in the registry"
+    } else {
+        "from Cargo.toml, the package isn't published yet"
+    };
+    let commits: Vec<String> = diff
+        .commits
+        .iter()
+        .map(|c| c.message.lines().next().unwrap_or_default().to_string())
+        .collect();
+    let is_updated = diff.is_release_needed(&package.version, next_version);
+    let increment_reason = if next_version == &package.version {
+        if diff.release_skipped {
+            "no commit matches `release_commits`"
+        } else if !diff.registry_package_exists {
+            "the package is released with its current version"
+        } else if !diff.is_version_published {
+            "the current version isn't published yet"
+        } else {
+            "no changes since the last release"
+        }
+    } else if inherits_workspace_version {
+        "the package inherits the new workspace version"
+    } else if matches!(diff.semver_check, SemverCheck::Incompatible(_)) {
+        "cargo-semver-checks found incompatible API changes"
+    } else {
+        "computed from the commits"
+    };
+    let update_reason = if is_updated {
+        "the version changed or the package isn't published yet"
+    } else if diff.release_skipped {
+        "the release is skipped by `release_commits`"
+    } else {
+        "the package is up to date"
+    };
+    PlanTrace::new(&package.name)
+        .step_because(
+            "previous version",
+            &package.version,
+            previous_version_source,
+        )
+        .step("commits considered", commits.len())
+        .with_details(commits)
+        .step_because(
+            "version increment",
+            increment_name(&package.version, next_version),
+            increment_reason,
+        )
+        .step("next version", next_version)
+        .step_because("update", yes_no(is_updated), update_reason)
+}
+
+/// Name of the increment from `current` to `next`, e.g. `minor`.
+fn increment_name(current: &Version, next: &Version) -> &'static str {
+    if current.major != next.major {
+        "major"
+    } else if current.minor != next.minor {
+        "minor"
+    } else if current.patch != next.patch {
+        "patch"
+    } else if current.pre != next.pre {
+        "pre-release"
+    } else {
+        "none"
+    }
+}
+
+pub(crate) fn yes_no(value: bool) -> &'static str {
+    if value {
+        "yes"
+    } else {
+        "no"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use git_cliff_core::commit::Commit;
+
+    use crate::NO_COMMIT_ID;
+
+    use super::*;
+
+    fn package(name: &str, version: &str) -> Package {
+        let mut package: Package = fake_package::FakePackage::new(name).into();
+        package.version = Version::parse(version).unwrap();
+        package
+    }
+
+    #[test]
+    fn version_trace_of_two_packages_explains_decisions() {
+        let changed = package("changed", "1.2.3");
+        let mut changed_diff = Diff::new(true);
+        changed_diff.commits = vec![
+            Commit::new(
+                NO_COMMIT_ID.to_string(),
+                "feat: add api\n\nbody".to_string(),
+            ),
+            Commit::new(NO_COMMIT_ID.to_string(), "fix: typo".to_string()),
+        ];
+        let unchanged = package("unchanged", "0.4.0");
+        let unchanged_diff = Diff::new(true);
+
+        let traces = [
+            version_trace(&changed, &changed_diff, &Version::new(1, 3, 0), false),
+            version_trace(&unchanged, &unchanged_diff, &Version::new(0, 4, 0), false),
+        ]
+        .map(|trace| trace.to_string())
+        .join("\n");
+
+        expect_test::expect![[r#"
+            changed: plan
+              previous version: 1.2.3 (from Cargo.toml, the package is in the registry)
+              commits considered: 2
+                - feat: add api
+                - fix: typo
+              version increment: minor (computed from the commits)
+              next version: 1.3.0
+              update: yes (the version changed or the package isn't published yet)
+            unchanged: plan
+              previous version: 0.4.0 (from Cargo.toml, the package is in the registry)
+              commits considered: 0
+              version increment: none (no changes since the last release)
+              next version: 0.4.0
+              update: no (the package is up to date)"#]]
+        .assert_eq(&traces);
+    }
+
+    #[test]
+    fn version_trace_explains_workspace_and_new_packages() {
+        let new_package = package("new", "0.1.0");
+        let trace = version_trace(
+            &new_package,
+            &Diff::new(false),
+            &Version::new(0, 1, 0),
+            false,
+        );
+        assert!(trace.to_string().contains(
+            "version increment: none (the package is released with its current version)"
+        ));
+
+        let member = package("member", "0.1.0");
+        let mut diff = Diff::new(true);
+        diff.commits = vec![Commit::new(
+            NO_COMMIT_ID.to_string(),
+            "fix: typo".to_string(),
+        )];
+        let trace = version_trace(&member, &diff, &Version::new(0, 2, 0), true);
+        assert!(trace
+            .to_string()
+            .contains("version increment: minor (the package inherits the new workspace version)"));
+    }
+}
@@ -1,9 +1,12 @@
 use chrono::SecondsFormat;
 
-use crate::PackagesUpdate;
+use crate::{PackagesUpdate, RepoUrl};
 
 pub const BRANCH_PREFIX: &str = "release-plz-";
 pub const OLD_BRANCH_PREFIX: &str = "release-plz/";
+const RELEASE_COMMIT_MESSAGE: &str = "chore: release";
+const PR_BODY_FOOTER: &str =
+    "---\nThis PR was generated with [release-plz](https://github.com/MarcoIeni/release-plz/).";
 
 #[derive(Debug)]
 pub struct Pr {
@@ -13,6 +16,10 @@ pub struct Pr {
     pub body: String,
     pub draft: bool,
     pub labels: Vec<String>,
+    /// Label that identifies the release PR, applied in addition to `labels`.
+    pub marker_label: Option<String>,
+    /// Message of the release commit.
+    pub commit_message: String,
 }
 
 impl Pr {
@@ -20,14 +27,21 @@ impl Pr {
         default_branch: &str,
         packages_to_update: &PackagesUpdate,
         project_contains_multiple_pub_packages: bool,
+        semver_check_report: bool,
     ) -> Self {
         Self {
             branch: release_branch(),
             base_branch: default_branch.to_string(),
             title: pr_title(packages_to_update, project_contains_multiple_pub_packages),
-            body: pr_body(packages_to_update, project_contains_multiple_pub_packages),
+            body: pr_body(
+                packages_to_update,
+                project_contains_multiple_pub_packages,
+                semver_check_report,
+            ),
             draft: false,
             labels: vec![],
+            marker_label: None,
+            commit_message: RELEASE_COMMIT_MESSAGE.to_string(),
         }
     }
 
@@ -40,6 +54,61 @@ impl Pr {
         self.labels = labels;
         self
     }
+
+    pub fn with_marker_label(mut self, marker_label: Option<String>) -> Self {
+        self.marker_label = marker_label;
+        self
+    }
+
+    /// The marker label, followed by the other labels, without duplicates.
+    pub fn all_labels(&self) -> Vec<&str> {
+        let mut labels: Vec<&str> = vec![];
+        for label in self.marker_label.iter().chain(&self.labels) {
+            if !labels.contains(&label.as_str()) {
+                labels.push(label);
+            }
+        }
+        labels
+    }
+
+    /// Add the `marker` trailer to the release commit, so that
+    /// release-plz (and other tools) can recognize it.
+    pub fn with_commit_marker(mut self, marker: &str) -> Self {
+        self.commit_message = release_commit_message(marker);
+        self
+    }
+
+    /// Add a closing keyword for each issue to the body, so that the issues are closed
+    /// when the PR is merged.
+    /// The issues are linked with their url if `repo_url` is known.
+    pub fn with_closed_issues(mut self, repo_url: Option<&RepoUrl>, issues: &[u64]) -> Self {
+        if issues.is_empty() {
+            return self;
+        }
+        if let Some(body) = self.body.strip_suffix(PR_BODY_FOOTER) {
+            self.body = format!(
+                "{body}{}\n{PR_BODY_FOOTER}",
+                closing_keywords(repo_url, issues)
+            );
+        }
+        self
+    }
+}
+
+/// `Closes <issue>` line for each issue.
+/// GitHub, Gitea and GitLab close the issues referenced this way, either by url or by number.
+fn closing_keywords(repo_url: Option<&RepoUrl>, issues: &[u64]) -> String {
+    issues
+        .iter()
+        .map(|&issue| match repo_url {
+            Some(repo_url) => format!("Closes {}\n", repo_url.issue_link(issue)),
+            None => format!("Closes #{issue}\n"),
+        })
+        .collect()
+}
+
+fn release_commit_message(marker: &str) -> String {
+    format!("{RELEASE_COMMIT_MESSAGE}\n\n{marker}")
 }
 
 fn release_branch() -> String {
@@ -67,13 +136,15 @@ fn pr_title(
     }
 }
 
+/// - `semver_check_report`: if true, the body contains the breaking changes found by cargo-semver-checks.
 fn pr_body(
     packages_to_update: &PackagesUpdate,
     project_contains_multiple_pub_packages: bool,
+    semver_check_report: bool,
 ) -> String {
     let header = "## 🤖 New release";
 
-    let summary = packages_to_update.summary();
+    let summary = packages_to_update.pr_summary(semver_check_report);
     let changes = {
         let changes = packages_to_update.changes(project_contains_multiple_pub_packages);
         format!(
@@ -82,7 +153,133 @@ fn pr_body(
         )
     };
 
-    let footer =
-        "---\nThis PR was generated with [release-plz](https://github.com/MarcoIeni/release-plz/).";
-    format!("{header}{summary}\n{changes}\n{footer}")
+    format!("{header}{summary}\n{changes}\n{PR_BODY_FOOTER}")
+}
+
+#[cfg(test)]
+mod tests {
+    use cargo_metadata::semver::Version;
+
+    use crate::{semver_check::SemverCheck, UpdateResult};
+
+    use super::*;
+
+    fn packages_update(semver_check: SemverCheck) -> PackagesUpdate {
+        PackagesUpdate::new(vec![(
+            fake_package::FakePackage::new("foo").into(),
+            UpdateResult {
+                version: Version::new(0, 2, 0),
+                changelog: None,
+                changelog_format: crate::ChangelogFormat::Markdown,
+                semver_check,
+            },
+        )])
+    }
+
+    #[test]
+    fn semver_check_report_is_rendered_in_pr_body() {
+        let packages = packages_update(SemverCheck::Incompatible(
+            "\n--- failure function_missing: pub fn removed ---\n".to_string(),
+        ));
+        expect_test::expect![[r#"
+            ## 🤖 New release
+            * `foo`: 0.1.0 -> 0.2.0 (⚠️ API breaking changes)
+
+            <details><summary><b>⚠️ `foo` breaking changes</b></summary>
+
+            ```
+            --- failure function_missing: pub fn removed ---
+            ```
+            </details>
+
+            <details><summary><i><b>Changelog</b></i></summary><p>
+
+
+            </p></details>
+
+            ---
+            This PR was generated with [release-plz](https://github.com/MarcoIeni/release-plz/)."#]]
+        .assert_eq(&pr_body(&packages, false, true));
+    }
+
+    #[test]
+    fn semver_check_report_is_omitted_without_breaking_changes() {
+        let packages = packages_update(SemverCheck::Compatible);
+        assert!(!pr_body(&packages, false, true).contains("<details><summary><b>"));
+    }
+
+    #[test]
+    fn disabled_semver_check_report_is_omitted_from_pr_body() {
+        let packages = packages_update(SemverCheck::Incompatible(
+            "--- failure function_missing: pub fn removed ---".to_string(),
+        ));
+        let body = pr_body(&packages, false, false);
+        assert!(!body.contains("function_missing"));
+        assert!(body.contains("(⚠️ API breaking changes)"));
+    }
+
+    fn pr_closing_issues(repo_url: Option<&str>) -> Pr {
+        let repo_url = repo_url.map(|url| RepoUrl::new(url).unwrap());
+        Pr::new(
+            "main",
+            &packages_update(SemverCheck::Compatible),
+            false,
+            true,
+        )
+        .with_closed_issues(repo_url.as_ref(), &[12, 34])
+    }
+
+    #[test]
+    fn github_issues_are_closed_in_pr_body() {
+        let pr = pr_closing_issues(Some("https://github.com/owner/repo"));
+        expect_test::expect![[r#"
+            ## 🤖 New release
+            * `foo`: 0.1.0 -> 0.2.0 (✓ API compatible changes)
+
+            <details><summary><i><b>Changelog</b></i></summary><p>
+
+
+            </p></details>
+
+            Closes https://github.com/owner/repo/issues/12
+            Closes https://github.com/owner/repo/issues/34
+
+            ---
+            This PR was generated with [release-plz](https://github.com/MarcoIeni/release-plz/)."#]]
+        .assert_eq(&pr.body);
+    }
+
+    #[test]
+    fn gitea_issues_are_closed_in_pr_body() {
+        let pr = pr_closing_issues(Some("https://gitea.example.com/owner/repo"));
+        assert!(pr
+            .body
+            .contains("Closes https://gitea.example.com/owner/repo/issues/12\n"));
+        assert!(pr
+            .body
+            .contains("Closes https://gitea.example.com/owner/repo/issues/34\n"));
+    }
+
+    #[test]
+    fn issues_are_closed_by_number_without_repo_url() {
+        let pr = pr_closing_issues(None);
+        assert!(pr.body.contains("Closes #12\nCloses #34\n"));
+    }
+
+    #[test]
+    fn pr_body_is_unchanged_without_closed_issues() {
+        let packages = packages_update(SemverCheck::Compatible);
+        let pr = Pr::new("main", &packages, false, true);
+        let body = pr.body.clone();
+        let repo_url = RepoUrl::new("https://github.com/owner/repo").unwrap();
+        assert_eq!(pr.with_closed_issues(Some(&repo_url), &[]).body, body);
+    }
+
+    #[test]
+    fn release_commit_message_contains_marker() {
+        assert_eq!(
+            release_commit_message("release-plz: true"),
+            "chore: release\n\nrelease-plz: true"
+        );
+    }
 }
@@ -4,7 +4,7 @@ use anyhow::Context;
 use cargo_metadata::Package;
 use tempfile::{tempdir, TempDir};
 
-use crate::{download, next_ver};
+use crate::{download, next_ver, CRATES_IO_REGISTRY};
 
 pub struct PackagesCollection {
     packages: BTreeMap<String, Package>,
@@ -19,10 +19,15 @@ impl PackagesCollection {
     }
 }
 
+/// Get the packages published in the registry.
+/// `registry` returns the registry where each package is published. `None` is crates.io.
+/// If `allow_yanked` is false and the latest version of a package was yanked,
+/// the latest non-yanked version is returned.
 pub fn get_registry_packages(
     registry_manifest: Option<&PathBuf>,
     local_packages: &[&Package],
-    registry: Option<&str>,
+    registry: impl Fn(&Package) -> Option<String>,
+    allow_yanked: bool,
 ) -> anyhow::Result<PackagesCollection> {
     let (temp_dir, registry_packages) = match registry_manifest {
         Some(manifest) => (
@@ -31,17 +36,29 @@ pub fn get_registry_packages(
         ),
         None => {
             let temp_dir = tempdir().context("failed to get a temporary directory")?;
-            let local_packages_names: Vec<&str> =
-                local_packages.iter().map(|c| c.name.as_str()).collect();
-            let directory = temp_dir.as_ref().to_str().context("invalid tempdir path")?;
-
-            let mut downloader = download::PackageDownloader::new(local_packages_names, directory);
-            if let Some(registry) = registry {
-                downloader = downloader.with_registry(registry.to_string());
+            let mut registry_packages = vec![];
+            let local_versions: BTreeMap<String, String> = local_packages
+                .iter()
+                .map(|p| (p.name.clone(), p.version.to_string()))
+                .collect();
+            for (registry, packages_names) in packages_by_registry(local_packages, registry) {
+                // Download the packages of each registry in a different directory,
+                // so that packages with the same name don't overwrite each other.
+                let directory = temp_dir
+                    .as_ref()
+                    .join(registry.as_deref().unwrap_or(CRATES_IO_REGISTRY));
+                let directory = directory.to_str().context("invalid tempdir path")?;
+                let mut downloader = download::PackageDownloader::new(packages_names, directory)
+                    .with_local_versions(local_versions.clone())
+                    .with_allow_yanked(allow_yanked);
+                if let Some(registry) = registry {
+                    downloader = downloader.with_registry(registry);
+                }
+                let packages = downloader
+                    .download()
+                    .context("failed to download packages")?;
+                registry_packages.extend(packages);
             }
-            let registry_packages = downloader
-                .download()
-                .context("failed to download packages")?;
             (Some(temp_dir), registry_packages)
         }
     };
@@ -57,3 +74,47 @@ pub fn get_registry_packages(
         packages: registry_packages,
     })
 }
+
+/// Group the names of the packages by the registry where they are published.
+/// The packages of crates.io have `None` as registry.
+fn packages_by_registry<'a>(
+    packages: &[&'a Package],
+    registry: impl Fn(&Package) -> Option<String>,
+) -> BTreeMap<Option<String>, Vec<&'a str>> {
+    let mut packages_by_registry: BTreeMap<Option<String>, Vec<&str>> = BTreeMap::new();
+    for package in packages {
+        let registry = registry(package).filter(|r| r != CRATES_IO_REGISTRY);
+        packages_by_registry
+            .entry(registry)
+            .or_default()
+            .push(package.name.as_str());
+    }
+    packages_by_registry
+}
+
+#[cfg(test)]
+mod tests {
+    use fake_package::FakePackage;
+
+    use super::*;
+
+    #[test]
+    fn packages_are_looked_up_in_their_registry() {
+        let pkg_a: Package = FakePackage::new("a").into();
+        let pkg_b: Package = FakePackage::new("b").into();
+        let pkg_c: Package = FakePackage::new("c").into();
+        let registries =
+            packages_by_registry(&[&pkg_a, &pkg_b, &pkg_c], |p| match p.name.as_str() {
+                "a" => Some("my-registry".to_string()),
+                "b" => Some(CRATES_IO_REGISTRY.to_string()),
+                _ => None,
+            });
+        assert_eq!(
+            registries,
+            BTreeMap::from([
+                (None, vec!["b", "c"]),
+                (Some("my-registry".to_string()), vec!["a"]),
+            ])
+        );
+    }
+}
@@ -1,20 +1,33 @@
 use anyhow::{anyhow, Context};
 use git_cmd::Repo;
-use git_url_parse::GitUrl;
+use git_url_parse::{GitUrl, Scheme};
+
+const AZURE_DEVOPS_HOST: &str = "dev.azure.com";
+const AZURE_DEVOPS_SSH_HOST: &str = "ssh.dev.azure.com";
 
 #[derive(Debug, Clone)]
 pub struct RepoUrl {
     pub scheme: String,
     pub host: String,
     port: Option<u16>,
+    /// Owner of the repository.
+    /// In Azure, this is the project containing the repository.
     pub owner: String,
     pub name: String,
+    /// Azure organization. `None` for other git hosts.
+    pub organization: Option<String>,
 }
 
 impl RepoUrl {
+    /// Parse the url of a git repository.
+    /// The query and the fragment, e.g. `?tab=readme#section`, are ignored.
     pub fn new(git_host_url: &str) -> anyhow::Result<Self> {
         let git_url = GitUrl::parse(git_host_url)
             .map_err(|err| anyhow!("cannot parse git url {}: {}", git_host_url, err))?;
+        anyhow::ensure!(
+            git_url.scheme != Scheme::File,
+            "git url {git_host_url} is a path on disk, so it doesn't have a web page"
+        );
         let owner = git_url
             .owner
             .with_context(|| format!("cannot find owner in git url {git_host_url}"))?;
@@ -24,12 +37,28 @@ impl RepoUrl {
             .with_context(|| format!("cannot find host in git url {git_host_url}"))?;
         let port = git_url.port;
         let scheme = git_url.scheme.to_string();
+        if host == AZURE_DEVOPS_HOST || host == AZURE_DEVOPS_SSH_HOST {
+            let organization = git_url.organization.with_context(|| {
+                format!("cannot find organization in Azure DevOps url {git_host_url}")
+            })?;
+            return Ok(RepoUrl {
+                owner,
+                name,
+                // The web interface is always served over https by the main host,
+                // even if the repository was cloned via ssh.
+                host: AZURE_DEVOPS_HOST.to_string(),
+                port: None,
+                scheme: "https".to_string(),
+                organization: Some(organization),
+            });
+        }
         Ok(RepoUrl {
             owner,
             name,
             host,
             port,
             scheme,
+            organization: None,
         })
     }
 
@@ -44,9 +73,74 @@ impl RepoUrl {
         self.host.contains("github")
     }
 
-    /// Get GitHub/Gitea release link
+    pub fn is_on_gitlab(&self) -> bool {
+        self.host.contains("gitlab")
+    }
+
+    pub fn is_on_azure_devops(&self) -> bool {
+        self.organization.is_some()
+    }
+
+    /// Whether `self` and `other` point to the same repository, even if their scheme differs,
+    /// e.g. the ssh and the https url of a repository.
+    /// GitHub owners and repository names are case-insensitive, while other git hosts,
+    /// like Gitea, can be case-sensitive.
+    pub fn same_repo(&self, other: &RepoUrl) -> bool {
+        let case_insensitive = self.is_on_github();
+        let eq = |a: &str, b: &str| {
+            if case_insensitive {
+                a.eq_ignore_ascii_case(b)
+            } else {
+                a == b
+            }
+        };
+        self.host.eq_ignore_ascii_case(&other.host)
+            && self.organization == other.organization
+            && eq(&self.owner, &other.owner)
+            && eq(&self.name, &other.name)
+    }
+
+    /// Web page of the repository, if it's hosted in Azure.
+    fn azure_devops_url(&self) -> Option<String> {
+        self.organization.as_ref().map(|organization| {
+            format!(
+                "https://{}/{organization}/{}/_git/{}",
+                self.host, self.owner, self.name
+            )
+        })
+    }
+
+    /// Web page of the repository, e.g. `https://github.com/MarcoIeni/release-plz`.
+    pub fn web_url(&self) -> String {
+        if let Some(azure_url) = self.azure_devops_url() {
+            return azure_url;
+        }
+        // The port of ssh urls isn't the one of the web interface.
+        let (scheme, port) = match self.scheme.as_str() {
+            "http" | "https" => (self.scheme.as_str(), self.port),
+            _ => ("https", None),
+        };
+        match port {
+            Some(port) => format!(
+                "{scheme}://{}:{port}/{}/{}",
+                self.host, self.owner, self.name
+            ),
+            None => format!("{scheme}://{}/{}/{}", self.host, self.owner, self.name),
+        }
+    }
+
+    /// Get GitHub/Gitea/Azure release link
     pub fn git_release_link(&self, prev_tag: &str, new_tag: &str) -> String {
-        let host = format!("https://{}/{}/{}", self.host, self.owner, self.name);
+        if let Some(azure_url) = self.azure_devops_url() {
+            return if prev_tag == new_tag {
+                format!("{azure_url}?version=GT{new_tag}")
+            } else {
+                format!(
+                    "{azure_url}/branchCompare?baseVersion=GT{prev_tag}&targetVersion=GT{new_tag}"
+                )
+            };
+        }
+        let host = self.web_url();
 
         if prev_tag == new_tag {
             format!("{host}/releases/tag/{new_tag}")
@@ -55,12 +149,77 @@ impl RepoUrl {
         }
     }
 
+    /// Get the link comparing `tag` with the tip of `branch`.
+    /// Useful to show the changes that haven't been released yet.
+    pub fn compare_to_branch_link(&self, tag: &str, branch: &str) -> String {
+        if let Some(azure_url) = self.azure_devops_url() {
+            return format!(
+                "{azure_url}/branchCompare?baseVersion=GT{tag}&targetVersion=GB{branch}"
+            );
+        }
+        let host = self.web_url();
+        let compare_path = if self.is_on_gitlab() {
+            "-/compare"
+        } else {
+            "compare"
+        };
+        format!("{host}/{compare_path}/{tag}...{branch}")
+    }
+
     pub fn git_pr_link(&self) -> String {
-        let host = format!("https://{}/{}/{}", self.host, self.owner, self.name);
+        if let Some(azure_url) = self.azure_devops_url() {
+            return format!("{azure_url}/pullrequest");
+        }
+        let host = self.web_url();
         let pull_path = if self.is_on_github() { "pull" } else { "pulls" };
         format!("{host}/{pull_path}")
     }
 
+    /// Get the link to the commits of the repository.
+    /// Append `/<commit_id>` to get the link of a commit.
+    pub fn git_commit_link(&self) -> String {
+        format!("{}/commit", self.web_url())
+    }
+
+    /// Get the link of the issue with the given number.
+    pub fn issue_link(&self, number: u64) -> String {
+        if let Some(organization) = &self.organization {
+            // Azure work items belong to the project, not to the repository.
+            return format!(
+                "https://{}/{organization}/{}/_workitems/edit/{number}",
+                self.host, self.owner
+            );
+        }
+        format!("{}/issues/{number}", self.web_url())
+    }
+
+    /// Get the link of the Pull Request with the given number.
+    pub fn pull_link(&self, number: u64) -> String {
+        format!("{}/{number}", self.git_pr_link())
+    }
+
+    /// Get the link to the raw content of the file at `path` in `git_ref`, e.g. to embed an image.
+    /// `path` is relative to the root of the repository.
+    pub fn raw_content_link(&self, git_ref: &str, path: &str) -> String {
+        let path = path.trim_start_matches('/');
+        if let Some(organization) = &self.organization {
+            return format!(
+                "https://{}/{organization}/{}/_apis/git/repositories/{}/items?path=/{path}&versionDescriptor.version={git_ref}",
+                self.host, self.owner, self.name
+            );
+        }
+        if self.host == "github.com" {
+            format!(
+                "https://raw.githubusercontent.com/{}/{}/{git_ref}/{path}",
+                self.owner, self.name
+            )
+        } else if self.is_on_github() {
+            format!("{}/raw/{git_ref}/{path}", self.web_url())
+        } else {
+            format!("{}/raw/branch/{git_ref}/{path}", self.web_url())
+        }
+    }
+
     pub fn gitea_api_url(&self) -> String {
         let v1 = "api/v1/";
         if let Some(port) = self.port {
@@ -98,4 +257,230 @@ mod tests {
         let release_link = repo.git_release_link(previous_tag, next_tag);
         assert_eq!(expected_url, release_link);
     }
+
+    #[test]
+    fn gh_compare_to_branch_link_is_generated() {
+        let repo = RepoUrl::new(GITHUB_REPO_URL).unwrap();
+        let compare_link = repo.compare_to_branch_link("v0.1.0", "main");
+        assert_eq!(
+            format!("{GITHUB_REPO_URL}/compare/v0.1.0...main"),
+            compare_link
+        );
+    }
+
+    #[test]
+    fn gitea_compare_to_branch_link_is_generated() {
+        let repo = RepoUrl::new("https://example.com/MarcoIeni/release-plz").unwrap();
+        let compare_link = repo.compare_to_branch_link("v0.1.0", "main");
+        assert_eq!(
+            "https://example.com/MarcoIeni/release-plz/compare/v0.1.0...main",
+            compare_link
+        );
+    }
+
+    #[test]
+    fn gitlab_compare_to_branch_link_is_generated() {
+        let repo = RepoUrl::new("https://gitlab.com/MarcoIeni/release-plz").unwrap();
+        let compare_link = repo.compare_to_branch_link("v0.1.0", "main");
+        assert_eq!(
+            "https://gitlab.com/MarcoIeni/release-plz/-/compare/v0.1.0...main",
+            compare_link
+        );
+    }
+
+    #[test]
+    fn web_url_of_plain_host_is_generated() {
+        for url in [
+            GITHUB_REPO_URL,
+            "git@github.com:MarcoIeni/release-plz.git",
+            "ssh://git@github.com:2222/MarcoIeni/release-plz.git",
+        ] {
+            let repo = RepoUrl::new(url).unwrap();
+            assert_eq!(repo.web_url(), GITHUB_REPO_URL, "{url}");
+        }
+    }
+
+    #[test]
+    fn web_url_of_host_with_port_is_generated() {
+        let repo = RepoUrl::new("https://example.com:3000/MarcoIeni/release-plz").unwrap();
+        assert_eq!(
+            repo.web_url(),
+            "https://example.com:3000/MarcoIeni/release-plz"
+        );
+        assert_eq!(
+            repo.git_pr_link(),
+            "https://example.com:3000/MarcoIeni/release-plz/pulls"
+        );
+    }
+
+    #[test]
+    fn gh_issue_and_pull_links_are_generated() {
+        let repo = RepoUrl::new("git@github.com:MarcoIeni/release-plz.git").unwrap();
+        assert_eq!(
+            repo.issue_link(123),
+            format!("{GITHUB_REPO_URL}/issues/123")
+        );
+        assert_eq!(repo.pull_link(45), format!("{GITHUB_REPO_URL}/pull/45"));
+        assert_eq!(repo.git_commit_link(), format!("{GITHUB_REPO_URL}/commit"));
+    }
+
+    #[test]
+    fn gh_raw_content_link_is_generated() {
+        let repo = RepoUrl::new("git@github.com:MarcoIeni/release-plz.git").unwrap();
+        let expected =
+            "https://raw.githubusercontent.com/MarcoIeni/release-plz/main/assets/logo.png";
+        assert_eq!(repo.raw_content_link("main", "assets/logo.png"), expected);
+        assert_eq!(repo.raw_content_link("main", "/assets/logo.png"), expected);
+    }
+
+    #[test]
+    fn gitea_raw_content_link_is_generated() {
+        let repo = RepoUrl::new("https://example.com:3000/MarcoIeni/release-plz").unwrap();
+        assert_eq!(
+            repo.raw_content_link("main", "assets/logo.png"),
+            "https://example.com:3000/MarcoIeni/release-plz/raw/branch/main/assets/logo.png"
+        );
+    }
+
+    #[test]
+    fn gitea_issue_and_pull_links_are_generated() {
+        let repo = RepoUrl::new("https://example.com:3000/MarcoIeni/release-plz").unwrap();
+        assert_eq!(
+            repo.issue_link(123),
+            "https://example.com:3000/MarcoIeni/release-plz/issues/123"
+        );
+        assert_eq!(
+            repo.pull_link(45),
+            "https://example.com:3000/MarcoIeni/release-plz/pulls/45"
+        );
+        assert_eq!(
+            repo.git_commit_link(),
+            "https://example.com:3000/MarcoIeni/release-plz/commit"
+        );
+
+        let repo = RepoUrl::new("ssh://git@example.com:2222/MarcoIeni/release-plz.git").unwrap();
+        assert_eq!(
+            repo.issue_link(123),
+            "https://example.com/MarcoIeni/release-plz/issues/123"
+        );
+        assert_eq!(
+            repo.pull_link(45),
+            "https://example.com/MarcoIeni/release-plz/pulls/45"
+        );
+    }
+
+    const AZURE_REPO_URL: &str = "https://dev.azure.com/my-org/my-project/_git/my-repo";
+
+    #[test]
+    fn azure_devops_https_url_is_parsed() {
+        for url in [
+            AZURE_REPO_URL,
+            "https://my-org@dev.azure.com/my-org/my-project/_git/my-repo",
+            "git@ssh.dev.azure.com:v3/my-org/my-project/my-repo",
+        ] {
+            let repo = RepoUrl::new(url).unwrap();
+            assert!(repo.is_on_azure_devops(), "{url}");
+            assert!(!repo.is_on_github(), "{url}");
+            assert_eq!(repo.organization.as_deref(), Some("my-org"), "{url}");
+            assert_eq!(repo.owner, "my-project", "{url}");
+            assert_eq!(repo.name, "my-repo", "{url}");
+            assert_eq!(repo.host, "dev.azure.com", "{url}");
+            assert_eq!(repo.scheme, "https", "{url}");
+        }
+    }
+
+    #[test]
+    fn azure_devops_links_are_generated() {
+        let repo = RepoUrl::new(AZURE_REPO_URL).unwrap();
+        assert_eq!(
+            repo.git_release_link("v0.1.0", "v0.1.0"),
+            format!("{AZURE_REPO_URL}?version=GTv0.1.0")
+        );
+        assert_eq!(
+            repo.git_release_link("v0.1.0", "v0.2.0"),
+            format!("{AZURE_REPO_URL}/branchCompare?baseVersion=GTv0.1.0&targetVersion=GTv0.2.0")
+        );
+        assert_eq!(
+            repo.compare_to_branch_link("v0.1.0", "main"),
+            format!("{AZURE_REPO_URL}/branchCompare?baseVersion=GTv0.1.0&targetVersion=GBmain")
+        );
+        assert_eq!(repo.git_pr_link(), format!("{AZURE_REPO_URL}/pullrequest"));
+        assert_eq!(
+            repo.pull_link(45),
+            format!("{AZURE_REPO_URL}/pullrequest/45")
+        );
+        assert_eq!(
+            repo.issue_link(123),
+            "https://dev.azure.com/my-org/my-project/_workitems/edit/123"
+        );
+        assert_eq!(
+            repo.raw_content_link("main", "assets/logo.png"),
+            "https://dev.azure.com/my-org/my-project/_apis/git/repositories/my-repo/items?path=/assets/logo.png&versionDescriptor.version=main"
+        );
+    }
+
+    #[test]
+    fn local_git_url_is_rejected() {
+        for url in [
+            "file:///tmp/repos/release-plz.git",
+            "/tmp/repos/release-plz.git",
+        ] {
+            let err = RepoUrl::new(url).unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                format!("git url {url} is a path on disk, so it doesn't have a web page"),
+            );
+        }
+    }
+
+    #[test]
+    fn query_and_fragment_are_ignored() {
+        for url in [
+            "https://github.com/MarcoIeni/release-plz?foo=bar#frag",
+            "https://github.com/MarcoIeni/release-plz?tab=readme",
+            "https://github.com/MarcoIeni/release-plz#section",
+            "git@github.com:MarcoIeni/release-plz.git?foo=bar#frag",
+            "ssh://git@github.com/MarcoIeni/release-plz.git#frag",
+        ] {
+            let repo = RepoUrl::new(url).unwrap();
+            assert_eq!(repo.owner, "MarcoIeni", "{url}");
+            assert_eq!(repo.name, "release-plz", "{url}");
+            assert_eq!(repo.web_url(), GITHUB_REPO_URL, "{url}");
+            assert_eq!(
+                repo.git_release_link("v0.1.0", "v0.2.0"),
+                format!("{GITHUB_REPO_URL}/compare/v0.1.0...v0.2.0"),
+                "{url}"
+            );
+            assert_eq!(repo.pull_link(45), format!("{GITHUB_REPO_URL}/pull/45"));
+        }
+    }
+
+    #[test]
+    fn github_repos_are_compared_case_insensitively() {
+        let repo = RepoUrl::new(GITHUB_REPO_URL).unwrap();
+        for url in [
+            "https://github.com/marcoieni/Release-Plz",
+            "git@GitHub.com:MARCOIENI/release-plz.git",
+        ] {
+            assert!(repo.same_repo(&RepoUrl::new(url).unwrap()), "{url}");
+        }
+        let other = RepoUrl::new("https://github.com/MarcoIeni/release-plz-action").unwrap();
+        assert!(!repo.same_repo(&other));
+    }
+
+    #[test]
+    fn gitea_repos_are_compared_case_sensitively() {
+        let repo = RepoUrl::new("https://gitea.example.com/MarcoIeni/release-plz").unwrap();
+        let ssh = RepoUrl::new("git@gitea.example.com:MarcoIeni/release-plz.git").unwrap();
+        assert!(repo.same_repo(&ssh));
+        let other_case = RepoUrl::new("https://gitea.example.com/marcoieni/release-plz").unwrap();
+        assert!(!repo.same_repo(&other_case));
+    }
+
+    #[test]
+    fn github_url_has_no_organization() {
+        let repo = RepoUrl::new(GITHUB_REPO_URL).unwrap();
+        assert!(!repo.is_on_azure_devops());
+        assert_eq!(repo.organization, None);
+    }
 }
@@ -1,4 +1,5 @@
 use std::{
+    ffi::OsString,
     path::{Path, PathBuf},
     process::Command,
 };
@@ -23,6 +24,23 @@ pub fn is_cargo_semver_checks_installed() -> bool {
         .unwrap_or(false)
 }
 
+/// What to do if cargo-semver-checks should run, but it isn't installed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SemverCheckMissing {
+    /// Fail the run.
+    Error,
+    /// Log a warning and skip the semver check.
+    #[default]
+    Warn,
+    /// Skip the semver check.
+    Skip,
+}
+
+/// Message explaining how to install cargo-semver-checks.
+pub fn missing_cargo_semver_checks_message(package: &str) -> String {
+    format!("{package}: cargo-semver-checks isn't installed, so the API compatibility of the package can't be checked. Install it with `cargo install cargo-semver-checks --locked`, or disable `semver_check`")
+}
+
 /// Outcome of semver check.
 #[derive(Debug, Clone)]
 pub enum SemverCheck {
@@ -44,42 +62,81 @@ impl SemverCheck {
     }
 }
 
+/// Version of the package to compare the local package against.
+#[derive(Debug, Clone, Copy)]
+pub enum SemverCheckBaseline<'a> {
+    /// Package downloaded from the registry.
+    Registry(&'a Path),
+    /// Git revision of the repository (e.g. a tag or a branch).
+    GitRev(&'a str),
+}
+
+impl SemverCheckBaseline<'_> {
+    fn args(&self) -> Vec<OsString> {
+        match self {
+            SemverCheckBaseline::Registry(path) => {
+                vec!["--baseline-root".into(), path.join(CARGO_TOML).into()]
+            }
+            SemverCheckBaseline::GitRev(rev) => vec!["--baseline-rev".into(), rev.into()],
+        }
+    }
+}
+
+/// Files created by cargo-semver-checks in a package directory.
+struct GeneratedFiles {
+    cargo_lock: PathBuf,
+    target_dir: PathBuf,
+    contained_cargo_lock: bool,
+    contained_target: bool,
+}
+
+impl GeneratedFiles {
+    fn new(package: &Path) -> Self {
+        let cargo_lock = cargo_lock(package);
+        let target_dir = target_dir(package);
+        Self {
+            contained_cargo_lock: cargo_lock.exists(),
+            contained_target: target_dir.exists(),
+            cargo_lock,
+            target_dir,
+        }
+    }
+
+    /// Delete Cargo.lock file and target dir if cargo-semver-checks created them.
+    fn clean(self) -> anyhow::Result<()> {
+        if !self.contained_cargo_lock && self.cargo_lock.exists() {
+            std::fs::remove_file(self.cargo_lock)?;
+        }
+        if !self.contained_target && self.target_dir.exists() {
+            std::fs::remove_dir_all(self.target_dir)?;
+        }
+        Ok(())
+    }
+}
+
 pub fn run_semver_check(
     local_package: &Path,
-    registry_package: &Path,
+    baseline: SemverCheckBaseline,
 ) -> anyhow::Result<SemverCheck> {
-    let local_cargo_lock = cargo_lock(local_package);
-    let registry_cargo_lock = cargo_lock(registry_package);
-    let local_target_dir = target_dir(local_package);
-    let registry_target_dir = target_dir(registry_package);
-
-    let local_package_contained_cargo_lock = local_cargo_lock.exists();
-    let registry_package_contained_cargo_lock = registry_cargo_lock.exists();
-    let local_package_contained_target = local_target_dir.exists();
-    let registry_package_contained_target = registry_target_dir.exists();
+    let local_files = GeneratedFiles::new(local_package);
+    let registry_files = match baseline {
+        SemverCheckBaseline::Registry(registry_package) => {
+            Some(GeneratedFiles::new(registry_package))
+        }
+        SemverCheckBaseline::GitRev(_) => None,
+    };
 
     let output = Command::new("cargo-semver-checks")
         .args(["semver-checks", "check-release"])
         .arg("--manifest-path")
         .arg(&local_package.join(CARGO_TOML))
-        .arg("--baseline-root")
-        .arg(&registry_package.join(CARGO_TOML))
+        .args(baseline.args())
         .output()
         .with_context(|| format!("error while running cargo-semver-checks on {local_package:?}"))?;
 
-    // Delete Cargo.lock file if cargo-semver-checks created it.
-    if !local_package_contained_cargo_lock && local_cargo_lock.exists() {
-        std::fs::remove_file(local_cargo_lock)?;
-    }
-    if !registry_package_contained_cargo_lock && registry_cargo_lock.exists() {
-        std::fs::remove_file(registry_cargo_lock)?;
-    }
-    // Delete target dir if cargo-semver-checks created it.
-    if !local_package_contained_target && local_target_dir.exists() {
-        std::fs::remove_dir_all(local_target_dir)?;
-    }
-    if !registry_package_contained_target && registry_target_dir.exists() {
-        std::fs::remove_dir_all(registry_target_dir)?;
+    local_files.clean()?;
+    if let Some(registry_files) = registry_files {
+        registry_files.clean()?;
     }
 
     if output.status.success() {
@@ -98,3 +155,32 @@ pub fn run_semver_check(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn registry_baseline_is_passed_as_root() {
+        let baseline = SemverCheckBaseline::Registry(Path::new("registry/pkg"));
+        assert_eq!(
+            baseline.args(),
+            vec![
+                OsString::from("--baseline-root"),
+                Path::new("registry/pkg").join(CARGO_TOML).into()
+            ]
+        );
+    }
+
+    #[test]
+    fn git_ref_baseline_is_passed_as_rev() {
+        let baseline = SemverCheckBaseline::GitRev("release/1.x");
+        assert_eq!(
+            baseline.args(),
+            vec![
+                OsString::from("--baseline-rev"),
+                OsString::from("release/1.x")
+            ]
+        );
+    }
+}
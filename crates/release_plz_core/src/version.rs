@@ -1,23 +1,114 @@
+use std::collections::HashMap;
+
 use cargo_metadata::semver::Version;
-use next_version::{NextVersion, VersionIncrement};
+use next_version::{VersionIncrement, VersionUpdater};
 
 use crate::{diff::Diff, semver_check::SemverCheck};
 
+/// Overrides of the version increment computed from the commits.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VersionBumpOverrides {
+    /// Increment to use instead of the one computed from the commits.
+    pub version_bump: Option<VersionIncrement>,
+    /// Minimum increment, used if the one computed from the commits is smaller.
+    /// Ignored for pre-release versions, which are always incremented as pre-releases.
+    pub min_version_bump: Option<VersionIncrement>,
+    /// Increment caused by the commits of each type, e.g. `perf` -> minor.
+    /// Commit types that aren't listed follow the conventional commits rules.
+    pub version_bump_rules: HashMap<String, VersionIncrement>,
+    /// If true, `0.0.z` versions are only incremented in the last segment,
+    /// even if the commits or the other overrides require a bigger increment.
+    pub zero_based_versioning: bool,
+}
+
+/// Next version of a package at `current_version` after the given `commits`,
+/// together with the applied increment, according to
+/// [conventional commits](https://www.conventionalcommits.org/).
+///
+/// If there are no commits, the version is unchanged, the increment is `None`
+/// and the `overrides` are ignored.
+///
+/// ```rust
+/// use cargo_metadata::semver::Version;
+/// use next_version::VersionIncrement;
+/// use release_plz_core::{next_version_from_commits, VersionBumpOverrides};
+///
+/// let version = Version::new(1, 2, 3);
+/// let next = next_version_from_commits(&version, ["feat: add api"], &VersionBumpOverrides::default());
+/// assert_eq!(next, (Version::new(1, 3, 0), Some(VersionIncrement::Minor)));
+/// ```
+pub fn next_version_from_commits<I>(
+    current_version: &Version,
+    commits: I,
+    overrides: &VersionBumpOverrides,
+) -> (Version, Option<VersionIncrement>)
+where
+    I: IntoIterator,
+    I::Item: AsRef<str>,
+{
+    let updater =
+        VersionUpdater::new().with_commit_type_increments(overrides.version_bump_rules.clone());
+    let Some(increment) =
+        VersionIncrement::from_commits_with_updater(&updater, current_version, commits)
+    else {
+        return (current_version.clone(), None);
+    };
+    let increment = overrides_increment(current_version, increment, overrides);
+    (increment.bump(current_version), Some(increment))
+}
+
+/// Apply the `overrides` to the `increment` computed from the commits.
+fn overrides_increment(
+    current_version: &Version,
+    increment: VersionIncrement,
+    overrides: &VersionBumpOverrides,
+) -> VersionIncrement {
+    let increment = match (overrides.version_bump, overrides.min_version_bump) {
+        (Some(version_bump), _) => version_bump,
+        (None, Some(min))
+            if increment != VersionIncrement::Prerelease && rank(min) > rank(increment) =>
+        {
+            min
+        }
+        _ => increment,
+    };
+    let is_zero_zero = current_version.major == 0 && current_version.minor == 0;
+    if overrides.zero_based_versioning
+        && is_zero_zero
+        && rank(increment) > rank(VersionIncrement::Patch)
+    {
+        VersionIncrement::Patch
+    } else {
+        increment
+    }
+}
+
+/// Size of the increment, used to compare increments of non pre-release versions.
+fn rank(increment: VersionIncrement) -> u8 {
+    match increment {
+        VersionIncrement::Prerelease => 0,
+        VersionIncrement::Patch => 1,
+        VersionIncrement::Minor => 2,
+        VersionIncrement::Major => 3,
+    }
+}
+
 pub(crate) trait NextVersionFromDiff {
     /// Analyze commits and determine which part of version to increment based on
     /// [conventional commits](https://www.conventionalcommits.org/)
-    fn next_from_diff(&self, diff: &Diff) -> Self;
+    fn next_from_diff(&self, diff: &Diff, overrides: &VersionBumpOverrides) -> Self;
 }
 
 impl NextVersionFromDiff for Version {
-    fn next_from_diff(&self, diff: &Diff) -> Self {
+    fn next_from_diff(&self, diff: &Diff, overrides: &VersionBumpOverrides) -> Self {
         if !diff.should_update_version() {
             self.clone()
         } else if matches!(diff.semver_check, SemverCheck::Incompatible(_)) {
             let increment = VersionIncrement::breaking(self);
             increment.bump(self)
         } else {
-            self.next(diff.commits.iter().map(|c| &c.message))
+            let commits = diff.commits.iter().map(|c| &c.message);
+            next_version_from_commits(self, commits, overrides).0
         }
     }
 }
@@ -35,7 +126,128 @@ mod tests {
         let registry_package_exists = false;
         let diff = Diff::new(registry_package_exists);
         let version = Version::new(1, 2, 3);
-        assert_eq!(version.clone().next_from_diff(&diff), version);
+        assert_eq!(
+            version
+                .clone()
+                .next_from_diff(&diff, &VersionBumpOverrides::default()),
+            version
+        );
+    }
+
+    fn next(version: &str, commits: &[&str], overrides: &VersionBumpOverrides) -> String {
+        let version = Version::parse(version).unwrap();
+        let (next_version, _) = next_version_from_commits(&version, commits, overrides);
+        next_version.to_string()
+    }
+
+    #[test]
+    fn increment_is_derived_from_commits() {
+        let version = Version::new(1, 2, 3);
+        let no_overrides = VersionBumpOverrides::default();
+        let increment =
+            |commits: &[&str]| next_version_from_commits(&version, commits, &no_overrides);
+        assert_eq!(increment(&[]), (version.clone(), None));
+        assert_eq!(
+            increment(&["fix: fix bug"]),
+            (Version::new(1, 2, 4), Some(VersionIncrement::Patch))
+        );
+        assert_eq!(
+            increment(&["fix: fix bug", "feat: add api"]),
+            (Version::new(1, 3, 0), Some(VersionIncrement::Minor))
+        );
+        assert_eq!(
+            increment(&["feat!: remove api"]),
+            (Version::new(2, 0, 0), Some(VersionIncrement::Major))
+        );
+    }
+
+    #[test]
+    fn version_bump_overrides_the_commits() {
+        let overrides = VersionBumpOverrides {
+            version_bump: Some(VersionIncrement::Patch),
+            min_version_bump: Some(VersionIncrement::Minor),
+            ..Default::default()
+        };
+        assert_eq!(next("1.2.3", &["feat!: remove api"], &overrides), "1.2.4");
+        assert_eq!(next("1.2.3", &[], &overrides), "1.2.3");
+    }
+
+    #[test]
+    fn min_version_bump_raises_smaller_increments() {
+        let overrides = VersionBumpOverrides {
+            min_version_bump: Some(VersionIncrement::Minor),
+            ..Default::default()
+        };
+        assert_eq!(next("1.2.3", &["fix: fix bug"], &overrides), "1.3.0");
+        assert_eq!(next("1.2.3", &["feat!: remove api"], &overrides), "2.0.0");
+        assert_eq!(next("1.2.3", &[], &overrides), "1.2.3");
+        assert_eq!(
+            next("1.2.3-rc.1", &["fix: fix bug"], &overrides),
+            "1.2.3-rc.2"
+        );
+    }
+
+    #[test]
+    fn version_bump_rules_customize_commit_types() {
+        let overrides = VersionBumpOverrides {
+            version_bump_rules: HashMap::from([
+                ("perf".to_string(), VersionIncrement::Minor),
+                ("deps".to_string(), VersionIncrement::Patch),
+            ]),
+            ..Default::default()
+        };
+        assert_eq!(next("1.2.3", &["perf: faster"], &overrides), "1.3.0");
+        assert_eq!(next("1.2.3", &["deps: bump serde"], &overrides), "1.2.4");
+        assert_eq!(next("1.2.3", &["feat: add api"], &overrides), "1.3.0");
+        let defaults = VersionBumpOverrides::default();
+        assert_eq!(next("1.2.3", &["perf: faster"], &defaults), "1.2.4");
+    }
+
+    #[test]
+    fn breaking_changes_of_zero_zero_versions_increment_the_last_segment() {
+        let defaults = VersionBumpOverrides::default();
+        assert_eq!(next("0.0.3", &["feat!: remove api"], &defaults), "0.0.4");
+        let rules = VersionBumpOverrides {
+            version_bump_rules: HashMap::from([("perf".to_string(), VersionIncrement::Major)]),
+            ..Default::default()
+        };
+        assert_eq!(next("0.0.3", &["perf: faster"], &rules), "0.0.4");
+
+        let diff = Diff {
+            registry_package_exists: true,
+            commits: vec![Commit::new(
+                NO_COMMIT_ID.to_string(),
+                "fix: myfix".to_string(),
+            )],
+            is_version_published: true,
+            semver_check: SemverCheck::Incompatible("breaking".to_string()),
+            commit_groups: std::collections::HashMap::new(),
+            release_skipped: false,
+        };
+        assert_eq!(
+            Version::new(0, 0, 3).next_from_diff(&diff, &defaults),
+            Version::new(0, 0, 4)
+        );
+    }
+
+    #[test]
+    fn zero_based_versioning_caps_increments_of_zero_zero_versions() {
+        let overrides = VersionBumpOverrides {
+            zero_based_versioning: true,
+            ..Default::default()
+        };
+        assert_eq!(next("0.0.3", &["feat!: remove api"], &overrides), "0.0.4");
+        let overrides = VersionBumpOverrides {
+            min_version_bump: Some(VersionIncrement::Minor),
+            ..overrides
+        };
+        assert_eq!(next("0.0.3", &["feat!: remove api"], &overrides), "0.0.4");
+        assert_eq!(next("0.1.3", &["fix: fix bug"], &overrides), "0.2.0");
+        let overrides = VersionBumpOverrides {
+            zero_based_versioning: false,
+            ..overrides
+        };
+        assert_eq!(next("0.0.3", &["feat!: remove api"], &overrides), "0.1.0");
     }
 
     #[test]
@@ -48,8 +260,13 @@ mod tests {
             )],
             is_version_published: true,
             semver_check: SemverCheck::Skipped,
+            commit_groups: std::collections::HashMap::new(),
+            release_skipped: false,
         };
         let version = Version::new(1, 2, 3);
-        assert_eq!(version.next_from_diff(&diff), Version::new(1, 2, 4));
+        assert_eq!(
+            version.next_from_diff(&diff, &VersionBumpOverrides::default()),
+            Version::new(1, 2, 4)
+        );
     }
 }
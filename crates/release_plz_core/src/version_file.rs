@@ -0,0 +1,138 @@
+use std::{fs, path::Path};
+
+use anyhow::Context;
+use cargo_metadata::semver::Version;
+use regex::{Captures, Regex};
+
+/// Placeholder of the version in the template of a version file.
+const VERSION_PLACEHOLDER: &str = "{version}";
+
+/// Regex matching a semver version.
+const VERSION_RE: &str = r"\d+\.\d+\.\d+(?:-[0-9A-Za-z.-]+)?(?:\+[0-9A-Za-z.-]+)?";
+
+/// Write `new_version` in a file of the package in `package_dir`.
+///
+/// `version_file` is the path of the file, relative to the package directory.
+/// It can be followed by `:` and a template of the text containing the version,
+/// where `{version}` is the placeholder of the version,
+/// e.g. `src/version.rs:pub const VERSION: &str = "{version}";`.
+/// Without template, the occurrences of `current_version` are replaced.
+pub fn update_version_file(
+    package_dir: &Path,
+    version_file: &str,
+    current_version: &Version,
+    new_version: &Version,
+) -> anyhow::Result<()> {
+    let (path, template) = match version_file.split_once(':') {
+        Some((path, template)) => (path, Some(template)),
+        None => (version_file, None),
+    };
+    let path = package_dir.join(path);
+    let content =
+        fs::read_to_string(&path).with_context(|| format!("cannot read version file {path:?}"))?;
+    let new_content = match template {
+        Some(template) => replace_in_template(&content, template, new_version)?,
+        None => {
+            let current_version = current_version.to_string();
+            content
+                .contains(&current_version)
+                .then(|| content.replace(&current_version, &new_version.to_string()))
+        }
+    }
+    .with_context(|| format!("cannot find the version in version file {path:?}"))?;
+    fs::write(&path, new_content).with_context(|| format!("cannot write version file {path:?}"))
+}
+
+/// Replace the version in the text matching `template`.
+/// Return `None` if no text matches.
+fn replace_in_template(
+    content: &str,
+    template: &str,
+    new_version: &Version,
+) -> anyhow::Result<Option<String>> {
+    let (prefix, suffix) = template
+        .split_once(VERSION_PLACEHOLDER)
+        .filter(|(_, suffix)| !suffix.contains(VERSION_PLACEHOLDER))
+        .with_context(|| {
+            format!("version file template `{template}` must contain `{VERSION_PLACEHOLDER}` once")
+        })?;
+    let re = Regex::new(&format!(
+        "({}){VERSION_RE}({})",
+        regex::escape(prefix),
+        regex::escape(suffix)
+    ))
+    .with_context(|| format!("invalid version file template `{template}`"))?;
+    if !re.is_match(content) {
+        return Ok(None);
+    }
+    let new_content = re.replace_all(content, |c: &Captures| {
+        format!("{}{new_version}{}", &c[1], &c[2])
+    });
+    Ok(Some(new_content.into_owned()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn update(version_file: &str, files: &[(&str, &str)]) -> anyhow::Result<tempfile::TempDir> {
+        let dir = tempfile::tempdir().unwrap();
+        for (path, content) in files {
+            let path = dir.path().join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+        update_version_file(
+            dir.path(),
+            version_file,
+            &Version::new(0, 1, 0),
+            &Version::new(0, 2, 0),
+        )?;
+        Ok(dir)
+    }
+
+    fn read(dir: &tempfile::TempDir, path: &str) -> String {
+        fs::read_to_string(dir.path().join(path)).unwrap()
+    }
+
+    #[test]
+    fn version_file_is_rewritten() {
+        let dir = update("VERSION", &[("VERSION", "0.1.0\n")]).unwrap();
+        assert_eq!(read(&dir, "VERSION"), "0.2.0\n");
+    }
+
+    #[test]
+    fn version_constant_is_rewritten() {
+        let version_rs = r#"pub const VERSION: &str = "0.1.0";
+pub const MIN_SUPPORTED: &str = "0.1.0";
+"#;
+        let dir = update(
+            r#"src/version.rs:pub const VERSION: &str = "{version}";"#,
+            &[("src/version.rs", version_rs)],
+        )
+        .unwrap();
+        assert_eq!(
+            read(&dir, "src/version.rs"),
+            r#"pub const VERSION: &str = "0.2.0";
+pub const MIN_SUPPORTED: &str = "0.1.0";
+"#
+        );
+    }
+
+    #[test]
+    fn missing_version_is_an_error() {
+        let error = update("VERSION", &[("VERSION", "1.0.0\n")]).unwrap_err();
+        assert!(error
+            .to_string()
+            .starts_with("cannot find the version in version file"));
+    }
+
+    #[test]
+    fn template_without_placeholder_is_an_error() {
+        let error = update("VERSION:version", &[("VERSION", "0.1.0\n")]).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "version file template `version` must contain `{version}` once"
+        );
+    }
+}
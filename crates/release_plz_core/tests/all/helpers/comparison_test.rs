@@ -1,12 +1,13 @@
-use std::{fs, path::PathBuf};
+use std::{collections::HashMap, fs, path::PathBuf};
 
 use crate::helpers::gitea_mock_server::GiteaMockServer;
 use anyhow::Context;
 use cargo_utils::get_manifest_metadata;
 use chrono::NaiveDate;
 use release_plz_core::{
-    are_packages_equal, copy_to_temp_dir, ChangelogRequest, GitBackend, GitHub, Gitea,
-    ReleasePrRequest, RepoUrl, UpdateRequest, CARGO_TOML, CHANGELOG_FILENAME,
+    are_packages_equal, copy_to_temp_dir, ChangelogFormat, ChangelogRequest, ChangelogSource,
+    GitBackend, GitHub, GitPr, Gitea, ReleasePrRequest, RepoUrl, UpdateConfig, UpdateRequest,
+    CARGO_TOML, CHANGELOG_FILENAME,
 };
 use secrecy::Secret;
 use tempfile::{tempdir, TempDir};
@@ -26,6 +27,25 @@ const PROJECT_NAME: &str = "myproject";
 pub const OWNER: &str = "owner";
 pub const REPO: &str = "repo";
 
+fn changelog_request() -> ChangelogRequest {
+    ChangelogRequest {
+        release_date: NaiveDate::from_ymd_opt(2015, 5, 15),
+        changelog_config: None,
+        commit_summary_prefix: None,
+        sort_by_scope: false,
+        commit_sort: None,
+        skip_ci_commits: false,
+        unreleased_section: false,
+        dedup_existing: false,
+        include_merge_commits: false,
+        header_links: true,
+        commit_links: false,
+        max_entries: None,
+        context: HashMap::default(),
+        format: ChangelogFormat::Markdown,
+    }
+}
+
 impl ComparisonTest {
     pub async fn new() -> Self {
         test_logs::init();
@@ -52,10 +72,7 @@ impl ComparisonTest {
         let metadata = get_manifest_metadata(&self.local_project_manifest()).unwrap();
         UpdateRequest::new(metadata)
             .unwrap()
-            .with_changelog_req(ChangelogRequest {
-                release_date: NaiveDate::from_ymd_opt(2015, 5, 15),
-                changelog_config: None,
-            })
+            .with_changelog_req(changelog_request())
             .with_registry_project_manifest(self.registry_project_manfifest())
             .unwrap()
     }
@@ -65,6 +82,44 @@ impl ComparisonTest {
         release_plz_core::update(&update_request).unwrap();
     }
 
+    /// Diff of the changelogs that `update` would write.
+    pub fn changelog_diff(&self) -> String {
+        release_plz_core::changelog_diff(&self.update_request()).unwrap()
+    }
+
+    /// Run `update` with the links of a GitHub repository in the changelog.
+    pub fn run_update_with_merge_commits(&self, include_merge_commits: bool) {
+        let repo_url = RepoUrl::new(&format!("https://github.com/{OWNER}/{REPO}")).unwrap();
+        let update_request = self
+            .update_request()
+            .with_changelog_req(ChangelogRequest {
+                include_merge_commits,
+                ..changelog_request()
+            })
+            .with_repo_url(repo_url);
+        release_plz_core::update(&update_request).unwrap();
+    }
+
+    /// Run `update` ignoring the release commits marked with `marker`.
+    pub fn run_update_with_release_commit_marker(&self, marker: &str, include_merge_commits: bool) {
+        let update_request = self
+            .update_request()
+            .with_changelog_req(ChangelogRequest {
+                include_merge_commits,
+                ..changelog_request()
+            })
+            .with_release_commit_marker(marker);
+        release_plz_core::update(&update_request).unwrap();
+    }
+
+    /// Run `update` releasing the package only if a commit matches `release_commits`.
+    pub fn run_update_with_release_commits(&self, release_commits: &str) {
+        let update_request = self.update_request().with_default_package_config(
+            UpdateConfig::default().with_release_commits(release_commits),
+        );
+        release_plz_core::update(&update_request).unwrap();
+    }
+
     fn github_release_pr_request(&self, base_url: Url) -> ReleasePrRequest {
         let github = GitBackend::Github(
             GitHub::new(
@@ -77,10 +132,57 @@ impl ComparisonTest {
         ReleasePrRequest::new(github, self.update_request())
     }
 
+    /// Run `release-pr` with the creation of the release PR disabled.
+    pub async fn github_release_pr_without_pr_creation(&self) -> anyhow::Result<Option<GitPr>> {
+        let base_url = self.github_mock_server.base_url();
+        let release_pr_request = self
+            .github_release_pr_request(base_url)
+            .with_create_pr(false);
+        release_plz_core::release_pr(&release_pr_request).await
+    }
+
+    /// Run `release-pr` with the given pre hook and the creation of the release PR disabled.
+    pub async fn github_release_pr_with_pre_hook(
+        &self,
+        pre_hook: &str,
+    ) -> anyhow::Result<Option<GitPr>> {
+        let base_url = self.github_mock_server.base_url();
+        let release_pr_request = self
+            .github_release_pr_request(base_url)
+            .with_create_pr(false)
+            .with_pre_hook(Some(pre_hook.to_string()));
+        release_plz_core::release_pr(&release_pr_request).await
+    }
+
+    /// Run `release-pr` with the given changelog source and the creation of the release PR disabled.
+    /// Return the changelog of the release PR.
+    pub async fn github_release_pr_changelog(
+        &self,
+        changelog_source: ChangelogSource,
+    ) -> anyhow::Result<String> {
+        let base_url = self.github_mock_server.base_url();
+        let release_pr_request = self
+            .github_release_pr_request(base_url)
+            .with_changelog_source(changelog_source);
+        release_pr_changelog(release_pr_request).await
+    }
+
+    /// Run `release-pr` crediting the authors of the commits, with the creation of the
+    /// release PR disabled. Return the changelog of the release PR.
+    pub async fn github_release_pr_changelog_crediting_authors(&self) -> anyhow::Result<String> {
+        let base_url = self.github_mock_server.base_url();
+        let mut release_pr_request = self.github_release_pr_request(base_url);
+        release_pr_request.update_request = release_pr_request
+            .update_request
+            .with_changelog_credit_authors(true);
+        release_pr_changelog(release_pr_request).await
+    }
+
     pub async fn github_open_release_pr(&self) -> anyhow::Result<()> {
         let base_url = self.github_mock_server.base_url();
         let release_pr_request = self.github_release_pr_request(base_url);
-        release_plz_core::release_pr(&release_pr_request).await
+        release_plz_core::release_pr(&release_pr_request).await?;
+        Ok(())
     }
 
     fn gitea_release_pr_request(&self, base_url: Url) -> anyhow::Result<ReleasePrRequest> {
@@ -95,7 +197,8 @@ impl ComparisonTest {
         let release_pr_request = self
             .gitea_release_pr_request(base_url)
             .context("failed to run release-pr")?;
-        release_plz_core::release_pr(&release_pr_request).await
+        release_plz_core::release_pr(&release_pr_request).await?;
+        Ok(())
     }
 
     pub fn local_project(&self) -> PathBuf {
@@ -114,6 +217,17 @@ impl ComparisonTest {
         self.registry_project().join(CARGO_TOML)
     }
 
+    /// Rename the package of the registry, so that the local package looks unpublished.
+    pub fn unpublish_package(&self) {
+        let manifest = self.registry_project_manfifest();
+        let content = fs::read_to_string(&manifest).unwrap();
+        let content = content.replace(
+            &format!("name = \"{PROJECT_NAME}\""),
+            "name = \"other-project\"",
+        );
+        fs::write(manifest, content).unwrap();
+    }
+
     pub fn are_projects_equal(&self) -> bool {
         are_packages_equal(&self.local_project(), &self.registry_project()).unwrap()
     }
@@ -144,3 +258,18 @@ impl ComparisonTest {
         &self.gitea_mock_server
     }
 }
+
+/// Run `release-pr` with the creation of the release PR disabled.
+/// Return the changelog of the release PR.
+async fn release_pr_changelog(release_pr_request: ReleasePrRequest) -> anyhow::Result<String> {
+    let hook_output = tempdir().unwrap();
+    let copied_changelog = hook_output.path().join(CHANGELOG_FILENAME);
+    let release_pr_request = release_pr_request
+        .with_create_pr(false)
+        .with_pre_hook(Some(format!(
+            "cp {CHANGELOG_FILENAME} '{}'",
+            copied_changelog.display()
+        )));
+    release_plz_core::release_pr(&release_pr_request).await?;
+    Ok(fs::read_to_string(copied_changelog)?)
+}
@@ -1,6 +1,6 @@
 use url::Url;
 use wiremock::{
-    matchers::{method, path},
+    matchers::{method, path, query_param},
     Mock, MockServer, ResponseTemplate,
 };
 
@@ -33,6 +33,28 @@ impl GitHubMockServer {
             .await;
     }
 
+    /// Return these closed PRs when listing the PRs.
+    pub async fn closed_prs(&self, prs: serde_json::Value) {
+        Mock::given(method("GET"))
+            .and(path(self.pulls_path()))
+            .and(query_param("state", "closed"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(prs))
+            .expect(1)
+            .mount(&self.server)
+            .await;
+    }
+
+    /// Fail the listing of the closed PRs.
+    pub async fn failing_closed_prs(&self) {
+        Mock::given(method("GET"))
+            .and(path(self.pulls_path()))
+            .and(query_param("state", "closed"))
+            .respond_with(ResponseTemplate::new(403))
+            .expect(1)
+            .mount(&self.server)
+            .await;
+    }
+
     /// Return an empty list of PRs.
     pub async fn _no_prs(&self) {
         let no_prs = {
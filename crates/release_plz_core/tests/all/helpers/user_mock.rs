@@ -8,3 +8,35 @@ pub fn add_feature(project: &Path, message: &str) {
     let commit_message = format!("feat: {message}");
     git_in_dir(project, &["commit", "-m", &commit_message]).unwrap();
 }
+
+/// Add a feature in a branch and merge it with a merge commit.
+pub fn merge_feature(project: &Path, message: &str, merge_message: &str) {
+    merge_branch(project, "feature", merge_message, || {
+        add_feature(project, message)
+    });
+}
+
+/// Commit a changelog entry, like the release commit created by release-plz,
+/// with the `marker` trailer.
+pub fn add_release_commit(project: &Path, marker: &str) {
+    fs::write(project.join("CHANGELOG.md"), "# Changelog\n").unwrap();
+    git_in_dir(project, &["add", "."]).unwrap();
+    let commit_message = format!("chore: release\n\n{marker}");
+    git_in_dir(project, &["commit", "-m", &commit_message]).unwrap();
+}
+
+/// Add a release commit in a branch and merge it with a merge commit,
+/// like when merging the release PR.
+pub fn merge_release_commit(project: &Path, marker: &str, merge_message: &str) {
+    merge_branch(project, "release-plz", merge_message, || {
+        add_release_commit(project, marker)
+    });
+}
+
+fn merge_branch(project: &Path, branch: &str, merge_message: &str, commit: impl FnOnce()) {
+    let default_branch = git_in_dir(project, &["rev-parse", "--abbrev-ref", "HEAD"]).unwrap();
+    git_in_dir(project, &["checkout", "-b", branch]).unwrap();
+    commit();
+    git_in_dir(project, &["checkout", &default_branch]).unwrap();
+    git_in_dir(project, &["merge", "--no-ff", "-m", merge_message, branch]).unwrap();
+}
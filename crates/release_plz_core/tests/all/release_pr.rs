@@ -1,4 +1,8 @@
-use crate::helpers::comparison_test::ComparisonTest;
+use cargo_metadata::semver::Version;
+use git_cmd::git_in_dir;
+use release_plz_core::{read_package, ChangelogSource};
+
+use crate::helpers::{comparison_test::ComparisonTest, user_mock};
 
 #[tokio::test]
 async fn github_up_to_date_project_should_not_raise_pr() {
@@ -19,3 +23,158 @@ async fn gitea_up_to_date_project_should_not_raise_pr() {
         .await;
     comparison_test.gitea_open_release_pr().await.unwrap();
 }
+
+#[tokio::test]
+async fn disabled_pr_creation_updates_packages_without_raising_pr() {
+    let comparison_test = ComparisonTest::new().await;
+    user_mock::add_feature(&comparison_test.local_project(), "do awesome stuff");
+    comparison_test
+        .github_mock_server()
+        .expect_no_created_prs()
+        .await;
+    let release_pr = comparison_test
+        .github_release_pr_without_pr_creation()
+        .await
+        .unwrap();
+    assert!(release_pr.is_none());
+    let local_package = read_package(comparison_test.local_project()).unwrap();
+    assert_eq!(local_package.version, Version::new(0, 1, 1));
+    let changelog = comparison_test.local_project_changelog();
+    assert!(changelog.contains("## [0.1.1]"), "{changelog}");
+    assert!(changelog.contains("- do awesome stuff"), "{changelog}");
+}
+
+#[tokio::test]
+async fn pre_hook_runs_after_changelog_is_updated() {
+    let comparison_test = ComparisonTest::new().await;
+    user_mock::add_feature(&comparison_test.local_project(), "do awesome stuff");
+    let hook_output = tempfile::tempdir().unwrap();
+    let copied_changelog = hook_output.path().join("CHANGELOG.md");
+    comparison_test
+        .github_release_pr_with_pre_hook(&format!(
+            "cp CHANGELOG.md '{}'",
+            copied_changelog.display()
+        ))
+        .await
+        .unwrap();
+    let changelog = std::fs::read_to_string(copied_changelog).unwrap();
+    assert!(changelog.contains("do awesome stuff"), "{changelog}");
+}
+
+#[tokio::test]
+async fn failing_pre_hook_aborts_release_pr() {
+    let comparison_test = ComparisonTest::new().await;
+    user_mock::add_feature(&comparison_test.local_project(), "do awesome stuff");
+    let error = comparison_test
+        .github_release_pr_with_pre_hook("exit 1")
+        .await
+        .unwrap_err();
+    assert!(
+        error
+            .to_string()
+            .contains("release PR pre hook `exit 1` failed"),
+        "{error}"
+    );
+}
+
+#[tokio::test]
+async fn changelog_uses_titles_of_merged_prs() {
+    let comparison_test = ComparisonTest::new().await;
+    let project = comparison_test.local_project();
+    user_mock::add_feature(&project, "do awesome stuff");
+    std::fs::write(project.join("src").join("api.rs"), "pub fn api(){}").unwrap();
+    git_in_dir(&project, &["add", "."]).unwrap();
+    git_in_dir(&project, &["commit", "-m", "feat: api commit"]).unwrap();
+    let merge_commit_sha = git_in_dir(&project, &["rev-parse", "HEAD"]).unwrap();
+    comparison_test
+        .github_mock_server()
+        .closed_prs(serde_json::json!([{
+            "number": 7,
+            "title": "feat: add awesome api",
+            "merge_commit_sha": merge_commit_sha,
+            "merged_at": "2024-03-01T10:00:00Z",
+            "updated_at": "2024-03-01T10:00:00Z"
+        }]))
+        .await;
+
+    let changelog = comparison_test
+        .github_release_pr_changelog(ChangelogSource::PullRequests)
+        .await
+        .unwrap();
+
+    assert!(changelog.contains("- add awesome api (#7)"), "{changelog}");
+    assert!(!changelog.contains("api commit"), "{changelog}");
+    // The commit without a merged PR keeps its message.
+    assert!(changelog.contains("- do awesome stuff"), "{changelog}");
+}
+
+#[tokio::test]
+async fn changelog_uses_commits_if_merged_prs_cannot_be_retrieved() {
+    let comparison_test = ComparisonTest::new().await;
+    user_mock::add_feature(&comparison_test.local_project(), "do awesome stuff");
+    comparison_test
+        .github_mock_server()
+        .failing_closed_prs()
+        .await;
+
+    let changelog = comparison_test
+        .github_release_pr_changelog(ChangelogSource::PullRequests)
+        .await
+        .unwrap();
+
+    assert!(changelog.contains("- do awesome stuff"), "{changelog}");
+}
+
+#[tokio::test]
+async fn changelog_credits_authors_of_merged_prs() {
+    let comparison_test = ComparisonTest::new().await;
+    let project = comparison_test.local_project();
+    user_mock::add_feature(&project, "do awesome stuff");
+    std::fs::write(project.join("src").join("api.rs"), "pub fn api(){}").unwrap();
+    git_in_dir(&project, &["add", "."]).unwrap();
+    git_in_dir(&project, &["commit", "-m", "feat: add api"]).unwrap();
+    let merge_commit_sha = git_in_dir(&project, &["rev-parse", "HEAD"]).unwrap();
+    comparison_test
+        .github_mock_server()
+        .closed_prs(serde_json::json!([{
+            "number": 7,
+            "title": "feat: add awesome api",
+            "merge_commit_sha": merge_commit_sha,
+            "merged_at": "2024-03-01T10:00:00Z",
+            "updated_at": "2024-03-01T10:00:00Z",
+            "user": { "login": "octocat" }
+        }]))
+        .await;
+
+    let changelog = comparison_test
+        .github_release_pr_changelog_crediting_authors()
+        .await
+        .unwrap();
+
+    assert!(changelog.contains("- add api (@octocat)"), "{changelog}");
+    // The commit without a merged PR is credited to its git author.
+    assert!(
+        changelog.contains("- do awesome stuff (author_name)"),
+        "{changelog}"
+    );
+}
+
+#[tokio::test]
+async fn changelog_credits_git_authors_if_merged_prs_cannot_be_retrieved() {
+    let comparison_test = ComparisonTest::new().await;
+    user_mock::add_feature(&comparison_test.local_project(), "do awesome stuff");
+    comparison_test
+        .github_mock_server()
+        .failing_closed_prs()
+        .await;
+
+    let changelog = comparison_test
+        .github_release_pr_changelog_crediting_authors()
+        .await
+        .unwrap();
+
+    assert!(
+        changelog.contains("- do awesome stuff (author_name)"),
+        "{changelog}"
+    );
+}
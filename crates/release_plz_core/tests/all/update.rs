@@ -1,5 +1,7 @@
 use cargo_metadata::semver::Version;
-use release_plz_core::{read_package, CHANGELOG_HEADER};
+use release_plz_core::{
+    read_package, CHANGELOG_FILENAME, CHANGELOG_HEADER, DEFAULT_RELEASE_COMMIT_MARKER,
+};
 
 use crate::helpers::{comparison_test::ComparisonTest, user_mock};
 
@@ -41,6 +43,49 @@ async fn version_is_updated_when_project_changed() {
     .assert_eq(&comparison_test.local_project_changelog());
 }
 
+#[tokio::test]
+async fn changelog_diff_is_computed_without_updating_the_changelog() {
+    let old_body = r#"
+## [0.1.0] - 1970-01-01
+
+### Fixed
+- fix important bug
+"#;
+    let comparison_test = ComparisonTest::new().await;
+    let old_changelog = format!("{CHANGELOG_HEADER}{old_body}");
+    comparison_test.write_local_project_changelog(&old_changelog);
+    user_mock::add_feature(&comparison_test.local_project(), "do awesome stuff");
+
+    let diff = comparison_test.changelog_diff();
+
+    // The empty context lines are a single space: trim it to compare the diff.
+    let diff: String = diff
+        .lines()
+        .flat_map(|line| [line.trim_end(), "\n"])
+        .collect();
+
+    expect_test::expect![[r#"
+        --- a/CHANGELOG.md
+        +++ b/CHANGELOG.md
+        @@ -6,6 +6,11 @@
+ 
+         ## [Unreleased]
+ 
+        +## [0.1.1] - 2015-05-15
+        +
+        +### Added
+        +- do awesome stuff
+        +
+         ## [0.1.0] - 1970-01-01
+ 
+         ### Fixed
+    "#]]
+    .assert_eq(&diff);
+    assert_eq!(comparison_test.local_project_changelog(), old_changelog);
+    let local_package = read_package(comparison_test.local_project()).unwrap();
+    assert_eq!(local_package.version, Version::new(0, 1, 0));
+}
+
 #[tokio::test]
 async fn changelog_is_updated_if_changelog_already_exists() {
     let old_body = r#"
@@ -80,3 +125,135 @@ async fn changelog_is_updated_if_changelog_already_exists() {
     "####]]
     .assert_eq(&comparison_test.local_project_changelog());
 }
+
+#[tokio::test]
+async fn merge_commit_is_added_to_changelog_if_enabled() {
+    let comparison_test = ComparisonTest::new().await;
+    user_mock::merge_feature(
+        &comparison_test.local_project(),
+        "do awesome stuff",
+        "Merge pull request #12 from owner/feature\n\nfeat: add awesome api",
+    );
+
+    comparison_test.run_update_with_merge_commits(true);
+
+    expect_test::expect![[r####"
+        # Changelog
+        All notable changes to this project will be documented in this file.
+
+        The format is based on [Keep a Changelog](https://keepachangelog.com/en/1.0.0/),
+        and this project adheres to [Semantic Versioning](https://semver.org/spec/v2.0.0.html).
+
+        ## [Unreleased]
+
+        ## [0.1.1](https://github.com/owner/repo/compare/v0.1.0...v0.1.1) - 2015-05-15
+
+        ### Added
+        - add awesome api ([#12](https://github.com/owner/repo/pull/12))
+    "####]]
+    .assert_eq(&comparison_test.local_project_changelog());
+}
+
+#[tokio::test]
+async fn merge_commit_is_excluded_from_changelog_by_default() {
+    let comparison_test = ComparisonTest::new().await;
+    user_mock::merge_feature(
+        &comparison_test.local_project(),
+        "do awesome stuff",
+        "Merge pull request #12 from owner/feature\n\nfeat: add awesome api",
+    );
+
+    comparison_test.run_update_with_merge_commits(false);
+
+    expect_test::expect![[r####"
+        # Changelog
+        All notable changes to this project will be documented in this file.
+
+        The format is based on [Keep a Changelog](https://keepachangelog.com/en/1.0.0/),
+        and this project adheres to [Semantic Versioning](https://semver.org/spec/v2.0.0.html).
+
+        ## [Unreleased]
+
+        ## [0.1.1](https://github.com/owner/repo/compare/v0.1.0...v0.1.1) - 2015-05-15
+
+        ### Added
+        - do awesome stuff
+    "####]]
+    .assert_eq(&comparison_test.local_project_changelog());
+}
+
+#[tokio::test]
+async fn release_commit_does_not_trigger_a_new_release() {
+    let comparison_test = ComparisonTest::new().await;
+    let marker = "release-plz: true";
+    user_mock::add_release_commit(&comparison_test.local_project(), marker);
+
+    comparison_test.run_update_with_release_commit_marker(marker, false);
+
+    let local_package = read_package(comparison_test.local_project()).unwrap();
+    assert_eq!(local_package.version, Version::new(0, 1, 0));
+    assert_eq!(comparison_test.local_project_changelog(), "# Changelog\n");
+}
+
+#[tokio::test]
+async fn release_commit_with_default_marker_does_not_trigger_a_new_release() {
+    let comparison_test = ComparisonTest::new().await;
+    user_mock::add_release_commit(
+        &comparison_test.local_project(),
+        DEFAULT_RELEASE_COMMIT_MARKER,
+    );
+
+    comparison_test.run_update();
+
+    let local_package = read_package(comparison_test.local_project()).unwrap();
+    assert_eq!(local_package.version, Version::new(0, 1, 0));
+    assert_eq!(comparison_test.local_project_changelog(), "# Changelog\n");
+}
+
+#[tokio::test]
+async fn unpublished_package_is_not_released_without_release_commits() {
+    let comparison_test = ComparisonTest::new().await;
+    comparison_test.unpublish_package();
+    user_mock::add_feature(&comparison_test.local_project(), "do awesome stuff");
+
+    comparison_test.run_update_with_release_commits("^fix");
+
+    assert!(!comparison_test
+        .local_project()
+        .join(CHANGELOG_FILENAME)
+        .exists());
+}
+
+#[tokio::test]
+async fn unpublished_package_is_released_with_release_commits() {
+    let comparison_test = ComparisonTest::new().await;
+    comparison_test.unpublish_package();
+    user_mock::add_feature(&comparison_test.local_project(), "do awesome stuff");
+
+    comparison_test.run_update_with_release_commits("^feat");
+
+    let local_package = read_package(comparison_test.local_project()).unwrap();
+    assert_eq!(local_package.version, Version::new(0, 1, 0));
+    assert!(comparison_test
+        .local_project_changelog()
+        .contains("- do awesome stuff"));
+}
+
+#[tokio::test]
+async fn merged_release_pr_does_not_trigger_a_new_release() {
+    let comparison_test = ComparisonTest::new().await;
+    let marker = "release-plz: true";
+    user_mock::merge_release_commit(
+        &comparison_test.local_project(),
+        marker,
+        "Merge pull request #12 from owner/release-plz\n\nchore: release",
+    );
+
+    // Merge commits are walked with `--first-parent`, so the release commit
+    // is only reachable through the merge commit.
+    comparison_test.run_update_with_release_commit_marker(marker, true);
+
+    let local_package = read_package(comparison_test.local_project()).unwrap();
+    assert_eq!(local_package.version, Version::new(0, 1, 0));
+    assert_eq!(comparison_test.local_project_changelog(), "# Changelog\n");
+}